@@ -57,6 +57,11 @@ pub enum QueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
+    #[error("The limit of contract state values to return must be positive")]
+    InvalidStateLimit {
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -180,6 +185,9 @@ pub enum Error {
     /// Invalid shard id
     #[error("Invalid state request: {0}")]
     InvalidStateRequest(String),
+    /// Epoch sync response is inconsistent with the data it should prove
+    #[error("Invalid epoch sync response: {0}")]
+    InvalidEpochSyncResponse(String),
     /// Invalid VRF proof, or incorrect random_output in the header
     #[error("Invalid Randomness Beacon Output")]
     InvalidRandomnessBeaconOutput,
@@ -280,6 +288,7 @@ impl Error {
             | Error::InvalidBalanceBurnt
             | Error::InvalidShardId(_)
             | Error::InvalidStateRequest(_)
+            | Error::InvalidEpochSyncResponse(_)
             | Error::InvalidRandomnessBeaconOutput
             | Error::InvalidBlockMerkleRoot
             | Error::NotAValidator
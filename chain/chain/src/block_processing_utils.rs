@@ -1,6 +1,8 @@
-use crate::chain::{BlockMissingChunks, OrphanMissingChunks};
+use crate::chain::{BlockMissingChunks, OrphanMissingChunks, MAX_ORPHAN_SIZE};
+use crate::metrics;
 use crate::near_chain_primitives::error::BlockKnownError::KnownInProcessing;
 use crate::Provenance;
+use lru::LruCache;
 use near_primitives::block::Block;
 use near_primitives::challenge::{ChallengeBody, ChallengesResult};
 use near_primitives::hash::CryptoHash;
@@ -8,7 +10,7 @@ use near_primitives::sharding::{ReceiptProof, StateSyncInfo};
 use near_primitives::types::ShardId;
 use once_cell::sync::OnceCell;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 /// Max number of blocks that can be in the pool at once.
@@ -76,6 +78,83 @@ pub type DoneApplyChunkCallback = Arc<dyn Fn(CryptoHash) -> () + Send + Sync + '
 #[derive(Debug)]
 pub struct BlockNotInPoolError;
 
+/// Thread pool the chunks of blocks are applied on.
+///
+/// The chunks of a block are applied in parallel on the threads of the pool,
+/// and idle threads steal work scheduled for other blocks, so the client actor
+/// thread is free to preprocess the next blocks in the meantime.  By default
+/// the global rayon thread pool is used.
+///
+/// Catching up blocks, building the state of split shards and validating
+/// blocks ahead of processing run on the same pool.
+#[derive(Clone, Default)]
+pub struct ApplyChunksExecutor {
+    pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+impl ApplyChunksExecutor {
+    /// Creates an executor with a dedicated pool of `num_threads` threads, or
+    /// one thread per CPU if `num_threads` is zero.
+    pub fn new(num_threads: usize) -> Result<Self, near_chain_primitives::Error> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|index| format!("apply_chunks_{}", index))
+            .build()
+            .map_err(|err| {
+                near_chain_primitives::Error::Other(format!(
+                    "failed to create apply chunks thread pool: {}",
+                    err
+                ))
+            })?;
+        Ok(Self { pool: Some(Arc::new(pool)) })
+    }
+
+    /// Runs `f` on the pool without waiting for it to finish.  Parallel
+    /// iterators used by `f` run on the same pool.
+    pub fn spawn(&self, f: impl FnOnce() + Send + 'static) {
+        // Propagate `tracing` context across threads.
+        let dispatcher = tracing::dispatcher::get_default(|it| it.clone());
+        let queue_timer = metrics::APPLY_CHUNKS_QUEUE_DELAY.start_timer();
+        let f = move || {
+            queue_timer.observe_duration();
+            tracing::dispatcher::with_default(&dispatcher, f)
+        };
+        match &self.pool {
+            Some(pool) => pool.spawn(f),
+            None => rayon::spawn(f),
+        }
+    }
+}
+
+/// Hashes of orphan blocks whose body was validated on the apply chunks
+/// executor while the chunks of their previous block were being applied.
+#[derive(Clone)]
+pub(crate) struct PrevalidatedBlocks {
+    inner: Arc<Mutex<LruCache<CryptoHash, ()>>>,
+}
+
+impl Default for PrevalidatedBlocks {
+    fn default() -> Self {
+        Self { inner: Arc::new(Mutex::new(LruCache::new(MAX_ORPHAN_SIZE))) }
+    }
+}
+
+impl PrevalidatedBlocks {
+    pub(crate) fn insert(&self, block_hash: CryptoHash) {
+        self.inner.lock().unwrap().put(block_hash, ());
+    }
+
+    #[cfg(test)]
+    pub(crate) fn contains(&self, block_hash: &CryptoHash) -> bool {
+        self.inner.lock().unwrap().contains(block_hash)
+    }
+
+    /// Returns whether the block was validated and forgets about it.
+    pub(crate) fn take(&self, block_hash: &CryptoHash) -> bool {
+        self.inner.lock().unwrap().pop(block_hash).is_some()
+    }
+}
+
 impl BlocksInProcessing {
     pub(crate) fn new() -> Self {
         BlocksInProcessing { preprocessed_blocks: HashMap::new() }
@@ -116,6 +195,10 @@ impl BlocksInProcessing {
         }
     }
 
+    pub(crate) fn contains(&self, block_hash: &CryptoHash) -> bool {
+        self.preprocessed_blocks.contains_key(block_hash)
+    }
+
     pub(crate) fn has_blocks_to_catch_up(&self, prev_hash: &CryptoHash) -> bool {
         self.preprocessed_blocks
             .iter()
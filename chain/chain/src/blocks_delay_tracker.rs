@@ -1,13 +1,24 @@
+use chrono::DateTime;
 use itertools::Itertools;
+use near_chain_configs::BlockTimelineLogConfig;
+use near_client_primitives::debug::{BlockProcessingTimelineView, ChunkProcessingTimelineView};
 use near_primitives::block::Block;
 use near_primitives::hash::CryptoHash;
 use near_primitives::sharding::ChunkHash;
+use near_primitives::time::Clock;
 use near_primitives::types::{BlockHeight, ShardId};
-use std::collections::HashMap;
-use std::time::Instant;
-use tracing::error;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
 
 use crate::metrics;
+use crate::Error;
+
+/// Number of timelines of processed blocks kept in memory for the debug page.
+const MAX_FINISHED_BLOCK_TIMELINES: usize = 1000;
 
 /// Provides monitoring information about the important timestamps throughout the lifetime of
 /// blocks and chunks. It keeps information of all pending blocks and chunks that have not been fully processed yet.
@@ -20,6 +31,10 @@ pub struct BlocksDelayTracker {
     /// An entry gets created when a chunk gets requested for the first time.
     /// Chunks get deleted when the block gets processed.
     pub chunks_in_progress: HashMap<ChunkHash, ChunkInProgress>,
+    /// Timelines of the most recently processed blocks, oldest first.
+    pub finished_blocks: VecDeque<BlockProcessingTimelineView>,
+    /// File the timelines of processed blocks are appended to, if configured.
+    timeline_log: Option<BlockTimelineLog>,
 }
 
 #[derive(Debug)]
@@ -38,6 +53,8 @@ pub struct BlockInProgress {
     pub height: BlockHeight,
     /// Hashes of the chunks that belong to this block.
     pub chunks: Vec<ChunkHash>,
+    /// How long applying the chunk of each shard took.
+    pub apply_durations: HashMap<ShardId, Duration>,
 }
 
 /// Records timestamps of requesting and receiving a chunk. Assumes that each chunk is requested
@@ -67,6 +84,7 @@ impl BlocksDelayTracker {
             removed_from_missing_chunks_timestamp: None,
             height,
             chunks,
+            apply_durations: HashMap::new(),
         });
     }
 
@@ -106,6 +124,17 @@ impl BlocksDelayTracker {
         }
     }
 
+    pub fn mark_chunk_applied(
+        &mut self,
+        block_hash: &CryptoHash,
+        shard_id: ShardId,
+        duration: Duration,
+    ) {
+        if let Some(block_entry) = self.blocks_in_progress.get_mut(block_hash) {
+            block_entry.apply_durations.insert(shard_id, duration);
+        }
+    }
+
     pub fn mark_chunk_received(&mut self, chunk_hash: &ChunkHash, timestamp: Instant) {
         self.chunks_in_progress
             .get_mut(&chunk_hash)
@@ -125,18 +154,85 @@ impl BlocksDelayTracker {
         });
     }
 
+    /// Sets the file the timelines of processed blocks are appended to.
+    pub fn set_timeline_log(&mut self, timeline_log: BlockTimelineLog) {
+        self.timeline_log = Some(timeline_log);
+    }
+
     pub fn finish_block_processing(&mut self, block_hash: &CryptoHash, chunks: &[ChunkHash]) {
         if let Some(processed_block) = self.blocks_in_progress.remove(&block_hash) {
             self.update_block_metrics(&processed_block);
+            let mut processed_chunks = Vec::with_capacity(chunks.len());
             for (shard_id, chunk_hash) in chunks.iter().enumerate() {
-                if let Some(processed_chunk) = self.chunks_in_progress.remove(&chunk_hash) {
+                let processed_chunk = self.chunks_in_progress.remove(&chunk_hash);
+                if let Some(processed_chunk) = &processed_chunk {
                     self.update_chunk_metrics(
                         &processed_block,
-                        &processed_chunk,
+                        processed_chunk,
                         shard_id as ShardId,
                     );
                 }
+                processed_chunks.push((chunk_hash, processed_chunk));
+            }
+            let timeline = Self::make_timeline(
+                *block_hash,
+                &processed_block,
+                &processed_chunks,
+                Clock::instant(),
+                Clock::utc(),
+            );
+            if let Some(timeline_log) = &mut self.timeline_log {
+                timeline_log.append(&timeline);
+            }
+            if self.finished_blocks.len() == MAX_FINISHED_BLOCK_TIMELINES {
+                self.finished_blocks.pop_front();
             }
+            self.finished_blocks.push_back(timeline);
+        }
+    }
+
+    /// Builds the timeline of a processed block, converting the monotonic
+    /// timestamps to wall clock time using `now` and `now_utc`, which must
+    /// refer to the same moment.
+    fn make_timeline(
+        block_hash: CryptoHash,
+        block: &BlockInProgress,
+        chunks: &[(&ChunkHash, Option<ChunkInProgress>)],
+        now: Instant,
+        now_utc: DateTime<chrono::Utc>,
+    ) -> BlockProcessingTimelineView {
+        let to_utc = |timestamp: Instant| {
+            now_utc
+                - chrono::Duration::from_std(now.saturating_duration_since(timestamp))
+                    .unwrap_or_else(|_| chrono::Duration::zero())
+        };
+        let chunks = chunks
+            .iter()
+            .enumerate()
+            .map(|(shard_id, (chunk_hash, chunk))| {
+                let shard_id = shard_id as ShardId;
+                ChunkProcessingTimelineView {
+                    shard_id,
+                    chunk_hash: (*chunk_hash).clone(),
+                    requested: chunk.as_ref().map(|chunk| to_utc(chunk.chunk_requested)),
+                    received: chunk.as_ref().and_then(|chunk| chunk.chunk_received.map(to_utc)),
+                    apply_duration_ms: block
+                        .apply_durations
+                        .get(&shard_id)
+                        .map(|duration| duration.as_millis() as u64),
+                }
+            })
+            .collect();
+        BlockProcessingTimelineView {
+            block_hash,
+            height: block.height,
+            received: to_utc(block.received_timestamp),
+            orphaned: block.orphaned_timestamp.map(to_utc),
+            unorphaned: block.removed_from_orphan_timestamp.map(to_utc),
+            missing_chunks: block.missing_chunks_timestamp.map(to_utc),
+            completed_missing_chunks: block.removed_from_missing_chunks_timestamp.map(to_utc),
+            finished: now_utc,
+            chunks,
         }
     }
 
@@ -178,3 +274,133 @@ impl BlocksDelayTracker {
         }
     }
 }
+
+/// Rolling JSON-lines file with one timeline of a processed block per line.
+///
+/// Once the file grows over the configured size it is renamed to `<path>.1`,
+/// shifting the previously rotated files by one and removing the oldest.
+/// Failures to write are logged and don't affect block processing.
+#[derive(Debug)]
+pub struct BlockTimelineLog {
+    config: BlockTimelineLogConfig,
+    file: File,
+    file_size: u64,
+}
+
+impl BlockTimelineLog {
+    pub fn open(config: &BlockTimelineLogConfig) -> Result<Self, Error> {
+        if let Some(dir) = config.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+        let file_size = file.metadata()?.len();
+        Ok(Self { config: config.clone(), file, file_size })
+    }
+
+    fn append(&mut self, timeline: &BlockProcessingTimelineView) {
+        let mut line = match serde_json::to_vec(timeline) {
+            Ok(line) => line,
+            Err(err) => {
+                warn!(target: "blocks_delay_tracker", ?err, "Failed to serialize block timeline");
+                return;
+            }
+        };
+        line.push(b'\n');
+        if self.file_size > 0 && self.file_size + line.len() as u64 > self.config.max_file_size {
+            if let Err(err) = self.rotate() {
+                warn!(target: "blocks_delay_tracker", ?err, path = %self.config.path.display(), "Failed to rotate block timeline log");
+            }
+        }
+        match self.file.write_all(&line) {
+            Ok(()) => self.file_size += line.len() as u64,
+            Err(err) => {
+                warn!(target: "blocks_delay_tracker", ?err, path = %self.config.path.display(), "Failed to write block timeline log");
+            }
+        }
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let path = &self.config.path;
+        if self.config.max_rotated_files > 0 {
+            for index in (1..self.config.max_rotated_files).rev() {
+                let from = Self::rotated_path(path, index);
+                if from.exists() {
+                    std::fs::rename(&from, Self::rotated_path(path, index + 1))?;
+                }
+            }
+            std::fs::rename(path, Self::rotated_path(path, 1))?;
+        }
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(path)?;
+        self.file_size = 0;
+        Ok(())
+    }
+
+    fn rotated_path(path: &Path, index: usize) -> PathBuf {
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(format!(".{}", index));
+        path.with_file_name(file_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::hash::hash;
+
+    #[test]
+    fn test_make_timeline() {
+        let now = Instant::now();
+        let now_utc = Clock::utc();
+        let ago = |millis| now - Duration::from_millis(millis);
+        let utc_ago = |millis| now_utc - chrono::Duration::milliseconds(millis);
+
+        let chunk_hashes = vec![ChunkHash(hash(&[0])), ChunkHash(hash(&[1]))];
+        let block = BlockInProgress {
+            received_timestamp: ago(500),
+            orphaned_timestamp: None,
+            missing_chunks_timestamp: Some(ago(400)),
+            removed_from_orphan_timestamp: None,
+            removed_from_missing_chunks_timestamp: Some(ago(100)),
+            height: 10,
+            chunks: chunk_hashes.clone(),
+            apply_durations: HashMap::from([(1, Duration::from_millis(50))]),
+        };
+        let requested_chunk = ChunkInProgress {
+            chunk_requested: ago(400),
+            chunk_received: Some(ago(150)),
+            block_hash: hash(&[2]),
+        };
+        let chunks = vec![(&chunk_hashes[0], None), (&chunk_hashes[1], Some(requested_chunk))];
+
+        let timeline = BlocksDelayTracker::make_timeline(hash(&[2]), &block, &chunks, now, now_utc);
+        assert_eq!(
+            timeline,
+            BlockProcessingTimelineView {
+                block_hash: hash(&[2]),
+                height: 10,
+                received: utc_ago(500),
+                orphaned: None,
+                unorphaned: None,
+                missing_chunks: Some(utc_ago(400)),
+                completed_missing_chunks: Some(utc_ago(100)),
+                finished: now_utc,
+                chunks: vec![
+                    ChunkProcessingTimelineView {
+                        shard_id: 0,
+                        chunk_hash: chunk_hashes[0].clone(),
+                        requested: None,
+                        received: None,
+                        apply_duration_ms: None,
+                    },
+                    ChunkProcessingTimelineView {
+                        shard_id: 1,
+                        chunk_hash: chunk_hashes[1].clone(),
+                        requested: Some(utc_ago(400)),
+                        received: Some(utc_ago(150)),
+                        apply_duration_ms: Some(50),
+                    },
+                ],
+            }
+        );
+    }
+}
@@ -21,6 +21,8 @@ use near_primitives::challenge::{
     MaybeEncodedShardChunk, PartialState, SlashedValidator,
 };
 use near_primitives::checked_feature;
+use near_primitives::epoch_manager::block_info::BlockInfo;
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::merkle::{
     combine_hash, merklize, verify_path, Direction, MerklePath, MerklePathItem, PartialMerkleTree,
@@ -32,8 +34,9 @@ use near_primitives::sharding::{
 };
 use near_primitives::state_part::PartId;
 use near_primitives::syncing::{
-    get_num_state_parts, ReceiptProofResponse, RootProof, ShardStateSyncResponseHeader,
-    ShardStateSyncResponseHeaderV1, ShardStateSyncResponseHeaderV2, StateHeaderKey, StatePartKey,
+    get_num_state_parts, EpochSyncFinalizationResponse, EpochSyncResponse, ReceiptProofResponse,
+    RootProof, ShardStateSyncResponseHeader, ShardStateSyncResponseHeaderV1,
+    ShardStateSyncResponseHeaderV2, StateHeaderKey, StatePartKey,
 };
 use near_primitives::transaction::{ExecutionOutcomeWithIdAndProof, SignedTransaction};
 use near_primitives::types::chunk_extra::ChunkExtra;
@@ -51,13 +54,19 @@ use near_primitives::views::{
 use near_store::{DBCol, ShardTries, StoreUpdate};
 
 use crate::block_processing_utils::{
-    BlockPreprocessInfo, BlockProcessingArtifact, BlocksInProcessing, DoneApplyChunkCallback,
+    ApplyChunksExecutor, BlockPreprocessInfo, BlockProcessingArtifact, BlocksInProcessing,
+    DoneApplyChunkCallback, PrevalidatedBlocks,
 };
 use crate::blocks_delay_tracker::BlocksDelayTracker;
 use crate::crypto_hash_timer::CryptoHashTimer;
-use crate::lightclient::get_epoch_block_producers_view;
+use crate::gc_policy::{
+    get_deferred_gc_key_rev, get_retention_cutoff, ColumnGCPolicies, DeferredGCEntry,
+    RETAINABLE_COLUMNS,
+};
+use crate::lightclient::{get_epoch_block_producers_view, light_client_block_hash};
 use crate::migrations::check_if_block_is_first_with_chunk_of_version;
 use crate::missing_chunks::{BlockLike, MissingChunksPool};
+use crate::optimistic_apply::{OptimisticApplyCache, OptimisticApplyKey, OptimisticApplyStats};
 use crate::store::{ChainStore, ChainStoreAccess, ChainStoreUpdate, GCMode};
 use crate::types::{
     AcceptedBlock, ApplySplitStateResult, ApplySplitStateResultOrStateChanges,
@@ -73,6 +82,7 @@ use crate::{metrics, DoomslugThresholdMode};
 use actix::Message;
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use delay_detector::DelayDetector;
+use near_metrics::prometheus::HistogramTimer;
 use near_primitives::shard_layout::{
     account_id_to_shard_id, account_id_to_shard_uid, ShardLayout, ShardUId,
 };
@@ -425,7 +435,8 @@ pub fn check_known(
     check_known_store(chain, block_hash)
 }
 
-type BlockApplyChunksResult = (CryptoHash, Vec<Result<ApplyChunkResult, Error>>);
+/// Results of applying the chunks of a block, with a timer started once the chunks were applied.
+type BlockApplyChunksResult = (CryptoHash, Vec<Result<ApplyChunkResult, Error>>, HistogramTimer);
 
 /// Facade to the blockchain block processing and storage.
 /// Provides current view on the state according to the chain state.
@@ -446,6 +457,12 @@ pub struct Chain {
     /// `blocks_in_processing` keeps track of all the blocks that have been preprocessed but are
     /// waiting for chunks being applied.
     pub(crate) blocks_in_processing: BlocksInProcessing,
+    /// Thread pool async_apply_chunks runs on.
+    apply_chunks_executor: ApplyChunksExecutor,
+    /// Orphan blocks validated on the apply chunks executor.
+    pub(crate) prevalidated_blocks: PrevalidatedBlocks,
+    /// Results of chunks applied while their block was waiting for other chunks.
+    optimistic_apply_cache: OptimisticApplyCache<ApplyTransactionResult>,
     /// Used by async_apply_chunks to send apply chunks results back to chain
     apply_chunks_sender: Sender<BlockApplyChunksResult>,
     /// Used to receive apply chunks results
@@ -533,6 +550,9 @@ impl Chain {
             block_economics_config: BlockEconomicsConfig::from(chain_genesis),
             doomslug_threshold_mode,
             blocks_delay_tracker: BlocksDelayTracker::default(),
+            apply_chunks_executor: ApplyChunksExecutor::default(),
+            prevalidated_blocks: PrevalidatedBlocks::default(),
+            optimistic_apply_cache: OptimisticApplyCache::default(),
             apply_chunks_sender: sc,
             apply_chunks_receiver: rc,
             last_time_head_updated: Clock::instant(),
@@ -669,6 +689,9 @@ impl Chain {
             block_economics_config: BlockEconomicsConfig::from(chain_genesis),
             doomslug_threshold_mode,
             blocks_delay_tracker: BlocksDelayTracker::default(),
+            apply_chunks_executor: ApplyChunksExecutor::default(),
+            prevalidated_blocks: PrevalidatedBlocks::default(),
+            optimistic_apply_cache: OptimisticApplyCache::default(),
             apply_chunks_sender: sc,
             apply_chunks_receiver: rc,
             last_time_head_updated: Clock::instant(),
@@ -742,6 +765,127 @@ impl Chain {
         create_light_client_block_view(&final_block_header, chain_store, Some(next_block_producers))
     }
 
+    /// Builds the response to an Epoch Sync request for `epoch_id`: the light client block of the
+    /// epoch, or `UpToDate` if it's the epoch of our head. Returns `None` if the epoch is unknown.
+    pub fn get_epoch_sync_response(
+        &self,
+        epoch_id: &EpochId,
+    ) -> Result<Option<EpochSyncResponse>, Error> {
+        if &self.head()?.epoch_id == epoch_id {
+            return Ok(Some(EpochSyncResponse::UpToDate));
+        }
+        match self.store.get_epoch_light_client_block(&epoch_id.0) {
+            Ok(light_client_block) => Ok(Some(EpochSyncResponse::Advance {
+                light_client_block_view: LightClientBlockView::clone(&light_client_block),
+            })),
+            Err(Error::DBNotFoundErr(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Builds the data a node needs to finish Epoch Sync at `epoch_id`. The last block of the
+    /// previous epoch is where header sync continues from. Returns `None` if the epoch is unknown
+    /// or is the genesis epoch.
+    pub fn get_epoch_sync_finalization_response(
+        &self,
+        epoch_id: &EpochId,
+    ) -> Result<Option<EpochSyncFinalizationResponse>, Error> {
+        let head = self.head()?;
+        let block_in_epoch = if &head.epoch_id == epoch_id {
+            head.last_block_hash
+        } else {
+            match self.store.get_epoch_light_client_block(&epoch_id.0) {
+                Ok(light_client_block) => light_client_block_hash(&light_client_block),
+                Err(Error::DBNotFoundErr(_)) => return Ok(None),
+                Err(err) => return Err(err),
+            }
+        };
+        let epoch_start_height = self.runtime_adapter.get_epoch_start_height(&block_in_epoch)?;
+        let cur_epoch_header = self.get_header_by_height(epoch_start_height)?;
+        if cur_epoch_header.epoch_id() != epoch_id
+            || cur_epoch_header.height() == self.genesis().height()
+        {
+            return Ok(None);
+        }
+        let header_sync_init_header = self.get_previous_header(&cur_epoch_header)?;
+        let (
+            prev_epoch_first_block_info,
+            prev_epoch_prev_last_block_info,
+            prev_epoch_last_block_info,
+            prev_epoch_info,
+            cur_epoch_info,
+            next_epoch_info,
+        ) = self.runtime_adapter.get_epoch_sync_data(
+            header_sync_init_header.hash(),
+            epoch_id,
+            cur_epoch_header.next_epoch_id(),
+        )?;
+        let mut prev_epoch_headers =
+            vec![self.get_block_header(prev_epoch_first_block_info.hash())?];
+        if prev_epoch_prev_last_block_info.hash() != prev_epoch_first_block_info.hash() {
+            prev_epoch_headers.push(self.get_block_header(prev_epoch_prev_last_block_info.hash())?);
+        }
+        let header_sync_init_header_tree = PartialMerkleTree::clone(
+            &*self.store.get_block_merkle_tree(header_sync_init_header.hash())?,
+        );
+        Ok(Some(EpochSyncFinalizationResponse {
+            cur_epoch_header,
+            prev_epoch_headers,
+            header_sync_init_header,
+            header_sync_init_header_tree,
+            prev_epoch_first_block_info: BlockInfo::clone(&prev_epoch_first_block_info),
+            prev_epoch_prev_last_block_info: BlockInfo::clone(&prev_epoch_prev_last_block_info),
+            prev_epoch_last_block_info: BlockInfo::clone(&prev_epoch_last_block_info),
+            prev_epoch_info: EpochInfo::clone(&prev_epoch_info),
+            cur_epoch_info: EpochInfo::clone(&cur_epoch_info),
+            next_epoch_info: EpochInfo::clone(&next_epoch_info),
+        }))
+    }
+
+    /// Applies the result of Epoch Sync: initializes the epoch manager and makes the last header
+    /// of the previous epoch the header head, so that header sync continues from it.
+    /// The response must be validated by the caller.
+    pub fn init_after_epoch_sync(
+        &mut self,
+        response: EpochSyncFinalizationResponse,
+    ) -> Result<(), Error> {
+        let EpochSyncFinalizationResponse {
+            cur_epoch_header,
+            prev_epoch_headers,
+            header_sync_init_header,
+            header_sync_init_header_tree,
+            prev_epoch_first_block_info,
+            prev_epoch_prev_last_block_info,
+            prev_epoch_last_block_info,
+            prev_epoch_info,
+            cur_epoch_info,
+            next_epoch_info,
+        } = response;
+        let prev_epoch_id = prev_epoch_last_block_info.epoch_id().clone();
+        self.runtime_adapter.epoch_sync_init_epoch_manager(
+            prev_epoch_first_block_info,
+            prev_epoch_prev_last_block_info,
+            prev_epoch_last_block_info,
+            &prev_epoch_id,
+            prev_epoch_info,
+            cur_epoch_header.epoch_id(),
+            cur_epoch_info,
+            cur_epoch_header.next_epoch_id(),
+            next_epoch_info,
+        )?;
+
+        let tip = Tip::from_header(&header_sync_init_header);
+        let mut chain_store_update = self.store.store_update();
+        for header in prev_epoch_headers {
+            chain_store_update.save_block_header_no_update_tree(header)?;
+        }
+        chain_store_update
+            .save_block_merkle_tree(*header_sync_init_header.hash(), header_sync_init_header_tree);
+        chain_store_update.save_block_header_no_update_tree(header_sync_init_header)?;
+        chain_store_update.force_save_header_head(&tip)?;
+        chain_store_update.commit()
+    }
+
     pub fn save_block(&mut self, block: MaybeValidated<Block>) -> Result<(), Error> {
         if self.store.get_block(block.hash()).is_ok() {
             return Ok(());
@@ -893,6 +1037,10 @@ impl Chain {
             chain_store_update.commit()?;
             fork_tail = gc_stop_height;
         }
+        let policies = ColumnGCPolicies::from_config(gc_config)?;
+        self.store.set_gc_column_policies(policies.clone());
+        self.clear_deferred_data(&policies, &head, gc_config.gc_blocks_limit)?;
+
         let mut gc_blocks_remaining = gc_config.gc_blocks_limit;
 
         // Forks Cleaning
@@ -945,6 +1093,44 @@ impl Chain {
         Ok(())
     }
 
+    /// Removes data kept by column retention policies which has fallen out of
+    /// its retention.  See [`crate::gc_policy`].
+    ///
+    /// Data of at most `limit` garbage collected blocks or chunks is removed.
+    /// Data of columns which no longer have a policy is removed regardless of
+    /// its age.
+    fn clear_deferred_data(
+        &mut self,
+        policies: &ColumnGCPolicies,
+        head: &Tip,
+        limit: NumBlocks,
+    ) -> Result<(), Error> {
+        let head_header = self.get_block_header(&head.last_block_hash)?;
+        let mut remaining = limit;
+        for col in RETAINABLE_COLUMNS {
+            let cutoff = get_retention_cutoff(&self.store, &head_header, policies.get(col))?;
+            let prefix = [col as u8];
+            let mut expired = vec![];
+            for item in
+                self.store.store().iter_prefix_ser::<DeferredGCEntry>(DBCol::DeferredGC, &prefix)
+            {
+                let (key, entry) = item?;
+                let (height, _) = get_deferred_gc_key_rev(&key)?;
+                if expired.len() as u64 >= remaining || !cutoff.is_expired(height, &entry) {
+                    break;
+                }
+                expired.push((key, entry));
+            }
+            for (key, entry) in expired {
+                let mut chain_store_update = self.store.store_update();
+                chain_store_update.gc_deferred_entry(col, &key, entry)?;
+                chain_store_update.commit()?;
+                remaining -= 1;
+            }
+        }
+        Ok(())
+    }
+
     /// Garbage collect data which archival node doesn’t need to keep.
     ///
     /// Normally, archival nodes keep all the data from the genesis block and
@@ -1548,7 +1734,7 @@ impl Chain {
 
     /// Start processing a received or produced block. This function will process block asynchronously.
     /// It preprocesses the block by verifying that the block is valid and ready to process, then
-    /// schedules the work of applying chunks in rayon thread pool. The function will return before
+    /// schedules the work of applying chunks in the apply chunks executor. The function will return before
     /// the block processing is finished.
     /// This function is used in conjunction with the function postprocess_ready_blocks, which checks
     /// if any of the blocks in processing has finished applying chunks to finish postprocessing
@@ -1556,7 +1742,7 @@ impl Chain {
     /// `block_processing_artifacts`: Callers can pass an empty object or an existing BlockProcessingArtifact.
     ///              This function will add the effect from processing this block to there.
     /// `apply_chunks_done_callback`: This callback will be called after apply_chunks are finished
-    ///              (so it also happens asynchronously in the rayon thread pool). Callers can
+    ///              (so it also happens asynchronously in the apply chunks executor). Callers can
     ///              use this callback as a way to receive notifications when apply chunks are done
     ///              so it can call postprocess_ready_blocks.
     pub fn start_process_block_async(
@@ -1605,7 +1791,10 @@ impl Chain {
     ) -> (Vec<AcceptedBlock>, HashMap<CryptoHash, Error>) {
         let mut accepted_blocks = vec![];
         let mut errors = HashMap::new();
-        while let Ok((block_hash, apply_result)) = self.apply_chunks_receiver.try_recv() {
+        while let Ok((block_hash, apply_result, result_timer)) =
+            self.apply_chunks_receiver.try_recv()
+        {
+            result_timer.observe_duration();
             match self.postprocess_block(
                 me,
                 block_hash,
@@ -1933,6 +2122,9 @@ impl Chain {
                         });
                         let time = Clock::instant();
                         self.blocks_delay_tracker.mark_block_has_missing_chunks(block.hash(), time);
+                        if let Err(err) = self.apply_chunks_optimistically(me, &block) {
+                            debug!(target: "chain", %block_hash, ?err, "Failed to start applying chunks optimistically");
+                        }
                         let orphan = Orphan { block, provenance, added: time };
                         self.blocks_with_missing_chunks
                             .add_block_with_missing_chunks(orphan, missing_chunk_hashes.clone());
@@ -1967,7 +2159,7 @@ impl Chain {
         let apply_chunks_done_marker = block_preprocess_info.apply_chunks_done.clone();
         self.blocks_in_processing.add(block, block_preprocess_info)?;
 
-        // 2) schedule apply chunks, which will be executed in the rayon thread pool.
+        // 2) schedule apply chunks, which will be executed in the apply chunks executor.
         self.schedule_apply_chunks(
             block_hash,
             block_height,
@@ -1979,7 +2171,7 @@ impl Chain {
         Ok(())
     }
 
-    /// Applying chunks async by starting the work at the rayon thread pool
+    /// Applying chunks async by starting the work at the apply chunks executor
     /// `apply_chunks_done_marker`: a marker that will be set to true once applying chunks is finished
     /// `apply_chunks_done_callback`: a callback that will be called once applying chunks is finished
     fn schedule_apply_chunks(
@@ -1991,25 +2183,35 @@ impl Chain {
         apply_chunks_done_callback: DoneApplyChunkCallback,
     ) {
         let sc = self.apply_chunks_sender.clone();
-        spawn(move || {
+        self.apply_chunks_executor.spawn(move || {
             // do_apply_chunks runs `work` parallelly, but still waits for all of them to finish
             let res = do_apply_chunks(block_hash, block_height, work);
+            let result_timer = metrics::APPLY_CHUNKS_RESULT_DELAY.start_timer();
             // If we encounter error here, that means the receiver is deallocated and the client
             // thread is already shut down. The node is already crashed, so we can unwrap here
-            sc.send((block_hash.clone(), res)).unwrap();
+            sc.send((block_hash.clone(), res, result_timer)).unwrap();
             if let Err(_) = apply_chunks_done_marker.set(()) {
                 // This should never happen, if it does, it means there is a bug in our code.
                 log_assert!(false, "apply chunks are called twice for block {block_hash:?}");
             }
             apply_chunks_done_callback(block_hash);
         });
+    }
 
-        /// `rayon::spawn` decorated to propagate `tracing` context across
-        /// threads.
-        fn spawn(f: impl FnOnce() + Send + 'static) {
-            let dispatcher = tracing::dispatcher::get_default(|it| it.clone());
-            rayon::spawn(move || tracing::dispatcher::with_default(&dispatcher, f))
-        }
+    /// Validates the body of an orphan block on the apply chunks executor, so
+    /// that the validation doesn't have to be done once the block leaves the
+    /// orphan pool.  Failures are ignored and reported when the block is
+    /// processed, as the epoch manager may not know the previous block yet.
+    fn schedule_block_prevalidation(&self, block: &Block) {
+        let block = block.clone();
+        let runtime_adapter = self.runtime_adapter.clone();
+        let genesis = self.genesis.clone();
+        let prevalidated_blocks = self.prevalidated_blocks.clone();
+        self.apply_chunks_executor.spawn(move || {
+            if Chain::validate_block_impl(runtime_adapter.as_ref(), &genesis, &block).is_ok() {
+                prevalidated_blocks.insert(*block.hash());
+            }
+        });
     }
 
     /// Run postprocessing on this block, which stores the block on chain.
@@ -2037,6 +2239,21 @@ impl Chain {
             height = block.header().height())
         .entered();
 
+        for apply_result in apply_results.iter().flatten() {
+            let (shard_uid, apply_duration) = match apply_result {
+                ApplyChunkResult::SameHeight(result) => (result.shard_uid, result.apply_duration),
+                ApplyChunkResult::DifferentHeight(result) => {
+                    (result.shard_uid, result.apply_duration)
+                }
+                ApplyChunkResult::SplitState(_) => continue,
+            };
+            self.blocks_delay_tracker.mark_chunk_applied(
+                &block_hash,
+                shard_uid.shard_id(),
+                apply_duration,
+            );
+        }
+
         let prev_head = self.store.head()?;
         let mut chain_update = self.chain_update();
         let provenance = block_preprocess_info.provenance.clone();
@@ -2067,6 +2284,7 @@ impl Chain {
             }
 
             self.last_time_head_updated = Clock::instant();
+            self.optimistic_apply_cache.prune(tip.height);
         };
 
         metrics::BLOCK_PROCESSED_TOTAL.inc();
@@ -2156,6 +2374,11 @@ impl Chain {
                 return Err(Error::InvalidSignature);
             }
             block.check_validity()?;
+            // If the previous block is having its chunks applied, this block will be processed
+            // as soon as it's done.  Validate the block body in the meantime.
+            if self.blocks_in_processing.contains(block.header().prev_hash()) {
+                self.schedule_block_prevalidation(block);
+            }
             // TODO: enable after #3729 and #3863
             // self.verify_orphan_header_approvals(&block.header())?;
             return Err(Error::Orphan);
@@ -2211,6 +2434,12 @@ impl Chain {
             return Err(Error::InvalidRandomnessBeaconOutput);
         }
 
+        // Chunk headers are bound to the block hash only through the header's
+        // root of them, so check it again before trusting an earlier validation.
+        if self.prevalidated_blocks.take(block.hash()) && block.check_validity().is_ok() {
+            metrics::BLOCK_PREVALIDATED_TOTAL.inc();
+            block.mark_as_valid();
+        }
         let res = block.validate_with(|block| {
             Chain::validate_block_impl(self.runtime_adapter.as_ref(), &self.genesis, block)
                 .map(|_| true)
@@ -2421,6 +2650,18 @@ impl Chain {
                 }
             }
         }
+        // Chunks received since the blocks still waiting for chunks were last checked may be
+        // enough to apply some of their shards.
+        let waiting_blocks: Vec<_> = self
+            .blocks_with_missing_chunks
+            .blocks_waiting_for_chunks()
+            .map(|orphan| orphan.block.clone())
+            .collect();
+        for block in waiting_blocks {
+            if let Err(err) = self.apply_chunks_optimistically(me, &block) {
+                debug!(target: "chain", block_hash = %block.hash(), ?err, "Failed to start applying chunks optimistically");
+            }
+        }
     }
 
     /// Check for orphans that are ready to be processed or request missing chunks, process these blocks.
@@ -3400,6 +3641,155 @@ impl Chain {
             .collect()
     }
 
+    /// Starts applying the chunks of a block which is waiting for other chunks.
+    ///
+    /// A chunk is applied if the block has all partial chunks, which contain the incoming
+    /// receipts of the shard, and the chunk itself is available.  The results are kept in
+    /// `optimistic_apply_cache` and used by `apply_chunks_preprocessing` once the block is
+    /// processed.  Chunks of blocks at the end of an epoch where the shard layout changes are
+    /// never applied optimistically because their states also have to be split.
+    fn apply_chunks_optimistically(
+        &self,
+        me: &Option<AccountId>,
+        block: &Block,
+    ) -> Result<(), Error> {
+        let prev_hash = block.header().prev_hash();
+        let prev_block = match self.get_block(prev_hash) {
+            Ok(prev_block) => prev_block,
+            Err(_) => return Ok(()),
+        };
+        if self.runtime_adapter.will_shard_layout_change_next_epoch(prev_hash)? {
+            return Ok(());
+        }
+        let height = block.header().height();
+        for chunk_header in block.chunks().iter() {
+            if chunk_header.height_included() == height
+                && self.store.get_partial_chunk(&chunk_header.chunk_hash()).is_err()
+            {
+                return Ok(());
+            }
+        }
+        let prev_chunk_headers =
+            Chain::get_prev_chunk_headers(&*self.runtime_adapter, &prev_block)?;
+        // The block is checked again every time one of its chunks arrives, so skip the shards
+        // which were already applied before doing any expensive work.
+        let chunk_headers = block.chunks();
+        let mut shards_to_apply = vec![];
+        for (shard_id, (chunk_header, prev_chunk_header)) in
+            chunk_headers.iter().zip(prev_chunk_headers.iter()).enumerate()
+        {
+            let shard_id = shard_id as ShardId;
+            if chunk_header.height_included() != height
+                || !self.runtime_adapter.cares_about_shard(me.as_ref(), prev_hash, shard_id, true)
+            {
+                continue;
+            }
+            let key = OptimisticApplyKey {
+                prev_state_root: chunk_header.prev_state_root(),
+                chunk_hash: chunk_header.chunk_hash(),
+            };
+            if self.optimistic_apply_cache.contains(&key) {
+                continue;
+            }
+            shards_to_apply.push((shard_id, chunk_header, prev_chunk_header, key));
+        }
+        if shards_to_apply.is_empty() {
+            return Ok(());
+        }
+
+        let incoming_receipts = self.collect_incoming_receipts_from_block(me, block)?;
+        for (shard_id, chunk_header, prev_chunk_header, key) in shards_to_apply {
+            let receipt_proofs = match incoming_receipts.get(&shard_id) {
+                Some(receipt_proofs) => receipt_proofs,
+                None => continue,
+            };
+            let chunk = match self.get_chunk_clone_from_header(chunk_header) {
+                Ok(chunk) => chunk,
+                Err(_) => continue,
+            };
+            let chunk_inner = chunk.cloned_header().take_inner();
+            if !self.optimistic_apply_cache.start(&key) {
+                continue;
+            }
+
+            // Inputs must be exactly the same as in `apply_chunks_preprocessing`, otherwise the
+            // result can't be reused.
+            let mut receipts = collect_receipts(receipt_proofs);
+            let receipts_from_prev_blocks = self.store().get_incoming_receipts_for_shard(
+                shard_id,
+                *prev_hash,
+                prev_chunk_header.height_included(),
+            );
+            let is_first_block_with_chunk_of_version =
+                check_if_block_is_first_with_chunk_of_version(
+                    self.store(),
+                    self.runtime_adapter.as_ref(),
+                    prev_block.hash(),
+                    shard_id,
+                );
+            let (receipts_from_prev_blocks, is_first_block_with_chunk_of_version) =
+                match (receipts_from_prev_blocks, is_first_block_with_chunk_of_version) {
+                    (Ok(receipts), Ok(is_first)) => (receipts, is_first),
+                    (Err(err), _) | (_, Err(err)) => {
+                        self.optimistic_apply_cache.finish(key, *block.hash(), height, None);
+                        return Err(err);
+                    }
+                };
+            receipts.extend(collect_receipts_from_response(&receipts_from_prev_blocks));
+
+            let runtime_adapter = self.runtime_adapter.clone();
+            let cache = self.optimistic_apply_cache.clone();
+            let block_hash = *block.hash();
+            let challenges_result = block.header().challenges_result().clone();
+            let block_timestamp = block.header().raw_timestamp();
+            let gas_price = prev_block.header().gas_price();
+            let random_seed = *block.header().random_value();
+            let prev_block_hash = *chunk_header.prev_block_hash();
+            self.apply_chunks_executor.spawn(move || {
+                let _span = tracing::debug_span!(
+                    target: "chain",
+                    "apply_chunk_optimistically",
+                    shard_id,
+                    height)
+                .entered();
+                // The key must be released even if applying the chunk panics, otherwise the
+                // chunk is never applied optimistically again.
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    runtime_adapter.apply_transactions(
+                        shard_id,
+                        chunk_inner.prev_state_root(),
+                        height,
+                        block_timestamp,
+                        &prev_block_hash,
+                        &block_hash,
+                        &receipts,
+                        chunk.transactions(),
+                        chunk_inner.validator_proposals(),
+                        gas_price,
+                        chunk_inner.gas_limit(),
+                        &challenges_result,
+                        random_seed,
+                        true,
+                        is_first_block_with_chunk_of_version,
+                        None,
+                    )
+                }));
+                let result = match result {
+                    Ok(result) => result,
+                    Err(panic) => {
+                        cache.finish(key, block_hash, height, None);
+                        std::panic::resume_unwind(panic);
+                    }
+                };
+                if let Err(err) = &result {
+                    debug!(target: "chain", %block_hash, shard_id, ?err, "Failed to apply chunk optimistically");
+                }
+                cache.finish(key, block_hash, height, result.ok());
+            });
+        }
+        Ok(())
+    }
+
     /// Creates jobs that would apply chunks
     fn apply_chunks_preprocessing(
         &self,
@@ -3581,6 +3971,11 @@ impl Chain {
                     let random_seed = *block.header().random_value();
                     let height = chunk_header.height_included();
                     let prev_block_hash = chunk_header.prev_block_hash().clone();
+                    let optimistic_apply_cache = self.optimistic_apply_cache.clone();
+                    let optimistic_apply_key = OptimisticApplyKey {
+                        prev_state_root: *chunk_inner.prev_state_root(),
+                        chunk_hash: chunk_header.chunk_hash(),
+                    };
 
                     result.push(Box::new(move |parent_span| -> Result<ApplyChunkResult, Error> {
                         let _span = tracing::debug_span!(
@@ -3590,25 +3985,39 @@ impl Chain {
                             shard_id)
                         .entered();
                         let _timer = CryptoHashTimer::new(chunk.chunk_hash().0);
-                        match runtime_adapter.apply_transactions(
-                            shard_id,
-                            chunk_inner.prev_state_root(),
-                            height,
-                            block_timestamp,
-                            &prev_block_hash,
-                            &block_hash,
-                            &receipts,
-                            chunk.transactions(),
-                            chunk_inner.validator_proposals(),
-                            gas_price,
-                            gas_limit,
-                            &challenges_result,
-                            random_seed,
-                            true,
-                            is_first_block_with_chunk_of_version,
-                            state_patch,
-                        ) {
+                        let apply_start = Clock::instant();
+                        // The chunk may have been applied while the block was waiting for other
+                        // chunks.  Patching the state is never done optimistically.
+                        let optimistic_apply_result = if state_patch.is_none() {
+                            optimistic_apply_cache.take(&optimistic_apply_key, &block_hash)
+                        } else {
+                            None
+                        };
+                        let apply_result = match optimistic_apply_result {
+                            Some(apply_result) => Ok(apply_result),
+                            None => runtime_adapter.apply_transactions(
+                                shard_id,
+                                chunk_inner.prev_state_root(),
+                                height,
+                                block_timestamp,
+                                &prev_block_hash,
+                                &block_hash,
+                                &receipts,
+                                chunk.transactions(),
+                                chunk_inner.validator_proposals(),
+                                gas_price,
+                                gas_limit,
+                                &challenges_result,
+                                random_seed,
+                                true,
+                                is_first_block_with_chunk_of_version,
+                                state_patch,
+                            ),
+                        };
+                        match apply_result {
                             Ok(apply_result) => {
+                                let apply_duration =
+                                    Clock::instant().saturating_duration_since(apply_start);
                                 let apply_split_result_or_state_changes =
                                     if will_shard_layout_change {
                                         Some(ChainUpdate::apply_split_state_changes(
@@ -3626,6 +4035,7 @@ impl Chain {
                                     shard_uid,
                                     apply_result,
                                     apply_split_result_or_state_changes,
+                                    apply_duration,
                                 }))
                             }
                             Err(err) => Err(err),
@@ -3650,6 +4060,7 @@ impl Chain {
                             "existing_chunk",
                             shard_id)
                         .entered();
+                        let apply_start = Clock::instant();
                         match runtime_adapter.apply_transactions(
                             shard_id,
                             new_extra.state_root(),
@@ -3669,6 +4080,8 @@ impl Chain {
                             state_patch,
                         ) {
                             Ok(apply_result) => {
+                                let apply_duration =
+                                    Clock::instant().saturating_duration_since(apply_start);
                                 let apply_split_result_or_state_changes =
                                     if will_shard_layout_change {
                                         Some(ChainUpdate::apply_split_state_changes(
@@ -3685,6 +4098,7 @@ impl Chain {
                                     shard_uid,
                                     apply_result,
                                     apply_split_result_or_state_changes,
+                                    apply_duration,
                                 }))
                             }
                             Err(err) => Err(err),
@@ -4094,6 +4508,21 @@ impl Chain {
         &self.store
     }
 
+    /// Returns the state of the results of optimistically applied chunks.
+    pub fn optimistic_apply_stats(&self) -> OptimisticApplyStats {
+        self.optimistic_apply_cache.stats()
+    }
+
+    /// Returns the thread pool the chunks of blocks are applied on.
+    pub fn apply_chunks_executor(&self) -> &ApplyChunksExecutor {
+        &self.apply_chunks_executor
+    }
+
+    /// Sets the thread pool the chunks of blocks are applied on.
+    pub fn set_apply_chunks_executor(&mut self, apply_chunks_executor: ApplyChunksExecutor) {
+        self.apply_chunks_executor = apply_chunks_executor;
+    }
+
     /// Returns mutable ChainStore.
     #[inline]
     pub fn mut_store(&mut self) -> &mut ChainStore {
@@ -4344,12 +4773,16 @@ pub struct SameHeightResult {
     gas_limit: Gas,
     apply_result: ApplyTransactionResult,
     apply_split_result_or_state_changes: Option<ApplySplitStateResultOrStateChanges>,
+    // How long applying the transactions and receipts took.
+    apply_duration: TimeDuration,
 }
 
 pub struct DifferentHeightResult {
     shard_uid: ShardUId,
     apply_result: ApplyTransactionResult,
     apply_split_result_or_state_changes: Option<ApplySplitStateResultOrStateChanges>,
+    // How long applying the receipts took.
+    apply_duration: TimeDuration,
 }
 
 pub struct SplitStateResult {
@@ -4608,6 +5041,7 @@ impl<'a> ChainUpdate<'a> {
                 shard_uid,
                 apply_result,
                 apply_split_result_or_state_changes,
+                apply_duration: _,
             }) => {
                 let (outcome_root, outcome_paths) =
                     ApplyTransactionResult::compute_outcomes_proof(&apply_result.outcomes);
@@ -4652,6 +5086,7 @@ impl<'a> ChainUpdate<'a> {
                 shard_uid,
                 apply_result,
                 apply_split_result_or_state_changes,
+                apply_duration: _,
             }) => {
                 let old_extra =
                     self.chain_store_update.get_chunk_extra(&prev_block_hash, &shard_uid)?;
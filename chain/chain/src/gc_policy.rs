@@ -0,0 +1,365 @@
+//! Retention policies of individual columns.
+//!
+//! By default garbage collection removes data of a block from all columns at
+//! once.  A [`ColumnGCPolicy`] lets a node keep data of some columns for longer,
+//! e.g. an RPC node may keep transaction outcomes for a month while dropping
+//! state history after a few epochs.  When a block is garbage collected, keys of
+//! its data in such columns are recorded in [`DBCol::DeferredGC`] instead of
+//! being deleted and [`crate::Chain::clear_data`] removes them once they fall out
+//! of the column’s retention.
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use strum::IntoEnumIterator;
+
+use near_chain_configs::{ColumnGCPolicy, GCConfig};
+use near_primitives::block::BlockHeader;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use near_store::DBCol;
+
+use crate::store::{ChainStore, ChainStoreAccess};
+use crate::{Error, RuntimeAdapter};
+
+/// Columns whose data may be kept after the block it belongs to is garbage
+/// collected.
+///
+/// Garbage collection of other columns doesn’t depend on data of these
+/// columns, so it can outlive the rest of the block.  Their references to the
+/// block and its chunks are dangling afterwards; the store validator skips
+/// such checks for data recorded in [`DBCol::DeferredGC`].  Data of all other
+/// columns (e.g. State or Block) must be removed together with the block.
+pub const RETAINABLE_COLUMNS: [DBCol; 3] =
+    [DBCol::TransactionResult, DBCol::OutcomeIds, DBCol::Receipts];
+
+/// How long data of a column is kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Retention {
+    /// Data is kept for the given number of epochs.
+    Epochs(u64),
+    /// Data is kept until it is as old as the given duration.
+    MaxAge(Duration),
+}
+
+/// Validated column retention policies from [`GCConfig::column_policies`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColumnGCPolicies {
+    policies: Vec<(DBCol, Retention)>,
+}
+
+impl ColumnGCPolicies {
+    pub fn from_config(config: &GCConfig) -> Result<Self, Error> {
+        let mut policies: Vec<(DBCol, Retention)> = Vec::new();
+        for policy in &config.column_policies {
+            let (col, retention) =
+                parse_policy(policy, config.gc_num_epochs_to_keep()).map_err(|msg| {
+                    Error::GCError(format!("invalid policy for column {}: {msg}", policy.column))
+                })?;
+            if policies.iter().any(|(other, _)| *other == col) {
+                return Err(Error::GCError(format!(
+                    "column {} has more than one policy",
+                    policy.column
+                )));
+            }
+            policies.push((col, retention));
+        }
+        Ok(Self { policies })
+    }
+
+    pub fn get(&self, col: DBCol) -> Option<Retention> {
+        self.policies.iter().find(|(other, _)| *other == col).map(|(_, retention)| *retention)
+    }
+
+    pub fn is_retained(&self, col: DBCol) -> bool {
+        self.get(col).is_some()
+    }
+}
+
+fn parse_policy(
+    policy: &ColumnGCPolicy,
+    gc_num_epochs_to_keep: u64,
+) -> Result<(DBCol, Retention), String> {
+    let col = DBCol::iter()
+        .find(|col| col.variant_name() == policy.column)
+        .ok_or_else(|| "no such column".to_string())?;
+    if !RETAINABLE_COLUMNS.contains(&col) {
+        return Err(format!(
+            "data can be kept longer than the block it belongs to only in {:?}",
+            RETAINABLE_COLUMNS
+        ));
+    }
+    let retention = match (policy.num_epochs_to_keep, policy.max_age) {
+        (Some(num_epochs), None) => {
+            if num_epochs < gc_num_epochs_to_keep {
+                return Err(format!(
+                    "num_epochs_to_keep {num_epochs} is lower than \
+                     gc_num_epochs_to_keep {gc_num_epochs_to_keep}"
+                ));
+            }
+            Retention::Epochs(num_epochs)
+        }
+        (None, Some(max_age)) => Retention::MaxAge(max_age),
+        _ => return Err("exactly one of num_epochs_to_keep and max_age must be set".to_string()),
+    };
+    Ok((col, retention))
+}
+
+/// Value of [`DBCol::DeferredGC`]: data of a garbage collected block or chunk
+/// which is still kept in one of the [`RETAINABLE_COLUMNS`].
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DeferredGCEntry {
+    /// Timestamp of the block the data belongs to, in nanoseconds.
+    pub timestamp: u64,
+    /// Keys of the data in the column.
+    pub keys: Vec<Vec<u8>>,
+}
+
+pub fn get_deferred_gc_key(col: DBCol, height: BlockHeight, hash: &CryptoHash) -> Vec<u8> {
+    let mut res = Vec::with_capacity(41);
+    res.push(col as u8);
+    res.extend_from_slice(&height.to_be_bytes());
+    res.extend_from_slice(hash.as_ref());
+    res
+}
+
+pub fn get_deferred_gc_key_rev(key: &[u8]) -> io::Result<(BlockHeight, CryptoHash)> {
+    if key.len() != 41 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("wrong DeferredGC key length: {key:?}"),
+        ));
+    }
+    let height = BlockHeight::from_be_bytes(key[1..9].try_into().unwrap());
+    let hash = CryptoHash::try_from(&key[9..]).unwrap();
+    Ok((height, hash))
+}
+
+/// Which data of a column has fallen out of its retention.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetentionCutoff {
+    /// All data is expired, i.e. the column has no policy anymore.
+    Everything,
+    /// Data of blocks at or below the height is expired.
+    Height(BlockHeight),
+    /// Data of blocks with timestamp at or below the one given (in
+    /// nanoseconds) is expired.
+    Timestamp(u64),
+    /// Chain isn’t long enough for any data to expire.
+    Nothing,
+}
+
+impl RetentionCutoff {
+    pub fn is_expired(&self, height: BlockHeight, entry: &DeferredGCEntry) -> bool {
+        match *self {
+            RetentionCutoff::Everything => true,
+            RetentionCutoff::Height(cutoff) => height <= cutoff,
+            RetentionCutoff::Timestamp(cutoff) => entry.timestamp <= cutoff,
+            RetentionCutoff::Nothing => false,
+        }
+    }
+}
+
+/// Returns which data kept with given retention has expired when the chain head
+/// is at `head_header`.
+pub(crate) fn get_retention_cutoff(
+    chain_store: &ChainStore,
+    head_header: &BlockHeader,
+    retention: Option<Retention>,
+) -> Result<RetentionCutoff, Error> {
+    let num_epochs = match retention {
+        None => return Ok(RetentionCutoff::Everything),
+        Some(Retention::MaxAge(max_age)) => {
+            let max_age = max_age.as_nanos().try_into().unwrap_or(u64::MAX);
+            return Ok(match head_header.raw_timestamp().checked_sub(max_age) {
+                Some(cutoff) => RetentionCutoff::Timestamp(cutoff),
+                None => RetentionCutoff::Nothing,
+            });
+        }
+        Some(Retention::Epochs(num_epochs)) => num_epochs,
+    };
+    // Id of an epoch is the hash of the last block of the epoch two epochs
+    // before it, so the id of the epoch after a block’s one is the hash of the
+    // last block of the epoch before it.  Block headers are never garbage
+    // collected so they link epochs to their predecessors even after the
+    // blocks are gone.
+    let mut epoch_id = head_header.next_epoch_id().clone();
+    for i in 1..=num_epochs {
+        // Last block of the epoch `i` epochs before the head one.
+        let header = match chain_store.get_block_header(&epoch_id.0) {
+            Ok(header) => header,
+            // The chain doesn’t have that many epochs yet.
+            Err(Error::DBNotFoundErr(_)) => return Ok(RetentionCutoff::Nothing),
+            Err(err) => return Err(err),
+        };
+        if i == num_epochs {
+            return Ok(RetentionCutoff::Height(header.height()));
+        }
+        epoch_id = header.next_epoch_id().clone();
+    }
+    Ok(RetentionCutoff::Nothing)
+}
+
+/// Number of garbage collected blocks or chunks whose data is kept in a column
+/// and number of keys of that data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeferredGCCount {
+    pub num_entries: u64,
+    pub num_keys: u64,
+}
+
+impl DeferredGCCount {
+    pub fn add(&mut self, entry: &DeferredGCEntry) {
+        self.num_entries += 1;
+        self.num_keys += entry.keys.len() as u64;
+    }
+}
+
+/// Data of a column kept by its retention policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColumnGCReport {
+    pub col: DBCol,
+    pub retention: Option<Retention>,
+    /// Data which garbage collection would remove now.
+    pub expired: DeferredGCCount,
+    /// Data which is still within the retention.
+    pub retained: DeferredGCCount,
+}
+
+/// What garbage collection would remove, see [`gc_dry_run`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GCReport {
+    pub tail: BlockHeight,
+    pub gc_stop_height: BlockHeight,
+    pub columns: Vec<ColumnGCReport>,
+}
+
+impl fmt::Display for GCReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.tail + 1 < self.gc_stop_height {
+            writeln!(
+                f,
+                "Blocks at heights {}..{} would be garbage collected",
+                self.tail + 1,
+                self.gc_stop_height
+            )?;
+        } else {
+            writeln!(f, "No blocks would be garbage collected")?;
+        }
+        for report in &self.columns {
+            let retention = match report.retention {
+                Some(Retention::Epochs(num_epochs)) => format!("kept for {num_epochs} epochs"),
+                Some(Retention::MaxAge(max_age)) => format!("kept for {max_age:?}"),
+                None => "no policy".to_string(),
+            };
+            writeln!(
+                f,
+                "{} ({retention}): {} keys of {} garbage collected blocks or chunks would be \
+                 removed, {} keys of {} are retained",
+                report.col,
+                report.expired.num_keys,
+                report.expired.num_entries,
+                report.retained.num_keys,
+                report.retained.num_entries,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reports what [`crate::Chain::clear_data`] with given config would remove,
+/// without modifying the store.
+pub fn gc_dry_run(
+    chain_store: &ChainStore,
+    runtime_adapter: &dyn RuntimeAdapter,
+    gc_config: &GCConfig,
+) -> Result<GCReport, Error> {
+    let policies = ColumnGCPolicies::from_config(gc_config)?;
+    let head = chain_store.head()?;
+    let head_header = chain_store.get_block_header(&head.last_block_hash)?;
+    let mut columns = vec![];
+    for col in RETAINABLE_COLUMNS {
+        let retention = policies.get(col);
+        let cutoff = get_retention_cutoff(chain_store, &head_header, retention)?;
+        let mut report = ColumnGCReport {
+            col,
+            retention,
+            expired: Default::default(),
+            retained: Default::default(),
+        };
+        let prefix = [col as u8];
+        for item in
+            chain_store.store().iter_prefix_ser::<DeferredGCEntry>(DBCol::DeferredGC, &prefix)
+        {
+            let (key, entry) = item?;
+            let (height, _) = get_deferred_gc_key_rev(&key)?;
+            if cutoff.is_expired(height, &entry) {
+                report.expired.add(&entry);
+            } else {
+                report.retained.add(&entry);
+            }
+        }
+        columns.push(report);
+    }
+    Ok(GCReport {
+        tail: chain_store.tail()?,
+        gc_stop_height: runtime_adapter.get_gc_stop_height(&head.last_block_hash),
+        columns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(column: &str, num_epochs_to_keep: Option<u64>, max_age: Option<u64>) -> GCConfig {
+        GCConfig {
+            column_policies: vec![ColumnGCPolicy {
+                column: column.to_string(),
+                num_epochs_to_keep,
+                max_age: max_age.map(Duration::from_secs),
+            }],
+            ..GCConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_column_gc_policies_validation() {
+        let policies =
+            ColumnGCPolicies::from_config(&policy("TransactionResult", Some(10), None)).unwrap();
+        assert_eq!(policies.get(DBCol::TransactionResult), Some(Retention::Epochs(10)));
+        assert!(!policies.is_retained(DBCol::OutcomeIds));
+        let policies =
+            ColumnGCPolicies::from_config(&policy("Receipts", None, Some(3600))).unwrap();
+        assert_eq!(
+            policies.get(DBCol::Receipts),
+            Some(Retention::MaxAge(Duration::from_secs(3600)))
+        );
+
+        // Unknown column.
+        assert!(ColumnGCPolicies::from_config(&policy("Foo", Some(10), None)).is_err());
+        // State can't outlive the block.
+        assert!(ColumnGCPolicies::from_config(&policy("State", Some(10), None)).is_err());
+        // Shorter than the default retention.
+        assert!(ColumnGCPolicies::from_config(&policy("OutcomeIds", Some(2), None)).is_err());
+        // Retention must be given exactly once.
+        assert!(ColumnGCPolicies::from_config(&policy("OutcomeIds", None, None)).is_err());
+        assert!(ColumnGCPolicies::from_config(&policy("OutcomeIds", Some(10), Some(10))).is_err());
+
+        let mut config = policy("OutcomeIds", Some(10), None);
+        config.column_policies.push(config.column_policies[0].clone());
+        assert!(ColumnGCPolicies::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_deferred_gc_key() {
+        let hash = CryptoHash::hash_bytes(b"block");
+        let key = get_deferred_gc_key(DBCol::Receipts, 42, &hash);
+        assert_eq!(key[0], DBCol::Receipts as u8);
+        assert_eq!(get_deferred_gc_key_rev(&key).unwrap(), (42, hash));
+        // Keys of a column are ordered by height.
+        assert!(key < get_deferred_gc_key(DBCol::Receipts, 256, &CryptoHash::default()));
+        assert!(get_deferred_gc_key_rev(&key[1..]).is_err());
+    }
+}
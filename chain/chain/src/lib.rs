@@ -1,9 +1,15 @@
-pub use block_processing_utils::{BlockProcessingArtifact, DoneApplyChunkCallback};
+pub use block_processing_utils::{
+    ApplyChunksExecutor, BlockProcessingArtifact, DoneApplyChunkCallback,
+};
 pub use chain::{check_known, collect_receipts, Chain, MAX_ORPHAN_SIZE};
 pub use doomslug::{Doomslug, DoomslugBlockProductionReadiness, DoomslugThresholdMode};
-pub use lightclient::{create_light_client_block_view, get_epoch_block_producers_view};
+pub use lightclient::{
+    create_light_client_block_view, get_epoch_block_producers_view, light_client_block_hash,
+    validate_light_client_block,
+};
 pub use near_chain_primitives::{self, Error};
 pub use near_primitives::receipt::ReceiptResult;
+pub use optimistic_apply::OptimisticApplyStats;
 pub use store::{ChainStore, ChainStoreAccess, ChainStoreUpdate};
 pub use store_validator::{ErrorMessage, StoreValidator};
 pub use types::{Block, BlockHeader, BlockStatus, ChainGenesis, Provenance, RuntimeAdapter};
@@ -13,10 +19,12 @@ pub mod blocks_delay_tracker;
 pub mod chain;
 pub mod crypto_hash_timer;
 mod doomslug;
+pub mod gc_policy;
 mod lightclient;
 mod metrics;
 pub mod migrations;
 pub mod missing_chunks;
+mod optimistic_apply;
 mod store;
 pub mod store_validator;
 pub mod test_utils;
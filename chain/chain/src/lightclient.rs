@@ -1,11 +1,15 @@
+use borsh::BorshSerialize;
 use near_chain_primitives::Error;
-use near_primitives::block::BlockHeader;
+use near_primitives::block::{Approval, ApprovalInner, BlockHeader};
+use near_primitives::block_header::BlockHeaderInnerLite;
 use near_primitives::hash::{hash, CryptoHash};
-use near_primitives::types::EpochId;
+use near_primitives::merkle::combine_hash;
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{Balance, BlockHeight, EpochId};
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{BlockHeaderInnerLiteView, LightClientBlockView};
 
-use crate::{ChainStoreAccess, RuntimeAdapter};
+use crate::{Chain, ChainStoreAccess, RuntimeAdapter};
 
 pub fn get_epoch_block_producers_view(
     epoch_id: &EpochId,
@@ -70,3 +74,76 @@ pub fn create_light_client_block_view(
         approvals_after_next,
     })
 }
+
+/// Computes the hash of the block a `LightClientBlock` was created for.
+pub fn light_client_block_hash(light_client_block: &LightClientBlockView) -> CryptoHash {
+    let inner_lite = BlockHeaderInnerLite::from(light_client_block.inner_lite.clone());
+    let inner_lite_hash = hash(&inner_lite.try_to_vec().expect("Failed to serialize"));
+    let inner_hash = combine_hash(&inner_lite_hash, &light_client_block.inner_rest_hash);
+    combine_hash(&inner_hash, &light_client_block.prev_block_hash)
+}
+
+/// Validates a `LightClientBlock` of epoch `epoch_id` the same way light clients do and returns
+/// the block producers of the next epoch it commits to.
+///
+/// # Arguments
+///  * `light_client_block` - the block to validate, it must contain `next_bps`
+///  * `epoch_id` - the epoch the block is expected to belong to
+///  * `last_known_height` - height of the last light client block validated before this one
+///  * `block_producers` - the ordered list of block producers of `epoch_id`, more than 2/3 of
+///                   their stake must have approved the block
+pub fn validate_light_client_block(
+    light_client_block: &LightClientBlockView,
+    epoch_id: &EpochId,
+    last_known_height: BlockHeight,
+    block_producers: &[ValidatorStake],
+) -> Result<Vec<ValidatorStake>, Error> {
+    let inner_lite = &light_client_block.inner_lite;
+    if inner_lite.height <= last_known_height {
+        return Err(Error::InvalidBlockHeight(inner_lite.height));
+    }
+    if inner_lite.epoch_id != epoch_id.0 {
+        return Err(Error::InvalidEpochHash);
+    }
+    let next_block_producers: Vec<ValidatorStake> = match &light_client_block.next_bps {
+        Some(next_bps) => next_bps.iter().cloned().map(|bp| bp.into_validator_stake()).collect(),
+        None => return Err(Error::InvalidNextBPHash),
+    };
+
+    // The approvals of the block after next endorse the next block, and there are no skips
+    // between the three blocks.
+    let block_hash = light_client_block_hash(light_client_block);
+    let next_block_hash = combine_hash(&light_client_block.next_block_inner_hash, &block_hash);
+    let approval_message = Approval::get_data_for_sig(
+        &ApprovalInner::Endorsement(next_block_hash),
+        inner_lite.height + 2,
+    );
+    // Every block producer of the epoch must have an approval slot. There may be more slots,
+    // for the block producers of the next epoch which approve blocks at the end of this one.
+    if light_client_block.approvals_after_next.len() < block_producers.len() {
+        return Err(Error::InvalidApprovals);
+    }
+    let total_stake: Balance = block_producers.iter().map(|bp| bp.stake()).sum();
+    let mut approved_stake: Balance = 0;
+    for (approval, bp) in light_client_block.approvals_after_next.iter().zip(block_producers) {
+        if let Some(signature) = approval {
+            if !signature.verify(&approval_message, bp.public_key()) {
+                return Err(Error::InvalidApprovals);
+            }
+            approved_stake += bp.stake();
+        }
+    }
+    if approved_stake <= total_stake * 2 / 3 {
+        return Err(Error::NotEnoughApprovals);
+    }
+
+    // Blocks produced before `BlockHeaderV3` commit to the V1 encoding of block producers.
+    let next_bp_hash = Chain::compute_collection_hash(next_block_producers.clone())?;
+    let next_bp_hash_v1 = Chain::compute_collection_hash(
+        next_block_producers.iter().cloned().map(|bp| bp.into_v1()).collect::<Vec<_>>(),
+    )?;
+    if inner_lite.next_bp_hash != next_bp_hash && inner_lite.next_bp_hash != next_bp_hash_v1 {
+        return Err(Error::InvalidNextBPHash);
+    }
+    Ok(next_block_producers)
+}
@@ -100,3 +100,45 @@ pub static BLOCK_MISSING_CHUNKS_DELAY: Lazy<Histogram> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static APPLY_CHUNKS_QUEUE_DELAY: Lazy<Histogram> = Lazy::new(|| {
+    try_create_histogram(
+        "near_apply_chunks_queue_delay",
+        "Time the chunks of a block wait for a free thread of the apply chunks executor",
+    )
+    .unwrap()
+});
+pub static APPLY_CHUNKS_RESULT_DELAY: Lazy<Histogram> = Lazy::new(|| {
+    try_create_histogram(
+        "near_apply_chunks_result_delay",
+        "Time between the chunks of a block being applied and the client actor starting to postprocess the block, i.e. how long the block is stalled by the client actor",
+    )
+    .unwrap()
+});
+pub static BLOCK_PREVALIDATED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_block_prevalidated_total",
+        "Number of blocks validated while the chunks of their previous block were being applied",
+    )
+    .unwrap()
+});
+pub static OPTIMISTIC_APPLY_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_optimistic_apply_total",
+        "Number of chunks applied while their block was waiting for other chunks",
+    )
+    .unwrap()
+});
+pub static OPTIMISTIC_APPLY_USED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_optimistic_apply_used_total",
+        "Number of optimistically applied chunks whose result was used by block processing",
+    )
+    .unwrap()
+});
+pub static OPTIMISTIC_APPLY_DISCARDED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_optimistic_apply_discarded_total",
+        "Number of optimistically applied chunks whose result was dropped, e.g. because their block ended up on an abandoned fork",
+    )
+    .unwrap()
+});
@@ -75,6 +75,11 @@ impl<Block: BlockLike> MissingChunksPool<Block> {
         self.blocks_waiting_for_chunks.len()
     }
 
+    /// Blocks which are still missing some of their chunks.
+    pub fn blocks_waiting_for_chunks(&self) -> impl Iterator<Item = &Block> {
+        self.blocks_waiting_for_chunks.values()
+    }
+
     pub fn ready_blocks(&mut self) -> Vec<Block> {
         if self.blocks_ready_to_process.is_empty() {
             return Vec::new();
@@ -0,0 +1,221 @@
+//! Optimistic application of chunks.
+//!
+//! A block which arrives before all of its chunks waits in the missing chunks
+//! pool.  Instead of applying all of its chunks once the last one arrives, every
+//! chunk whose inputs are already available is applied right away on the apply
+//! chunks executor.  The results are kept in an [`OptimisticApplyCache`] and
+//! taken by the regular block processing, which then doesn't have to apply
+//! these chunks again.
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::types::{BlockHeight, StateRoot};
+
+use crate::metrics;
+
+/// Maximum number of chunks which are applied or whose results are kept at the
+/// same time.  Blocks only wait for chunks for a few heights, so this is never
+/// reached unless there are many forks.
+const MAX_OPTIMISTIC_APPLY_RESULTS: usize = 100;
+
+/// Identifies the application of a chunk on top of a state.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct OptimisticApplyKey {
+    pub(crate) prev_state_root: StateRoot,
+    pub(crate) chunk_hash: ChunkHash,
+}
+
+struct CachedResult<R> {
+    /// Block the chunk was applied for.  The result depends on the timestamp,
+    /// random seed and incoming receipts of the block, so it can't be reused
+    /// for another block including the same chunk.
+    block_hash: CryptoHash,
+    height: BlockHeight,
+    result: R,
+}
+
+struct Inner<R> {
+    results: HashMap<OptimisticApplyKey, CachedResult<R>>,
+    in_progress: HashSet<OptimisticApplyKey>,
+    num_used: u64,
+}
+
+/// Snapshot of the state of an [`OptimisticApplyCache`], used by tests.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OptimisticApplyStats {
+    /// Chunks which are being applied.
+    pub num_in_progress: usize,
+    /// Results which weren't taken by the block processing yet.
+    pub num_results: usize,
+    /// Results taken by the block processing since the chain was created.
+    pub num_used: u64,
+}
+
+/// Results of optimistically applied chunks, shared between the client actor
+/// and the apply chunks executor.
+///
+/// The result type is a parameter to make testing easier; the chain uses
+/// [`crate::types::ApplyTransactionResult`].
+pub(crate) struct OptimisticApplyCache<R> {
+    inner: Arc<Mutex<Inner<R>>>,
+}
+
+impl<R> Clone for OptimisticApplyCache<R> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+impl<R> Default for OptimisticApplyCache<R> {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                results: HashMap::new(),
+                in_progress: HashSet::new(),
+                num_used: 0,
+            })),
+        }
+    }
+}
+
+impl<R> OptimisticApplyCache<R> {
+    /// Whether the chunk is being applied or its result is already known.
+    pub(crate) fn contains(&self, key: &OptimisticApplyKey) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.results.contains_key(key) || inner.in_progress.contains(key)
+    }
+
+    /// Marks the chunk as being applied.  Returns false if it's already being
+    /// applied, its result is already known or the cache is full.
+    pub(crate) fn start(&self, key: &OptimisticApplyKey) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.results.contains_key(key)
+            || inner.results.len() + inner.in_progress.len() >= MAX_OPTIMISTIC_APPLY_RESULTS
+        {
+            return false;
+        }
+        inner.in_progress.insert(key.clone())
+    }
+
+    /// Records the result of applying the chunk for the given block.  `None`
+    /// means that applying the chunk failed, in which case the block
+    /// processing applies it again and reports the error.
+    pub(crate) fn finish(
+        &self,
+        key: OptimisticApplyKey,
+        block_hash: CryptoHash,
+        height: BlockHeight,
+        result: Option<R>,
+    ) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.in_progress.remove(&key);
+        if let Some(result) = result {
+            metrics::OPTIMISTIC_APPLY_TOTAL.inc();
+            inner.results.insert(key, CachedResult { block_hash, height, result });
+        }
+    }
+
+    /// Takes the result of applying the chunk for the given block, if it was
+    /// applied optimistically.  A result computed for another block, i.e. on
+    /// another fork, is dropped.
+    pub(crate) fn take(&self, key: &OptimisticApplyKey, block_hash: &CryptoHash) -> Option<R> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.results.remove(key) {
+            Some(cached) if &cached.block_hash == block_hash => {
+                metrics::OPTIMISTIC_APPLY_USED_TOTAL.inc();
+                inner.num_used += 1;
+                Some(cached.result)
+            }
+            Some(_) => {
+                metrics::OPTIMISTIC_APPLY_DISCARDED_TOTAL.inc();
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Drops the results of chunks applied for blocks at or below `height`.
+    /// Such blocks were either already processed or are on abandoned forks.
+    pub(crate) fn prune(&self, height: BlockHeight) {
+        let mut inner = self.inner.lock().unwrap();
+        let num_results = inner.results.len();
+        inner.results.retain(|_, cached| cached.height > height);
+        metrics::OPTIMISTIC_APPLY_DISCARDED_TOTAL
+            .inc_by((num_results - inner.results.len()) as u64);
+    }
+
+    pub(crate) fn stats(&self) -> OptimisticApplyStats {
+        let inner = self.inner.lock().unwrap();
+        OptimisticApplyStats {
+            num_in_progress: inner.in_progress.len(),
+            num_results: inner.results.len(),
+            num_used: inner.num_used,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::hash::hash;
+
+    fn key(chunk: u8) -> OptimisticApplyKey {
+        OptimisticApplyKey { prev_state_root: hash(&[0]), chunk_hash: ChunkHash(hash(&[chunk])) }
+    }
+
+    #[test]
+    fn test_take_result_for_same_block() {
+        let cache = OptimisticApplyCache::default();
+        let block_hash = hash(&[10]);
+        assert!(!cache.contains(&key(1)));
+        assert!(cache.start(&key(1)));
+        // The chunk is already being applied.
+        assert!(cache.contains(&key(1)));
+        assert!(!cache.start(&key(1)));
+        cache.finish(key(1), block_hash, 10, Some("result"));
+        // The result is already known.
+        assert!(cache.contains(&key(1)));
+        assert!(!cache.start(&key(1)));
+        assert_eq!(cache.take(&key(1), &block_hash), Some("result"));
+        assert_eq!(cache.take(&key(1), &block_hash), None);
+        assert!(!cache.contains(&key(1)));
+        assert_eq!(
+            cache.stats(),
+            OptimisticApplyStats { num_in_progress: 0, num_results: 0, num_used: 1 }
+        );
+    }
+
+    #[test]
+    fn test_discard_result_for_other_fork() {
+        let cache = OptimisticApplyCache::default();
+        assert!(cache.start(&key(1)));
+        cache.finish(key(1), hash(&[10]), 10, Some("result"));
+        assert_eq!(cache.take(&key(1), &hash(&[11])), None);
+        assert_eq!(cache.take(&key(1), &hash(&[10])), None);
+    }
+
+    #[test]
+    fn test_failed_application() {
+        let cache = OptimisticApplyCache::<&str>::default();
+        assert!(cache.start(&key(1)));
+        cache.finish(key(1), hash(&[10]), 10, None);
+        assert_eq!(cache.take(&key(1), &hash(&[10])), None);
+        // The chunk can be applied again.
+        assert!(cache.start(&key(1)));
+    }
+
+    #[test]
+    fn test_prune() {
+        let cache = OptimisticApplyCache::default();
+        for (chunk, height) in [(1, 10), (2, 11), (3, 12)] {
+            assert!(cache.start(&key(chunk)));
+            cache.finish(key(chunk), hash(&[chunk]), height, Some(height));
+        }
+        cache.prune(11);
+        assert_eq!(cache.take(&key(1), &hash(&[1])), None);
+        assert_eq!(cache.take(&key(2), &hash(&[2])), None);
+        assert_eq!(cache.take(&key(3), &hash(&[3])), Some(12));
+    }
+}
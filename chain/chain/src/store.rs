@@ -39,6 +39,9 @@ use near_store::{
     LATEST_KNOWN_KEY, TAIL_KEY,
 };
 
+use crate::gc_policy::{
+    get_deferred_gc_key, get_deferred_gc_key_rev, ColumnGCPolicies, DeferredGCEntry,
+};
 use crate::types::{Block, BlockHeader, LatestKnown};
 use crate::{byzantine_assert, RuntimeAdapter};
 use near_store::db::StoreStatistics;
@@ -367,6 +370,9 @@ pub struct ChainStore {
     processed_block_heights: CellLruCache<Vec<u8>, ()>,
     /// Is this a non-archival node that needs to store to DBCol::TrieChanges?
     save_trie_changes: bool,
+    /// Columns whose data is kept after the blocks it belongs to are garbage
+    /// collected.
+    gc_column_policies: ColumnGCPolicies,
 }
 
 fn option_to_not_found<T, F>(res: io::Result<Option<T>>, field_name: F) -> Result<T, Error>
@@ -410,9 +416,14 @@ impl ChainStore {
             block_ordinal_to_hash: CellLruCache::new(CACHE_SIZE),
             processed_block_heights: CellLruCache::new(CACHE_SIZE),
             save_trie_changes,
+            gc_column_policies: ColumnGCPolicies::default(),
         }
     }
 
+    pub fn set_gc_column_policies(&mut self, policies: ColumnGCPolicies) {
+        self.gc_column_policies = policies;
+    }
+
     pub fn store_update(&mut self) -> ChainStoreUpdate<'_> {
         ChainStoreUpdate::new(self)
     }
@@ -1955,8 +1966,22 @@ impl<'a> ChainStoreUpdate<'a> {
                 for transaction in chunk.transactions() {
                     self.gc_col(DBCol::Transactions, transaction.get_hash().as_bytes());
                 }
-                for receipt in chunk.receipts() {
-                    self.gc_col(DBCol::Receipts, receipt.get_hash().as_bytes());
+                if self.chain_store.gc_column_policies.is_retained(DBCol::Receipts) {
+                    let keys = chunk
+                        .receipts()
+                        .iter()
+                        .map(|receipt| receipt.get_hash().as_bytes().to_vec())
+                        .collect();
+                    // Chunks don’t have a timestamp, use the one of the block they
+                    // were built on.  Genesis chunks have no previous block.
+                    let timestamp = self
+                        .get_block_header(chunk.prev_block())
+                        .map_or(0, |header| header.raw_timestamp());
+                    self.defer_gc(DBCol::Receipts, height, &chunk_hash.0, timestamp, keys)?;
+                } else {
+                    for receipt in chunk.receipts() {
+                        self.gc_col(DBCol::Receipts, receipt.get_hash().as_bytes());
+                    }
                 }
 
                 // 2. Delete chunk_hash-indexed data
@@ -2282,7 +2307,11 @@ impl<'a> ChainStoreUpdate<'a> {
 
     pub fn gc_outcomes(&mut self, block: &Block) -> Result<(), Error> {
         let block_hash = block.hash();
-        let mut store_update = self.store().store_update();
+        let policies = &self.chain_store.gc_column_policies;
+        let retain_results = policies.is_retained(DBCol::TransactionResult);
+        let retain_outcome_ids = policies.is_retained(DBCol::OutcomeIds);
+        let mut retained_results = vec![];
+        let mut retained_outcome_ids = vec![];
         for chunk_header in
             block.chunks().iter().filter(|h| h.height_included() == block.header().height())
         {
@@ -2290,20 +2319,92 @@ impl<'a> ChainStoreUpdate<'a> {
             let outcome_ids =
                 self.chain_store.get_outcomes_by_block_hash_and_shard_id(block_hash, shard_id)?;
             for outcome_id in outcome_ids {
-                let mut outcomes_with_id = self.chain_store.get_outcomes_by_id(&outcome_id)?;
-                outcomes_with_id.retain(|outcome| &outcome.block_hash != block_hash);
-                if outcomes_with_id.is_empty() {
-                    self.gc_col(DBCol::TransactionResult, outcome_id.as_bytes());
+                if retain_results {
+                    retained_results.push(outcome_id.as_bytes().to_vec());
                 } else {
-                    store_update.set_ser(
-                        DBCol::TransactionResult,
-                        outcome_id.as_bytes(),
-                        &outcomes_with_id,
-                    )?;
+                    self.gc_transaction_result(block_hash, &outcome_id)?;
                 }
             }
-            self.gc_col(DBCol::OutcomeIds, &get_block_shard_id(block_hash, shard_id));
+            let key = get_block_shard_id(block_hash, shard_id);
+            if retain_outcome_ids {
+                retained_outcome_ids.push(key);
+            } else {
+                self.gc_col(DBCol::OutcomeIds, &key);
+            }
         }
+        let height = block.header().height();
+        let timestamp = block.header().raw_timestamp();
+        self.defer_gc(DBCol::TransactionResult, height, block_hash, timestamp, retained_results)?;
+        self.defer_gc(DBCol::OutcomeIds, height, block_hash, timestamp, retained_outcome_ids)?;
+        Ok(())
+    }
+
+    /// Removes outcomes of `block_hash` from DBCol::TransactionResult, keeping
+    /// outcomes of the same id on other forks.
+    fn gc_transaction_result(
+        &mut self,
+        block_hash: &CryptoHash,
+        outcome_id: &CryptoHash,
+    ) -> Result<(), Error> {
+        let mut outcomes_with_id = self.chain_store.get_outcomes_by_id(outcome_id)?;
+        outcomes_with_id.retain(|outcome| &outcome.block_hash != block_hash);
+        if outcomes_with_id.is_empty() {
+            self.gc_col(DBCol::TransactionResult, outcome_id.as_bytes());
+        } else {
+            let mut store_update = self.store().store_update();
+            store_update.set_ser(
+                DBCol::TransactionResult,
+                outcome_id.as_bytes(),
+                &outcomes_with_id,
+            )?;
+            self.merge(store_update);
+        }
+        Ok(())
+    }
+
+    /// Records keys of data in `col` which is kept by the column’s retention
+    /// policy after the block or chunk `hash` at `height` is garbage collected.
+    fn defer_gc(
+        &mut self,
+        col: DBCol,
+        height: BlockHeight,
+        hash: &CryptoHash,
+        timestamp: u64,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<(), Error> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let mut store_update = self.store().store_update();
+        store_update.set_ser(
+            DBCol::DeferredGC,
+            &get_deferred_gc_key(col, height, hash),
+            &DeferredGCEntry { timestamp, keys },
+        )?;
+        self.merge(store_update);
+        Ok(())
+    }
+
+    /// Removes data of `col` recorded by [`Self::defer_gc`] under `key`.
+    pub fn gc_deferred_entry(
+        &mut self,
+        col: DBCol,
+        key: &[u8],
+        entry: DeferredGCEntry,
+    ) -> Result<(), Error> {
+        let (_, hash) = get_deferred_gc_key_rev(key)?;
+        for data_key in entry.keys {
+            match col {
+                DBCol::TransactionResult => {
+                    let outcome_id = CryptoHash::try_from(data_key.as_slice())
+                        .map_err(|_| Error::GCError(format!("invalid outcome id {data_key:?}")))?;
+                    self.gc_transaction_result(&hash, &outcome_id)?;
+                }
+                _ => self.gc_col(col, &data_key),
+            }
+        }
+        let mut store_update = self.store().store_update();
+        store_update.delete(DBCol::DeferredGC, key);
         self.merge(store_update);
         Ok(())
     }
@@ -2437,7 +2538,8 @@ impl<'a> ChainStoreUpdate<'a> {
             | DBCol::_LastBlockWithNewChunk
             | DBCol::_TransactionRefCount
             | DBCol::StateChangesForSplitStates
-            | DBCol::CachedContractCode => {
+            | DBCol::CachedContractCode
+            | DBCol::DeferredGC => {
                 unreachable!();
             }
         }
@@ -3009,23 +3111,28 @@ impl<'a> ChainStoreUpdate<'a> {
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
+    use std::time::Duration;
 
     use borsh::BorshSerialize;
     use near_primitives::merkle::PartialMerkleTree;
     use strum::IntoEnumIterator;
 
-    use near_chain_configs::{GCConfig, GenesisConfig};
+    use near_chain_configs::{ColumnGCPolicy, GCConfig, GenesisConfig};
     use near_crypto::KeyType;
     use near_primitives::block::{Block, Tip};
     use near_primitives::epoch_manager::block_info::BlockInfo;
     use near_primitives::errors::InvalidTxError;
     use near_primitives::hash::hash;
     use near_primitives::types::{BlockHeight, EpochId, GCCount, NumBlocks};
-    use near_primitives::utils::index_to_bytes;
+    use near_primitives::utils::{get_block_shard_id, index_to_bytes};
     use near_primitives::validator_signer::InMemoryValidatorSigner;
     use near_store::test_utils::create_test_store;
     use near_store::DBCol;
 
+    use crate::gc_policy::{
+        gc_dry_run, get_deferred_gc_key, get_retention_cutoff, DeferredGCCount, DeferredGCEntry,
+        Retention, RetentionCutoff,
+    };
     use crate::store::{ChainStoreAccess, GCMode};
     use crate::store_validator::StoreValidator;
     use crate::test_utils::{KeyValueRuntime, ValidatorSchedule};
@@ -3392,6 +3499,139 @@ mod tests {
         *prev_block = block.clone();
     }
 
+    /// Test that data of a column with a retention policy outlives the block it
+    /// belongs to and is removed once it falls out of the retention.
+    #[test]
+    fn test_clear_old_data_column_policies() {
+        let mut chain = get_chain_with_epoch_length(1);
+        let runtime_adapter = chain.runtime_adapter.clone();
+        let genesis = chain.get_block_by_height(0).unwrap();
+        let signer = Arc::new(InMemoryValidatorSigner::from_seed(
+            "test1".parse().unwrap(),
+            KeyType::ED25519,
+            "test1",
+        ));
+        let mut prev_block = genesis;
+        let mut blocks = vec![prev_block.clone()];
+        for i in 1..15 {
+            add_block(
+                &mut chain,
+                runtime_adapter.clone(),
+                &mut prev_block,
+                &mut blocks,
+                signer.clone(),
+                i,
+            );
+        }
+        let keep_outcome_ids = |max_age| GCConfig {
+            gc_blocks_limit: 100,
+            column_policies: vec![ColumnGCPolicy {
+                column: "OutcomeIds".to_string(),
+                num_epochs_to_keep: None,
+                max_age: Some(max_age),
+            }],
+            ..GCConfig::default()
+        };
+        let outcome_ids_report = |chain: &Chain, config: &GCConfig| {
+            let report = gc_dry_run(chain.store(), &*chain.runtime_adapter, config).unwrap();
+            report.columns.into_iter().find(|report| report.col == DBCol::OutcomeIds).unwrap()
+        };
+        let outcome_ids_gc_count = |chain: &Chain| {
+            chain
+                .store()
+                .store
+                .get_ser::<GCCount>(DBCol::GCCount, &DBCol::OutcomeIds.try_to_vec().unwrap())
+                .unwrap()
+        };
+        let deferred_key = get_deferred_gc_key(DBCol::OutcomeIds, 0, blocks[0].hash());
+
+        let trie = chain.runtime_adapter.get_tries();
+        let config = keep_outcome_ids(Duration::from_secs(3600));
+        chain.clear_data(trie.clone(), &config).unwrap();
+        assert!(chain.get_block(blocks[0].hash()).is_err());
+        assert_eq!(outcome_ids_gc_count(&chain), None);
+        // Only genesis block includes new chunks.
+        let entry = chain
+            .store()
+            .store
+            .get_ser::<DeferredGCEntry>(DBCol::DeferredGC, &deferred_key)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.keys, vec![get_block_shard_id(blocks[0].hash(), 0)]);
+        let one = DeferredGCCount { num_entries: 1, num_keys: 1 };
+        let report = outcome_ids_report(&chain, &config);
+        assert_eq!((report.expired, report.retained), (DeferredGCCount::default(), one));
+        // Outcome ids of the garbage collected block don’t break store validation.
+        let mut genesis = GenesisConfig::default();
+        genesis.genesis_height = 0;
+        let mut store_validator = StoreValidator::new(
+            None,
+            genesis,
+            chain.runtime_adapter.clone(),
+            chain.store().store().clone(),
+            false,
+        );
+        store_validator.validate();
+        println!("errors = {:?}", store_validator.errors);
+        assert!(!store_validator.is_failed());
+
+        // Dry run doesn’t modify the store.
+        let report = outcome_ids_report(&chain, &keep_outcome_ids(Duration::ZERO));
+        assert_eq!((report.expired, report.retained), (one, DeferredGCCount::default()));
+        assert!(chain.store().store.exists(DBCol::DeferredGC, &deferred_key).unwrap());
+
+        // Without the policy the data isn’t retained anymore.
+        chain.clear_data(trie, &GCConfig { gc_blocks_limit: 100, ..GCConfig::default() }).unwrap();
+        assert!(!chain.store().store.exists(DBCol::DeferredGC, &deferred_key).unwrap());
+        assert_eq!(outcome_ids_gc_count(&chain), Some(1));
+    }
+
+    /// Data kept for `n` epochs expires once it is older than the last block of
+    /// the epoch `n` epochs before the head one.
+    #[test]
+    fn test_retention_cutoff_epochs() {
+        let mut chain = get_chain_with_epoch_length(1);
+        let runtime_adapter = chain.runtime_adapter.clone();
+        let genesis = chain.get_block_by_height(0).unwrap();
+        let signer = Arc::new(InMemoryValidatorSigner::from_seed(
+            "test1".parse().unwrap(),
+            KeyType::ED25519,
+            "test1",
+        ));
+        let mut prev_block = genesis;
+        let mut blocks = vec![prev_block.clone()];
+        for i in 1..10 {
+            add_block(
+                &mut chain,
+                runtime_adapter.clone(),
+                &mut prev_block,
+                &mut blocks,
+                signer.clone(),
+                i,
+            );
+        }
+        // Heights of the last blocks of the epochs before the head one.
+        let last_heights: Vec<BlockHeight> = blocks
+            .windows(2)
+            .filter(|pair| pair[0].header().epoch_id() != pair[1].header().epoch_id())
+            .map(|pair| pair[0].header().height())
+            .collect();
+        assert!(last_heights.len() >= 2);
+        let head_header = blocks.last().unwrap().header();
+        for num_epochs in 1..=last_heights.len() {
+            let cutoff = get_retention_cutoff(
+                chain.store(),
+                head_header,
+                Some(Retention::Epochs(num_epochs as u64)),
+            )
+            .unwrap();
+            let expected = last_heights[last_heights.len() - num_epochs];
+            assert_eq!(cutoff, RetentionCutoff::Height(expected), "{num_epochs} epochs");
+        }
+        let cutoff = get_retention_cutoff(chain.store(), head_header, None).unwrap();
+        assert_eq!(cutoff, RetentionCutoff::Everything);
+    }
+
     #[test]
     fn test_clear_old_data_fixed_height() {
         let mut chain = get_chain();
@@ -19,13 +19,13 @@ use near_primitives::syncing::{ShardStateSyncResponseHeader, StateHeaderKey, Sta
 use near_primitives::transaction::ExecutionOutcomeWithIdAndProof;
 use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::{AccountId, BlockHeight, EpochId, GCCount};
-use near_primitives::utils::get_block_shard_id_rev;
+use near_primitives::utils::{get_block_shard_id, get_block_shard_id_rev, index_to_bytes};
 use near_store::db::refcount;
 use near_store::{DBCol, Store, TrieChanges};
 use validate::StoreValidatorError;
 
 use crate::RuntimeAdapter;
-use near_primitives::shard_layout::get_block_shard_uid_rev;
+use near_primitives::shard_layout::{get_block_shard_uid, get_block_shard_uid_rev};
 use near_primitives::time::Clock;
 
 mod validate;
@@ -52,6 +52,13 @@ pub struct StoreValidatorCache {
     receipt_refcount: HashMap<CryptoHash, u64>,
     block_refcount: HashMap<CryptoHash, u64>,
     genesis_blocks: Vec<CryptoHash>,
+
+    /// Garbage collected blocks whose `DBCol::OutcomeIds` data is kept by
+    /// a column retention policy.
+    deferred_outcome_ids_blocks: HashSet<CryptoHash>,
+    /// Block hashes and ids of outcomes of garbage collected blocks which are
+    /// kept in `DBCol::TransactionResult` by a column retention policy.
+    deferred_outcomes: HashSet<(CryptoHash, CryptoHash)>,
 }
 
 impl StoreValidatorCache {
@@ -67,6 +74,8 @@ impl StoreValidatorCache {
             receipt_refcount: HashMap::new(),
             block_refcount: HashMap::new(),
             genesis_blocks: vec![],
+            deferred_outcome_ids_blocks: HashSet::new(),
+            deferred_outcomes: HashSet::new(),
         }
     }
 }
@@ -358,6 +367,10 @@ impl StoreValidator {
         if let Err(e) = validate::head_tail_validity(self) {
             self.process_error(e, "HEAD / HEADER_HEAD / TAIL / CHUNK_TAIL", DBCol::BlockMisc)
         }
+        // Data kept after its block was garbage collected
+        if let Err(e) = validate::deferred_gc_entries(self) {
+            self.process_error(e, "DEFERRED_GC", DBCol::DeferredGC)
+        }
 
         // Main loop
         for col in DBCol::iter() {
@@ -401,6 +414,162 @@ impl StoreValidator {
         }
     }
 
+    /// Checks Head-Tail validity and reference counts of blocks, transactions
+    /// and receipts.
+    ///
+    /// This is the subset of [`Self::validate`] which catches a database that
+    /// was copied inconsistently, e.g. a broken backup, while only scanning the
+    /// columns the refcounts are computed from.  The timeout is ignored.
+    pub fn validate_refcounts(&mut self) {
+        self.start_time = Clock::instant();
+
+        if let Err(e) = validate::head_tail_validity(self) {
+            self.process_error(e, "HEAD / HEADER_HEAD / TAIL / CHUNK_TAIL", DBCol::BlockMisc)
+        }
+        // Data kept after its block was garbage collected
+        if let Err(e) = validate::deferred_gc_entries(self) {
+            self.process_error(e, "DEFERRED_GC", DBCol::DeferredGC)
+        }
+
+        // Blocks and Chunks have to be scanned first as they fill the expected
+        // refcounts.
+        for col in [
+            DBCol::Block,
+            DBCol::Chunks,
+            DBCol::Transactions,
+            DBCol::Receipts,
+            DBCol::BlockRefCount,
+        ] {
+            if let Err(e) = self.validate_col(col) {
+                self.process_error(e, col.to_string(), col)
+            }
+        }
+
+        if let Err(e) = validate::tx_refcount_final(self) {
+            self.process_error(e, "TX_REFCOUNT", DBCol::Transactions)
+        }
+        if let Err(e) = validate::receipt_refcount_final(self) {
+            self.process_error(e, "RECEIPT_REFCOUNT", DBCol::Receipts)
+        }
+        if let Err(e) = validate::block_refcount_final(self) {
+            self.process_error(e, "BLOCK_REFCOUNT", DBCol::BlockRefCount)
+        }
+    }
+
+    /// Validates a single block which has just become final, together with
+    /// the data it references.
+    ///
+    /// Unlike [`Self::validate`] this doesn't scan whole columns so it's cheap
+    /// enough to be run by a live node.  Checks which need to look at the
+    /// entire database (refcounts, GC counters, blocks below tail) and full
+    /// trie traversals are skipped.  `errors` and `tests_done` only reflect
+    /// this call.
+    pub fn validate_block(&mut self, block_hash: &CryptoHash) {
+        self.start_time = Clock::instant();
+        self.inner = StoreValidatorCache::new();
+        self.errors.clear();
+        self.tests = 0;
+
+        if let Err(e) = validate::head_tail_validity(self) {
+            self.process_error(e, "HEAD / HEADER_HEAD / TAIL / CHUNK_TAIL", DBCol::BlockMisc)
+        }
+
+        let col = DBCol::BlockHeader;
+        if let Some(header) = self.get_for_validation::<BlockHeader>(col, block_hash.as_ref()) {
+            self.check(&validate::block_header_hash_validity, block_hash, &header, col);
+            self.check(&validate::block_header_height_validity, block_hash, &header, col);
+            self.check(&validate::header_hash_indexed_by_height, block_hash, &header, col);
+        }
+
+        let col = DBCol::Block;
+        let block = match self.get_for_validation::<Block>(col, block_hash.as_ref()) {
+            Some(block) => block,
+            None => return,
+        };
+        self.check(&validate::block_hash_validity, block_hash, &block, col);
+        self.check(&validate::block_height_validity, block_hash, &block, col);
+        self.check(&validate::block_indexed_by_height, block_hash, &block, col);
+        self.check(&validate::block_header_exists, block_hash, &block, col);
+        self.check(&validate::block_chunks_exist, block_hash, &block, col);
+        self.check(&validate::block_chunks_height_validity, block_hash, &block, col);
+        self.check(&validate::block_info_exists, block_hash, &block, col);
+
+        let height = block.header().height();
+        let col = DBCol::BlockHeight;
+        if let Some(hash) = self.get_for_validation::<CryptoHash>(col, &index_to_bytes(height)) {
+            self.check(&validate::canonical_header_validity, &height, &hash, col);
+            self.check(&validate::canonical_prev_block_validity, &height, &hash, col);
+        }
+
+        let col = DBCol::BlockInfo;
+        if let Some(block_info) = self.get_for_validation::<BlockInfo>(col, block_hash.as_ref()) {
+            self.check(&validate::block_info_block_header_exists, block_hash, &block_info, col);
+        }
+
+        for chunk_header in block.chunks().iter() {
+            let shard_id = chunk_header.shard_id();
+            if chunk_header.height_included() == height {
+                let col = DBCol::Chunks;
+                let chunk_hash = chunk_header.chunk_hash();
+                if let Some(shard_chunk) =
+                    self.get_for_validation::<ShardChunk>(col, chunk_hash.as_ref())
+                {
+                    self.check(&validate::chunk_hash_validity, &chunk_hash, &shard_chunk, col);
+                    self.check(&validate::chunk_tail_validity, &chunk_hash, &shard_chunk, col);
+                    self.check(
+                        &validate::chunk_indexed_by_height_created,
+                        &chunk_hash,
+                        &shard_chunk,
+                        col,
+                    );
+                    self.check(&validate::chunk_tx_exists, &chunk_hash, &shard_chunk, col);
+                }
+            }
+
+            let col = DBCol::OutcomeIds;
+            let key = get_block_shard_id(block_hash, shard_id);
+            if let Some(outcome_ids) = self.get_for_validation::<Vec<CryptoHash>>(col, &key) {
+                self.check(&validate::outcome_by_outcome_id_exists, block_hash, &outcome_ids, col);
+                self.check(&validate::outcome_id_block_exists, block_hash, &outcome_ids, col);
+            }
+
+            let shard_uid =
+                match self.runtime_adapter.shard_id_to_uid(shard_id, block.header().epoch_id()) {
+                    Ok(shard_uid) => shard_uid,
+                    Err(err) => {
+                        let err = StoreValidatorError::DBNotFound {
+                            func_name: String::from("get_shard_layout"),
+                            reason: err.to_string(),
+                        };
+                        self.process_error(err, block_hash, DBCol::Block);
+                        continue;
+                    }
+                };
+            let col = DBCol::ChunkExtra;
+            let key = get_block_shard_uid(block_hash, &shard_uid);
+            if let Some(chunk_extra) = self.get_for_validation::<ChunkExtra>(col, &key) {
+                self.check(
+                    &validate::chunk_extra_block_exists,
+                    &(*block_hash, shard_uid),
+                    &chunk_extra,
+                    col,
+                );
+            }
+        }
+    }
+
+    /// Reads a value which [`Self::validate_block`] is going to check.  Read
+    /// and deserialisation errors are recorded and `None` is returned.
+    fn get_for_validation<T: BorshDeserialize>(&mut self, col: DBCol, key: &[u8]) -> Option<T> {
+        match self.store.get_ser::<T>(col, key) {
+            Ok(value) => value,
+            Err(e) => {
+                self.process_error(e.into(), key, col);
+                None
+            }
+        }
+    }
+
     fn check<K: std::fmt::Debug, V>(
         &mut self,
         f: &dyn Fn(&mut StoreValidator, &K, &V) -> Result<(), StoreValidatorError>,
@@ -496,6 +665,65 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_validate_block() {
+        let (chain, mut sv) = init();
+        let block_hash = *chain.get_block_by_height(0).unwrap().hash();
+        let mut store_update = chain.store().store().store_update();
+        store_update
+            .insert_ser(DBCol::BlockInfo, block_hash.as_ref(), &BlockInfo::default())
+            .unwrap();
+        store_update.commit().unwrap();
+        sv.validate_block(&block_hash);
+        assert!(!sv.is_failed(), "{:?}", sv.errors);
+
+        let mut store_update = chain.store().store().store_update();
+        store_update.delete(DBCol::BlockInfo, block_hash.as_ref());
+        store_update.commit().unwrap();
+        sv.validate_block(&block_hash);
+        assert_eq!(sv.num_failed(), 1);
+        match &sv.errors[0].err {
+            StoreValidatorError::DBNotFound { func_name, .. } => {
+                assert_eq!(func_name, "block_info_exists")
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn test_validate_refcounts() {
+        let (mut chain, runtime_adapter, signer) = crate::test_utils::setup();
+        for _ in 0..3 {
+            let prev_hash = *chain.head_header().unwrap().hash();
+            let prev = chain.get_block(&prev_hash).unwrap();
+            chain.process_block_test(&None, Block::empty(&prev, &*signer)).unwrap();
+        }
+        let store = chain.store().store().clone();
+        let new_validator = || {
+            StoreValidator::new(
+                None,
+                GenesisConfig::default(),
+                runtime_adapter.clone(),
+                store.clone(),
+                false,
+            )
+        };
+        let mut sv = new_validator();
+        sv.validate_refcounts();
+        assert!(!sv.is_failed(), "{:?}", sv.errors);
+        assert!(sv.tests_done() > 0);
+
+        // Block 1 is only referenced by block 2.
+        let block_hash = *chain.get_block_by_height(1).unwrap().hash();
+        let mut store_update = store.store_update();
+        store_update.set_ser(DBCol::BlockRefCount, block_hash.as_ref(), &2u64).unwrap();
+        store_update.commit().unwrap();
+        let mut sv = new_validator();
+        sv.validate_refcounts();
+        assert_eq!(sv.num_failed(), 1, "{:?}", sv.errors);
+        assert_eq!(sv.errors[0].col, to_string(&DBCol::BlockRefCount));
+    }
+
     #[test]
     fn test_validation_failed() {
         let (_chain, mut sv) = init();
@@ -1,6 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use strum::IntoEnumIterator;
 use thiserror::Error;
 
@@ -20,6 +20,7 @@ use near_store::{
     DBCol, TrieChanges, CHUNK_TAIL_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY, TAIL_KEY,
 };
 
+use crate::gc_policy::{get_deferred_gc_key_rev, DeferredGCEntry};
 use crate::StoreValidator;
 use near_primitives::shard_layout::{get_block_shard_uid, ShardUId};
 
@@ -649,12 +650,56 @@ pub(crate) fn header_hash_of_height_exists(
     Ok(())
 }
 
+/// Collects data which is kept after the block it belongs to was garbage
+/// collected, as recorded in `DBCol::DeferredGC`.  Such data no longer has the
+/// block and chunks it is normally checked against.
+pub(crate) fn deferred_gc_entries(sv: &mut StoreValidator) -> Result<(), StoreValidatorError> {
+    for item in sv.store.iter(DBCol::DeferredGC) {
+        let (key, value) = item?;
+        let (_height, hash) = get_deferred_gc_key_rev(&key)?;
+        let entry = DeferredGCEntry::try_from_slice(&value)?;
+        if key[0] == DBCol::OutcomeIds as u8 {
+            sv.inner.deferred_outcome_ids_blocks.insert(hash);
+        } else if key[0] == DBCol::TransactionResult as u8 {
+            for outcome_id in entry.keys {
+                let outcome_id = unwrap_or_err!(
+                    CryptoHash::try_from(outcome_id.as_slice()),
+                    "Invalid outcome id {:?} kept for Block {:?}",
+                    outcome_id,
+                    hash
+                );
+                sv.inner.deferred_outcomes.insert((hash, outcome_id));
+            }
+        } else if key[0] == DBCol::Receipts as u8 {
+            // Refcounts of receipts of a garbage collected chunk are kept
+            // until the receipts are removed.
+            for receipt_id in entry.keys {
+                let receipt_id = unwrap_or_err!(
+                    CryptoHash::try_from(receipt_id.as_slice()),
+                    "Invalid receipt id {:?} kept for Chunk {:?}",
+                    receipt_id,
+                    hash
+                );
+                sv.inner.receipt_refcount.entry(receipt_id).and_modify(|x| *x += 1).or_insert(1);
+            }
+        } else {
+            err!("DeferredGC entry {:?} of unexpected column", key);
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn outcome_by_outcome_id_exists(
     sv: &mut StoreValidator,
     block_hash: &CryptoHash,
     outcome_ids: &Vec<CryptoHash>,
 ) -> Result<(), StoreValidatorError> {
+    let block_is_gced = sv.inner.deferred_outcome_ids_blocks.contains(block_hash);
     for outcome_id in outcome_ids {
+        if block_is_gced && !sv.inner.deferred_outcomes.contains(&(*block_hash, *outcome_id)) {
+            // Outcomes of the block were garbage collected before its outcome ids.
+            continue;
+        }
         let outcomes = unwrap_or_err_db!(
             sv.store.get_ser::<Vec<ExecutionOutcomeWithIdAndProof>>(
                 DBCol::TransactionResult,
@@ -664,7 +709,7 @@ pub(crate) fn outcome_by_outcome_id_exists(
             outcome_id
         );
         if outcomes.iter().find(|outcome| &outcome.block_hash == block_hash).is_none() {
-            panic!("Invalid TransactionResult {:?} stored", outcomes);
+            err!("Invalid TransactionResult {:?} stored", outcomes);
         }
     }
     Ok(())
@@ -675,6 +720,9 @@ pub(crate) fn outcome_id_block_exists(
     block_hash: &CryptoHash,
     _outcome_ids: &Vec<CryptoHash>,
 ) -> Result<(), StoreValidatorError> {
+    if sv.inner.deferred_outcome_ids_blocks.contains(block_hash) {
+        return Ok(());
+    }
     unwrap_or_err_db!(
         sv.store.get_ser::<Block>(DBCol::Block, block_hash.as_ref()),
         "Can't get Block from DB"
@@ -688,6 +736,10 @@ pub(crate) fn outcome_indexed_by_block_hash(
     outcomes: &Vec<ExecutionOutcomeWithIdAndProof>,
 ) -> Result<(), StoreValidatorError> {
     for outcome in outcomes {
+        if sv.inner.deferred_outcomes.contains(&(outcome.block_hash, *outcome_id)) {
+            // The block of the outcome was garbage collected.
+            continue;
+        }
         let block = unwrap_or_err_db!(
             sv.store.get_ser::<Block>(DBCol::Block, outcome.block_hash.as_ref()),
             "Can't get Block {} from DB",
@@ -40,8 +40,9 @@ use near_primitives::types::{
 use near_primitives::validator_signer::InMemoryValidatorSigner;
 use near_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
 use near_primitives::views::{
-    AccessKeyInfoView, AccessKeyList, CallResult, ContractCodeView, EpochValidatorInfo,
-    QueryRequest, QueryResponse, QueryResponseKind, ViewStateResult,
+    AccessKeyInfoView, AccessKeyList, CallResult, ContractCodeView, ContractPrecompilationView,
+    EpochValidatorInfo, QueryRequest, QueryResponse, QueryResponseKind, StoragePrefixUsageView,
+    StorageUsageView, ViewStateResult,
 };
 use near_store::test_utils::create_test_store;
 use near_store::{
@@ -684,6 +685,7 @@ impl RuntimeAdapter for KeyValueRuntime {
         _state_update: Option<StateRoot>,
         _transaction: &SignedTransaction,
         _verify_signature: bool,
+        _min_block_height: BlockHeight,
         _epoch_id: &EpochId,
         _current_protocol_version: ProtocolVersion,
     ) -> Result<Option<InvalidTxError>, Error> {
@@ -984,6 +986,30 @@ impl RuntimeAdapter for KeyValueRuntime {
                 kind: QueryResponseKind::ViewState(ViewStateResult {
                     values: Default::default(),
                     proof: vec![],
+                    next_key: None,
+                }),
+                block_height,
+                block_hash: *block_hash,
+            }),
+            QueryRequest::ViewStorageUsage { .. } => Ok(QueryResponse {
+                kind: QueryResponseKind::StorageUsage(StorageUsageView {
+                    storage_usage: 0,
+                    locked_for_storage: 0,
+                    account_bytes: 0,
+                    code_bytes: 0,
+                    access_keys: StoragePrefixUsageView {
+                        prefix: vec![].into(),
+                        num_records: 0,
+                        bytes: 0,
+                    },
+                    contract_data: StoragePrefixUsageView {
+                        prefix: vec![].into(),
+                        num_records: 0,
+                        bytes: 0,
+                    },
+                    contract_data_prefixes: vec![],
+                    largest_keys: vec![],
+                    truncated: false,
                 }),
                 block_height,
                 block_hash: *block_hash,
@@ -1043,6 +1069,19 @@ impl RuntimeAdapter for KeyValueRuntime {
         Ok(())
     }
 
+    fn precompile_contracts_for_next_epoch(
+        &self,
+        _epoch_id: &EpochId,
+        _next_epoch_id: &EpochId,
+        _state_roots: Vec<(ShardUId, StateRoot)>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get_contract_precompilation_progress(&self) -> Option<ContractPrecompilationView> {
+        None
+    }
+
     fn get_state_root_node(
         &self,
         _shard_id: ShardId,
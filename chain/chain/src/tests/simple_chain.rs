@@ -1,12 +1,16 @@
 use crate::near_chain_primitives::error::BlockKnownError;
 use crate::test_utils::{setup, wait_for_all_blocks_in_processing};
-use crate::{Block, BlockProcessingArtifact, ChainStoreAccess, Error};
+use crate::{
+    metrics, ApplyChunksExecutor, Block, BlockProcessingArtifact, ChainStoreAccess, Error,
+    Provenance,
+};
 use assert_matches::assert_matches;
 use chrono;
 use chrono::TimeZone;
 use near_logger_utils::init_test_logger;
 use near_primitives::hash::CryptoHash;
 use near_primitives::time::MockClockGuard;
+use near_primitives::utils::MaybeValidated;
 use near_primitives::version::PROTOCOL_VERSION;
 use num_rational::Ratio;
 use std::sync::Arc;
@@ -133,6 +137,58 @@ fn build_chain_with_orhpans() {
     );
 }
 
+#[test]
+fn build_chain_with_apply_chunks_executor() {
+    init_test_logger();
+    let (mut chain, _, signer) = setup();
+    chain.set_apply_chunks_executor(ApplyChunksExecutor::new(2).unwrap());
+    let genesis = chain.get_block(&chain.genesis().hash().clone()).unwrap();
+    let block1 = Block::empty(&genesis, &*signer);
+    let block2 = Block::empty(&block1, &*signer);
+    let block2_hash = *block2.hash();
+    let num_prevalidated = metrics::BLOCK_PREVALIDATED_TOTAL.get();
+
+    let mut block_processing_artifacts = BlockProcessingArtifact::default();
+    chain
+        .start_process_block_async(
+            &None,
+            MaybeValidated::from(block1),
+            Provenance::PRODUCED,
+            &mut block_processing_artifacts,
+            Arc::new(|_| {}),
+        )
+        .unwrap();
+    // The second block waits in the orphan pool until the first one is processed, but is
+    // validated on the executor while the chunks of the first one are being applied.
+    assert_matches!(
+        chain
+            .start_process_block_async(
+                &None,
+                MaybeValidated::from(block2),
+                Provenance::PRODUCED,
+                &mut block_processing_artifacts,
+                Arc::new(|_| {}),
+            )
+            .unwrap_err(),
+        Error::Orphan
+    );
+    for _ in 0..100 {
+        if chain.prevalidated_blocks.contains(&block2_hash) {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    assert!(chain.prevalidated_blocks.contains(&block2_hash));
+
+    while wait_for_all_blocks_in_processing(&mut chain) {
+        chain.postprocess_ready_blocks(&None, &mut block_processing_artifacts, Arc::new(|_| {}));
+    }
+    assert_eq!(chain.head().unwrap().height, 2);
+    // The validation was reused when the second block left the orphan pool.
+    assert!(metrics::BLOCK_PREVALIDATED_TOTAL.get() > num_prevalidated);
+    assert!(!chain.prevalidated_blocks.contains(&block2_hash));
+}
+
 #[test]
 fn build_chain_with_skips_and_forks() {
     init_test_logger();
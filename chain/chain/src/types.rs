@@ -35,7 +35,9 @@ use near_primitives::version::{
     ProtocolVersion, MIN_GAS_PRICE_NEP_92, MIN_GAS_PRICE_NEP_92_FIX, MIN_PROTOCOL_VERSION_NEP_92,
     MIN_PROTOCOL_VERSION_NEP_92_FIX,
 };
-use near_primitives::views::{EpochValidatorInfo, QueryRequest, QueryResponse};
+use near_primitives::views::{
+    ContractPrecompilationView, EpochValidatorInfo, QueryRequest, QueryResponse,
+};
 use near_store::{PartialStorage, ShardTries, Store, StoreUpdate, Trie, WrappedTrieChanges};
 
 pub use near_primitives::block::{Block, BlockHeader, Tip};
@@ -284,6 +286,7 @@ pub trait RuntimeAdapter: Send + Sync {
     /// Validates a given signed transaction.
     /// If the state root is given, then the verification will use the account. Otherwise it will
     /// only validate the transaction math, limits and signatures.
+    /// `min_block_height` is the lowest height of a block the transaction can be included in.
     /// Returns an option of `InvalidTxError`, it contains `Some(InvalidTxError)` if there is
     /// a validation error, or `None` in case the transaction succeeded.
     /// Throws an `Error` with `ErrorKind::StorageError` in case the runtime throws
@@ -294,6 +297,7 @@ pub trait RuntimeAdapter: Send + Sync {
         state_root: Option<StateRoot>,
         transaction: &SignedTransaction,
         verify_signature: bool,
+        min_block_height: BlockHeight,
         epoch_id: &EpochId,
         current_protocol_version: ProtocolVersion,
     ) -> Result<Option<InvalidTxError>, Error>;
@@ -737,6 +741,20 @@ pub trait RuntimeAdapter: Send + Sync {
         epoch_id: &EpochId,
     ) -> Result<(), Error>;
 
+    /// Starts precompiling in the background the contracts deployed in the shards with given
+    /// state roots for the protocol version of the epoch `next_epoch_id`, unless the contracts
+    /// compiled for the epoch `epoch_id` can be reused.  The precompilation for a protocol
+    /// version is started only once.
+    fn precompile_contracts_for_next_epoch(
+        &self,
+        epoch_id: &EpochId,
+        next_epoch_id: &EpochId,
+        state_roots: Vec<(ShardUId, StateRoot)>,
+    ) -> Result<(), Error>;
+
+    /// Returns the progress of the latest background precompilation of contracts if any.
+    fn get_contract_precompilation_progress(&self) -> Option<ContractPrecompilationView>;
+
     /// Returns StateRootNode of a state.
     /// `block_hash` is a block whose `prev_state_root` is `state_root`
     /// Panics if requested hash is not in storage.
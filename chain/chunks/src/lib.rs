@@ -31,9 +31,16 @@
 //! chunk producer, or a block producer or peer who tracks the shard, and sends out the network
 //! requests. Check the logic there for details regarding how targets of requests are chosen.
 //!
+//! Since any `num_data_parts` parts are enough to reconstruct a chunk, a node that needs the full
+//! chunk only requests as many parts as it is missing for that, besides the parts it owns.
+//! `part_tracker` keeps track of the parts in flight and of which accounts hold which parts, so
+//! that parts are requested from the fastest known sources and a part that doesn't arrive in
+//! time is requested again from another source.
+//!
 //! Once a request is added the pool, it can be resent through `resend_chunk_requests`,
-//! which is done periodically through client_actor. A request is only removed from the pool when
-//! all needed parts and receipts in the requested chunk are received.
+//! which is done periodically through client_actor. A resent request skips parts that are still
+//! in flight. A request is only removed from the pool when all needed parts and receipts in the
+//! requested chunk are received.
 //!
 //! ** Storing chunks
 //! Before a chunk can be reconstructed fully, parts and receipts in the chunk are stored in
@@ -104,7 +111,6 @@ use near_primitives::sharding::{
     PartialEncodedChunkPart, PartialEncodedChunkV1, PartialEncodedChunkV2, ReceiptList,
     ReceiptProof, ReedSolomonWrapper, ShardChunk, ShardChunkHeader, ShardProof,
 };
-use near_primitives::time::Clock;
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{
@@ -116,8 +122,10 @@ use near_primitives::version::ProtocolVersion;
 use near_primitives::{checked_feature, unwrap_or_return};
 
 use crate::chunk_cache::{EncodedChunksCache, EncodedChunksCacheEntry};
+use crate::part_tracker::{MissingPart, PartAvailabilityTracker};
 use near_chain::near_chain_primitives::error::Error::DBNotFoundErr;
 pub use near_chunks_primitives::Error;
+use near_network_primitives::time;
 use near_network_primitives::types::{
     AccountIdOrPeerTrackingShard, PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg,
     PartialEncodedChunkResponseMsg,
@@ -127,6 +135,7 @@ use rand::Rng;
 
 mod chunk_cache;
 mod metrics;
+mod part_tracker;
 pub mod test_utils;
 
 const CHUNK_PRODUCER_BLACKLIST_SIZE: usize = 100;
@@ -218,17 +227,17 @@ impl RequestPool {
         self.requests.remove(chunk_hash);
     }
 
-    pub fn fetch(&mut self) -> Vec<(ChunkHash, ChunkRequestInfo)> {
+    pub fn fetch(&mut self, now: Instant) -> Vec<(ChunkHash, ChunkRequestInfo)> {
         let mut removed_requests = HashSet::<ChunkHash>::default();
         let mut requests = Vec::new();
         for (chunk_hash, mut chunk_request) in self.requests.iter_mut() {
-            if chunk_request.added.elapsed() > self.max_duration {
+            if now.saturating_duration_since(chunk_request.added) > self.max_duration {
                 debug!(target: "chunks", "Evicted chunk requested that was never fetched {} (shard_id: {})", chunk_hash.0, chunk_request.shard_id);
                 removed_requests.insert(chunk_hash.clone());
                 continue;
             }
-            if chunk_request.last_requested.elapsed() > self.retry_duration {
-                chunk_request.last_requested = Clock::instant();
+            if now.saturating_duration_since(chunk_request.last_requested) > self.retry_duration {
+                chunk_request.last_requested = now;
                 requests.push((chunk_hash.clone(), chunk_request.clone()));
             }
         }
@@ -478,8 +487,11 @@ pub struct ShardsManager {
     encoded_chunks: EncodedChunksCache,
     requested_partial_encoded_chunks: RequestPool,
     chunk_forwards_cache: lru::LruCache<ChunkHash, HashMap<u64, PartialEncodedChunkPart>>,
+    part_tracker: PartAvailabilityTracker,
 
     seals_mgr: SealsManager,
+    /// Clock used to time chunk requests, so that tests can run on virtual time.
+    clock: time::Clock,
     /// Useful to make tests deterministic and reproducible,
     /// while keeping the security of randomization of transactions in pool
     rng_seed: RngSeed,
@@ -487,6 +499,7 @@ pub struct ShardsManager {
 
 impl ShardsManager {
     pub fn new(
+        clock: time::Clock,
         me: Option<AccountId>,
         runtime_adapter: Arc<dyn RuntimeAdapter>,
         network_adapter: Arc<dyn PeerManagerAdapter>,
@@ -506,7 +519,11 @@ impl ShardsManager {
                 Duration::from_millis(CHUNK_REQUEST_RETRY_MAX_MS),
             ),
             chunk_forwards_cache: lru::LruCache::new(CHUNK_FORWARD_CACHE_SIZE),
+            part_tracker: PartAvailabilityTracker::new(Duration::from_millis(
+                CHUNK_REQUEST_RETRY_MS,
+            )),
             seals_mgr: SealsManager::new(me, runtime_adapter),
+            clock,
             rng_seed,
         }
     }
@@ -598,39 +615,43 @@ impl ShardsManager {
 
         let seal = self.seals_mgr.get_seal(chunk_hash, ancestor_hash, height, shard_id)?;
 
+        // Parts we own (or that are sealed) are always fetched. If we need the full chunk, we
+        // also fetch as many other parts as needed to reconstruct it.
+        let mut missing_parts = vec![];
         for part_ord in 0..self.runtime_adapter.num_total_parts() {
             let part_ord = part_ord as u64;
             if cache_entry.map_or(false, |cache_entry| cache_entry.parts.contains_key(&part_ord)) {
                 continue;
             }
 
-            let need_to_fetch_part = if request_full || seal.contains_part_ord(&part_ord) {
-                true
+            let part_owner = self.runtime_adapter.get_part_owner(ancestor_hash, part_ord)?;
+            let mandatory = seal.contains_part_ord(&part_ord) || Some(&part_owner) == me;
+            if !request_full && !mandatory {
+                continue;
+            }
+
+            let default_source = if request_from_archival || Some(&part_owner) == me {
+                // If missing own part, request it from the chunk producer / node tracking shard
+                shard_representative_target.clone()
             } else {
-                if let Some(me) = me {
-                    &self.runtime_adapter.get_part_owner(ancestor_hash, part_ord)? == me
-                } else {
-                    false
-                }
+                Some(part_owner)
             };
+            missing_parts.push(MissingPart { part_ord, default_source, mandatory });
+        }
 
-            if need_to_fetch_part {
-                let fetch_from = if request_from_archival {
-                    shard_representative_target.clone()
-                } else {
-                    let part_owner =
-                        self.runtime_adapter.get_part_owner(ancestor_hash, part_ord)?;
-
-                    if Some(&part_owner) == me {
-                        // If missing own part, request it from the chunk producer / node tracking shard
-                        shard_representative_target.clone()
-                    } else {
-                        Some(part_owner)
-                    }
-                };
-
-                bp_to_parts.entry(fetch_from).or_default().push(part_ord);
-            }
+        let num_parts_needed = if request_full {
+            let num_parts_present = cache_entry.map_or(0, |cache_entry| cache_entry.parts.len());
+            self.runtime_adapter.num_data_parts().saturating_sub(num_parts_present)
+        } else {
+            0
+        };
+        for (part_ord, fetch_from) in self.part_tracker.plan_requests(
+            chunk_hash,
+            missing_parts,
+            num_parts_needed,
+            self.clock.now().into_inner(),
+        ) {
+            bp_to_parts.entry(fetch_from).or_default().push(part_ord);
         }
 
         let shards_to_fetch_receipts =
@@ -673,7 +694,7 @@ impl ShardsManager {
                     NetworkRequests::PartialEncodedChunkRequest {
                         target,
                         request,
-                        create_time: Clock::instant().into(),
+                        create_time: self.clock.now(),
                     },
                 ));
             } else {
@@ -808,8 +829,8 @@ impl ShardsManager {
                 prev_block_hash,
                 ancestor_hash,
                 shard_id,
-                last_requested: Clock::instant(),
-                added: Clock::instant(),
+                last_requested: self.clock.now().into_inner(),
+                added: self.clock.now().into_inner(),
             },
         );
 
@@ -910,7 +931,8 @@ impl ShardsManager {
             pool_size = self.requested_partial_encoded_chunks.len())
         .entered();
         // Process chunk one part requests.
-        let requests = self.requested_partial_encoded_chunks.fetch();
+        let now = self.clock.now().into_inner();
+        let requests = self.requested_partial_encoded_chunks.fetch(now);
         for (chunk_hash, chunk_request) in requests {
             let fetch_from_archival = self.runtime_adapter
                 .chunk_needs_to_be_fetched_from_archival(&chunk_request.ancestor_hash, &header_head.last_block_hash).unwrap_or_else(|err| {
@@ -926,10 +948,10 @@ impl ShardsManager {
                 &chunk_request.ancestor_hash,
                 chunk_request.shard_id,
                 &chunk_hash,
-                chunk_request.added.elapsed()
+                now.saturating_duration_since(chunk_request.added)
                     > self.requested_partial_encoded_chunks.switch_to_full_fetch_duration,
                 old_block
-                    || chunk_request.added.elapsed()
+                    || now.saturating_duration_since(chunk_request.added)
                         > self.requested_partial_encoded_chunks.switch_to_others_duration,
                 fetch_from_archival,
             ) {
@@ -1433,6 +1455,42 @@ impl ShardsManager {
         Ok(header)
     }
 
+    /// Records who holds the forwarded parts, which makes them candidates for re-requesting the
+    /// parts from.  Parts are forwarded only by their owner, and the forward doesn't say which
+    /// peer relayed it, so the holders are the owner and every other account the owner forwards
+    /// the parts to.
+    /// Must be called after `validate_partial_encoded_chunk_forward` succeeded.
+    pub fn track_forwarded_parts(&mut self, forward: &PartialEncodedChunkForwardMsg) {
+        let epoch_id =
+            match self.runtime_adapter.get_epoch_id_from_prev_block(&forward.prev_block_hash) {
+                Ok(epoch_id) => epoch_id,
+                Err(_) => return,
+            };
+        for part in forward.parts.iter() {
+            let owner = match self
+                .runtime_adapter
+                .get_part_owner(&forward.prev_block_hash, part.part_ord)
+            {
+                Ok(owner) => owner,
+                Err(_) => continue,
+            };
+            let recipients = self
+                .get_forward_recipients(
+                    &owner,
+                    &epoch_id,
+                    &forward.prev_block_hash,
+                    forward.shard_id,
+                    forward.height_created,
+                )
+                .unwrap_or_default();
+            for holder in recipients.into_iter().chain(std::iter::once(owner)) {
+                if Some(&holder) != self.me.as_ref() {
+                    self.part_tracker.add_holder(&forward.chunk_hash, part.part_ord, holder);
+                }
+            }
+        }
+    }
+
     pub fn insert_forwarded_chunk(&mut self, forward: PartialEncodedChunkForwardMsg) {
         let chunk_hash = forward.chunk_hash.clone();
         let num_total_parts = self.runtime_adapter.num_total_parts() as u64;
@@ -1685,6 +1743,11 @@ impl ShardsManager {
 
         // Merge parts and receipts included in the partial encoded chunk into chunk cache
         self.encoded_chunks.merge_in_partial_encoded_chunk(partial_encoded_chunk);
+        self.part_tracker.record_received(
+            &chunk_hash,
+            partial_encoded_chunk.parts.iter().map(|part| part.part_ord),
+            self.clock.now().into_inner(),
+        );
 
         // 3. Process the forwarded parts in chunk_forwards_cache
         if let Some(forwarded_parts) = self.chunk_forwards_cache.pop(&chunk_hash) {
@@ -1695,6 +1758,13 @@ impl ShardsManager {
                 parts: forwarded_parts.into_iter().map(|(_, part)| part).collect(),
                 receipts: Vec::new(),
             };
+            // The cached parts are merged now, so stop tracking them as pending.
+            self.encoded_chunks.merge_in_partial_encoded_chunk(&forwarded_chunk);
+            self.part_tracker.record_received(
+                &chunk_hash,
+                forwarded_chunk.parts.iter().map(|part| part.part_ord),
+                self.clock.now().into_inner(),
+            );
             // Call process_partial_encoded_chunk recursively, "simulating" as that forwarded
             // part is just received from the network
             return self.process_partial_encoded_chunk(
@@ -1862,9 +1932,21 @@ impl ShardsManager {
 
     /// A helper function to be called after a chunk is considered complete
     fn complete_chunk(&mut self, chunk_hash: &ChunkHash) {
+        if let Some(request) = self.requested_partial_encoded_chunks.get_request_info(chunk_hash) {
+            metrics::PARTIAL_ENCODED_CHUNK_REQUEST_COMPLETION_TIME
+                .with_label_values(&[&request.shard_id.to_string()])
+                .observe(
+                    self.clock
+                        .now()
+                        .into_inner()
+                        .saturating_duration_since(request.added)
+                        .as_secs_f64(),
+                );
+        }
         self.encoded_chunks.mark_entry_complete(chunk_hash);
         self.encoded_chunks.remove_from_cache_if_outside_horizon(chunk_hash);
         self.requested_partial_encoded_chunks.remove(chunk_hash);
+        self.part_tracker.forget(chunk_hash);
     }
 
     /// Send the parts of the partial_encoded_chunk that are owned by `self.me` to the
@@ -1900,19 +1982,45 @@ impl ShardsManager {
             owned_parts,
         );
 
-        let block_producers =
-            self.runtime_adapter.get_epoch_block_producers_ordered(&epoch_id, &parent_hash)?;
-        let current_chunk_height = partial_encoded_chunk.header.height_created();
-        let next_chunk_producer = self.runtime_adapter.get_chunk_producer(
+        let recipients = self.get_forward_recipients(
+            me,
             &epoch_id,
-            current_chunk_height + 1,
+            &parent_hash,
             shard_id,
+            partial_encoded_chunk.header.height_created(),
         )?;
+        for account_id in recipients {
+            self.peer_manager_adapter.do_send(PeerManagerMessageRequest::NetworkRequests(
+                NetworkRequests::PartialEncodedChunkForward {
+                    account_id,
+                    forward: forward.clone(),
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the accounts `forwarder` forwards its owned parts of a chunk to: the block
+    /// producers tracking the shard and the next chunk producer.
+    fn get_forward_recipients(
+        &self,
+        forwarder: &AccountId,
+        epoch_id: &EpochId,
+        parent_hash: &CryptoHash,
+        shard_id: ShardId,
+        height_created: BlockHeight,
+    ) -> Result<Vec<AccountId>, Error> {
+        let block_producers =
+            self.runtime_adapter.get_epoch_block_producers_ordered(epoch_id, parent_hash)?;
+        let next_chunk_producer =
+            self.runtime_adapter.get_chunk_producer(epoch_id, height_created + 1, shard_id)?;
+        let mut recipients = vec![];
         let mut next_chunk_producer_forwarded = false;
         for (bp, _) in block_producers {
             let bp_account_id = bp.take_account_id();
-            // no need to send anything to myself
-            if me == &bp_account_id {
+            // no need to send anything to itself
+            if forwarder == &bp_account_id {
                 continue;
             }
             if &bp_account_id == &next_chunk_producer {
@@ -1921,30 +2029,20 @@ impl ShardsManager {
 
             let cares_about_shard = self.cares_about_shard_this_or_next_epoch(
                 Some(&bp_account_id),
-                &parent_hash,
+                parent_hash,
                 shard_id,
                 false,
             );
             if cares_about_shard {
-                self.peer_manager_adapter.do_send(PeerManagerMessageRequest::NetworkRequests(
-                    NetworkRequests::PartialEncodedChunkForward {
-                        account_id: bp_account_id,
-                        forward: forward.clone(),
-                    },
-                ));
+                recipients.push(bp_account_id);
             }
         }
 
         if !next_chunk_producer_forwarded {
-            self.peer_manager_adapter.do_send(PeerManagerMessageRequest::NetworkRequests(
-                NetworkRequests::PartialEncodedChunkForward {
-                    account_id: next_chunk_producer,
-                    forward,
-                },
-            ));
+            recipients.push(next_chunk_producer);
         }
 
-        Ok(())
+        Ok(recipients)
     }
 
     fn need_receipt(&self, prev_block_hash: &CryptoHash, shard_id: ShardId) -> bool {
@@ -2257,6 +2355,7 @@ mod test {
     use near_primitives::hash::{hash, CryptoHash};
     use near_primitives::merkle::merklize;
     use near_primitives::sharding::ReedSolomonWrapper;
+    use near_primitives::time::Clock;
     use near_primitives::types::EpochId;
     use near_primitives::validator_signer::InMemoryValidatorSigner;
     use near_primitives::version::PROTOCOL_VERSION;
@@ -2273,6 +2372,7 @@ mod test {
         let runtime_adapter = Arc::new(KeyValueRuntime::new(create_test_store(), 5));
         let network_adapter = Arc::new(MockPeerManagerAdapter::default());
         let mut shards_manager = ShardsManager::new(
+            time::Clock::real(),
             Some("test".parse().unwrap()),
             runtime_adapter,
             network_adapter.clone(),
@@ -2326,6 +2426,7 @@ mod test {
         let network_adapter = Arc::new(MockPeerManagerAdapter::default());
         let mut chain_store = ChainStore::new(create_test_store(), 0, true);
         let mut shards_manager = ShardsManager::new(
+            time::Clock::real(),
             Some("test".parse().unwrap()),
             runtime_adapter.clone(),
             network_adapter,
@@ -2493,6 +2594,7 @@ mod test {
         // Test that resending chunk requests won't request for parts the node already received
         let mut fixture = ChunkTestFixture::new(true);
         let mut shards_manager = ShardsManager::new(
+            time::Clock::real(),
             Some(fixture.mock_shard_tracker.clone()),
             fixture.mock_runtime.clone(),
             fixture.mock_network.clone(),
@@ -2530,8 +2632,26 @@ mod test {
             }
             parts
         };
+        // Parts we own or that are in the seal are always requested. Other parts are requested,
+        // lowest part ords first, only until enough parts to reconstruct the chunk are expected.
+        let chunk_hash = fixture.mock_chunk_header.chunk_hash();
+        let runtime = fixture.mock_runtime.clone();
+        let me = fixture.mock_shard_tracker.clone();
+        let num_total_parts = fixture.mock_chunk_parts.len() as u64;
+        let expected_parts = |shards_manager: &ShardsManager, present: &[u64]| -> HashSet<u64> {
+            let seal_parts = &shards_manager.seals_mgr.active_demurs[&chunk_hash].part_ords;
+            let (mandatory, optional): (Vec<u64>, Vec<u64>) = (0..num_total_parts)
+                .filter(|part_ord| !present.contains(part_ord))
+                .partition(|part_ord| {
+                    seal_parts.contains(part_ord)
+                        || runtime.get_part_owner(&CryptoHash::default(), *part_ord).unwrap() == me
+                });
+            let num_optional =
+                (runtime.num_data_parts() - present.len()).saturating_sub(mandatory.len());
+            mandatory.into_iter().chain(optional.into_iter().take(num_optional)).collect()
+        };
         let requested_parts = collect_request_parts(&mut fixture);
-        assert_eq!(requested_parts, (1..fixture.mock_chunk_parts.len() as u64).collect());
+        assert_eq!(requested_parts, expected_parts(&shards_manager, &[0]));
 
         // process chunk part 1
         let partial_encoded_chunk = fixture.make_partial_encoded_chunk(&[1]);
@@ -2550,7 +2670,7 @@ mod test {
         shards_manager.resend_chunk_requests(&fixture.mock_chain_head);
 
         let requested_parts = collect_request_parts(&mut fixture);
-        assert_eq!(requested_parts, (2..fixture.mock_chunk_parts.len() as u64).collect());
+        assert_eq!(requested_parts, expected_parts(&shards_manager, &[0, 1]));
 
         // immediately resend chunk requests
         // this should not send any new requests because it doesn't pass the time check
@@ -2564,6 +2684,7 @@ mod test {
         // Test that process_partial_encoded_chunk will reject invalid chunk
         let mut fixture = ChunkTestFixture::default();
         let mut shards_manager = ShardsManager::new(
+            time::Clock::real(),
             Some(fixture.mock_shard_tracker.clone()),
             fixture.mock_runtime.clone(),
             fixture.mock_network.clone(),
@@ -2590,6 +2711,7 @@ mod test {
         // and not request any parts (yet).
         let mut fixture = ChunkTestFixture::default();
         let mut shards_manager = ShardsManager::new(
+            time::Clock::real(),
             Some(fixture.mock_chunk_part_owner.clone()),
             fixture.mock_runtime.clone(),
             fixture.mock_network.clone(),
@@ -2656,6 +2778,7 @@ mod test {
             next_epoch_id: EpochId::default(),
         };
         let mut shards_manager = ShardsManager::new(
+            time::Clock::real(),
             account_id,
             fixture.mock_runtime.clone(),
             fixture.mock_network.clone(),
@@ -2711,6 +2834,7 @@ mod test {
 
         let mut fixture = ChunkTestFixture::new_with_chunk_only_producers();
         let mut shards_manager = ShardsManager::new(
+            time::Clock::real(),
             Some(fixture.mock_chunk_part_owner.clone()),
             fixture.mock_runtime.clone(),
             fixture.mock_network.clone(),
@@ -2793,6 +2917,7 @@ mod test {
         // case too
         let mut fixture = ChunkTestFixture::new(true);
         let mut shards_manager = ShardsManager::new(
+            time::Clock::real(),
             Some(fixture.mock_shard_tracker.clone()),
             fixture.mock_runtime.clone(),
             fixture.mock_network.clone(),
@@ -2855,6 +2980,107 @@ mod test {
             .is_none());
     }
 
+    #[test]
+    fn test_forwarded_parts_rerequested_from_other_recipients() {
+        let fixture = ChunkTestFixture::new(false);
+        let mut shards_manager = ShardsManager::new(
+            time::Clock::real(),
+            Some(fixture.mock_shard_tracker.clone()),
+            fixture.mock_runtime.clone(),
+            fixture.mock_network.clone(),
+            TEST_SEED,
+        );
+        let part = fixture.mock_chunk_parts[0].clone();
+        let owner = fixture
+            .mock_runtime
+            .get_part_owner(&fixture.mock_chunk_header.prev_block_hash(), part.part_ord)
+            .unwrap();
+        let forward = PartialEncodedChunkForwardMsg::from_header_and_parts(
+            &fixture.mock_chunk_header,
+            vec![part.clone()],
+        );
+        shards_manager.track_forwarded_parts(&forward);
+
+        let chunk_hash = fixture.mock_chunk_header.chunk_hash();
+        let missing = || {
+            vec![MissingPart {
+                part_ord: part.part_ord,
+                default_source: Some(owner.clone()),
+                mandatory: true,
+            }]
+        };
+        let now = Instant::now();
+        let planned = shards_manager.part_tracker.plan_requests(&chunk_hash, missing(), 1, now);
+        assert_eq!(planned, vec![(part.part_ord, Some(owner.clone()))]);
+
+        // The owner didn't deliver the part in time, so it is re-requested from another account
+        // the owner forwarded it to.
+        let later = now + Duration::from_secs(3600);
+        let planned = shards_manager.part_tracker.plan_requests(&chunk_hash, missing(), 1, later);
+        assert_eq!(planned.len(), 1);
+        let source = planned[0].1.clone().unwrap();
+        assert_ne!(source, owner);
+        assert_ne!(source, fixture.mock_shard_tracker);
+    }
+
+    #[test]
+    fn test_cached_forward_marks_parts_received() {
+        let mut fixture = ChunkTestFixture::new(true);
+        let mut shards_manager = ShardsManager::new(
+            time::Clock::real(),
+            Some(fixture.mock_shard_tracker.clone()),
+            fixture.mock_runtime.clone(),
+            fixture.mock_network.clone(),
+            TEST_SEED,
+        );
+        let (most_parts, other_parts) = {
+            let mut most_parts = fixture.mock_chunk_parts.clone();
+            let n = most_parts.len();
+            let other_parts = most_parts.split_off(n - (n / 4));
+            (most_parts, other_parts)
+        };
+        let chunk_hash = fixture.mock_chunk_header.chunk_hash();
+        let missing = |parts: &[PartialEncodedChunkPart]| -> Vec<MissingPart> {
+            parts
+                .iter()
+                .map(|part| MissingPart {
+                    part_ord: part.part_ord,
+                    default_source: None,
+                    mandatory: true,
+                })
+                .collect()
+        };
+        // The forwarded parts are in flight when the forward arrives before the header.
+        let now = Instant::now();
+        let planned =
+            shards_manager.part_tracker.plan_requests(&chunk_hash, missing(&most_parts), 0, now);
+        assert_eq!(planned.len(), most_parts.len());
+        let forward = PartialEncodedChunkForwardMsg::from_header_and_parts(
+            &fixture.mock_chunk_header,
+            most_parts.clone(),
+        );
+        shards_manager.insert_forwarded_chunk(forward);
+
+        let partial_encoded_chunk = PartialEncodedChunkV2 {
+            header: fixture.mock_chunk_header.clone(),
+            parts: other_parts,
+            receipts: Vec::new(),
+        };
+        shards_manager
+            .process_partial_encoded_chunk(
+                MaybeValidated::from(&partial_encoded_chunk),
+                Some(&fixture.mock_chain_head),
+                &mut fixture.chain_store,
+                &mut fixture.rs,
+            )
+            .unwrap();
+
+        // Merging the cached parts marked them received, so they are no longer in flight.
+        let planned =
+            shards_manager.part_tracker.plan_requests(&chunk_hash, missing(&most_parts), 0, now);
+        assert_eq!(planned.len(), most_parts.len());
+    }
+
     #[test]
     fn test_random_seed_with_shard_id() {
         let seed0 = ShardsManager::random_seed(&TEST_SEED, 0);
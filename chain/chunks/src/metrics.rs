@@ -32,3 +32,17 @@ pub static DISTRIBUTE_ENCODED_CHUNK_TIME: Lazy<near_metrics::HistogramVec> = Laz
     )
     .unwrap()
 });
+
+pub static PARTIAL_ENCODED_CHUNK_REQUEST_COMPLETION_TIME: Lazy<near_metrics::HistogramVec> =
+    Lazy::new(|| {
+        near_metrics::try_create_histogram_vec(
+            "near_partial_encoded_chunk_request_completion_time",
+            concat!(
+                "Time from first requesting a chunk until all the parts and receipts needed ",
+                "for it are received, i.e. until the chunk is reconstructed if we track its shard",
+            ),
+            &["shard_id"],
+            Some(exponential_buckets(0.001, 2.0, 16).unwrap()),
+        )
+        .unwrap()
+    });
@@ -0,0 +1,407 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use near_primitives::sharding::ChunkHash;
+use near_primitives::types::AccountId;
+
+// This file implements PartAvailabilityTracker, which ShardsManager uses to decide which chunk
+// parts to request and from whom.
+// 1) For every chunk being fetched, it remembers which accounts are known to hold which parts.
+//    Part owners hold their parts by construction, and an account that answered a request for
+//    a part obviously has it too.
+// 2) It keeps track of the part requests that are in flight. A part that has been requested
+//    and not received within `request_timeout` is considered lost; the source it was requested
+//    from is avoided for that part from then on.
+// 3) It keeps a smoothed response latency for every account we received parts from, so that
+//    parts are requested from the fastest known sources.
+//
+// Responses don't say who sent them, so a received part is credited to the account it was
+// last requested from.
+
+/// Maximum number of chunks for which part availability is tracked.
+const TRACKED_CHUNKS_CACHE_SIZE: usize = 1000;
+/// Maximum number of accounts for which response latency is tracked.
+const TRACKED_SOURCES_CACHE_SIZE: usize = 1000;
+/// Weight of a new sample in the exponentially smoothed latency of a source.
+const LATENCY_SMOOTHING_FACTOR: f64 = 0.25;
+
+/// A part of a chunk that we don't have yet.
+#[derive(Clone, Debug)]
+pub(crate) struct MissingPart {
+    pub part_ord: u64,
+    /// Where the part would be requested from if we knew nothing about other sources;
+    /// `None` means any peer tracking the shard.
+    pub default_source: Option<AccountId>,
+    /// Whether the part is needed on its own (e.g. we own it), rather than only as one of
+    /// the parts needed to reconstruct the chunk.
+    pub mandatory: bool,
+}
+
+struct PendingPart {
+    source: Option<AccountId>,
+    requested: Instant,
+}
+
+#[derive(Default)]
+struct ChunkParts {
+    /// Accounts known to hold a part, other than its default source.
+    holders: HashMap<u64, HashSet<AccountId>>,
+    /// Parts requested but not received yet.
+    pending: HashMap<u64, PendingPart>,
+    /// Sources that didn't deliver a part in time.
+    failed: HashMap<u64, HashSet<Option<AccountId>>>,
+}
+
+pub(crate) struct PartAvailabilityTracker {
+    request_timeout: Duration,
+    chunks: lru::LruCache<ChunkHash, ChunkParts>,
+    latencies: lru::LruCache<AccountId, Duration>,
+}
+
+impl PartAvailabilityTracker {
+    pub fn new(request_timeout: Duration) -> Self {
+        Self {
+            request_timeout,
+            chunks: lru::LruCache::new(TRACKED_CHUNKS_CACHE_SIZE),
+            latencies: lru::LruCache::new(TRACKED_SOURCES_CACHE_SIZE),
+        }
+    }
+
+    fn chunk_mut(&mut self, chunk_hash: &ChunkHash) -> &mut ChunkParts {
+        if !self.chunks.contains(chunk_hash) {
+            self.chunks.put(chunk_hash.clone(), ChunkParts::default());
+        }
+        self.chunks.get_mut(chunk_hash).unwrap()
+    }
+
+    /// Records that `account_id` is known to hold part `part_ord` of the chunk.
+    pub fn add_holder(&mut self, chunk_hash: &ChunkHash, part_ord: u64, account_id: AccountId) {
+        self.chunk_mut(chunk_hash).holders.entry(part_ord).or_default().insert(account_id);
+    }
+
+    /// Records that the given parts of the chunk were received. Parts that were in flight are
+    /// credited to the source they were requested from.
+    pub fn record_received(
+        &mut self,
+        chunk_hash: &ChunkHash,
+        part_ords: impl IntoIterator<Item = u64>,
+        now: Instant,
+    ) {
+        let chunk = match self.chunks.get_mut(chunk_hash) {
+            Some(chunk) => chunk,
+            None => return,
+        };
+        let mut samples = vec![];
+        for part_ord in part_ords {
+            chunk.failed.remove(&part_ord);
+            let pending = match chunk.pending.remove(&part_ord) {
+                Some(pending) => pending,
+                None => continue,
+            };
+            if let Some(source) = pending.source {
+                samples.push((source.clone(), now.saturating_duration_since(pending.requested)));
+                chunk.holders.entry(part_ord).or_default().insert(source);
+            }
+        }
+        for (source, latency) in samples {
+            let smoothed = match self.latencies.get(&source) {
+                Some(old) => old
+                    .mul_f64(1.0 - LATENCY_SMOOTHING_FACTOR)
+                    .saturating_add(latency.mul_f64(LATENCY_SMOOTHING_FACTOR)),
+                None => latency,
+            };
+            self.latencies.put(source, smoothed);
+        }
+    }
+
+    /// Stops tracking the chunk, e.g. once it is complete.
+    pub fn forget(&mut self, chunk_hash: &ChunkHash) {
+        self.chunks.pop(chunk_hash);
+    }
+
+    /// Expected time for `source` to answer a part request. Sources we haven't heard from
+    /// yet are assumed to answer just before the request times out.
+    fn estimated_latency(&self, source: Option<&AccountId>) -> Duration {
+        source
+            .and_then(|source| self.latencies.peek(source))
+            .copied()
+            .unwrap_or(self.request_timeout)
+    }
+
+    /// Picks the fastest source for the part that hasn't failed to deliver it yet. Falls back
+    /// to any peer tracking the shard if all known sources failed.
+    fn pick_source(&self, chunk: &ChunkParts, part: &MissingPart) -> Option<AccountId> {
+        let failed = chunk.failed.get(&part.part_ord);
+        let is_failed = |source: &Option<AccountId>| failed.map_or(false, |f| f.contains(source));
+        let mut holders: Vec<_> = chunk
+            .holders
+            .get(&part.part_ord)
+            .into_iter()
+            .flatten()
+            .filter(|holder| part.default_source.as_ref() != Some(*holder))
+            .cloned()
+            .map(Some)
+            .collect();
+        holders.sort();
+        std::iter::once(part.default_source.clone())
+            .chain(holders)
+            .filter(|source| !is_failed(source))
+            .min_by_key(|source| self.estimated_latency(source.as_ref()))
+            .unwrap_or(None)
+    }
+
+    /// Decides which of the `missing` parts of the chunk to request and where from, and marks
+    /// them as in flight.
+    /// Mandatory parts are always requested unless already in flight. Other parts are only
+    /// requested until, together with the parts in flight, `num_parts_needed` parts are
+    /// expected to arrive; the ones available from the fastest sources are preferred.
+    pub fn plan_requests(
+        &mut self,
+        chunk_hash: &ChunkHash,
+        missing: Vec<MissingPart>,
+        num_parts_needed: usize,
+        now: Instant,
+    ) -> Vec<(u64, Option<AccountId>)> {
+        // Take the chunk out of the cache so that latency estimates can be read while it is
+        // being updated.
+        let mut chunk = self.chunks.pop(chunk_hash).unwrap_or_default();
+        let request_timeout = self.request_timeout;
+        let mut expired = vec![];
+        chunk.pending.retain(|part_ord, pending| {
+            if now.saturating_duration_since(pending.requested) >= request_timeout {
+                expired.push((*part_ord, pending.source.clone()));
+                false
+            } else {
+                true
+            }
+        });
+        for (part_ord, source) in expired {
+            chunk.failed.entry(part_ord).or_default().insert(source);
+        }
+
+        let mut num_expected = chunk.pending.len();
+        let mut planned = vec![];
+        let mut optional = vec![];
+        for part in missing {
+            if chunk.pending.contains_key(&part.part_ord) {
+                continue;
+            }
+            let source = self.pick_source(&chunk, &part);
+            if part.mandatory {
+                num_expected += 1;
+                planned.push((part.part_ord, source));
+            } else {
+                optional.push((self.estimated_latency(source.as_ref()), part.part_ord, source));
+            }
+        }
+        optional.sort();
+        planned.extend(
+            optional
+                .into_iter()
+                .take(num_parts_needed.saturating_sub(num_expected))
+                .map(|(_, part_ord, source)| (part_ord, source)),
+        );
+        for (part_ord, source) in planned.iter() {
+            chunk.pending.insert(*part_ord, PendingPart { source: source.clone(), requested: now });
+        }
+        self.chunks.put(chunk_hash.clone(), chunk);
+        planned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+    use std::time::{Duration, Instant};
+
+    use near_primitives::hash::hash;
+    use near_primitives::sharding::ChunkHash;
+    use near_primitives::types::AccountId;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::{MissingPart, PartAvailabilityTracker};
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    fn account(name: &str) -> AccountId {
+        name.parse().unwrap()
+    }
+
+    fn missing(part_ords: impl IntoIterator<Item = u64>, source: &str) -> Vec<MissingPart> {
+        part_ords
+            .into_iter()
+            .map(|part_ord| MissingPart {
+                part_ord,
+                default_source: Some(account(source)),
+                mandatory: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_requests_only_needed_parts() {
+        let chunk_hash = ChunkHash(hash(&[1]));
+        let mut tracker = PartAvailabilityTracker::new(TIMEOUT);
+        let now = Instant::now();
+
+        let mut parts = missing(0..10, "alice");
+        parts[7].mandatory = true;
+        let planned = tracker.plan_requests(&chunk_hash, parts, 3, now);
+        assert_eq!(planned.len(), 3);
+        assert!(planned.iter().any(|(part_ord, _)| *part_ord == 7));
+
+        // Parts in flight are not requested again before the timeout.
+        let planned = tracker.plan_requests(&chunk_hash, missing(0..10, "alice"), 3, now);
+        assert!(planned.is_empty());
+
+        // Once they time out, the same number of parts is requested again, but not from the
+        // source that failed to deliver them.
+        let planned = tracker.plan_requests(&chunk_hash, missing(0..10, "alice"), 3, now + TIMEOUT);
+        assert_eq!(planned.len(), 3);
+        for (part_ord, source) in planned {
+            if [0, 1, 7].contains(&part_ord) {
+                assert_eq!(source, None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefers_fastest_source() {
+        let chunk_hash = ChunkHash(hash(&[1]));
+        let mut tracker = PartAvailabilityTracker::new(TIMEOUT);
+        let now = Instant::now();
+
+        let mut parts = missing(0..1, "slow");
+        parts.extend(missing(1..2, "fast"));
+        let planned = tracker.plan_requests(&chunk_hash, parts, 2, now);
+        assert_eq!(planned.len(), 2);
+        tracker.record_received(&chunk_hash, [1], now + Duration::from_millis(5));
+        tracker.record_received(&chunk_hash, [0], now + Duration::from_millis(80));
+
+        // "fast" turned out to be faster, so parts it is known to hold are requested from it
+        // first.
+        let other_chunk = ChunkHash(hash(&[2]));
+        tracker.add_holder(&other_chunk, 3, account("fast"));
+        let mut parts = missing(2..3, "slow");
+        parts.extend(missing(3..4, "slow"));
+        let planned = tracker.plan_requests(&other_chunk, parts, 1, now);
+        assert_eq!(planned, vec![(3, Some(account("fast")))]);
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    enum Strategy {
+        /// Request all missing parts from their owners on every retry.
+        RequestAll,
+        /// Request only the needed parts, using `PartAvailabilityTracker`.
+        Tracked,
+    }
+
+    struct SimulationResult {
+        time_to_reconstruct: Duration,
+        parts_requested: usize,
+    }
+
+    /// Simulates fetching a chunk with `num_total_parts` parts, out of which any
+    /// `num_data_parts` are enough to reconstruct it. Every part has an owner with a random
+    /// response latency, and each request or response is lost with `loss_probability`.
+    /// Requests are retried every `TIMEOUT`, the way `resend_chunk_requests` does.
+    fn simulate(
+        strategy: Strategy,
+        seed: u64,
+        num_total_parts: u64,
+        num_data_parts: usize,
+        loss_probability: f64,
+    ) -> SimulationResult {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let num_owners = 8;
+        let owner_latencies: Vec<Duration> =
+            (0..num_owners).map(|_| Duration::from_millis(rng.gen_range(5, 150))).collect();
+        let owner = |part_ord: u64| account(&format!("owner{}", part_ord as usize % num_owners));
+        let latency = |source: &Option<AccountId>| match source {
+            Some(source) => owner_latencies[source.as_str()[5..].parse::<usize>().unwrap()],
+            // Some random peer tracking the shard.
+            None => Duration::from_millis(50),
+        };
+
+        let chunk_hash = ChunkHash(hash(&seed.to_le_bytes()));
+        let mut tracker = PartAvailabilityTracker::new(TIMEOUT);
+        let start = Instant::now();
+        let mut received = HashSet::new();
+        let mut in_flight = BTreeMap::<Duration, Vec<u64>>::new();
+        let mut parts_requested = 0;
+        let mut now = Duration::ZERO;
+        let mut next_retry = Duration::ZERO;
+        loop {
+            if now >= next_retry {
+                let missing: Vec<_> = (0..num_total_parts)
+                    .filter(|part_ord| !received.contains(part_ord))
+                    .map(|part_ord| MissingPart {
+                        part_ord,
+                        default_source: Some(owner(part_ord)),
+                        mandatory: false,
+                    })
+                    .collect();
+                let requests = match strategy {
+                    Strategy::RequestAll => missing
+                        .into_iter()
+                        .map(|part| (part.part_ord, part.default_source))
+                        .collect(),
+                    Strategy::Tracked => tracker.plan_requests(
+                        &chunk_hash,
+                        missing,
+                        num_data_parts - received.len(),
+                        start + now,
+                    ),
+                };
+                for (part_ord, source) in requests {
+                    parts_requested += 1;
+                    if rng.gen_bool(loss_probability) {
+                        continue;
+                    }
+                    in_flight.entry(now + latency(&source)).or_default().push(part_ord);
+                }
+                next_retry = now + TIMEOUT;
+            }
+            let next_arrival = in_flight.keys().next().copied();
+            let (arrival, part_ords) = match next_arrival {
+                Some(arrival) if arrival < next_retry => {
+                    (arrival, in_flight.remove(&arrival).unwrap())
+                }
+                _ => {
+                    now = next_retry;
+                    continue;
+                }
+            };
+            now = arrival;
+            tracker.record_received(&chunk_hash, part_ords.iter().copied(), start + now);
+            received.extend(part_ords);
+            if received.len() >= num_data_parts {
+                return SimulationResult { time_to_reconstruct: now, parts_requested };
+            }
+        }
+    }
+
+    /// Measures time to reconstruct a chunk under packet loss when requesting only the
+    /// missing parts from the fastest sources, compared to requesting all of them.
+    #[test]
+    fn test_chunk_distribution_simulation() {
+        let num_runs = 50;
+        for loss_probability in [0.0, 0.1, 0.3, 0.5] {
+            let mut total_requested = BTreeMap::new();
+            for strategy in [Strategy::RequestAll, Strategy::Tracked] {
+                for seed in 0..num_runs {
+                    let result = simulate(strategy, seed, 16, 5, loss_probability);
+                    assert!(
+                        result.time_to_reconstruct < 20 * TIMEOUT,
+                        "chunk took {:?} to reconstruct with loss probability {}",
+                        result.time_to_reconstruct,
+                        loss_probability,
+                    );
+                    *total_requested.entry(strategy).or_insert(0) += result.parts_requested;
+                }
+            }
+            assert!(total_requested[&Strategy::Tracked] < total_requested[&Strategy::RequestAll]);
+        }
+    }
+}
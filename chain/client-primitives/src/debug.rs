@@ -6,12 +6,12 @@ use std::collections::HashMap;
 use crate::types::{StatusError, SyncStatus};
 use actix::Message;
 use chrono::DateTime;
-use near_primitives::views::EpochValidatorInfo;
+use near_primitives::views::{ContractPrecompilationView, EpochValidatorInfo};
 use near_primitives::{
     block_header::ApprovalInner,
     hash::CryptoHash,
     sharding::ChunkHash,
-    types::{AccountId, BlockHeight},
+    types::{AccountId, BlockHeight, ShardId},
     views::ValidatorInfo,
 };
 use serde::{Deserialize, Serialize};
@@ -142,6 +142,40 @@ pub struct ValidatorStatus {
     pub production: HashMap<BlockHeight, ProductionAtHeight>,
 }
 
+// Timeline of a chunk of a processed block.
+// Used for debug purposes only.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChunkProcessingTimelineView {
+    pub shard_id: ShardId,
+    pub chunk_hash: ChunkHash,
+    // Time at which we requested the chunk, if it wasn't available when the block arrived.
+    pub requested: Option<DateTime<chrono::Utc>>,
+    // Time at which all the parts and receipts of the requested chunk were received.
+    pub received: Option<DateTime<chrono::Utc>>,
+    // How long applying the chunk took, if this node applied it.
+    pub apply_duration_ms: Option<u64>,
+}
+
+// Timeline of a block from the moment it was received until it was processed.
+// Used for debug purposes only.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockProcessingTimelineView {
+    pub block_hash: CryptoHash,
+    pub height: BlockHeight,
+    pub received: DateTime<chrono::Utc>,
+    // Time at which the block was put into and removed from the orphan pool.
+    pub orphaned: Option<DateTime<chrono::Utc>>,
+    pub unorphaned: Option<DateTime<chrono::Utc>>,
+    // Time at which the block was put into and removed from the missing chunks pool.
+    pub missing_chunks: Option<DateTime<chrono::Utc>>,
+    pub completed_missing_chunks: Option<DateTime<chrono::Utc>>,
+    // Time at which the processing of the block finished.
+    pub finished: DateTime<chrono::Utc>,
+    pub chunks: Vec<ChunkProcessingTimelineView>,
+}
+
 // Different debug requests that can be sent by HTML pages, via GET.
 pub enum DebugStatus {
     // Request for the current sync status
@@ -154,6 +188,10 @@ pub enum DebugStatus {
     BlockStatus,
     // Consensus related information.
     ValidatorStatus,
+    // Progress of the background precompilation of contracts for the next protocol version.
+    ContractPrecompilation,
+    // Timelines of the recently processed blocks.
+    BlockTimelines,
 }
 
 impl Message for DebugStatus {
@@ -170,4 +208,8 @@ pub enum DebugStatusResponse {
     BlockStatus(Vec<DebugBlockStatus>),
     // Detailed information about the validator (approvals, block & chunk production etc.)
     ValidatorStatus(ValidatorStatus),
+    // Progress of the latest precompilation of contracts, if any was started.
+    ContractPrecompilation(Option<ContractPrecompilationView>),
+    // Timelines of the recently processed blocks, in the order they finished processing.
+    BlockTimelines(Vec<BlockProcessingTimelineView>),
 }
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
@@ -311,6 +312,11 @@ pub enum QueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
+    #[error("The limit of contract state values to return must be positive")]
+    InvalidStateLimit {
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
     #[error("Access key for public key {public_key} has never been observed on the node at block #{block_height}")]
     UnknownAccessKey {
         public_key: near_crypto::PublicKey,
@@ -772,6 +778,16 @@ impl Message for GetReceipt {
     type Result = Result<Option<ReceiptView>, GetReceiptError>;
 }
 
+/// Creates a checkpoint of the database for a backup in `backup_dir`, which
+/// must not exist.  Used to back up the database of a running node.
+pub struct CreateBackupCheckpoint {
+    pub backup_dir: PathBuf,
+}
+
+impl Message for CreateBackupCheckpoint {
+    type Result = Result<(), String>;
+}
+
 pub struct GetProtocolConfig(pub BlockReference);
 
 impl Message for GetProtocolConfig {
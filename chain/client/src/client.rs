@@ -7,18 +7,21 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use near_client_primitives::debug::BlockProduction;
-use near_primitives::time::Clock;
 use tracing::{debug, error, info, trace, warn};
 
+use near_chain::blocks_delay_tracker::BlockTimelineLog;
 use near_chain::chain::{
     ApplyStatePartsRequest, BlockCatchUpRequest, BlockMissingChunks, BlocksCatchUpState,
     OrphanMissingChunks, StateSplitRequest, TX_ROUTING_HEIGHT_HORIZON,
 };
+use near_chain::gc_policy::ColumnGCPolicies;
+use near_chain::store_validator::StoreValidator;
 use near_chain::test_utils::format_hash;
 use near_chain::types::LatestKnown;
 use near_chain::{
-    BlockProcessingArtifact, BlockStatus, Chain, ChainGenesis, ChainStoreAccess,
-    DoneApplyChunkCallback, Doomslug, DoomslugThresholdMode, Provenance, RuntimeAdapter,
+    ApplyChunksExecutor, BlockProcessingArtifact, BlockStatus, Chain, ChainGenesis,
+    ChainStoreAccess, DoneApplyChunkCallback, Doomslug, DoomslugThresholdMode, Provenance,
+    RuntimeAdapter,
 };
 use near_chain_configs::{ClientConfig, LogSummaryStyle};
 use near_chunks::{ProcessPartialEncodedChunkResult, ShardsManager};
@@ -49,6 +52,7 @@ use near_chain::chain::ChainAccess;
 use near_chain::types::ValidatorInfoIdentifier;
 use near_client_primitives::types::{Error, ShardSyncDownload, ShardSyncStatus};
 use near_network::types::PeerManagerMessageRequest;
+use near_network_primitives::time;
 use near_network_primitives::types::{
     AccountKeys, ChainInfo, PartialEncodedChunkForwardMsg, PartialEncodedChunkResponseMsg,
     SetChainInfo,
@@ -118,6 +122,8 @@ pub struct Client {
     /// Last time the head was updated, or our head was rebroadcasted. Used to re-broadcast the head
     /// again to prevent network from stalling if a large percentage of the network missed a block
     last_time_head_progress_made: Instant,
+    /// Clock the client reads time from, so that tests can run on virtual time.
+    clock: time::Clock,
 
     /// Block and chunk production timing information.
     /// used only for debug purposes.
@@ -127,6 +133,12 @@ pub struct Client {
     /// Cached precomputed set of TIER1 accounts.
     /// See send_network_chain_info().
     tier1_accounts_cache: Option<(EpochId, Arc<AccountKeys>)>,
+
+    /// Validates store invariants of every newly finalized block if online
+    /// store validation is enabled.
+    store_validator: Option<StoreValidator>,
+    /// Height of the last final block checked by `store_validator`.
+    last_store_validated_height: Option<BlockHeight>,
 }
 
 // Debug information about the upcoming block.
@@ -157,6 +169,7 @@ pub struct UpcomingBlockDebugStatus {
 
 impl Client {
     pub fn new(
+        clock: time::Clock,
         config: ClientConfig,
         chain_genesis: ChainGenesis,
         runtime_adapter: Arc<dyn RuntimeAdapter>,
@@ -170,13 +183,23 @@ impl Client {
         } else {
             DoomslugThresholdMode::NoApprovals
         };
-        let chain = Chain::new(
+        let mut chain = Chain::new(
             runtime_adapter.clone(),
             &chain_genesis,
             doomslug_threshold_mode,
             !config.archive,
         )?;
+        // Reject invalid column GC policies on start rather than on the first
+        // garbage collection.
+        ColumnGCPolicies::from_config(&config.gc)?;
+        chain.set_apply_chunks_executor(ApplyChunksExecutor::new(config.apply_chunks_threads)?);
+        if let Some(block_timeline_log) = &config.block_timeline_log {
+            chain
+                .blocks_delay_tracker
+                .set_timeline_log(BlockTimelineLog::open(block_timeline_log)?);
+        }
         let shards_mgr = ShardsManager::new(
+            clock.clone(),
             validator_signer.as_ref().map(|x| x.validator_id().clone()),
             runtime_adapter.clone(),
             network_adapter.clone(),
@@ -187,7 +210,6 @@ impl Client {
         let epoch_sync = EpochSync::new(
             network_adapter.clone(),
             genesis_block.header().epoch_id().clone(),
-            genesis_block.header().next_epoch_id().clone(),
             runtime_adapter
                 .get_epoch_block_producers_ordered(
                     genesis_block.header().epoch_id(),
@@ -213,6 +235,21 @@ impl Client {
         let data_parts = runtime_adapter.num_data_parts();
         let parity_parts = runtime_adapter.num_total_parts() - data_parts;
 
+        let store_validator = if config.enable_online_store_validation {
+            // The protocol config of the genesis epoch is the genesis config.
+            let genesis_config = runtime_adapter
+                .get_protocol_config(chain.genesis().header().epoch_id())?
+                .genesis_config;
+            Some(StoreValidator::new(
+                validator_signer.as_ref().map(|x| x.validator_id().clone()),
+                genesis_config,
+                runtime_adapter.clone(),
+                chain.store().store().clone(),
+                config.archive,
+            ))
+        } else {
+            None
+        };
         let doomslug = Doomslug::new(
             chain.store().largest_target_height()?,
             config.min_block_production_delay,
@@ -246,24 +283,74 @@ impl Client {
             challenges: Default::default(),
             rs: ReedSolomonWrapper::new(data_parts, parity_parts),
             rebroadcasted_blocks: lru::LruCache::new(NUM_REBROADCAST_BLOCKS),
-            last_time_head_progress_made: Clock::instant(),
+            last_time_head_progress_made: clock.now().into_inner(),
             block_production_times: lru::LruCache::new(PRODUCTION_TIMES_CACHE_SIZE),
             chunk_production_times: lru::LruCache::new(PRODUCTION_TIMES_CACHE_SIZE),
             tier1_accounts_cache: None,
+            store_validator,
+            last_store_validated_height: None,
+            clock,
         })
     }
 
+    /// Runs online store validation for the blocks which became final since
+    /// the last validation, up to the given final block.  Violations are
+    /// logged and exported as metrics; they never stop the node.
+    fn validate_store_for_final_block(&mut self, last_final_block: &CryptoHash) {
+        if self.store_validator.is_none() || last_final_block == &CryptoHash::default() {
+            return;
+        }
+        let final_height = match self.chain.get_block_header(last_final_block) {
+            Ok(header) => header.height(),
+            Err(err) => {
+                error!(target: "client", block_hash = ?last_final_block, ?err, "Can't read final block for store validation");
+                return;
+            }
+        };
+        // The final block may move by several heights at once, so all the
+        // canonical blocks in between are validated too, except for the ones
+        // already garbage collected.
+        let start_height = match self.last_store_validated_height {
+            Some(height) if height >= final_height => return,
+            Some(height) => max(height + 1, self.chain.tail().unwrap_or(height + 1)),
+            None => final_height,
+        };
+        self.last_store_validated_height = Some(final_height);
+        let block_hashes: Vec<_> = (start_height..=final_height)
+            .filter_map(|height| self.chain.get_block_hash_by_height(height).ok())
+            .collect();
+
+        let store_validator = match self.store_validator.as_mut() {
+            Some(store_validator) => store_validator,
+            None => return,
+        };
+        let _span = tracing::debug_span!(
+            target: "client",
+            "online_store_validation",
+            start_height,
+            final_height)
+        .entered();
+        let _timer = metrics::ONLINE_STORE_VALIDATION_TIME.start_timer();
+        for block_hash in block_hashes {
+            store_validator.validate_block(&block_hash);
+            for error in store_validator.errors.iter() {
+                metrics::ONLINE_STORE_VALIDATION_ERRORS.with_label_values(&[&error.col]).inc();
+                error!(target: "client", ?block_hash, col = %error.col, key = %error.key, err = %error.err, "Store validation failed");
+            }
+        }
+    }
+
     // Checks if it's been at least `stall_timeout` since the last time the head was updated, or
     // this method was called. If yes, rebroadcasts the current head.
     pub fn check_head_progress_stalled(&mut self, stall_timeout: Duration) -> Result<(), Error> {
-        if Clock::instant() > self.last_time_head_progress_made + stall_timeout
-            && !self.sync_status.is_syncing()
+        let now = self.clock.now().into_inner();
+        if now > self.last_time_head_progress_made + stall_timeout && !self.sync_status.is_syncing()
         {
             let block = self.chain.get_block(&self.chain.head()?.last_block_hash)?;
             self.network_adapter.do_send(PeerManagerMessageRequest::NetworkRequests(
                 NetworkRequests::Block { block: block },
             ));
-            self.last_time_head_progress_made = Clock::instant();
+            self.last_time_head_progress_made = now;
         }
         Ok(())
     }
@@ -506,9 +593,10 @@ impl Client {
         };
 
         #[cfg(feature = "sandbox")]
-        let timestamp_override = Some(Clock::utc() + self.sandbox_delta_time());
+        let timestamp_override =
+            Some(time::to_chrono(self.clock.now_utc()) + self.sandbox_delta_time());
         #[cfg(not(feature = "sandbox"))]
-        let timestamp_override = None;
+        let timestamp_override = Some(time::to_chrono(self.clock.now_utc()));
 
         // Get block extra from previous block.
         let block_merkle_tree = self.chain.store().get_block_merkle_tree(&prev_hash)?;
@@ -933,10 +1021,11 @@ impl Client {
         forward: PartialEncodedChunkForwardMsg,
         apply_chunks_done_callback: DoneApplyChunkCallback,
     ) -> Result<(), Error> {
-        let maybe_header = self
-            .shards_mgr
-            .validate_partial_encoded_chunk_forward(&forward)
-            .and_then(|_| self.shards_mgr.get_partial_encoded_chunk_header(&forward.chunk_hash));
+        let maybe_header =
+            self.shards_mgr.validate_partial_encoded_chunk_forward(&forward).and_then(|_| {
+                self.shards_mgr.track_forwarded_parts(&forward);
+                self.shards_mgr.get_partial_encoded_chunk_header(&forward.chunk_hash)
+            });
 
         let header = match maybe_header {
             Ok(header) => Ok(header),
@@ -1038,7 +1127,7 @@ impl Client {
             ProcessPartialEncodedChunkResult::HaveAllPartsAndReceipts => {
                 self.chain
                     .blocks_delay_tracker
-                    .mark_chunk_received(&header.chunk_hash(), Clock::instant());
+                    .mark_chunk_received(&header.chunk_hash(), self.clock.now().into_inner());
                 // We're marking chunk as accepted.
                 self.chain.blocks_with_missing_chunks.accept_chunk(&header.chunk_hash());
                 // If this was the last chunk that was missing for a block, it will be processed now.
@@ -1072,7 +1161,7 @@ impl Client {
                 self.chain.get_block_header(&last_final_hash)?.height()
             };
             self.doomslug.set_tip(
-                Clock::instant(),
+                self.clock.now().into_inner(),
                 tip.last_block_hash,
                 tip.height,
                 last_final_height,
@@ -1093,7 +1182,12 @@ impl Client {
         } else {
             self.chain.get_block_header(&last_final_hash)?.height()
         };
-        self.doomslug.set_tip(Clock::instant(), tip.last_block_hash, height, last_final_height);
+        self.doomslug.set_tip(
+            self.clock.now().into_inner(),
+            tip.last_block_hash,
+            height,
+            last_final_height,
+        );
 
         Ok(())
     }
@@ -1231,6 +1325,8 @@ impl Client {
                 log_assert!(result.is_ok(), "Can't clear old data, {:?}", result);
             }
 
+            self.validate_store_for_final_block(last_final_block);
+
             if self.runtime_adapter.is_next_block_epoch_start(block.hash()).unwrap_or(false) {
                 let next_epoch_protocol_version = unwrap_or_return!(self
                     .runtime_adapter
@@ -1239,6 +1335,9 @@ impl Client {
                     panic!("The client protocol version is older than the protocol version of the network. Please update nearcore");
                 }
             }
+            if let Err(err) = self.precompile_contracts_for_next_epoch(block.header()) {
+                warn!(target: "client", ?err, "Failed to start precompiling contracts for the next epoch");
+            }
             // send_network_chain_info should be called whenever the chain head changes.
             // See send_network_chain_info() for more details.
             if let Err(err) = self.send_network_chain_info() {
@@ -1375,12 +1474,41 @@ impl Client {
         self.chain.blocks_delay_tracker.finish_block_processing(&block_hash, &chunk_hashes);
     }
 
+    /// If the protocol version of the next epoch is different, starts precompiling in the
+    /// background the contracts of the shards we track for it, so that the first calls of the
+    /// contracts after the upgrade don't have to compile them.
+    fn precompile_contracts_for_next_epoch(&self, header: &BlockHeader) -> Result<(), Error> {
+        let epoch_id = header.epoch_id();
+        let next_epoch_id = header.next_epoch_id();
+        if self.runtime_adapter.get_epoch_protocol_version(epoch_id)?
+            == self.runtime_adapter.get_epoch_protocol_version(next_epoch_id)?
+        {
+            return Ok(());
+        }
+        let me = self.validator_signer.as_ref().map(|signer| signer.validator_id());
+        let mut state_roots = vec![];
+        for shard_id in 0..self.runtime_adapter.num_shards(epoch_id)? {
+            if !self.runtime_adapter.cares_about_shard(me, header.prev_hash(), shard_id, true) {
+                continue;
+            }
+            let shard_uid = self.runtime_adapter.shard_id_to_uid(shard_id, epoch_id)?;
+            let chunk_extra = self.chain.get_chunk_extra(header.hash(), &shard_uid)?;
+            state_roots.push((shard_uid, *chunk_extra.state_root()));
+        }
+        self.runtime_adapter.precompile_contracts_for_next_epoch(
+            epoch_id,
+            next_epoch_id,
+            state_roots,
+        )?;
+        Ok(())
+    }
+
     pub fn request_missing_chunks(
         &mut self,
         blocks_missing_chunks: Vec<BlockMissingChunks>,
         orphans_missing_chunks: Vec<OrphanMissingChunks>,
     ) {
-        let now = Clock::instant();
+        let now = self.clock.now().into_inner();
         for BlockMissingChunks { prev_hash, missing_chunks, block_hash } in blocks_missing_chunks {
             for chunk in &missing_chunks {
                 self.chain.blocks_delay_tracker.mark_chunk_requested(
@@ -1599,7 +1727,11 @@ impl Client {
                     return;
                 }
             };
-        self.doomslug.on_approval_message(Clock::instant(), approval, &block_producer_stakes);
+        self.doomslug.on_approval_message(
+            self.clock.now().into_inner(),
+            approval,
+            &block_producer_stakes,
+        );
     }
 
     /// Forwards given transaction to upcoming validators.
@@ -1718,13 +1850,15 @@ impl Client {
             return Ok(NetworkClientResponses::InvalidTx(e));
         }
         let gas_price = cur_block_header.gas_price();
+        // The transaction can be included in a chunk of the next block at the earliest.
+        let min_block_height = head.height + 1;
         let epoch_id = self.runtime_adapter.get_epoch_id_from_prev_block(&head.last_block_hash)?;
 
         let protocol_version = self.runtime_adapter.get_epoch_protocol_version(&epoch_id)?;
 
         if let Some(err) = self
             .runtime_adapter
-            .validate_tx(gas_price, None, tx, true, &epoch_id, protocol_version)
+            .validate_tx(gas_price, None, tx, true, min_block_height, &epoch_id, protocol_version)
             .expect("no storage errors")
         {
             debug!(target: "client", "Invalid tx during basic validation: {:?}", err);
@@ -1752,7 +1886,15 @@ impl Client {
             };
             if let Some(err) = self
                 .runtime_adapter
-                .validate_tx(gas_price, Some(state_root), tx, false, &epoch_id, protocol_version)
+                .validate_tx(
+                    gas_price,
+                    Some(state_root),
+                    tx,
+                    false,
+                    min_block_height,
+                    &epoch_id,
+                    protocol_version,
+                )
                 .expect("no storage errors")
             {
                 debug!(target: "client", "Invalid tx: {:?}", err);
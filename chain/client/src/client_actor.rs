@@ -19,8 +19,8 @@ use near_chain::chain::{
 use near_chain::test_utils::format_hash;
 use near_chain::types::ValidatorInfoIdentifier;
 use near_chain::{
-    byzantine_assert, near_chain_primitives, Block, BlockHeader, BlockProcessingArtifact,
-    ChainGenesis, DoneApplyChunkCallback, Provenance, RuntimeAdapter,
+    byzantine_assert, near_chain_primitives, ApplyChunksExecutor, Block, BlockHeader,
+    BlockProcessingArtifact, ChainGenesis, DoneApplyChunkCallback, Provenance, RuntimeAdapter,
 };
 use near_chain_configs::ClientConfig;
 use near_client_primitives::types::{
@@ -34,6 +34,7 @@ use near_network::types::{
     NetworkClientMessages, NetworkClientResponses, NetworkInfo, NetworkRequests,
     PeerManagerAdapter, PeerManagerMessageRequest,
 };
+use near_network_primitives::time;
 use near_network_primitives::types::ReasonForBan;
 use near_performance_metrics;
 use near_performance_metrics_macros::{perf, perf_with_debug};
@@ -160,6 +161,7 @@ impl ClientActor {
         }
         let info_helper = InfoHelper::new(Some(telemetry_actor), &config, validator_signer.clone());
         let client = Client::new(
+            time::Clock::real(),
             config,
             chain_genesis,
             runtime_adapter,
@@ -169,6 +171,8 @@ impl ClientActor {
             rng_seed,
         )?;
 
+        let apply_chunks_executor = client.chain.apply_chunks_executor().clone();
+
         let now = Utc::now();
         Ok(ClientActor {
             adv,
@@ -195,13 +199,17 @@ impl ClientActor {
             chunk_request_retry_next_attempt: now,
             sync_started: false,
             state_parts_task_scheduler: create_sync_job_scheduler::<ApplyStatePartsRequest>(
-                sync_jobs_actor_addr.clone(),
+                sync_jobs_actor_addr,
             ),
-            block_catch_up_scheduler: create_sync_job_scheduler::<BlockCatchUpRequest>(
-                sync_jobs_actor_addr.clone(),
+            block_catch_up_scheduler: create_apply_chunks_job_scheduler(
+                apply_chunks_executor.clone(),
+                ctx.address(),
+                do_block_catch_up,
             ),
-            state_split_scheduler: create_sync_job_scheduler::<StateSplitRequest>(
-                sync_jobs_actor_addr,
+            state_split_scheduler: create_apply_chunks_job_scheduler(
+                apply_chunks_executor,
+                ctx.address(),
+                do_state_split,
             ),
             state_parts_client_arbiter: state_parts_arbiter,
 
@@ -232,6 +240,25 @@ where
     })
 }
 
+/// Creates a scheduler running `job` on the apply chunks executor and sending
+/// its result back to the client actor.
+fn create_apply_chunks_job_scheduler<M, R>(
+    executor: ApplyChunksExecutor,
+    client_addr: Addr<ClientActor>,
+    job: fn(M) -> R,
+) -> Box<dyn Fn(M)>
+where
+    M: Send + 'static,
+    R: Message + Send + 'static,
+    R::Result: Send,
+    ClientActor: Handler<R>,
+{
+    Box::new(move |msg: M| {
+        let client_addr = client_addr.clone();
+        executor.spawn(move || client_addr.do_send(job(msg)));
+    })
+}
+
 impl Actor for ClientActor {
     type Context = Context<Self>;
 
@@ -550,13 +577,39 @@ impl ClientActor {
 
                 NetworkClientResponses::NoResponse
             }
-            NetworkClientMessages::EpochSyncResponse(_peer_id, _response) => {
-                // TODO #3488
-                NetworkClientResponses::NoResponse
+            NetworkClientMessages::EpochSyncResponse(peer_id, response) => {
+                match self.client.epoch_sync.on_response(&peer_id, *response) {
+                    Ok(()) => NetworkClientResponses::NoResponse,
+                    Err(err) if err.is_bad_data() => {
+                        warn!(target: "sync", "Epoch sync: invalid response from {}: {}", peer_id, err);
+                        NetworkClientResponses::Ban {
+                            ban_reason: ReasonForBan::EpochSyncInvalidResponse,
+                        }
+                    }
+                    Err(err) => {
+                        error!(target: "sync", "Epoch sync: failed to process response from {}: {}", peer_id, err);
+                        NetworkClientResponses::NoResponse
+                    }
+                }
             }
-            NetworkClientMessages::EpochSyncFinalizationResponse(_peer_id, _response) => {
-                // TODO #3488
-                NetworkClientResponses::NoResponse
+            NetworkClientMessages::EpochSyncFinalizationResponse(peer_id, response) => {
+                match self.client.epoch_sync.on_finalization_response(
+                    &peer_id,
+                    *response,
+                    &mut self.client.chain,
+                ) {
+                    Ok(()) => NetworkClientResponses::NoResponse,
+                    Err(err) if err.is_bad_data() => {
+                        warn!(target: "sync", "Epoch sync: invalid finalization from {}: {}", peer_id, err);
+                        NetworkClientResponses::Ban {
+                            ban_reason: ReasonForBan::EpochSyncInvalidFinalizationResponse,
+                        }
+                    }
+                    Err(err) => {
+                        error!(target: "sync", "Epoch sync: failed to finalize with data from {}: {}", peer_id, err);
+                        NetworkClientResponses::NoResponse
+                    }
+                }
             }
             NetworkClientMessages::PartialEncodedChunkRequest(part_request_msg, route_back) => {
                 let _ = self.client.shards_mgr.process_partial_encoded_chunk_request(
@@ -1605,6 +1658,17 @@ impl ClientActor {
                 self.check_send_announce_account(head.prev_block_hash);
             }
             wait_period = self.client.config.sync_check_period;
+        } else if self.client.config.epoch_sync_enabled
+            && !self.client.config.archive
+            && !self.client.epoch_sync.done
+        {
+            // New nodes first prove the validator set of the latest epoch, header sync and state
+            // sync continue from there once epoch sync is done.
+            unwrap_or_run_later!(self.client.epoch_sync.run(
+                &mut self.client.sync_status,
+                &self.client.chain,
+                &self.network_info.highest_height_peers
+            ));
         } else {
             // Run each step of syncing separately.
             unwrap_or_run_later!(self.client.header_sync.run(
@@ -1872,21 +1936,12 @@ impl Handler<ApplyStatePartsResponse> for ClientActor {
     }
 }
 
-impl Handler<BlockCatchUpRequest> for SyncJobsActor {
-    type Result = ();
-
-    fn handle(&mut self, msg: BlockCatchUpRequest, _: &mut Self::Context) -> Self::Result {
-        let _span =
-            tracing::debug_span!(target: "client", "handle", handler = "BlockCatchUpRequest")
-                .entered();
-        let results = do_apply_chunks(msg.block_hash, msg.block_height, msg.work);
-
-        self.client_addr.do_send(BlockCatchUpResponse {
-            sync_hash: msg.sync_hash,
-            block_hash: msg.block_hash,
-            results,
-        });
-    }
+fn do_block_catch_up(msg: BlockCatchUpRequest) -> BlockCatchUpResponse {
+    let _span =
+        tracing::debug_span!(target: "client", "do_block_catch_up", block_hash = %msg.block_hash)
+            .entered();
+    let results = do_apply_chunks(msg.block_hash, msg.block_height, msg.work);
+    BlockCatchUpResponse { sync_hash: msg.sync_hash, block_hash: msg.block_hash, results }
 }
 
 impl Handler<BlockCatchUpResponse> for ClientActor {
@@ -1904,24 +1959,15 @@ impl Handler<BlockCatchUpResponse> for ClientActor {
     }
 }
 
-impl Handler<StateSplitRequest> for SyncJobsActor {
-    type Result = ();
-
-    fn handle(&mut self, msg: StateSplitRequest, _: &mut Self::Context) -> Self::Result {
-        let _span = tracing::debug_span!(target: "client", "handle", handler = "StateSplitRequest")
-            .entered();
-        let results = msg.runtime.build_state_for_split_shards(
-            msg.shard_uid,
-            &msg.state_root,
-            &msg.next_epoch_shard_layout,
-        );
-
-        self.client_addr.do_send(StateSplitResponse {
-            sync_hash: msg.sync_hash,
-            shard_id: msg.shard_id,
-            new_state_roots: results,
-        });
-    }
+fn do_state_split(msg: StateSplitRequest) -> StateSplitResponse {
+    let _span =
+        tracing::debug_span!(target: "client", "do_state_split", shard_id = msg.shard_id).entered();
+    let new_state_roots = msg.runtime.build_state_for_split_shards(
+        msg.shard_uid,
+        &msg.state_root,
+        &msg.next_epoch_shard_layout,
+    );
+    StateSplitResponse { sync_hash: msg.sync_hash, shard_id: msg.shard_id, new_state_roots }
 }
 
 impl Handler<StateSplitResponse> for ClientActor {
@@ -61,6 +61,12 @@ impl Handler<DebugStatus> for ClientActor {
             DebugStatus::ValidatorStatus => {
                 Ok(DebugStatusResponse::ValidatorStatus(self.get_validator_status()?))
             }
+            DebugStatus::ContractPrecompilation => Ok(DebugStatusResponse::ContractPrecompilation(
+                self.client.runtime_adapter.get_contract_precompilation_progress(),
+            )),
+            DebugStatus::BlockTimelines => Ok(DebugStatusResponse::BlockTimelines(
+                self.client.chain.blocks_delay_tracker.finished_blocks.iter().cloned().collect(),
+            )),
         }
     }
 }
@@ -68,6 +68,23 @@ pub(crate) static GC_TIME: Lazy<Histogram> = Lazy::new(|| {
     try_create_histogram("near_gc_time", "Time taken to do garbage collection").unwrap()
 });
 
+pub(crate) static ONLINE_STORE_VALIDATION_TIME: Lazy<Histogram> = Lazy::new(|| {
+    try_create_histogram(
+        "near_online_store_validation_time",
+        "Time taken to validate the store for newly finalized blocks",
+    )
+    .unwrap()
+});
+
+pub(crate) static ONLINE_STORE_VALIDATION_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_online_store_validation_errors_total",
+        "Number of store invariant violations found by online store validation",
+        &["col"],
+    )
+    .unwrap()
+});
+
 // Deprecated.
 pub(crate) static AVG_TGAS_USAGE: Lazy<IntGauge> = Lazy::new(|| {
     try_create_int_gauge(
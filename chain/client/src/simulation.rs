@@ -0,0 +1,533 @@
+//! Deterministic simulation of a network of clients.
+//!
+//! `Simulation` wraps a `TestEnv` and plays the role of both the actix timers and the network:
+//! it produces blocks at a fixed pace of virtual time and delivers the messages that clients
+//! send to each other through simulated links with configurable latency, jitter and loss.
+//! Nodes can be partitioned from each other and the partition healed later.
+//!
+//! Time is virtual: events are kept in a queue ordered by the `FakeClock` time at which they
+//! happen, so simulating minutes of network activity takes as long as processing the blocks
+//! and chunks involved. The clients read time from the same `FakeClock`, so their timeouts
+//! (e.g. chunk request retries) and the timestamps of the blocks they produce follow the
+//! virtual time too. All random decisions (jitter, loss, choice of a peer for requests
+//! without a target account) are drawn from a single seeded RNG and messages sent by a node in
+//! one step are put in a canonical order before that, so running a scenario twice with the same
+//! seed produces the same chain and delivers the same messages at the same virtual times.
+//!
+//! The simulation drives `Client`s directly instead of running `ClientActor` and
+//! `PeerManagerActor`: actix schedules actors and their timers on the real clock, which would
+//! make runs non-deterministic. So only what passes between clients is simulated: block
+//! propagation (with requests for the parents of orphans), approvals and chunk distribution
+//! (parts, forwards, requests and responses). The peer manager (handshakes, routing, peer
+//! discovery) and the sync loops of `ClientActor` (header, block and state sync) are not part
+//! of it. A node catching up after a partition does so by requesting missing blocks one by one.
+//!
+//! ```ignore
+//! let builder = TestEnv::builder(ChainGenesis::test()).clients_count(4).validator_seats(4);
+//! let mut sim = Simulation::new(builder, 42);
+//! sim.set_default_link(LinkConfig { latency: time::Duration::milliseconds(50), ..Default::default() });
+//! sim.partition(&[&[0, 1], &[2, 3]]);
+//! sim.run_for(time::Duration::seconds(5));
+//! sim.heal();
+//! assert!(sim.run_until(time::Duration::seconds(10), |env| /* all heads are equal */ true));
+//! ```
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tracing::debug;
+
+use near_chain::test_utils::wait_for_all_blocks_in_processing;
+use near_chain::Provenance;
+use near_crypto::{KeyType, SecretKey};
+use near_network::types::{NetworkRequests, PeerManagerMessageRequest};
+use near_network_primitives::time;
+use near_network_primitives::types::{
+    PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg,
+};
+use near_primitives::block::{Approval, Block};
+use near_primitives::block_header::ApprovalType;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::network::PeerId;
+use near_primitives::sharding::PartialEncodedChunk;
+use near_primitives::types::{AccountId, BlockHeight};
+use near_primitives::utils::MaybeValidated;
+
+use crate::test_utils::{TestEnv, TestEnvBuilder};
+
+/// Default time between consecutive block heights.
+const DEFAULT_BLOCK_PRODUCTION_DELAY: time::Duration = time::Duration::milliseconds(600);
+/// How often clients are asked to resend chunk requests they didn't get responses to.
+const RESEND_CHUNK_REQUESTS_INTERVAL: time::Duration = time::Duration::milliseconds(100);
+
+/// Properties of a directed link between two nodes.
+#[derive(Clone, Debug)]
+pub struct LinkConfig {
+    /// Minimal time it takes a message to get through the link.
+    pub latency: time::Duration,
+    /// Maximal random delay added on top of `latency`.
+    pub jitter: time::Duration,
+    /// Probability that a message sent through the link is lost.
+    pub loss: f64,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        Self { latency: time::Duration::milliseconds(10), jitter: time::Duration::ZERO, loss: 0.0 }
+    }
+}
+
+/// A message delivered by the simulated network, recorded for inspecting the run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeliveredMessage {
+    /// Virtual time since the start of the simulation.
+    pub at: time::Duration,
+    pub from: usize,
+    pub to: usize,
+    pub kind: &'static str,
+    pub height: Option<BlockHeight>,
+}
+
+#[derive(Clone, Debug)]
+enum Message {
+    Block { block: Block, requested: bool },
+    BlockRequest(CryptoHash),
+    Approval(Approval),
+    PartialEncodedChunk(PartialEncodedChunk),
+    PartialEncodedChunkForward(PartialEncodedChunkForwardMsg),
+    PartialEncodedChunkRequest(PartialEncodedChunkRequestMsg),
+    PartialEncodedChunkResponse(PartialEncodedChunkResponseMsg),
+}
+
+impl Message {
+    fn kind(&self) -> &'static str {
+        match self {
+            Message::Block { .. } => "Block",
+            Message::BlockRequest(_) => "BlockRequest",
+            Message::Approval(_) => "Approval",
+            Message::PartialEncodedChunk(_) => "PartialEncodedChunk",
+            Message::PartialEncodedChunkForward(_) => "PartialEncodedChunkForward",
+            Message::PartialEncodedChunkRequest(_) => "PartialEncodedChunkRequest",
+            Message::PartialEncodedChunkResponse(_) => "PartialEncodedChunkResponse",
+        }
+    }
+
+    fn height(&self) -> Option<BlockHeight> {
+        match self {
+            Message::Block { block, .. } => Some(block.header().height()),
+            Message::Approval(approval) => Some(approval.target_height),
+            Message::PartialEncodedChunk(chunk) => Some(chunk.cloned_header().height_created()),
+            Message::PartialEncodedChunkForward(forward) => Some(forward.height_created),
+            Message::BlockRequest(_)
+            | Message::PartialEncodedChunkRequest(_)
+            | Message::PartialEncodedChunkResponse(_) => None,
+        }
+    }
+
+    fn part_ords(&self) -> Vec<u64> {
+        let mut part_ords: Vec<u64> = match self {
+            Message::PartialEncodedChunk(chunk) => {
+                chunk.parts().iter().map(|part| part.part_ord).collect()
+            }
+            Message::PartialEncodedChunkForward(forward) => {
+                forward.parts.iter().map(|part| part.part_ord).collect()
+            }
+            Message::PartialEncodedChunkRequest(request) => request.part_ords.clone(),
+            Message::PartialEncodedChunkResponse(response) => {
+                response.parts.iter().map(|part| part.part_ord).collect()
+            }
+            _ => vec![],
+        };
+        part_ords.sort();
+        part_ords
+    }
+}
+
+enum Event {
+    Deliver { from: usize, to: usize, message: Message },
+    ProduceBlocks(BlockHeight),
+    ResendChunkRequests,
+}
+
+/// Simulation of a network of the clients in a `TestEnv`. See the module documentation.
+pub struct Simulation {
+    pub env: TestEnv,
+    clock: time::FakeClock,
+    start: time::Instant,
+    rng: StdRng,
+    block_production_delay: time::Duration,
+    default_link: LinkConfig,
+    links: HashMap<(usize, usize), LinkConfig>,
+    /// Partition group of every node; nodes can only talk within their group.
+    partition: Option<Vec<usize>>,
+    events: BTreeMap<(time::Instant, u64), Event>,
+    next_event_id: u64,
+    peer_ids: Vec<PeerId>,
+    /// `route_back` hash identifying every node in chunk requests.
+    route_backs: Vec<CryptoHash>,
+    account_to_node: HashMap<AccountId, usize>,
+    delivered: Vec<DeliveredMessage>,
+}
+
+impl Simulation {
+    /// Creates a simulation of the clients built by `builder`, with all random decisions
+    /// derived from `seed`. The clients are built on the simulation's virtual clock, which
+    /// starts at the genesis time. Block production starts at height 1 right away.
+    pub fn new(builder: TestEnvBuilder, seed: u64) -> Self {
+        let clock = time::FakeClock::default();
+        let env = builder.clock(clock.clock()).build();
+        let num_nodes = env.clients.len();
+        let account_ids: Vec<AccountId> =
+            (0..num_nodes).map(|idx| env.get_client_id(idx).clone()).collect();
+        let peer_ids = account_ids
+            .iter()
+            .map(|account_id| {
+                PeerId::new(
+                    SecretKey::from_seed(KeyType::ED25519, account_id.as_ref()).public_key(),
+                )
+            })
+            .collect();
+        let route_backs = (0..num_nodes as u64).map(|idx| hash(&idx.to_le_bytes())).collect();
+        let account_to_node = account_ids
+            .into_iter()
+            .enumerate()
+            .map(|(idx, account_id)| (account_id, idx))
+            .collect();
+        let mut sim = Self {
+            env,
+            start: clock.now(),
+            clock,
+            rng: StdRng::seed_from_u64(seed),
+            block_production_delay: DEFAULT_BLOCK_PRODUCTION_DELAY,
+            default_link: LinkConfig::default(),
+            links: HashMap::new(),
+            partition: None,
+            events: BTreeMap::new(),
+            next_event_id: 0,
+            peer_ids,
+            route_backs,
+            account_to_node,
+            delivered: vec![],
+        };
+        sim.schedule(time::Duration::ZERO, Event::ProduceBlocks(1));
+        sim.schedule(RESEND_CHUNK_REQUESTS_INTERVAL, Event::ResendChunkRequests);
+        sim
+    }
+
+    /// Sets the time between consecutive block heights.
+    pub fn set_block_production_delay(&mut self, delay: time::Duration) {
+        self.block_production_delay = delay;
+    }
+
+    /// Sets the properties of all links which weren't configured with `set_link`.
+    pub fn set_default_link(&mut self, link: LinkConfig) {
+        self.default_link = link;
+    }
+
+    /// Sets the properties of the link from node `from` to node `to`.
+    pub fn set_link(&mut self, from: usize, to: usize, link: LinkConfig) {
+        self.links.insert((from, to), link);
+    }
+
+    /// Splits the network into the given groups of nodes. Messages between nodes in different
+    /// groups are dropped. Nodes not listed in any group are isolated from all other nodes.
+    pub fn partition(&mut self, groups: &[&[usize]]) {
+        let num_nodes = self.env.clients.len();
+        let mut partition: Vec<usize> = (groups.len()..groups.len() + num_nodes).collect();
+        for (group_id, group) in groups.iter().enumerate() {
+            for node in group.iter() {
+                partition[*node] = group_id;
+            }
+        }
+        self.partition = Some(partition);
+    }
+
+    /// Removes the partition set up by `partition`.
+    pub fn heal(&mut self) {
+        self.partition = None;
+    }
+
+    /// Virtual time elapsed since the start of the simulation.
+    pub fn elapsed(&self) -> time::Duration {
+        self.clock.now() - self.start
+    }
+
+    /// Messages delivered so far, in the order of delivery.
+    pub fn delivered(&self) -> &[DeliveredMessage] {
+        &self.delivered
+    }
+
+    /// Runs the simulation for `duration` of virtual time.
+    pub fn run_for(&mut self, duration: time::Duration) {
+        let deadline = self.clock.now() + duration;
+        while self.step(deadline) {}
+        self.clock.advance(deadline - self.clock.now());
+    }
+
+    /// Runs the simulation until `condition` holds, but for at most `timeout` of virtual
+    /// time. Returns whether the condition was met.
+    pub fn run_until(
+        &mut self,
+        timeout: time::Duration,
+        mut condition: impl FnMut(&TestEnv) -> bool,
+    ) -> bool {
+        let deadline = self.clock.now() + timeout;
+        loop {
+            if condition(&self.env) {
+                return true;
+            }
+            if !self.step(deadline) {
+                self.clock.advance(deadline - self.clock.now());
+                return condition(&self.env);
+            }
+        }
+    }
+
+    fn schedule(&mut self, delay: time::Duration, event: Event) {
+        let at = self.clock.now() + delay;
+        self.events.insert((at, self.next_event_id), event);
+        self.next_event_id += 1;
+    }
+
+    /// Processes the next event, if it happens before `deadline`.
+    fn step(&mut self, deadline: time::Instant) -> bool {
+        let key = match self.events.keys().next() {
+            Some(key) if key.0 <= deadline => *key,
+            _ => return false,
+        };
+        let event = self.events.remove(&key).unwrap();
+        self.clock.advance(key.0 - self.clock.now());
+        match event {
+            Event::Deliver { from, to, message } => {
+                self.delivered.push(DeliveredMessage {
+                    at: self.elapsed(),
+                    from,
+                    to,
+                    kind: message.kind(),
+                    height: message.height(),
+                });
+                self.deliver(from, to, message);
+                self.postprocess_blocks(to);
+            }
+            Event::ProduceBlocks(height) => {
+                for node in 0..self.env.clients.len() {
+                    self.produce_block(node, height);
+                    self.postprocess_blocks(node);
+                }
+                self.schedule(self.block_production_delay, Event::ProduceBlocks(height + 1));
+            }
+            Event::ResendChunkRequests => {
+                for client in self.env.clients.iter_mut() {
+                    if let Ok(header_head) = client.chain.header_head() {
+                        client.shards_mgr.resend_chunk_requests(&header_head);
+                    }
+                }
+                self.schedule(RESEND_CHUNK_REQUESTS_INTERVAL, Event::ResendChunkRequests);
+            }
+        }
+        self.collect_outgoing_messages();
+        true
+    }
+
+    fn produce_block(&mut self, node: usize, height: BlockHeight) {
+        let client = &mut self.env.clients[node];
+        let block = match client.produce_block(height) {
+            Ok(Some(block)) => block,
+            Ok(None) => return,
+            Err(err) => {
+                debug!(target: "simulation", node, height, ?err, "Failed to produce block");
+                return;
+            }
+        };
+        if let Err(err) = client.start_process_block(
+            MaybeValidated::from(block.clone()),
+            Provenance::PRODUCED,
+            Arc::new(|_| {}),
+        ) {
+            debug!(target: "simulation", node, height, ?err, "Failed to process produced block");
+        }
+        self.broadcast(node, Message::Block { block, requested: false });
+    }
+
+    fn deliver(&mut self, from: usize, to: usize, message: Message) {
+        let client = &mut self.env.clients[to];
+        let result = match message {
+            Message::Block { block, requested } => {
+                let prev_hash = *block.header().prev_hash();
+                let provenance = if requested { Provenance::SYNC } else { Provenance::NONE };
+                match client.start_process_block(
+                    MaybeValidated::from(block),
+                    provenance,
+                    Arc::new(|_| {}),
+                ) {
+                    Err(near_chain::Error::Orphan) => {
+                        if !client.chain.is_orphan(&prev_hash) {
+                            self.send(to, from, Message::BlockRequest(prev_hash));
+                        }
+                        Ok(())
+                    }
+                    // Missing chunks were requested while processing the block.
+                    Err(near_chain::Error::ChunksMissing(_)) => Ok(()),
+                    result => result.map_err(|err| err.to_string()),
+                }
+            }
+            Message::BlockRequest(hash) => {
+                if let Ok(block) = client.chain.get_block(&hash) {
+                    self.send(to, from, Message::Block { block, requested: true });
+                }
+                Ok(())
+            }
+            Message::Approval(approval) => {
+                client.collect_block_approval(
+                    &approval,
+                    ApprovalType::PeerApproval(self.peer_ids[from].clone()),
+                );
+                Ok(())
+            }
+            Message::PartialEncodedChunk(chunk) => client
+                .process_partial_encoded_chunk(MaybeValidated::from(chunk), Arc::new(|_| {}))
+                .map_err(|err| err.to_string()),
+            Message::PartialEncodedChunkForward(forward) => client
+                .process_partial_encoded_chunk_forward(forward, Arc::new(|_| {}))
+                .map_err(|err| err.to_string()),
+            Message::PartialEncodedChunkRequest(request) => {
+                client.shards_mgr.process_partial_encoded_chunk_request(
+                    request,
+                    self.route_backs[from],
+                    client.chain.mut_store(),
+                    &mut client.rs,
+                );
+                Ok(())
+            }
+            Message::PartialEncodedChunkResponse(response) => client
+                .process_partial_encoded_chunk_response(response, Arc::new(|_| {}))
+                .map_err(|err| err.to_string()),
+        };
+        if let Err(err) = result {
+            debug!(target: "simulation", from, to, %err, "Failed to process message");
+        }
+    }
+
+    /// Finishes processing of the blocks whose chunks were applied, the way `ClientActor`
+    /// does when it is notified about it.
+    fn postprocess_blocks(&mut self, node: usize) {
+        let client = &mut self.env.clients[node];
+        wait_for_all_blocks_in_processing(&mut client.chain);
+        let (_, errors) = client.postprocess_ready_blocks(Arc::new(|_| {}), true);
+        for (hash, err) in errors {
+            debug!(target: "simulation", node, ?hash, ?err, "Failed to process block");
+        }
+    }
+
+    /// Sends out the messages clients passed to their network adapters.
+    fn collect_outgoing_messages(&mut self) {
+        for from in 0..self.env.clients.len() {
+            let mut outgoing = vec![];
+            while let Some(request) = self.env.network_adapters[from].pop() {
+                let request = match request {
+                    PeerManagerMessageRequest::NetworkRequests(request) => request,
+                    _ => continue,
+                };
+                match request {
+                    NetworkRequests::Block { block } => {
+                        for to in 0..self.env.clients.len() {
+                            if to != from {
+                                outgoing.push((
+                                    to,
+                                    Message::Block { block: block.clone(), requested: false },
+                                ));
+                            }
+                        }
+                    }
+                    NetworkRequests::Approval { approval_message } => {
+                        if let Some(to) = self.account_to_node.get(&approval_message.target) {
+                            outgoing.push((*to, Message::Approval(approval_message.approval)));
+                        }
+                    }
+                    NetworkRequests::BlockRequest { hash, peer_id } => {
+                        if let Some(to) = self.peer_ids.iter().position(|id| id == &peer_id) {
+                            outgoing.push((to, Message::BlockRequest(hash)));
+                        }
+                    }
+                    NetworkRequests::PartialEncodedChunkRequest { target, request, .. } => {
+                        let to = match target.account_id {
+                            Some(account_id) => self.account_to_node.get(&account_id).copied(),
+                            None => self.random_peer(from),
+                        };
+                        if let Some(to) = to {
+                            outgoing.push((to, Message::PartialEncodedChunkRequest(request)));
+                        }
+                    }
+                    NetworkRequests::PartialEncodedChunkResponse { route_back, response } => {
+                        if let Some(to) = self.route_backs.iter().position(|r| r == &route_back) {
+                            outgoing.push((to, Message::PartialEncodedChunkResponse(response)));
+                        }
+                    }
+                    NetworkRequests::PartialEncodedChunkMessage {
+                        account_id,
+                        partial_encoded_chunk,
+                    } => {
+                        if let Some(to) = self.account_to_node.get(&account_id) {
+                            outgoing.push((
+                                *to,
+                                Message::PartialEncodedChunk(partial_encoded_chunk.into()),
+                            ));
+                        }
+                    }
+                    NetworkRequests::PartialEncodedChunkForward { account_id, forward } => {
+                        if let Some(to) = self.account_to_node.get(&account_id) {
+                            outgoing.push((*to, Message::PartialEncodedChunkForward(forward)));
+                        }
+                    }
+                    request => {
+                        debug!(target: "simulation", from, ?request, "Dropping unsupported request")
+                    }
+                }
+            }
+            // Clients may emit messages in an order that depends on hash map iteration, so put
+            // them in a canonical order before random delays are assigned.
+            outgoing.sort_by_cached_key(|(to, message)| {
+                (*to, message.kind(), message.height(), message.part_ords())
+            });
+            for (to, message) in outgoing {
+                self.send(from, to, message);
+            }
+        }
+    }
+
+    fn random_peer(&mut self, node: usize) -> Option<usize> {
+        let num_nodes = self.env.clients.len();
+        if num_nodes < 2 {
+            return None;
+        }
+        let peer = self.rng.gen_range(0, num_nodes - 1);
+        Some(if peer >= node { peer + 1 } else { peer })
+    }
+
+    fn broadcast(&mut self, from: usize, message: Message) {
+        for to in 0..self.env.clients.len() {
+            if to != from {
+                self.send(from, to, message.clone());
+            }
+        }
+    }
+
+    /// Sends a message through the link between the nodes, subject to the link's latency and
+    /// loss and to the current partition.
+    fn send(&mut self, from: usize, to: usize, message: Message) {
+        if let Some(partition) = &self.partition {
+            if partition[from] != partition[to] {
+                return;
+            }
+        }
+        let link = self.links.get(&(from, to)).unwrap_or(&self.default_link).clone();
+        if self.rng.gen_bool(link.loss) {
+            return;
+        }
+        let jitter_us = link.jitter.whole_microseconds().max(0) as u64;
+        let jitter = if jitter_us > 0 { self.rng.gen_range(0, jitter_us + 1) } else { 0 };
+        let delay = link.latency + time::Duration::microseconds(jitter as i64);
+        self.schedule(delay, Event::Deliver { from, to, message });
+    }
+}
@@ -13,12 +13,14 @@ use rand::seq::{IteratorRandom, SliceRandom};
 use rand::{thread_rng, Rng};
 use tracing::{debug, error, info, warn};
 
-use near_chain::{Chain, RuntimeAdapter};
+use near_chain::{validate_light_client_block, Chain, RuntimeAdapter};
 use near_network::types::{FullPeerInfo, NetworkRequests, NetworkResponses, PeerManagerAdapter};
 use near_primitives::block::Tip;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::PeerId;
-use near_primitives::syncing::get_num_state_parts;
+use near_primitives::syncing::{
+    get_num_state_parts, EpochSyncFinalizationResponse, EpochSyncResponse,
+};
 use near_primitives::time::{Clock, Utc};
 use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{
@@ -53,9 +55,15 @@ pub const MAX_PENDING_PART: u64 = MAX_STATE_PART_REQUEST * 10000;
 
 pub const NS_PER_SECOND: u128 = 1_000_000_000;
 
-/// Helper to keep track of the Epoch Sync
-// TODO #3488
-#[allow(dead_code)]
+/// Helper to keep track of the Epoch Sync.
+///
+/// Epoch Sync lets a node starting from genesis skip downloading all the block headers. Starting
+/// from the genesis block producers, the node requests one light client block per epoch. Every
+/// such block is approved by more than 2/3 of the stake of the epoch we already trust and commits
+/// to the block producers of the next epoch, so following them proves the validator set of the
+/// latest epoch. Once peers report that there are no more epochs, the node requests the data
+/// needed to initialize the epoch manager, and header sync and state sync continue from the last
+/// block of the previous epoch.
 pub struct EpochSync {
     network_adapter: Arc<dyn PeerManagerAdapter>,
     /// Datastructure to keep track of when the last request to each peer was made.
@@ -64,14 +72,17 @@ pub struct EpochSync {
     peer_to_last_request_time: HashMap<PeerId, DateTime<Utc>>,
     /// Tracks all the peers who have reported that we are already up to date
     peers_reporting_up_to_date: HashSet<PeerId>,
+    /// Nothing needs to be synced while the network is in the genesis epoch.
+    genesis_epoch_id: EpochId,
     /// The last epoch we are synced to
     current_epoch_id: EpochId,
-    /// The next epoch id we need to sync
-    next_epoch_id: EpochId,
-    /// The block producers set to validate the light client block view for the next epoch
-    next_block_producers: Vec<ValidatorStake>,
-    /// The last epoch id that we have requested
-    requested_epoch_id: EpochId,
+    /// The ordered block producers of `current_epoch_id`, used to validate the light client
+    /// block of that epoch
+    current_block_producers: Vec<ValidatorStake>,
+    /// Height of the last validated light client block
+    last_light_client_block_height: BlockHeight,
+    /// Number of epochs synced so far
+    epoch_ord: u64,
     /// When and to whom was the last request made
     last_request_time: DateTime<Utc>,
     last_request_peer_id: Option<PeerId>,
@@ -87,19 +98,12 @@ pub struct EpochSync {
     /// Whether the Epoch Sync was performed to completion previously.
     /// Current state machine allows for only one Epoch Sync.
     pub done: bool,
-
-    pub sync_hash: CryptoHash,
-
-    received_epoch: bool,
-
-    is_just_started: bool,
 }
 
 impl EpochSync {
     pub fn new(
         network_adapter: Arc<dyn PeerManagerAdapter>,
         genesis_epoch_id: EpochId,
-        genesis_next_epoch_id: EpochId,
         first_epoch_block_producers: Vec<ValidatorStake>,
         request_timeout: TimeDuration,
         peer_timeout: TimeDuration,
@@ -108,21 +112,210 @@ impl EpochSync {
             network_adapter,
             peer_to_last_request_time: HashMap::new(),
             peers_reporting_up_to_date: HashSet::new(),
-            current_epoch_id: genesis_epoch_id.clone(),
-            next_epoch_id: genesis_next_epoch_id,
-            next_block_producers: first_epoch_block_producers,
-            requested_epoch_id: genesis_epoch_id,
+            genesis_epoch_id: genesis_epoch_id.clone(),
+            current_epoch_id: genesis_epoch_id,
+            current_block_producers: first_epoch_block_producers,
+            last_light_client_block_height: 0,
+            epoch_ord: 0,
             last_request_time: Clock::utc(),
             last_request_peer_id: None,
             request_timeout: Duration::from_std(request_timeout).unwrap(),
             peer_timeout: Duration::from_std(peer_timeout).unwrap(),
-            received_epoch: false,
             have_all_epochs: false,
             done: false,
-            sync_hash: CryptoHash::default(),
-            is_just_started: true,
         }
     }
+
+    /// Requests the light client block of the current epoch, or the finalization data once peers
+    /// agree that there are no more epochs. Marks Epoch Sync as done if the node already has
+    /// headers past genesis.
+    pub fn run(
+        &mut self,
+        sync_status: &mut SyncStatus,
+        chain: &Chain,
+        highest_height_peers: &[FullPeerInfo],
+    ) -> Result<(), near_chain::Error> {
+        let _span = tracing::debug_span!(target: "sync", "run", sync = "EpochSync").entered();
+        if self.done {
+            return Ok(());
+        }
+        if chain.header_head()?.height > chain.genesis().height() {
+            // Header sync has already made progress, continue with it.
+            self.done = true;
+            return Ok(());
+        }
+        *sync_status = SyncStatus::EpochSync { epoch_ord: self.epoch_ord };
+
+        let now = Clock::utc();
+        if let Some(peer_id) = &self.last_request_peer_id {
+            if now - self.last_request_time < self.request_timeout {
+                return Ok(());
+            }
+            debug!(target: "sync", "Epoch sync: request to {} timed out", peer_id);
+            self.last_request_peer_id = None;
+        }
+
+        let (up_to_date, behind): (Vec<_>, Vec<_>) = highest_height_peers
+            .iter()
+            .map(|peer| &peer.peer_info.id)
+            .partition(|peer_id| self.peers_reporting_up_to_date.contains(*peer_id));
+        if !self.have_all_epochs && !up_to_date.is_empty() && up_to_date.len() >= behind.len() {
+            info!(target: "sync", "Epoch sync: reached the last epoch {:?} after {} epochs", self.current_epoch_id, self.epoch_ord);
+            self.have_all_epochs = true;
+        }
+        if self.have_all_epochs && self.current_epoch_id == self.genesis_epoch_id {
+            // Headers of the genesis epoch are cheap to download with header sync.
+            self.done = true;
+            return Ok(());
+        }
+
+        let candidates = if self.have_all_epochs { up_to_date } else { behind };
+        let peer_id = match candidates
+            .into_iter()
+            .filter(|peer_id| {
+                self.peer_to_last_request_time
+                    .get(*peer_id)
+                    .map_or(true, |last_request| now - *last_request >= self.peer_timeout)
+            })
+            .choose(&mut thread_rng())
+        {
+            Some(peer_id) => peer_id.clone(),
+            None => return Ok(()),
+        };
+
+        let epoch_id = self.current_epoch_id.clone();
+        let request = if self.have_all_epochs {
+            debug!(target: "sync", "Epoch sync: requesting finalization of {:?} from {}", epoch_id, peer_id);
+            NetworkRequests::EpochSyncFinalizationRequest { peer_id: peer_id.clone(), epoch_id }
+        } else {
+            debug!(target: "sync", "Epoch sync: requesting light client block of {:?} from {}", epoch_id, peer_id);
+            NetworkRequests::EpochSyncRequest { peer_id: peer_id.clone(), epoch_id }
+        };
+        self.network_adapter.do_send(PeerManagerMessageRequest::NetworkRequests(request));
+        self.peer_to_last_request_time.insert(peer_id.clone(), now);
+        self.last_request_time = now;
+        self.last_request_peer_id = Some(peer_id);
+        Ok(())
+    }
+
+    /// Processes the response to the last light client block request. Returns an error if the
+    /// light client block is invalid.
+    pub fn on_response(
+        &mut self,
+        peer_id: &PeerId,
+        response: EpochSyncResponse,
+    ) -> Result<(), near_chain::Error> {
+        if self.done || self.last_request_peer_id.as_ref() != Some(peer_id) {
+            debug!(target: "sync", "Epoch sync: ignoring unexpected response from {}", peer_id);
+            return Ok(());
+        }
+        self.last_request_peer_id = None;
+        match response {
+            EpochSyncResponse::UpToDate => {
+                self.peers_reporting_up_to_date.insert(peer_id.clone());
+            }
+            EpochSyncResponse::Advance { light_client_block_view } => {
+                let next_block_producers = validate_light_client_block(
+                    &light_client_block_view,
+                    &self.current_epoch_id,
+                    self.last_light_client_block_height,
+                    &self.current_block_producers,
+                )?;
+                self.current_epoch_id = EpochId(light_client_block_view.inner_lite.next_epoch_id);
+                self.current_block_producers = next_block_producers;
+                self.last_light_client_block_height = light_client_block_view.inner_lite.height;
+                self.epoch_ord += 1;
+                self.peers_reporting_up_to_date.clear();
+                self.have_all_epochs = false;
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates the finalization data of the current epoch and initializes the chain with it.
+    /// Returns an error if the data doesn't match the block producers proven so far.
+    pub fn on_finalization_response(
+        &mut self,
+        peer_id: &PeerId,
+        response: EpochSyncFinalizationResponse,
+        chain: &mut Chain,
+    ) -> Result<(), near_chain::Error> {
+        if self.done || !self.have_all_epochs || self.last_request_peer_id.as_ref() != Some(peer_id)
+        {
+            debug!(target: "sync", "Epoch sync: ignoring unexpected finalization from {}", peer_id);
+            return Ok(());
+        }
+        self.last_request_peer_id = None;
+        self.validate_finalization(&response)?;
+        let height = response.header_sync_init_header.height();
+        chain.init_after_epoch_sync(response)?;
+        info!(target: "sync", "Epoch sync: done at {:?}, continuing with header sync from height {}", self.current_epoch_id, height);
+        self.done = true;
+        Ok(())
+    }
+
+    /// Checks that the finalization data belongs to the current epoch and is consistent with its
+    /// block producers. The first block of the epoch commits to the epoch data with
+    /// `epoch_sync_data_hash`; the blocks that follow are validated by header sync.
+    fn validate_finalization(
+        &self,
+        response: &EpochSyncFinalizationResponse,
+    ) -> Result<(), near_chain::Error> {
+        let invalid = |reason: &str| Err(Error::InvalidEpochSyncResponse(reason.to_string()));
+        let header = &response.cur_epoch_header;
+        let init_header = &response.header_sync_init_header;
+        if header.epoch_id() != &self.current_epoch_id {
+            return Err(Error::InvalidEpochHash);
+        }
+        if header.prev_hash() != init_header.hash()
+            || response.prev_epoch_last_block_info.hash() != init_header.hash()
+            || response.prev_epoch_last_block_info.epoch_id() != init_header.epoch_id()
+            || response.prev_epoch_prev_last_block_info.hash() != init_header.prev_hash()
+            || response.prev_epoch_first_block_info.hash()
+                != response.prev_epoch_last_block_info.epoch_first_block()
+        {
+            return invalid("block infos don't match headers");
+        }
+        if response.prev_epoch_headers.iter().any(|prev_header| {
+            prev_header.epoch_id() != init_header.epoch_id()
+                || (prev_header.hash() != response.prev_epoch_first_block_info.hash()
+                    && prev_header.hash() != response.prev_epoch_prev_last_block_info.hash())
+        }) {
+            return invalid("unexpected previous epoch header");
+        }
+        let mut block_merkle_tree = response.header_sync_init_header_tree.clone();
+        block_merkle_tree.insert(*init_header.hash());
+        if &block_merkle_tree.root() != header.block_merkle_root() {
+            return Err(Error::InvalidBlockMerkleRoot);
+        }
+
+        let epoch_info = &response.cur_epoch_info;
+        let mut seen = HashSet::new();
+        let block_producers: Vec<_> = epoch_info
+            .block_producers_settlement()
+            .iter()
+            .map(|validator_id| epoch_info.get_validator(*validator_id))
+            .filter(|validator| seen.insert(validator.account_id().clone()))
+            .collect();
+        if block_producers.len() != self.current_block_producers.len()
+            || block_producers.iter().zip(self.current_block_producers.iter()).any(|(a, b)| {
+                a.account_id() != b.account_id()
+                    || a.public_key() != b.public_key()
+                    || a.stake() != b.stake()
+            })
+        {
+            return invalid("epoch info doesn't match proven block producers");
+        }
+        let block_producer =
+            epoch_info.get_validator(epoch_info.sample_block_producer(header.height()));
+        if !header.verify_block_producer(block_producer.public_key()) {
+            return Err(Error::InvalidSignature);
+        }
+        if header.epoch_sync_data_hash() != Some(response.epoch_sync_data_hash()) {
+            return invalid("epoch data doesn't match epoch_sync_data_hash");
+        }
+        Ok(())
+    }
 }
 
 /// Helper to keep track of sync headers.
@@ -27,6 +27,7 @@ use near_network::types::{
     ConnectedPeerInfo, FullPeerInfo, NetworkClientMessages, NetworkClientResponses,
     NetworkRecipient, NetworkRequests, NetworkResponses, PeerManagerAdapter,
 };
+use near_network_primitives::time;
 use near_network_primitives::types::PartialEdgeInfo;
 use near_primitives::block::{ApprovalInner, Block, GenesisId};
 use near_primitives::hash::{hash, CryptoHash};
@@ -1084,6 +1085,7 @@ pub fn setup_no_network_with_validity_period_and_no_epoch_sync(
 }
 
 pub fn setup_client_with_runtime(
+    clock: time::Clock,
     num_validator_seats: NumSeats,
     account_id: Option<AccountId>,
     enable_doomslug: bool,
@@ -1099,6 +1101,7 @@ pub fn setup_client_with_runtime(
     let mut config = ClientConfig::test(true, 10, 20, num_validator_seats, false, true);
     config.epoch_length = chain_genesis.epoch_length;
     let mut client = Client::new(
+        clock,
         config,
         chain_genesis,
         runtime_adapter,
@@ -1113,6 +1116,7 @@ pub fn setup_client_with_runtime(
 }
 
 pub fn setup_client(
+    clock: time::Clock,
     store: Store,
     vs: ValidatorSchedule,
     account_id: Option<AccountId>,
@@ -1125,6 +1129,7 @@ pub fn setup_client(
     let runtime_adapter =
         Arc::new(KeyValueRuntime::new_with_validators(store, vs, chain_genesis.epoch_length));
     setup_client_with_runtime(
+        clock,
         num_validator_seats,
         account_id,
         enable_doomslug,
@@ -1146,6 +1151,7 @@ pub struct TestEnv {
     // random seed to be inject in each client according to AccountId
     // if not set, a default constant TEST_SEED will be injected
     seeds: HashMap<AccountId, RngSeed>,
+    clock: time::Clock,
 }
 
 /// A builder for the TestEnv structure.
@@ -1158,6 +1164,7 @@ pub struct TestEnvBuilder {
     // random seed to be inject in each client according to AccountId
     // if not set, a default constant TEST_SEED will be injected
     seeds: HashMap<AccountId, RngSeed>,
+    clock: Option<time::Clock>,
 }
 
 /// Builder for the [`TestEnv`] structure.
@@ -1174,6 +1181,7 @@ impl TestEnvBuilder {
             runtime_adapters: None,
             network_adapters: None,
             seeds,
+            clock: None,
         }
     }
 
@@ -1236,6 +1244,15 @@ impl TestEnvBuilder {
         self
     }
 
+    /// Specifies the clock the clients read time from.  The genesis time is
+    /// set to the current time of the clock, so that with a fake clock the
+    /// produced blocks don't depend on the wall clock.  By default the real
+    /// clock is used and the genesis time is left as configured.
+    pub fn clock(mut self, clock: time::Clock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
     /// Constructs new `TestEnv` structure.
     ///
     /// If no clients were configured (either through count or vector) one
@@ -1246,7 +1263,14 @@ impl TestEnvBuilder {
     /// the length of the vectors passed to them did not equal number of
     /// configured clients.
     pub fn build(self) -> TestEnv {
-        let chain_genesis = self.chain_genesis;
+        let mut chain_genesis = self.chain_genesis;
+        let clock = match self.clock {
+            Some(clock) => {
+                chain_genesis.time = time::to_chrono(clock.now_utc());
+                clock
+            }
+            None => time::Clock::real(),
+        };
         let clients = self.clients.clone();
         let num_clients = clients.len();
         let validators = self.validators;
@@ -1268,6 +1292,7 @@ impl TestEnvBuilder {
                     let vs = ValidatorSchedule::new()
                         .block_producers_per_epoch(vec![validators.clone()]);
                     setup_client(
+                        clock.clone(),
                         create_test_store(),
                         vs,
                         Some(account_id),
@@ -1290,6 +1315,7 @@ impl TestEnvBuilder {
                             None => TEST_SEED,
                         };
                         setup_client_with_runtime(
+                            clock.clone(),
                             u64::try_from(num_validators).unwrap(),
                             Some(account_id),
                             false,
@@ -1315,6 +1341,7 @@ impl TestEnvBuilder {
                 .map(|(index, client)| (client, index))
                 .collect(),
             seeds,
+            clock,
         }
     }
 
@@ -1502,7 +1529,13 @@ impl TestEnv {
                 last_block.header().prev_hash(),
                 last_block.header().hash(),
                 last_block.header().epoch_id(),
-                &QueryRequest::ViewState { account_id, prefix: vec![].into() },
+                &QueryRequest::ViewState {
+                    account_id,
+                    prefix: vec![].into(),
+                    start_key: None,
+                    limit: None,
+                    include_proof: false,
+                },
             )
             .unwrap();
         match response.kind {
@@ -1528,6 +1561,7 @@ impl TestEnv {
         };
         let vs = ValidatorSchedule::new().block_producers_per_epoch(vec![self.validators.clone()]);
         self.clients[idx] = setup_client(
+            self.clock.clone(),
             store,
             vs,
             Some(self.get_client_id(idx).clone()),
@@ -0,0 +1,66 @@
+use near_chain::ChainGenesis;
+use near_logger_utils::init_test_logger;
+use near_network_primitives::time;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+
+use crate::simulation::{DeliveredMessage, LinkConfig, Simulation};
+use crate::test_utils::TestEnv;
+
+fn make_simulation(num_nodes: usize, seed: u64) -> Simulation {
+    let mut genesis = ChainGenesis::test();
+    genesis.epoch_length = 1000;
+    let builder = TestEnv::builder(genesis).clients_count(num_nodes).validator_seats(num_nodes);
+    let mut sim = Simulation::new(builder, seed);
+    sim.set_default_link(LinkConfig {
+        latency: time::Duration::milliseconds(50),
+        jitter: time::Duration::milliseconds(100),
+        loss: 0.0,
+    });
+    sim
+}
+
+fn head_heights(sim: &Simulation) -> Vec<BlockHeight> {
+    sim.env.clients.iter().map(|client| client.chain.head().unwrap().height).collect()
+}
+
+fn head_hashes(env: &TestEnv) -> Vec<CryptoHash> {
+    env.clients.iter().map(|client| client.chain.head().unwrap().last_block_hash).collect()
+}
+
+fn heads_agree(env: &TestEnv) -> bool {
+    let heads = head_hashes(env);
+    heads.iter().all(|head| head == &heads[0])
+}
+
+/// Runs the same scenario twice with the same seed and checks that the network delivered
+/// exactly the same messages at the same virtual times and the nodes ended up on the same
+/// blocks.
+#[test]
+fn test_simulation_is_deterministic() {
+    init_test_logger();
+    let run = |seed| -> (Vec<DeliveredMessage>, Vec<BlockHeight>, Vec<CryptoHash>) {
+        let mut sim = make_simulation(4, seed);
+        sim.run_for(time::Duration::seconds(10));
+        (sim.delivered().to_vec(), head_heights(&sim), head_hashes(&sim.env))
+    };
+    let (delivered, heights, hashes) = run(7);
+    assert!(heights.iter().all(|height| *height >= 10), "{:?}", heights);
+    assert_eq!(run(7), (delivered, heights, hashes));
+}
+
+/// Isolates one of the validators, then heals the partition and checks that it catches up
+/// with the rest of the network.
+#[test]
+fn test_simulation_partition_heals() {
+    init_test_logger();
+    let mut sim = make_simulation(4, 11);
+    sim.partition(&[&[0, 1, 2]]);
+    sim.run_for(time::Duration::seconds(10));
+    assert!(!heads_agree(&sim.env), "{:?}", head_heights(&sim));
+
+    sim.heal();
+    assert!(sim.run_until(time::Duration::seconds(10), |env| {
+        env.clients[3].chain.head().unwrap().height >= 20 && heads_agree(env)
+    }));
+}
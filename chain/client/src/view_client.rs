@@ -19,11 +19,11 @@ use near_chain::{
 };
 use near_chain_configs::{ClientConfig, ProtocolConfigView};
 use near_client_primitives::types::{
-    Error, GetBlock, GetBlockError, GetBlockHash, GetBlockProof, GetBlockProofError,
-    GetBlockProofResponse, GetBlockWithMerkleTree, GetChunkError, GetExecutionOutcome,
-    GetExecutionOutcomeError, GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError,
-    GetNextLightClientBlockError, GetProtocolConfig, GetProtocolConfigError, GetReceipt,
-    GetReceiptError, GetStateChangesError, GetStateChangesWithCauseInBlock,
+    CreateBackupCheckpoint, Error, GetBlock, GetBlockError, GetBlockHash, GetBlockProof,
+    GetBlockProofError, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunkError,
+    GetExecutionOutcome, GetExecutionOutcomeError, GetExecutionOutcomesForBlock, GetGasPrice,
+    GetGasPriceError, GetNextLightClientBlockError, GetProtocolConfig, GetProtocolConfigError,
+    GetReceipt, GetReceiptError, GetStateChangesError, GetStateChangesWithCauseInBlock,
     GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
     TxStatus, TxStatusError,
 };
@@ -236,6 +236,7 @@ impl ViewClientActor {
             QueryRequest::ViewAccessKeyList { account_id, .. } => account_id,
             QueryRequest::CallFunction { account_id, .. } => account_id,
             QueryRequest::ViewCode { account_id, .. } => account_id,
+            QueryRequest::ViewStorageUsage { account_id, .. } => account_id,
         };
         let shard_id =
             self.runtime_adapter
@@ -325,6 +326,10 @@ impl ViewClientActor {
                     block_height,
                     block_hash,
                 },
+                near_chain::near_chain_primitives::error::QueryError::InvalidStateLimit {
+                    block_height,
+                    block_hash,
+                } => QueryError::InvalidStateLimit { block_height, block_hash },
             }),
         }
     }
@@ -1000,6 +1005,16 @@ impl Handler<GetProtocolConfig> for ViewClientActor {
     }
 }
 
+impl Handler<CreateBackupCheckpoint> for ViewClientActor {
+    type Result = Result<(), String>;
+
+    #[perf]
+    fn handle(&mut self, msg: CreateBackupCheckpoint, _: &mut Self::Context) -> Self::Result {
+        near_store::backup::create_backup_checkpoint(self.chain.store().store(), &msg.backup_dir)
+            .map_err(|err| err.to_string())
+    }
+}
+
 impl Handler<NetworkViewClientMessages> for ViewClientActor {
     type Result = NetworkViewClientResponses;
 
@@ -1290,13 +1305,31 @@ impl Handler<NetworkViewClientMessages> for ViewClientActor {
 
                 NetworkViewClientResponses::AnnounceAccount(filtered_announce_accounts)
             }
-            NetworkViewClientMessages::EpochSyncRequest { epoch_id: _epoch_id } => {
-                // TODO #3488
-                NetworkViewClientResponses::NoResponse
+            NetworkViewClientMessages::EpochSyncRequest { epoch_id } => {
+                match self.chain.get_epoch_sync_response(&epoch_id) {
+                    Ok(Some(response)) => {
+                        NetworkViewClientResponses::EpochSyncResponse(Box::new(response))
+                    }
+                    Ok(None) => NetworkViewClientResponses::NoResponse,
+                    Err(e) => {
+                        error!(target: "sync", "Failed to build epoch sync response for {:?}: {}", epoch_id, e);
+                        NetworkViewClientResponses::NoResponse
+                    }
+                }
             }
-            NetworkViewClientMessages::EpochSyncFinalizationRequest { epoch_id: _epoch_id } => {
-                // TODO #3488
-                NetworkViewClientResponses::NoResponse
+            NetworkViewClientMessages::EpochSyncFinalizationRequest { epoch_id } => {
+                match self.chain.get_epoch_sync_finalization_response(&epoch_id) {
+                    Ok(Some(response)) => {
+                        NetworkViewClientResponses::EpochSyncFinalizationResponse(Box::new(
+                            response,
+                        ))
+                    }
+                    Ok(None) => NetworkViewClientResponses::NoResponse,
+                    Err(e) => {
+                        error!(target: "sync", "Failed to build epoch sync finalization response for {:?}: {}", epoch_id, e);
+                        NetworkViewClientResponses::NoResponse
+                    }
+                }
             }
         }
     }
@@ -223,6 +223,9 @@ impl EpochManager {
         Ok(())
     }
 
+    /// Initializes the epoch manager of a node that skipped the headers before `epoch_id` with
+    /// Epoch Sync. The block infos of the previous epoch are enough to process the headers of
+    /// `epoch_id` from its first block on.
     pub fn init_after_epoch_sync(
         &mut self,
         prev_epoch_first_block_info: BlockInfo,
@@ -236,16 +239,18 @@ impl EpochManager {
         next_epoch_info: EpochInfo,
     ) -> Result<StoreUpdate, EpochError> {
         let mut store_update = self.store.store_update();
+        self.save_epoch_start(
+            &mut store_update,
+            prev_epoch_id,
+            prev_epoch_first_block_info.height(),
+        )?;
         self.save_block_info(&mut store_update, Arc::new(prev_epoch_first_block_info))?;
         self.save_block_info(&mut store_update, Arc::new(prev_epoch_prev_last_block_info))?;
         self.save_block_info(&mut store_update, Arc::new(prev_epoch_last_block_info))?;
         self.save_epoch_info(&mut store_update, prev_epoch_id, Arc::new(prev_epoch_info))?;
         self.save_epoch_info(&mut store_update, epoch_id, Arc::new(epoch_info))?;
         self.save_epoch_info(&mut store_update, next_epoch_id, Arc::new(next_epoch_info))?;
-        // TODO #3488
-        // put unreachable! here to avoid warnings
-        unreachable!();
-        // Ok(store_update)
+        Ok(store_update)
     }
 
     /// # Parameters
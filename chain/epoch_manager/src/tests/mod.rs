@@ -2392,6 +2392,68 @@ fn test_epoch_validators_cache() {
     assert_eq!(*epoch_validators_unique, *epoch_validators_unique_in_cache);
 }
 
+/// An epoch manager initialized with the data provided by Epoch Sync processes the blocks of the
+/// synced epoch exactly like the one that has seen the whole chain.
+#[test]
+fn test_init_after_epoch_sync() {
+    let stake_amount = 1_000;
+    let validators =
+        vec![("test1".parse().unwrap(), stake_amount), ("test2".parse().unwrap(), stake_amount)];
+    let mut epoch_manager = setup_default_epoch_manager(validators.clone(), 5, 1, 2, 0, 90, 60);
+    let h = hash_range(30);
+    record_block(&mut epoch_manager, CryptoHash::default(), h[0], 0, vec![]);
+    for i in 1..h.len() {
+        record_block(&mut epoch_manager, h[i - 1], h[i], i as u64, vec![]);
+    }
+
+    let epoch_first_block = *epoch_manager.get_block_info(&h[12]).unwrap().epoch_first_block();
+    let first_block_info = epoch_manager.get_block_info(&epoch_first_block).unwrap();
+    let prev_epoch_last_block_info =
+        epoch_manager.get_block_info(first_block_info.prev_hash()).unwrap();
+    let prev_epoch_first_block_info =
+        epoch_manager.get_block_info(prev_epoch_last_block_info.epoch_first_block()).unwrap();
+    let prev_epoch_prev_last_block_info =
+        epoch_manager.get_block_info(prev_epoch_last_block_info.prev_hash()).unwrap();
+    let prev_epoch_id = prev_epoch_last_block_info.epoch_id().clone();
+    let epoch_id = first_block_info.epoch_id().clone();
+    let next_epoch_id = epoch_manager.get_next_epoch_id(&epoch_first_block).unwrap();
+
+    let mut synced_epoch_manager = setup_default_epoch_manager(validators, 5, 1, 2, 0, 90, 60);
+    synced_epoch_manager
+        .init_after_epoch_sync(
+            BlockInfo::clone(&prev_epoch_first_block_info),
+            BlockInfo::clone(&prev_epoch_prev_last_block_info),
+            BlockInfo::clone(&prev_epoch_last_block_info),
+            &prev_epoch_id,
+            EpochInfo::clone(&epoch_manager.get_epoch_info(&prev_epoch_id).unwrap()),
+            &epoch_id,
+            EpochInfo::clone(&epoch_manager.get_epoch_info(&epoch_id).unwrap()),
+            &next_epoch_id,
+            EpochInfo::clone(&epoch_manager.get_epoch_info(&next_epoch_id).unwrap()),
+        )
+        .unwrap()
+        .commit()
+        .unwrap();
+
+    for i in first_block_info.height() as usize..h.len() {
+        record_block(&mut synced_epoch_manager, h[i - 1], h[i], i as u64, vec![]);
+        assert_eq!(
+            synced_epoch_manager.get_epoch_id(&h[i]).unwrap(),
+            epoch_manager.get_epoch_id(&h[i]).unwrap()
+        );
+        assert_eq!(
+            synced_epoch_manager.get_epoch_start_height(&h[i]).unwrap(),
+            epoch_manager.get_epoch_start_height(&h[i]).unwrap()
+        );
+        let next_epoch_id = epoch_manager.get_next_epoch_id(&h[i]).unwrap();
+        assert_eq!(synced_epoch_manager.get_next_epoch_id(&h[i]).unwrap(), next_epoch_id);
+        assert_eq!(
+            synced_epoch_manager.get_epoch_info(&next_epoch_id).unwrap(),
+            epoch_manager.get_epoch_info(&next_epoch_id).unwrap()
+        );
+    }
+}
+
 #[test]
 fn test_chunk_producers() {
     let amount_staked = 1_000_000;
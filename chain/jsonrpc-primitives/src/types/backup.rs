@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcBackupCheckpointRequest {
+    /// Directory to create the checkpoint in, which must not exist.  Relative
+    /// paths are resolved against the working directory of the node.
+    pub backup_dir: PathBuf,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcBackupCheckpointError {
+    #[error("Admin RPC is disabled on this node")]
+    AdminRpcDisabled,
+    #[error("Internal error: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcBackupCheckpointError> for crate::errors::RpcError {
+    fn from(error: RpcBackupCheckpointError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcBackupCheckpointError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
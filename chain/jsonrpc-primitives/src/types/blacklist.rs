@@ -0,0 +1,66 @@
+use near_primitives::network::PeerId;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcBlacklistAddRequest {
+    /// IP, IP:port or CIDR range (e.g. `192.0.2.0/24`) to blacklist.
+    pub entry: String,
+    /// If set, the entry is removed from the blacklist after that many seconds.
+    #[serde(default)]
+    pub ttl_seconds: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcBlacklistAddResponse {
+    /// Connected peers matching the entry, which have been disconnected.
+    pub disconnected_peers: Vec<PeerId>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcBlacklistRemoveRequest {
+    pub entry: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcBlacklistRemoveResponse {
+    /// Whether the entry was present on the blacklist.
+    pub removed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcBlacklistEntry {
+    pub entry: String,
+    /// Unix timestamp (in seconds) at which the entry expires, `None` if it never does.
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcBlacklistResponse {
+    pub entries: Vec<RpcBlacklistEntry>,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcBlacklistError {
+    #[error("Admin RPC is disabled on this node")]
+    AdminRpcDisabled,
+    #[error("Invalid blacklist entry {entry:?}: {error_message}")]
+    InvalidEntry { entry: String, error_message: String },
+    #[error("Internal error: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcBlacklistError> for crate::errors::RpcError {
+    fn from(error: RpcBlacklistError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcBlacklistError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
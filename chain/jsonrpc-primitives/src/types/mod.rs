@@ -1,3 +1,5 @@
+pub mod backup;
+pub mod blacklist;
 pub mod blocks;
 pub mod changes;
 pub mod chunks;
@@ -258,6 +258,13 @@ impl JsonRpcClient {
     ) -> RpcRequest<near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse> {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_protocol_config", request)
     }
+
+    pub fn admin_backup_checkpoint(
+        &self,
+        request: near_jsonrpc_primitives::types::backup::RpcBackupCheckpointRequest,
+    ) -> RpcRequest<()> {
+        call_method(&self.client, &self.server_addr, "admin_backup_checkpoint", request)
+    }
 }
 
 fn create_client() -> Client {
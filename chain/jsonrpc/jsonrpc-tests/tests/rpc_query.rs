@@ -7,6 +7,7 @@ use serde_json::json;
 use near_actix_test_utils::run_actix;
 use near_crypto::{KeyType, PublicKey, Signature};
 use near_jsonrpc::client::{new_client, ChunkId};
+use near_jsonrpc_primitives::types::backup::RpcBackupCheckpointRequest;
 use near_jsonrpc_primitives::types::query::QueryResponseKind;
 use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
 use near_logger_utils::init_test_logger;
@@ -297,6 +298,9 @@ fn test_query_state() {
                 request: QueryRequest::ViewState {
                     account_id: "test".parse().unwrap(),
                     prefix: vec![].into(),
+                    start_key: None,
+                    limit: None,
+                    include_proof: false,
                 },
             })
             .await
@@ -586,3 +590,16 @@ fn test_get_chunk_with_object_in_params() {
         assert_eq!(chunk.header.chunk_hash, same_chunk.header.chunk_hash);
     });
 }
+
+/// Admin methods are rejected unless admin RPC is enabled.
+#[test]
+fn test_backup_checkpoint_admin_rpc_disabled() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let backup_dir = std::env::temp_dir().join("test_backup_checkpoint_admin_rpc_disabled");
+        let request = RpcBackupCheckpointRequest { backup_dir: backup_dir.clone() };
+        let err = client.admin_backup_checkpoint(request).await.unwrap_err();
+        let data = serde_json::to_string(&err.data.unwrap()).unwrap();
+        assert!(data.contains("ADMIN_RPC_DISABLED"), "{}", data);
+        assert!(!backup_dir.exists());
+    });
+}
@@ -0,0 +1,26 @@
+use serde_json::Value;
+
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::backup::{
+    RpcBackupCheckpointError, RpcBackupCheckpointRequest,
+};
+
+use super::{parse_params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcBackupCheckpointRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcBackupCheckpointError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<String> for RpcBackupCheckpointError {
+    fn rpc_from(error_message: String) -> Self {
+        Self::InternalError { error_message }
+    }
+}
@@ -0,0 +1,43 @@
+use serde_json::Value;
+
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::blacklist::{
+    RpcBlacklistAddRequest, RpcBlacklistEntry, RpcBlacklistError, RpcBlacklistRemoveRequest,
+};
+use near_network_primitives::time;
+use near_network_primitives::types::BlacklistEntry;
+
+use super::{parse_params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcBlacklistAddRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcRequest for RpcBlacklistRemoveRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcBlacklistError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<(BlacklistEntry, Option<time::Utc>)> for RpcBlacklistEntry {
+    fn rpc_from((entry, expires_at): (BlacklistEntry, Option<time::Utc>)) -> Self {
+        Self {
+            entry: entry.to_string(),
+            expires_at: expires_at.map(|expires_at| expires_at.unix_timestamp()),
+        }
+    }
+}
+
+pub(crate) fn parse_blacklist_entry(entry: &str) -> Result<BlacklistEntry, RpcBlacklistError> {
+    entry.parse().map_err(|err: near_network_primitives::types::BlacklistEntryParseError| {
+        RpcBlacklistError::InvalidEntry { entry: entry.to_string(), error_message: err.to_string() }
+    })
+}
@@ -5,6 +5,8 @@ use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::errors::{RpcError, ServerError};
 use near_primitives::borsh::BorshDeserialize;
 
+mod backup;
+pub(crate) mod blacklist;
 mod blocks;
 mod changes;
 mod chunks;
@@ -52,7 +52,13 @@ impl RpcRequest for RpcQueryRequest {
                     },
                 },
                 "code" => QueryRequest::ViewCode { account_id },
-                "contract" => QueryRequest::ViewState { account_id, prefix: data.into() },
+                "contract" => QueryRequest::ViewState {
+                    account_id,
+                    prefix: data.into(),
+                    start_key: None,
+                    limit: None,
+                    include_proof: false,
+                },
                 "call" => match maybe_extra_arg {
                     Some(method_name) => QueryRequest::CallFunction {
                         account_id,
@@ -115,6 +121,9 @@ impl RpcFrom<QueryError> for RpcQueryError {
             QueryError::TooLargeContractState { contract_account_id, block_height, block_hash } => {
                 Self::TooLargeContractState { contract_account_id, block_height, block_hash }
             }
+            QueryError::InvalidStateLimit { block_height, block_hash } => {
+                Self::InvalidStateLimit { block_height, block_hash }
+            }
         }
     }
 }
@@ -152,6 +161,9 @@ impl RpcFrom<near_primitives::views::QueryResponseKind>
             near_primitives::views::QueryResponseKind::AccessKeyList(access_key_list) => {
                 Self::AccessKeyList(access_key_list)
             }
+            near_primitives::views::QueryResponseKind::StorageUsage(storage_usage) => {
+                Self::StorageUsage(storage_usage)
+            }
         }
     }
 }
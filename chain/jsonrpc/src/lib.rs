@@ -16,17 +16,22 @@ use tracing::info;
 
 use near_chain_configs::GenesisConfig;
 use near_client::{
-    ClientActor, DebugStatus, GetBlock, GetBlockProof, GetChunk, GetExecutionOutcome, GetGasPrice,
-    GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
-    GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered, Query, Status, TxStatus,
-    ViewClientActor,
+    ClientActor, CreateBackupCheckpoint, DebugStatus, GetBlock, GetBlockProof, GetChunk,
+    GetExecutionOutcome, GetGasPrice, GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig,
+    GetReceipt, GetStateChanges, GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered,
+    Query, Status, TxStatus, ViewClientActor,
 };
 pub use near_jsonrpc_client as client;
 use near_jsonrpc_primitives::errors::RpcError;
 use near_jsonrpc_primitives::message::{Message, Request};
 use near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse;
 use near_metrics::{prometheus, Encoder, TextEncoder};
-use near_network::types::{NetworkClientMessages, NetworkClientResponses};
+use near_network::types::{
+    NetworkClientMessages, NetworkClientResponses, PeerManagerMessageRequest,
+    PeerManagerMessageResponse,
+};
+use near_network::PeerManagerActor;
+use near_network_primitives::time;
 use near_primitives::hash::CryptoHash;
 use near_primitives::serialize::BaseEncode;
 use near_primitives::transaction::SignedTransaction;
@@ -70,6 +75,10 @@ fn default_enable_debug_rpc() -> bool {
     false
 }
 
+fn default_enable_admin_rpc() -> bool {
+    false
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RpcConfig {
     pub addr: String,
@@ -83,6 +92,10 @@ pub struct RpcConfig {
     // We disable it by default, as some of those endpoints might be quite CPU heavy.
     #[serde(default = "default_enable_debug_rpc")]
     pub enable_debug_rpc: bool,
+    // If true, enable admin RPC methods which change the node's state (like managing the
+    // network blacklist).  Only enable it if the RPC port is not publicly reachable.
+    #[serde(default = "default_enable_admin_rpc")]
+    pub enable_admin_rpc: bool,
 }
 
 impl Default for RpcConfig {
@@ -94,6 +107,7 @@ impl Default for RpcConfig {
             polling_config: Default::default(),
             limits_config: Default::default(),
             enable_debug_rpc: false,
+            enable_admin_rpc: false,
         }
     }
 }
@@ -217,6 +231,8 @@ struct JsonRpcHandler {
     polling_config: RpcPollingConfig,
     genesis_config: GenesisConfig,
     enable_debug_rpc: bool,
+    peer_manager_addr: Option<Addr<PeerManagerActor>>,
+    enable_admin_rpc: bool,
 }
 
 impl JsonRpcHandler {
@@ -268,6 +284,16 @@ impl JsonRpcHandler {
 
         match request.method.as_ref() {
             // Handlers ordered alphabetically
+            "admin_backup_checkpoint" => {
+                process_method_call(request, |params| self.backup_checkpoint(params)).await
+            }
+            "admin_blacklist" => process_method_call(request, |_params: ()| self.blacklist()).await,
+            "admin_blacklist_add" => {
+                process_method_call(request, |params| self.blacklist_add(params)).await
+            }
+            "admin_blacklist_remove" => {
+                process_method_call(request, |params| self.blacklist_remove(params)).await
+            }
             "block" => process_method_call(request, |params| self.block(params)).await,
             "broadcast_tx_async" => {
                 process_method_call(request, |params| async {
@@ -404,6 +430,24 @@ impl JsonRpcHandler {
         self.view_client_addr.send(msg).await.map_err(RpcFrom::rpc_from)?.map_err(RpcFrom::rpc_from)
     }
 
+    /// Sends a message to the `PeerManagerActor`, provided admin RPC methods are enabled.
+    async fn admin_peer_manager_send(
+        &self,
+        msg: PeerManagerMessageRequest,
+    ) -> Result<
+        PeerManagerMessageResponse,
+        near_jsonrpc_primitives::types::blacklist::RpcBlacklistError,
+    > {
+        match (self.enable_admin_rpc, &self.peer_manager_addr) {
+            (true, Some(peer_manager_addr)) => {
+                peer_manager_addr.send(msg).await.map_err(RpcFrom::rpc_from)
+            }
+            _ => {
+                Err(near_jsonrpc_primitives::types::blacklist::RpcBlacklistError::AdminRpcDisabled)
+            }
+        }
+    }
+
     async fn send_tx_async(
         &self,
         request_data: near_jsonrpc_primitives::types::transactions::RpcBroadcastTransactionRequest,
@@ -745,6 +789,12 @@ impl JsonRpcHandler {
                 "/debug/api/validator_status" => {
                     self.client_send(DebugStatus::ValidatorStatus).await?
                 }
+                "/debug/api/contract_precompilation" => {
+                    self.client_send(DebugStatus::ContractPrecompilation).await?
+                }
+                "/debug/api/block_timelines" => {
+                    self.client_send(DebugStatus::BlockTimelines).await?
+                }
                 _ => return Ok(None),
             };
             return Ok(Some(debug_status.rpc_into()));
@@ -921,6 +971,91 @@ impl JsonRpcHandler {
         })
     }
 
+    async fn backup_checkpoint(
+        &self,
+        request: near_jsonrpc_primitives::types::backup::RpcBackupCheckpointRequest,
+    ) -> Result<(), near_jsonrpc_primitives::types::backup::RpcBackupCheckpointError> {
+        if !self.enable_admin_rpc {
+            return Err(
+                near_jsonrpc_primitives::types::backup::RpcBackupCheckpointError::AdminRpcDisabled,
+            );
+        }
+        self.view_client_send(CreateBackupCheckpoint { backup_dir: request.backup_dir }).await
+    }
+
+    async fn blacklist(
+        &self,
+    ) -> Result<
+        near_jsonrpc_primitives::types::blacklist::RpcBlacklistResponse,
+        near_jsonrpc_primitives::types::blacklist::RpcBlacklistError,
+    > {
+        match self.admin_peer_manager_send(PeerManagerMessageRequest::FetchBlacklist).await? {
+            PeerManagerMessageResponse::FetchBlacklist(entries) => {
+                Ok(near_jsonrpc_primitives::types::blacklist::RpcBlacklistResponse {
+                    entries: entries.into_iter().map(RpcFrom::rpc_from).collect(),
+                })
+            }
+            response => {
+                Err(near_jsonrpc_primitives::types::blacklist::RpcBlacklistError::InternalError {
+                    error_message: format!("Unexpected response: {:?}", response),
+                })
+            }
+        }
+    }
+
+    async fn blacklist_add(
+        &self,
+        request: near_jsonrpc_primitives::types::blacklist::RpcBlacklistAddRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::blacklist::RpcBlacklistAddResponse,
+        near_jsonrpc_primitives::types::blacklist::RpcBlacklistError,
+    > {
+        let entry = api::blacklist::parse_blacklist_entry(&request.entry)?;
+        let ttl = request
+            .ttl_seconds
+            .map(|ttl| time::Duration::seconds(i64::try_from(ttl).unwrap_or(i64::MAX)));
+        match self
+            .admin_peer_manager_send(PeerManagerMessageRequest::BlacklistAdd { entry, ttl })
+            .await?
+        {
+            PeerManagerMessageResponse::BlacklistAdd { disconnected_peers } => {
+                Ok(near_jsonrpc_primitives::types::blacklist::RpcBlacklistAddResponse {
+                    disconnected_peers,
+                })
+            }
+            response => {
+                Err(near_jsonrpc_primitives::types::blacklist::RpcBlacklistError::InternalError {
+                    error_message: format!("Unexpected response: {:?}", response),
+                })
+            }
+        }
+    }
+
+    async fn blacklist_remove(
+        &self,
+        request: near_jsonrpc_primitives::types::blacklist::RpcBlacklistRemoveRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::blacklist::RpcBlacklistRemoveResponse,
+        near_jsonrpc_primitives::types::blacklist::RpcBlacklistError,
+    > {
+        let entry = api::blacklist::parse_blacklist_entry(&request.entry)?;
+        match self
+            .admin_peer_manager_send(PeerManagerMessageRequest::BlacklistRemove(entry))
+            .await?
+        {
+            PeerManagerMessageResponse::BlacklistRemove { removed } => {
+                Ok(near_jsonrpc_primitives::types::blacklist::RpcBlacklistRemoveResponse {
+                    removed,
+                })
+            }
+            response => {
+                Err(near_jsonrpc_primitives::types::blacklist::RpcBlacklistError::InternalError {
+                    error_message: format!("Unexpected response: {:?}", response),
+                })
+            }
+        }
+    }
+
     async fn network_info(
         &self,
     ) -> Result<
@@ -1298,6 +1433,8 @@ async fn display_debug_html(
         "chain_n_chunk_info" => Some(include_str!("../res/chain_n_chunk_info.html")),
         "sync" => Some(include_str!("../res/sync.html")),
         "validator" => Some(include_str!("../res/validator.html")),
+        "contract_precompilation" => Some(include_str!("../res/contract_precompilation.html")),
+        "block_timelines" => Some(include_str!("../res/block_timelines.html")),
         _ => None,
     };
 
@@ -1325,6 +1462,7 @@ pub fn start_http(
     genesis_config: GenesisConfig,
     client_addr: Addr<ClientActor>,
     view_client_addr: Addr<ViewClientActor>,
+    peer_manager_addr: Option<Addr<PeerManagerActor>>,
 ) -> Vec<(&'static str, actix_web::dev::ServerHandle)> {
     let RpcConfig {
         addr,
@@ -1333,6 +1471,7 @@ pub fn start_http(
         polling_config,
         limits_config,
         enable_debug_rpc,
+        enable_admin_rpc,
     } = config;
     let prometheus_addr = prometheus_addr.filter(|it| it != &addr);
     let cors_allowed_origins_clone = cors_allowed_origins.clone();
@@ -1347,6 +1486,8 @@ pub fn start_http(
                 polling_config,
                 genesis_config: genesis_config.clone(),
                 enable_debug_rpc,
+                peer_manager_addr: peer_manager_addr.clone(),
+                enable_admin_rpc,
             }))
             .app_data(web::JsonConfig::default().limit(limits_config.json_payload_max_size))
             .wrap(middleware::Logger::default())
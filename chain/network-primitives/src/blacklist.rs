@@ -1,4 +1,6 @@
-use std::collections::HashSet;
+use crate::time;
+use std::collections::HashMap;
+use std::fmt;
 use std::net;
 
 /// Only IPv6 addresses are stored.  IPv4 addresses are mapped to IPv6 before being added.
@@ -11,31 +13,116 @@ use std::net;
 pub enum Entry {
     Ip(net::Ipv6Addr),
     IpPort(net::Ipv6Addr, u16),
+    /// A CIDR range, i.e. all addresses sharing the first `prefix_len` bits with the given one.
+    /// The address is stored with all bits past the prefix cleared, and for IPv4 ranges
+    /// the prefix length is shifted by 96 bits to account for the IPv6 mapping.
+    IpRange(net::Ipv6Addr, u8),
+}
+
+fn to_ipv6(ip: net::IpAddr) -> net::Ipv6Addr {
+    match ip {
+        net::IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        net::IpAddr::V6(ip) => ip,
+    }
+}
+
+/// Length (in bits) of the prefix shared by all IPv4-mapped IPv6 addresses.
+const IPV4_MAPPED_PREFIX_LEN: u8 = 96;
+
+fn is_ipv4_mapped(ip: &net::Ipv6Addr) -> bool {
+    matches!(ip.segments(), [0, 0, 0, 0, 0, 0xffff, _, _])
+}
+
+fn mask(ip: net::Ipv6Addr, prefix_len: u8) -> net::Ipv6Addr {
+    let bits = u128::from(ip);
+    let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+    net::Ipv6Addr::from(bits & mask)
 }
 
 impl Entry {
     pub fn from_ip(ip: net::IpAddr) -> Entry {
-        Entry::Ip(match ip {
-            net::IpAddr::V4(ip) => ip.to_ipv6_mapped(),
-            net::IpAddr::V6(ip) => ip,
-        })
+        Entry::Ip(to_ipv6(ip))
     }
 
     pub fn from_addr(addr: net::SocketAddr) -> Entry {
-        Entry::IpPort(
-            match addr.ip() {
-                net::IpAddr::V4(ip) => ip.to_ipv6_mapped(),
-                net::IpAddr::V6(ip) => ip,
-            },
-            addr.port(),
-        )
+        Entry::IpPort(to_ipv6(addr.ip()), addr.port())
+    }
+
+    /// Creates an entry covering the `ip/prefix_len` CIDR range.  For IPv4 addresses the
+    /// prefix length is expressed in IPv4 bits.  Returns `None` if the prefix is too long.
+    pub fn from_range(ip: net::IpAddr, prefix_len: u8) -> Option<Entry> {
+        let prefix_len = match ip {
+            net::IpAddr::V4(_) if prefix_len <= 32 => prefix_len + IPV4_MAPPED_PREFIX_LEN,
+            net::IpAddr::V6(_) if prefix_len <= 128 => prefix_len,
+            _ => return None,
+        };
+        Some(Entry::IpRange(mask(to_ipv6(ip), prefix_len), prefix_len))
+    }
+
+    /// Returns whether given address is covered by this entry.
+    pub fn contains(&self, addr: net::SocketAddr) -> bool {
+        let ip = to_ipv6(addr.ip());
+        match *self {
+            Entry::Ip(entry_ip) => entry_ip == ip,
+            Entry::IpPort(entry_ip, port) => entry_ip == ip && port == addr.port(),
+            Entry::IpRange(prefix, prefix_len) => mask(ip, prefix_len) == prefix,
+        }
     }
 }
 
+/// Formats the address the way it was most likely written in the config, i.e. IPv4-mapped
+/// addresses are printed as plain IPv4.
+struct DisplayIp<'a>(&'a net::Ipv6Addr);
+
+impl fmt::Display for DisplayIp<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if is_ipv4_mapped(self.0) {
+            let [.., a, b, c, d] = self.0.octets();
+            net::Ipv4Addr::new(a, b, c, d).fmt(f)
+        } else {
+            self.0.fmt(f)
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Ip(ip) => write!(f, "{}", DisplayIp(ip)),
+            Entry::IpPort(ip, port) if is_ipv4_mapped(ip) => {
+                write!(f, "{}:{}", DisplayIp(ip), port)
+            }
+            Entry::IpPort(ip, port) => write!(f, "[{}]:{}", ip, port),
+            Entry::IpRange(ip, prefix_len)
+                if is_ipv4_mapped(ip) && *prefix_len >= IPV4_MAPPED_PREFIX_LEN =>
+            {
+                write!(f, "{}/{}", DisplayIp(ip), prefix_len - IPV4_MAPPED_PREFIX_LEN)
+            }
+            Entry::IpRange(ip, prefix_len) => write!(f, "{}/{}", ip, prefix_len),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum ParseEntryError {
+    #[error("invalid address: {0}")]
+    Addr(#[from] net::AddrParseError),
+    #[error("invalid prefix length: {0:?}")]
+    PrefixLen(String),
+}
+
 impl std::str::FromStr for Entry {
-    type Err = std::net::AddrParseError;
+    type Err = ParseEntryError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((ip, prefix_len)) = s.split_once('/') {
+            let ip = ip.parse::<net::IpAddr>()?;
+            return prefix_len
+                .parse::<u8>()
+                .ok()
+                .and_then(|prefix_len| Entry::from_range(ip, prefix_len))
+                .ok_or_else(|| ParseEntryError::PrefixLen(prefix_len.to_string()));
+        }
         match s.parse::<std::net::IpAddr>() {
             Ok(ip) => Ok(Entry::from_ip(ip)),
             Err(_) => Ok(Entry::from_addr(s.parse::<net::SocketAddr>()?)),
@@ -44,22 +131,73 @@ impl std::str::FromStr for Entry {
 }
 
 /// A blacklist for socket addresses.  Supports adding individual IP:port tuples
-/// to the blacklist or entire IPs.
+/// to the blacklist, entire IPs or CIDR ranges.
+///
+/// Every entry has an optional expiration time.  Entries loaded from the config
+/// never expire, while entries added at runtime may be temporary.  Expired
+/// entries no longer block addresses but they are not removed automatically,
+/// call [`Blacklist::remove_expired`] periodically to drop them.
 #[derive(Debug, Default, Clone)]
-pub struct Blacklist(HashSet<Entry>);
+pub struct Blacklist(HashMap<Entry, Option<time::Utc>>);
 
 // TODO(CP-34): merge Blacklist with whitelist functionality and replace them with sth
 // like AuthorizationConfig.
 impl FromIterator<Entry> for Blacklist {
     fn from_iter<I: IntoIterator<Item = Entry>>(i: I) -> Self {
-        Self(i.into_iter().collect())
+        Self(i.into_iter().map(|entry| (entry, None)).collect())
     }
 }
 
 impl Blacklist {
-    /// Returns whether given address is on the blacklist.
-    pub fn contains(&self, addr: net::SocketAddr) -> bool {
-        self.0.contains(&Entry::from_ip(addr.ip())) || self.0.contains(&Entry::from_addr(addr))
+    /// Returns whether given address is on the blacklist at time `now`.
+    /// Entries which expired at or before `now` are ignored even if they
+    /// haven't been removed yet.
+    pub fn contains(&self, addr: net::SocketAddr, now: time::Utc) -> bool {
+        let is_active = |expires_at: &Option<time::Utc>| expires_at.map_or(true, |t| t > now);
+        let contains_key = |entry: &Entry| self.0.get(entry).map_or(false, is_active);
+        contains_key(&Entry::from_ip(addr.ip()))
+            || contains_key(&Entry::from_addr(addr))
+            || self.0.iter().any(|(entry, expires_at)| {
+                matches!(entry, Entry::IpRange(..)) && entry.contains(addr) && is_active(expires_at)
+            })
+    }
+
+    /// Adds an entry to the blacklist, which expires at `expires_at` (or never, if `None`).
+    /// If the entry was already present, its expiration time is overwritten.
+    pub fn insert(&mut self, entry: Entry, expires_at: Option<time::Utc>) {
+        self.0.insert(entry, expires_at);
+    }
+
+    /// Removes an entry from the blacklist.  Returns whether the entry was present.
+    pub fn remove(&mut self, entry: &Entry) -> bool {
+        self.0.remove(entry).is_some()
+    }
+
+    /// Removes all the entries which expired before `now` and returns them.
+    pub fn remove_expired(&mut self, now: time::Utc) -> Vec<Entry> {
+        let expired: Vec<Entry> = self
+            .0
+            .iter()
+            .filter(|(_, expires_at)| expires_at.map_or(false, |t| t <= now))
+            .map(|(entry, _)| *entry)
+            .collect();
+        for entry in &expired {
+            self.0.remove(entry);
+        }
+        expired
+    }
+
+    /// Iterates over the entries together with their expiration time.
+    pub fn iter(&self) -> impl Iterator<Item = (&Entry, &Option<time::Utc>)> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 }
 
@@ -70,6 +208,10 @@ mod test {
     const LO4: net::IpAddr = net::IpAddr::V4(net::Ipv4Addr::LOCALHOST);
     const LO6: net::IpAddr = net::IpAddr::V6(net::Ipv6Addr::LOCALHOST);
 
+    fn now() -> time::Utc {
+        time::Utc::from_unix_timestamp(1_000_000).unwrap()
+    }
+
     #[test]
     fn test_parse_entry() {
         fn parse(value: &str) -> Option<Entry> {
@@ -78,7 +220,9 @@ mod test {
 
         assert_eq!(None, parse("foo"));
         assert_eq!(None, parse("192.0.2.*"));
-        assert_eq!(None, parse("192.0.2.0/24"));
+        assert_eq!(None, parse("192.0.2.0/33"));
+        assert_eq!(None, parse("::1/129"));
+        assert_eq!(None, parse("192.0.2.0/"));
         assert_eq!(None, parse("192.0.2.4.5"));
         assert_eq!(None, parse("192.0.2.4:424242"));
 
@@ -94,6 +238,20 @@ mod test {
             Entry::from_addr(net::SocketAddr::new(LO4, 42)),
             parse("[::ffff:127.0.0.1]:42").unwrap()
         );
+
+        assert_eq!(parse("192.0.2.0/24").unwrap(), parse("192.0.2.77/24").unwrap());
+        assert_eq!(parse("::ffff:192.0.2.0/120").unwrap(), parse("192.0.2.0/24").unwrap());
+        assert_eq!(
+            Entry::from_range(LO6, 64).unwrap(),
+            Entry::IpRange(net::Ipv6Addr::UNSPECIFIED, 64)
+        );
+    }
+
+    #[test]
+    fn test_display_entry() {
+        for s in ["192.0.2.4", "192.0.2.4:42", "::1", "[::1]:42", "192.0.2.0/24", "2001:db8::/32"] {
+            assert_eq!(s, s.parse::<Entry>().unwrap().to_string());
+        }
     }
 
     #[test]
@@ -113,15 +271,73 @@ mod test {
         .into_iter()
         .collect();
 
-        assert!(blacklist.contains(SocketAddr::new(LO4, 42)));
-        assert!(blacklist.contains(SocketAddr::new(LO4, 8080)));
-        assert!(blacklist.contains(SocketAddr::new(ip, 42)));
-        assert!(!blacklist.contains(SocketAddr::new(ip, 8080)));
-        assert!(blacklist.contains(SocketAddr::new(LO6, 42)));
-        assert!(!blacklist.contains(SocketAddr::new(LO6, 8080)));
-        assert!(blacklist.contains(SocketAddr::new(mapped_lo4, 42)));
-        assert!(blacklist.contains(SocketAddr::new(mapped_lo4, 8080)));
-        assert!(blacklist.contains(SocketAddr::new(mapped_ip, 42)));
-        assert!(!blacklist.contains(SocketAddr::new(mapped_ip, 8080)));
+        assert!(blacklist.contains(SocketAddr::new(LO4, 42), now()));
+        assert!(blacklist.contains(SocketAddr::new(LO4, 8080), now()));
+        assert!(blacklist.contains(SocketAddr::new(ip, 42), now()));
+        assert!(!blacklist.contains(SocketAddr::new(ip, 8080), now()));
+        assert!(blacklist.contains(SocketAddr::new(LO6, 42), now()));
+        assert!(!blacklist.contains(SocketAddr::new(LO6, 8080), now()));
+        assert!(blacklist.contains(SocketAddr::new(mapped_lo4, 42), now()));
+        assert!(blacklist.contains(SocketAddr::new(mapped_lo4, 8080), now()));
+        assert!(blacklist.contains(SocketAddr::new(mapped_ip, 42), now()));
+        assert!(!blacklist.contains(SocketAddr::new(mapped_ip, 8080), now()));
+    }
+
+    #[test]
+    fn test_blacklist_range() {
+        use std::net::*;
+
+        let blacklist: Blacklist =
+            ["192.0.2.0/24".parse().unwrap(), "2001:db8::/32".parse().unwrap()]
+                .into_iter()
+                .collect();
+
+        assert!(blacklist.contains("192.0.2.4:42".parse().unwrap(), now()));
+        assert!(blacklist.contains("[::ffff:192.0.2.255]:42".parse().unwrap(), now()));
+        assert!(!blacklist.contains("192.0.3.4:42".parse().unwrap(), now()));
+        assert!(blacklist.contains("[2001:db8::1]:42".parse().unwrap(), now()));
+        assert!(!blacklist.contains("[2001:db9::1]:42".parse().unwrap(), now()));
+        assert!(!blacklist.contains(SocketAddr::new(LO4, 42), now()));
+    }
+
+    #[test]
+    fn test_blacklist_expiry() {
+        let now = now();
+        let addr = "192.0.2.4:42".parse().unwrap();
+
+        let mut blacklist = Blacklist::default();
+        blacklist.insert("192.0.2.4".parse().unwrap(), Some(now + time::Duration::seconds(10)));
+        blacklist.insert("192.0.2.0/24".parse().unwrap(), None);
+        assert!(blacklist.contains(addr, now));
+
+        assert_eq!(Vec::<Entry>::new(), blacklist.remove_expired(now));
+        assert_eq!(
+            vec!["192.0.2.4".parse::<Entry>().unwrap()],
+            blacklist.remove_expired(now + time::Duration::seconds(10))
+        );
+        assert_eq!(1, blacklist.len());
+        assert!(blacklist.contains(addr, now));
+
+        assert!(blacklist.remove(&"192.0.2.0/24".parse().unwrap()));
+        assert!(!blacklist.remove(&"192.0.2.0/24".parse().unwrap()));
+        assert!(!blacklist.contains(addr, now));
+    }
+
+    #[test]
+    fn test_blacklist_ignores_expired_entries() {
+        let now = now();
+        let expires_at = now + time::Duration::seconds(10);
+
+        let mut blacklist = Blacklist::default();
+        blacklist.insert("192.0.2.4".parse().unwrap(), Some(expires_at));
+        blacklist.insert("192.0.2.5:42".parse().unwrap(), Some(expires_at));
+        blacklist.insert("198.51.100.0/24".parse().unwrap(), Some(expires_at));
+        for addr in ["192.0.2.4:42", "192.0.2.5:42", "198.51.100.7:42"] {
+            let addr = addr.parse().unwrap();
+            assert!(blacklist.contains(addr, now));
+            // Expired entries don't block the address even before they are removed.
+            assert!(!blacklist.contains(addr, expires_at));
+        }
+        assert_eq!(3, blacklist.len());
     }
 }
@@ -1,9 +1,11 @@
+use crate::config_json::MessageRateLimit;
 use crate::network_protocol::PeerInfo;
 use crate::types::{Blacklist, ROUTED_MESSAGE_TTL};
 use near_crypto::{KeyType, SecretKey};
 use near_primitives::network::PeerId;
 use near_primitives::types::AccountId;
 use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+use std::collections::HashMap;
 use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::sync::Arc;
 use std::time::Duration;
@@ -94,6 +96,8 @@ pub struct NetworkConfig {
     pub push_info_period: Duration,
     /// Nodes will not accept or try to establish connection to such peers.
     pub blacklist: Blacklist,
+    /// Per message type limits on the rate of messages received from a single peer.
+    pub received_message_rate_limits: HashMap<String, MessageRateLimit>,
     /// Flag to disable outbound connections. When this flag is active, nodes will not try to
     /// establish connection with other nodes, but will accept incoming connection if other requirements
     /// are satisfied.
@@ -176,6 +180,7 @@ impl NetworkConfig {
                 .iter()
                 .map(|e| e.parse().expect("failed to parse blacklist"))
                 .collect(),
+            received_message_rate_limits: cfg.received_message_rate_limits,
             outbound_disabled: false,
             archive,
         }
@@ -224,6 +229,7 @@ impl NetworkConfig {
             highest_peer_horizon: 5,
             push_info_period: Duration::from_millis(100),
             blacklist: Blacklist::default(),
+            received_message_rate_limits: HashMap::new(),
             outbound_disabled: false,
             archive: false,
         }
@@ -263,6 +269,18 @@ impl NetworkConfig {
                 self.peer_recent_time_window.as_secs(), UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE.as_secs()
             );
         }
+
+        for (msg_type, limit) in &self.received_message_rate_limits {
+            if !crate::network_protocol::is_known_message_type(msg_type) {
+                anyhow::bail!("Rate limit configured for unknown message type {}.", msg_type);
+            }
+            if !(limit.rate >= 0. && limit.rate.is_finite()) || limit.burst == 0 {
+                anyhow::bail!(
+                    "Invalid rate limit for {} messages: rate({}) must be a non-negative number and burst({}) must be positive.",
+                    msg_type, limit.rate, limit.burst
+                );
+            }
+        }
         Ok(())
     }
 }
@@ -274,7 +292,9 @@ pub const UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE: Duration = Duration::from_
 
 #[cfg(test)]
 mod test {
+    use crate::config_json::{MessageRateLimit, RateLimitPolicy};
     use crate::types::{NetworkConfig, UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE};
+    use std::collections::HashMap;
 
     #[test]
     fn test_network_config() {
@@ -300,5 +320,43 @@ mod test {
         nc.peer_recent_time_window = UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE;
         let res = nc.verify();
         assert!(res.is_err(), "{:?}", res);
+
+        let mut nc = NetworkConfig::from_seed("123", 213);
+        nc.received_message_rate_limits.insert(
+            "PeersRequest".to_string(),
+            MessageRateLimit { rate: 1., burst: 0, policy: RateLimitPolicy::Drop },
+        );
+        let res = nc.verify();
+        assert!(res.is_err(), "{:?}", res);
+
+        let limit = MessageRateLimit { rate: 1., burst: 1, policy: RateLimitPolicy::Drop };
+        let mut nc = NetworkConfig::from_seed("123", 213);
+        nc.received_message_rate_limits.insert("PeersRequest".to_string(), limit.clone());
+        nc.received_message_rate_limits.insert("StateRequestPart".to_string(), limit.clone());
+        assert!(nc.verify().is_ok());
+
+        // Routed messages are limited by the type of their body.
+        for msg_type in ["PeersRequests", "Routed"] {
+            let mut nc = NetworkConfig::from_seed("123", 213);
+            nc.received_message_rate_limits.insert(msg_type.to_string(), limit.clone());
+            let res = nc.verify();
+            assert!(res.is_err(), "{:?}", res);
+        }
+    }
+
+    #[test]
+    fn test_parse_rate_limits() {
+        let limits: HashMap<String, MessageRateLimit> = serde_json::from_str(
+            r#"{
+                "PeersRequest": {"rate": 0.5, "burst": 2},
+                "StateRequestPart": {"rate": 10, "burst": 20, "policy": "ban"}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(limits["PeersRequest"].policy, RateLimitPolicy::Drop);
+        assert_eq!(
+            limits["StateRequestPart"],
+            MessageRateLimit { rate: 10., burst: 20, policy: RateLimitPolicy::Ban }
+        );
     }
 }
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Duration;
 
 /// Time to persist Accounts Id in the router without removing them in seconds.
@@ -95,7 +96,8 @@ pub struct Config {
     /// Ban window for peers who misbehave.
     pub ban_window: Duration,
     /// List of addresses that will not be accepted as valid neighbors.
-    /// It can be IP:Port or IP (to blacklist all connections coming from this address).
+    /// It can be IP:Port, IP (to blacklist all connections coming from this address)
+    /// or a CIDR range such as `192.0.2.0/24` (to blacklist a whole subnet).
     #[serde(default)]
     pub blacklist: Vec<String>,
     /// Time to persist Accounts Id in the router without removing them in seconds.
@@ -104,6 +106,12 @@ pub struct Config {
     /// Period to check on peer status
     #[serde(default = "default_peer_stats_period")]
     pub peer_stats_period: Duration,
+    /// Limits on the rate of messages of a given type received from a single peer.
+    /// Keys are names of `PeerMessage` variants (e.g. `PeersRequest`) or, for routed
+    /// messages, names of `RoutedMessageBody` variants (e.g. `StateRequestPart`).
+    /// Message types without an entry are not rate limited.
+    #[serde(default)]
+    pub received_message_rate_limits: HashMap<String, MessageRateLimit>,
 
     /// List of the public addresses (IP:port) of this node. Useful only if this node is a validator.
     /// This list will be signed and broadcasted to the whole network, so that everyone
@@ -136,6 +144,34 @@ pub struct Config {
     pub trusted_stun_servers: Vec<String>,
 }
 
+/// What to do with a peer which sends messages faster than allowed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, strum::AsRefStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum RateLimitPolicy {
+    /// Drop the messages above the limit and count them in metrics.
+    Drop,
+    /// Ban the peer on the first message above the limit.
+    Ban,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        RateLimitPolicy::Drop
+    }
+}
+
+/// Token bucket limit on the rate of messages of a single type received from a peer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MessageRateLimit {
+    /// Sustained number of messages per second allowed.
+    pub rate: f64,
+    /// Maximum number of messages allowed in a burst.
+    pub burst: u32,
+    #[serde(default)]
+    pub policy: RateLimitPolicy,
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -157,6 +193,7 @@ impl Default for Config {
             blacklist: vec![],
             ttl_account_id_router: default_ttl_account_id_router(),
             peer_stats_period: default_peer_stats_period(),
+            received_message_rate_limits: HashMap::new(),
             public_addrs: vec![],
             trusted_stun_servers: vec![],
         }
@@ -187,7 +187,15 @@ pub struct Pong {
 
 // TODO(#1313): Use Box
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, strum::IntoStaticStr)]
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    strum::IntoStaticStr,
+    strum::EnumVariantNames,
+)]
 #[allow(clippy::large_enum_variant)]
 pub enum RoutedMessageBody {
     BlockApproval(Approval),
@@ -218,6 +226,39 @@ pub enum RoutedMessageBody {
     PartialEncodedChunkForward(PartialEncodedChunkForwardMsg),
 }
 
+/// Names of the variants of `PeerMessage`, which is defined in the `near-network`
+/// crate, other than `Routed`.  Routed messages are identified by the variant of
+/// their [`RoutedMessageBody`] instead.
+pub const PEER_MESSAGE_VARIANTS: &[&str] = &[
+    "Handshake",
+    "HandshakeFailure",
+    "LastEdge",
+    "SyncRoutingTable",
+    "RequestUpdateNonce",
+    "ResponseUpdateNonce",
+    "SyncAccountsData",
+    "PeersRequest",
+    "PeersResponse",
+    "BlockHeadersRequest",
+    "BlockHeaders",
+    "BlockRequest",
+    "Block",
+    "Transaction",
+    "Disconnect",
+    "Challenge",
+    "EpochSyncRequest",
+    "EpochSyncResponse",
+    "EpochSyncFinalizationRequest",
+    "EpochSyncFinalizationResponse",
+];
+
+/// Returns whether `msg_type` is a type of message received from peers, i.e. a name
+/// of a `PeerMessage` variant other than `Routed` or of a [`RoutedMessageBody`] variant.
+pub fn is_known_message_type(msg_type: &str) -> bool {
+    use strum::VariantNames;
+    PEER_MESSAGE_VARIANTS.contains(&msg_type) || RoutedMessageBody::VARIANTS.contains(&msg_type)
+}
+
 impl From<PartialEncodedChunkWithArcReceipts> for RoutedMessageBody {
     fn from(pec: PartialEncodedChunkWithArcReceipts) -> Self {
         if let ShardChunkHeader::V1(legacy_header) = pec.header {
@@ -86,6 +86,15 @@ impl Clock {
     }
 }
 
+/// Converts `utc` to the chrono type which the code outside of the network crates uses.
+// TODO: remove once the whole codebase has migrated from chrono.
+pub fn to_chrono(utc: Utc) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_utc(
+        chrono::NaiveDateTime::from_timestamp(utc.unix_timestamp(), utc.nanosecond()),
+        chrono::Utc,
+    )
+}
+
 struct FakeClockInner {
     mono: Instant,
     utc: Utc,
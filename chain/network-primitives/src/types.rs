@@ -30,15 +30,17 @@ use tokio::net::TcpStream;
 
 /// Exported types, which are part of network protocol.
 pub use crate::network_protocol::{
-    PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg,
-    PeerChainInfo, PeerChainInfoV2, PeerIdOrHash, PeerInfo, Ping, Pong, RoutedMessage,
-    RoutedMessageBody, RoutedMessageV2, StateResponseInfo, StateResponseInfoV1,
-    StateResponseInfoV2,
+    is_known_message_type, PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg,
+    PartialEncodedChunkResponseMsg, PeerChainInfo, PeerChainInfoV2, PeerIdOrHash, PeerInfo, Ping,
+    Pong, RoutedMessage, RoutedMessageBody, RoutedMessageV2, StateResponseInfo,
+    StateResponseInfoV1, StateResponseInfoV2, PEER_MESSAGE_VARIANTS,
 };
 
-pub use crate::blacklist::{Blacklist, Entry as BlacklistEntry};
+pub use crate::blacklist::{
+    Blacklist, Entry as BlacklistEntry, ParseEntryError as BlacklistEntryParseError,
+};
 pub use crate::config::{NetworkConfig, ValidatorConfig, ValidatorEndpoints};
-pub use crate::config_json::Config as ConfigJSON;
+pub use crate::config_json::{Config as ConfigJSON, MessageRateLimit, RateLimitPolicy};
 pub use crate::network_protocol::edge::{Edge, EdgeState, PartialEdgeInfo};
 
 /// Number of hops a message is allowed to travel before being dropped.
@@ -167,3 +167,11 @@ fn serialize_deserialize() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn peer_message_variants_are_known() {
+    use strum::VariantNames;
+    let variants: Vec<&str> =
+        PeerMessage::VARIANTS.iter().copied().filter(|variant| *variant != "Routed").collect();
+    assert_eq!(near_network_primitives::types::PEER_MESSAGE_VARIANTS, variants.as_slice());
+}
@@ -1,5 +1,6 @@
 pub(crate) mod codec;
 pub(crate) mod peer_actor;
+mod rate_limiter;
 mod tracker;
 mod transfer_stats;
 
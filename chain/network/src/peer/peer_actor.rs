@@ -1,6 +1,7 @@
 use crate::accounts_data;
 use crate::network_protocol::{Encoding, ParsePeerMessageError, SyncAccountsData};
 use crate::peer::codec::Codec;
+use crate::peer::rate_limiter::MessageRateLimiter;
 use crate::peer::tracker::Tracker;
 use crate::peer_manager::peer_manager_actor::NetworkState;
 use crate::private_actix::PeersResponse;
@@ -23,8 +24,8 @@ use near_crypto::Signature;
 use near_network_primitives::time;
 use near_network_primitives::types::{
     Ban, NetworkViewClientMessages, NetworkViewClientResponses, PeerChainInfoV2, PeerIdOrHash,
-    PeerInfo, PeerManagerRequest, PeerManagerRequestWithContext, PeerType, ReasonForBan,
-    RoutedMessage, RoutedMessageBody, RoutedMessageFrom, StateResponseInfo,
+    PeerInfo, PeerManagerRequest, PeerManagerRequestWithContext, PeerType, RateLimitPolicy,
+    ReasonForBan, RoutedMessage, RoutedMessageBody, RoutedMessageFrom, StateResponseInfo,
     UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE,
 };
 use near_network_primitives::types::{Edge, PartialEdgeInfo};
@@ -125,6 +126,8 @@ pub(crate) struct PeerActor {
     routed_message_cache: LruCache<(PeerId, PeerIdOrHash, Signature), time::Instant>,
     /// A helper data structure for limiting reading
     throttle_controller: ThrottleController,
+    /// Per message type limits on the rate of messages received from this peer.
+    rate_limiter: MessageRateLimiter,
     /// Whether we detected support for protocol buffers during handshake.
     protocol_buffers_supported: bool,
     /// Whether the PeerActor should skip protobuf support detection and use
@@ -172,6 +175,8 @@ impl PeerActor {
         event_sink: Sink<Event>,
     ) -> Self {
         let now = clock.now();
+        let rate_limiter =
+            MessageRateLimiter::new(&peer_manager_state.config.received_message_rate_limits, now);
         PeerActor {
             clock,
             my_node_info,
@@ -192,6 +197,7 @@ impl PeerActor {
             peer_counter,
             routed_message_cache: LruCache::new(ROUTED_MESSAGE_CACHE_SIZE),
             throttle_controller,
+            rate_limiter,
             protocol_buffers_supported: false,
             force_encoding,
             peer_manager_state,
@@ -692,6 +698,19 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for PeerActor {
             return;
         }
 
+        match self.rate_limiter.check(peer_msg.msg_variant(), self.clock.now()) {
+            Ok(()) => {}
+            Err(RateLimitPolicy::Drop) => {
+                trace!(target: "network", "Dropping {} message from {} above the rate limit", peer_msg.msg_variant(), self.peer_info);
+                return;
+            }
+            Err(RateLimitPolicy::Ban) => {
+                debug!(target: "network", "Banning {} for sending {} messages above the rate limit", self.peer_info, peer_msg.msg_variant());
+                self.ban_peer(ctx, ReasonForBan::Abusive);
+                return;
+            }
+        }
+
         // Drop duplicated messages routed within DROP_DUPLICATED_MESSAGES_PERIOD ms
         if let PeerMessage::Routed(msg) = &peer_msg {
             let msg = &msg.msg;
@@ -847,6 +866,7 @@ impl StreamHandler<Result<Vec<u8>, ReasonForBan>> for PeerActor {
                 self.peer_manager_wrapper_addr
                     .send(ActixMessageWrapper::new_without_size(PeerToManagerMsg::RegisterPeer(RegisterPeer {
                         actor: ctx.address(),
+                        peer_addr: self.peer_addr,
                         peer_info: peer_info.clone(),
                         peer_type: self.peer_type,
                         chain_info: handshake.sender_chain_info.clone(),
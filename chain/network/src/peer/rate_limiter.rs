@@ -0,0 +1,77 @@
+use crate::stats::metrics;
+use near_network_primitives::time;
+use near_network_primitives::types::{MessageRateLimit, RateLimitPolicy};
+use near_rate_limiter::TokenBucket;
+use std::collections::HashMap;
+
+/// Limits the rate of messages received from a single peer, separately for each message type.
+/// Message types are identified by `PeerMessage::msg_variant`, so routed messages are limited
+/// by the type of their body.
+pub(crate) struct MessageRateLimiter {
+    buckets: HashMap<String, (TokenBucket, RateLimitPolicy)>,
+}
+
+impl MessageRateLimiter {
+    pub fn new(limits: &HashMap<String, MessageRateLimit>, now: time::Instant) -> Self {
+        Self {
+            buckets: limits
+                .iter()
+                .map(|(msg_type, limit)| {
+                    let bucket = TokenBucket::new(limit.rate, limit.burst, now.into_inner());
+                    (msg_type.clone(), (bucket, limit.policy))
+                })
+                .collect(),
+        }
+    }
+
+    /// Registers a received message of the given type.  Returns `Err(policy)` if the message
+    /// exceeds the limit and `policy` should be applied to it.
+    pub fn check(&mut self, msg_type: &str, now: time::Instant) -> Result<(), RateLimitPolicy> {
+        let (bucket, policy) = match self.buckets.get_mut(msg_type) {
+            Some(it) => it,
+            None => return Ok(()),
+        };
+        if bucket.try_acquire(now.into_inner()) {
+            return Ok(());
+        }
+        metrics::PEER_MESSAGE_THROTTLED_BY_TYPE_TOTAL
+            .with_label_values(&[msg_type, policy.as_ref()])
+            .inc();
+        Err(*policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_per_message_type() {
+        let clock = time::FakeClock::default();
+        let limits = HashMap::from([
+            (
+                "PeersRequest".to_string(),
+                MessageRateLimit { rate: 1., burst: 2, policy: RateLimitPolicy::Drop },
+            ),
+            (
+                "StateRequestPart".to_string(),
+                MessageRateLimit { rate: 1., burst: 1, policy: RateLimitPolicy::Ban },
+            ),
+        ]);
+        let mut limiter = MessageRateLimiter::new(&limits, clock.now());
+
+        assert_eq!(Ok(()), limiter.check("PeersRequest", clock.now()));
+        assert_eq!(Ok(()), limiter.check("PeersRequest", clock.now()));
+        assert_eq!(Err(RateLimitPolicy::Drop), limiter.check("PeersRequest", clock.now()));
+        assert_eq!(Ok(()), limiter.check("StateRequestPart", clock.now()));
+        assert_eq!(Err(RateLimitPolicy::Ban), limiter.check("StateRequestPart", clock.now()));
+        // Message types without a limit are always accepted.
+        for _ in 0..10 {
+            assert_eq!(Ok(()), limiter.check("Block", clock.now()));
+        }
+
+        clock.advance(time::Duration::seconds(1));
+        assert_eq!(Ok(()), limiter.check("PeersRequest", clock.now()));
+        assert_eq!(Err(RateLimitPolicy::Drop), limiter.check("PeersRequest", clock.now()));
+    }
+}
@@ -25,6 +25,7 @@ use near_rate_limiter::{
 };
 
 use near_network_primitives::time::Utc;
+use std::net::SocketAddr;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 use tokio::net::{TcpListener, TcpStream};
@@ -38,6 +39,9 @@ pub struct PeerConfig {
     pub peers: Vec<PeerInfo>,
     pub start_handshake_with: Option<PeerId>,
     pub force_encoding: Option<crate::network_protocol::Encoding>,
+    /// Port advertised in the handshake.  Defaults to the local port of the
+    /// connection.
+    pub listen_port: Option<u16>,
 }
 
 impl PeerConfig {
@@ -203,6 +207,8 @@ impl PeerHandle {
         let actix = ActixSystem::spawn(move || {
             let my_addr = stream.local_addr().unwrap();
             let peer_addr = stream.peer_addr().unwrap();
+            let listen_addr =
+                SocketAddr::new(my_addr.ip(), cfg.listen_port.unwrap_or(my_addr.port()));
             let (read, write) = tokio::io::split(stream);
             let handshake_timeout = time::Duration::seconds(5);
             let fpm = FakePeerManagerActor { cfg: cfg.clone(), event_sink: send.sink() }.start();
@@ -218,7 +224,7 @@ impl PeerHandle {
                 PeerActor::add_stream(read, ctx);
                 PeerActor::new(
                     clock,
-                    PeerInfo { id: cfg.id(), addr: Some(my_addr), account_id: None },
+                    PeerInfo { id: cfg.id(), addr: Some(listen_addr), account_id: None },
                     peer_addr.clone(),
                     cfg.start_handshake_with.as_ref().map(|id| PeerInfo {
                         id: id.clone(),
@@ -31,6 +31,7 @@ async fn test_peer_communication(
         chain: chain.clone(),
         peers: (0..5).map(|_| data::make_peer_info(&mut rng)).collect(),
         force_encoding: inbound_encoding,
+        listen_port: None,
         start_handshake_with: None,
     };
     let outbound_cfg = PeerConfig {
@@ -38,6 +39,7 @@ async fn test_peer_communication(
         chain: chain.clone(),
         peers: (0..5).map(|_| data::make_peer_info(&mut rng)).collect(),
         force_encoding: outbound_encoding,
+        listen_port: None,
         start_handshake_with: Some(inbound_cfg.id()),
     };
 
@@ -195,6 +197,7 @@ async fn test_handshake(outbound_encoding: Option<Encoding>, inbound_encoding: O
         chain: chain.clone(),
         peers: (0..5).map(|_| data::make_peer_info(&mut rng)).collect(),
         force_encoding: inbound_encoding,
+        listen_port: None,
         start_handshake_with: None,
     };
     let outbound_cfg = PeerConfig {
@@ -202,6 +205,7 @@ async fn test_handshake(outbound_encoding: Option<Encoding>, inbound_encoding: O
         chain: chain.clone(),
         peers: (0..5).map(|_| data::make_peer_info(&mut rng)).collect(),
         force_encoding: outbound_encoding,
+        listen_port: None,
         start_handshake_with: None,
     };
     let (outbound_stream, inbound_stream) = PeerHandle::start_connection().await;
@@ -6,12 +6,15 @@ use near_network_primitives::time;
 use near_network_primitives::types::PeerType;
 use near_primitives::network::PeerId;
 use near_rate_limiter::ThrottleController;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 /// Contains information relevant to a connected peer.
 #[derive(Clone)]
 pub(crate) struct ConnectedPeer {
     pub addr: actix::Addr<PeerActor>,
+    /// Remote address of the connection.
+    pub peer_addr: SocketAddr,
     pub full_peer_info: FullPeerInfo,
     /// Number of bytes we've received from the peer.
     pub received_bytes_per_sec: u64,
@@ -27,11 +27,11 @@ use anyhow::bail;
 use futures::future;
 use near_network_primitives::time;
 use near_network_primitives::types::{
-    AccountOrPeerIdOrHash, Ban, ChainInfo, Edge, InboundTcpConnect, KnownPeerStatus, KnownProducer,
-    NetworkConfig, NetworkViewClientMessages, NetworkViewClientResponses, OutboundTcpConnect,
-    PeerIdOrHash, PeerInfo, PeerManagerRequest, PeerManagerRequestWithContext, PeerType, Ping,
-    Pong, RawRoutedMessage, ReasonForBan, RoutedMessageBody, RoutedMessageFrom, RoutedMessageV2,
-    SetChainInfo, StateResponseInfo,
+    AccountOrPeerIdOrHash, Ban, BlacklistEntry, ChainInfo, Edge, InboundTcpConnect,
+    KnownPeerStatus, KnownProducer, NetworkConfig, NetworkViewClientMessages,
+    NetworkViewClientResponses, OutboundTcpConnect, PeerIdOrHash, PeerInfo, PeerManagerRequest,
+    PeerManagerRequestWithContext, PeerType, Ping, Pong, RawRoutedMessage, ReasonForBan,
+    RoutedMessageBody, RoutedMessageFrom, RoutedMessageV2, SetChainInfo, StateResponseInfo,
 };
 use near_network_primitives::types::{EdgeState, PartialEdgeInfo};
 use near_performance_metrics::framed_write::FramedWrite;
@@ -606,6 +606,7 @@ impl PeerManagerActor {
         partial_edge_info: PartialEdgeInfo,
         peer_type: PeerType,
         addr: Addr<PeerActor>,
+        peer_addr: SocketAddr,
         throttle_controller: ThrottleController,
         ctx: &mut Context<Self>,
     ) {
@@ -630,6 +631,7 @@ impl PeerManagerActor {
 
         self.state.connected_peers.insert(ConnectedPeer {
             addr: addr.clone(),
+            peer_addr,
             full_peer_info,
             sent_bytes_per_sec: 0,
             received_bytes_per_sec: 0,
@@ -788,6 +790,34 @@ impl PeerManagerActor {
         };
     }
 
+    /// Adds an entry to the blacklist and disconnects all the connected peers matching it,
+    /// either by the remote address of the connection or by the advertised address.
+    /// Returns the list of disconnected peers.
+    fn blacklist_add(
+        &mut self,
+        entry: BlacklistEntry,
+        expires_at: Option<time::Utc>,
+    ) -> Vec<PeerId> {
+        info!(target: "network", %entry, ?expires_at, "Adding blacklist entry");
+        if let Err(err) = self.peer_store.blacklist_add(entry, expires_at) {
+            error!(target: "network", ?err, %entry, "Failed to save blacklist entry");
+        }
+        let mut disconnected_peers = vec![];
+        for (peer_id, peer) in self.state.connected_peers.read().iter() {
+            if entry.contains(peer.peer_addr)
+                || peer.full_peer_info.peer_info.addr.map_or(false, |addr| entry.contains(addr))
+            {
+                debug!(target: "network", ?peer_id, %entry, "Disconnecting blacklisted peer");
+                peer.addr.do_send(PeerManagerRequestWithContext {
+                    msg: PeerManagerRequest::UnregisterPeer,
+                    context: Span::current().context(),
+                });
+                disconnected_peers.push(peer_id.clone());
+            }
+        }
+        disconnected_peers
+    }
+
     /// Connects peer with given TcpStream and optional information if it's outbound.
     /// This might fail if the other peers drop listener at its endpoint while establishing connection.
     fn try_connect_peer(
@@ -1200,8 +1230,19 @@ impl PeerManagerActor {
             }
         }
 
+        match self.peer_store.blacklist_remove_expired(&self.clock) {
+            Ok(expired) => {
+                for entry in expired {
+                    info!(target: "network", %entry, "Monitor peers: blacklist entry expired");
+                }
+            }
+            Err(err) => {
+                error!(target: "network", ?err, "Failed to remove expired blacklist entries")
+            }
+        }
+
         if self.is_outbound_bootstrap_needed() {
-            if let Some(peer_info) = self.peer_store.unconnected_peer(|peer_state| {
+            if let Some(peer_info) = self.peer_store.unconnected_peer(&self.clock, |peer_state| {
                 // Ignore connecting to ourself
                 self.my_peer_id == peer_state.peer_info.id
                     || self.config.node_addr == peer_state.peer_info.addr
@@ -1692,6 +1733,12 @@ impl PeerManagerActor {
     #[perf]
     fn handle_msg_inbound_tcp_connect(&self, msg: InboundTcpConnect, ctx: &mut Context<Self>) {
         let _d = delay_detector::DelayDetector::new(|| "inbound tcp connect".into());
+        if let Ok(addr) = msg.stream.peer_addr() {
+            if self.peer_store.is_blacklisted(&self.clock, &addr) {
+                debug!(target: "network", %addr, "Inbound connection from blacklisted address dropped.");
+                return;
+            }
+        }
         if self.is_inbound_allowed()
             || msg
                 .stream
@@ -1762,8 +1809,12 @@ impl PeerManagerActor {
     ) -> RegisterPeerResponse {
         let _d = delay_detector::DelayDetector::new(|| "consolidate".into());
 
-        // Check if this is a blacklisted peer.
-        if (msg.peer_info.addr.as_ref()).map_or(true, |addr| self.peer_store.is_blacklisted(addr)) {
+        // Check if this is a blacklisted peer.  Both the address the connection
+        // comes from and the one the peer advertises have to be allowed.
+        if self.peer_store.is_blacklisted(&self.clock, &msg.peer_addr)
+            || (msg.peer_info.addr.as_ref())
+                .map_or(true, |addr| self.peer_store.is_blacklisted(&self.clock, addr))
+        {
             debug!(target: "network", peer_info = ?msg.peer_info, "Dropping connection from blacklisted peer or unknown address");
             return RegisterPeerResponse::Reject;
         }
@@ -1840,6 +1891,7 @@ impl PeerManagerActor {
             edge_info,
             msg.peer_type,
             msg.actor,
+            msg.peer_addr,
             msg.throttle_controller,
             ctx,
         );
@@ -1911,6 +1963,30 @@ impl PeerManagerActor {
                 self.send_ping(nonce, target);
                 PeerManagerMessageResponse::PingTo
             }
+            PeerManagerMessageRequest::BlacklistAdd { entry, ttl } => {
+                // A ttl too large to be represented is treated as no expiration at all.
+                let expires_at = ttl.and_then(|ttl| self.clock.now_utc().checked_add(ttl));
+                PeerManagerMessageResponse::BlacklistAdd {
+                    disconnected_peers: self.blacklist_add(entry, expires_at),
+                }
+            }
+            PeerManagerMessageRequest::BlacklistRemove(entry) => {
+                info!(target: "network", %entry, "Removing blacklist entry");
+                let removed = self.peer_store.blacklist_remove(&entry).unwrap_or_else(|err| {
+                    error!(target: "network", ?err, %entry, "Failed to remove blacklist entry");
+                    false
+                });
+                PeerManagerMessageResponse::BlacklistRemove { removed }
+            }
+            PeerManagerMessageRequest::FetchBlacklist => {
+                PeerManagerMessageResponse::FetchBlacklist(
+                    self.peer_store
+                        .blacklist()
+                        .iter()
+                        .map(|(entry, expires_at)| (*entry, *expires_at))
+                        .collect(),
+                )
+            }
         }
     }
 
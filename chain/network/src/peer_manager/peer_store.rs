@@ -2,7 +2,8 @@ use crate::store;
 use anyhow::bail;
 use near_network_primitives::time;
 use near_network_primitives::types::{
-    Blacklist, KnownPeerState, KnownPeerStatus, NetworkConfig, PeerInfo, ReasonForBan,
+    Blacklist, BlacklistEntry, KnownPeerState, KnownPeerStatus, NetworkConfig, PeerInfo,
+    ReasonForBan,
 };
 use near_primitives::network::PeerId;
 use rand::seq::IteratorRandom;
@@ -55,6 +56,9 @@ pub struct PeerStore {
 }
 
 impl PeerStore {
+    /// Creates the store.  The blacklist consists of entries from the config,
+    /// given in `blacklist`, and entries added at runtime which are loaded from
+    /// the store.  The former take precedence and never expire.
     pub(crate) fn new(
         clock: &time::Clock,
         store: store::Store,
@@ -69,6 +73,17 @@ impl PeerStore {
         let mut addr_2_peer = HashMap::default();
 
         let now = clock.now_utc();
+        let blacklist = {
+            let mut runtime_blacklist = Blacklist::default();
+            for (entry, expires_at) in store.list_blacklist_entries()? {
+                runtime_blacklist.insert(entry, expires_at);
+            }
+            for (entry, expires_at) in blacklist.iter() {
+                runtime_blacklist.insert(*entry, *expires_at);
+            }
+            runtime_blacklist
+        };
+
         for peer_info in boot_nodes {
             if peerid_2_state.contains_key(&peer_info.id) {
                 error!(id = ?peer_info.id, "There is a duplicated peer in boot_nodes");
@@ -108,7 +123,7 @@ impl PeerStore {
             };
 
             let is_blacklisted =
-                peer_state.peer_info.addr.map_or(false, |addr| blacklist.contains(addr));
+                peer_state.peer_info.addr.map_or(false, |addr| blacklist.contains(addr, now));
             if is_blacklisted {
                 info!(target: "network", "Removing {:?} because address is blacklisted", peer_state.peer_info);
                 peers_to_delete.push(peer_id);
@@ -147,8 +162,43 @@ impl PeerStore {
         Ok(peer_store)
     }
 
-    pub fn is_blacklisted(&self, addr: &SocketAddr) -> bool {
-        self.blacklist.contains(*addr)
+    pub fn is_blacklisted(&self, clock: &time::Clock, addr: &SocketAddr) -> bool {
+        self.blacklist.contains(*addr, clock.now_utc())
+    }
+
+    pub(crate) fn blacklist(&self) -> &Blacklist {
+        &self.blacklist
+    }
+
+    /// Adds an entry to the blacklist and saves it so that it survives restarts.
+    /// Known peers matching the entry are kept in the store, but we will neither
+    /// connect to them nor accept connections from them.
+    pub(crate) fn blacklist_add(
+        &mut self,
+        entry: BlacklistEntry,
+        expires_at: Option<time::Utc>,
+    ) -> anyhow::Result<()> {
+        self.blacklist.insert(entry, expires_at);
+        Ok(self.store.set_blacklist_entry(&entry, &expires_at)?)
+    }
+
+    /// Removes an entry from the blacklist.  Returns whether the entry was present.
+    /// Entries from the config are added back on restart.
+    pub(crate) fn blacklist_remove(&mut self, entry: &BlacklistEntry) -> anyhow::Result<bool> {
+        self.store.delete_blacklist_entries(std::slice::from_ref(entry))?;
+        Ok(self.blacklist.remove(entry))
+    }
+
+    /// Removes the blacklist entries which have expired and returns them.
+    pub(crate) fn blacklist_remove_expired(
+        &mut self,
+        clock: &time::Clock,
+    ) -> anyhow::Result<Vec<BlacklistEntry>> {
+        let expired = self.blacklist.remove_expired(clock.now_utc());
+        if !expired.is_empty() {
+            self.store.delete_blacklist_entries(&expired)?;
+        }
+        Ok(expired)
     }
 
     pub(crate) fn len(&self) -> usize {
@@ -245,16 +295,18 @@ impl PeerStore {
     }
 
     /// Return unconnected or peers with unknown status that we can try to connect to.
-    /// Peers with unknown addresses are filtered out.
+    /// Peers with unknown or blacklisted addresses are filtered out.
     pub(crate) fn unconnected_peer(
         &self,
+        clock: &time::Clock,
         ignore_fn: impl Fn(&KnownPeerState) -> bool,
     ) -> Option<PeerInfo> {
+        let now = clock.now_utc();
         self.find_peers(
             |p| {
                 (p.status == KnownPeerStatus::NotConnected || p.status == KnownPeerStatus::Unknown)
                     && !ignore_fn(p)
-                    && p.peer_info.addr.is_some()
+                    && p.peer_info.addr.map_or(false, |addr| !self.blacklist.contains(addr, now))
             },
             1,
         )
@@ -411,9 +463,11 @@ impl PeerStore {
     ) -> Result<(), Box<dyn std::error::Error>> {
         let mut total: usize = 0;
         let mut blacklisted: usize = 0;
+        let now = clock.now_utc();
         for peer_info in peers {
             total += 1;
-            let is_blacklisted = peer_info.addr.map_or(false, |addr| self.blacklist.contains(addr));
+            let is_blacklisted =
+                peer_info.addr.map_or(false, |addr| self.blacklist.contains(addr, now));
             if is_blacklisted {
                 blacklisted += 1;
             } else {
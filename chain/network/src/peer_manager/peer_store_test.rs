@@ -61,8 +61,8 @@ fn test_unconnected_peer() {
         let store = store::Store::from(opener.open());
         let peer_store =
             PeerStore::new(&clock.clock(), store, &boot_nodes, Default::default()).unwrap();
-        assert!(peer_store.unconnected_peer(|_| false).is_some());
-        assert!(peer_store.unconnected_peer(|_| true).is_none());
+        assert!(peer_store.unconnected_peer(&clock.clock(), |_| false).is_some());
+        assert!(peer_store.unconnected_peer(&clock.clock(), |_| true).is_none());
     }
 }
 
@@ -328,6 +328,63 @@ fn remove_blacklisted_peers_from_store() {
     assert_peers_in_store(&opener, &peer_ids[0..2]);
 }
 
+#[test]
+fn runtime_blacklist_skips_unconnected_peers() {
+    let clock = time::FakeClock::default();
+    let store = store::Store::from(create_test_store());
+    let peer_info = gen_peer_info(0);
+    let mut peer_store =
+        PeerStore::new(&clock.clock(), store, &[peer_info.clone()], Default::default()).unwrap();
+    assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false), Some(peer_info.clone()));
+
+    let entry: BlacklistEntry = "127.0.0.0/8".parse().unwrap();
+    peer_store.blacklist_add(entry, Some(clock.now_utc() + time::Duration::seconds(10))).unwrap();
+    assert!(peer_store.is_blacklisted(&clock.clock(), &peer_info.addr.unwrap()));
+    assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false), None);
+
+    // An expired entry doesn't block the peer even before it is removed.
+    clock.advance(time::Duration::seconds(10));
+    assert!(!peer_store.is_blacklisted(&clock.clock(), &peer_info.addr.unwrap()));
+    assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false), Some(peer_info));
+    assert_eq!(peer_store.blacklist_remove_expired(&clock.clock()).unwrap(), vec![entry]);
+}
+
+#[test]
+fn runtime_blacklist_survives_restart() {
+    let clock = time::FakeClock::default();
+    let store = store::Store::from(create_test_store());
+    let config_entry: BlacklistEntry = "192.168.0.1".parse().unwrap();
+    let config_blacklist: Blacklist = [config_entry].into_iter().collect();
+    let range: BlacklistEntry = "10.0.0.0/8".parse().unwrap();
+    let addr: BlacklistEntry = "10.0.0.1:24567".parse().unwrap();
+    let expires_at = clock.now_utc() + time::Duration::seconds(10);
+    let blacklist = |peer_store: &PeerStore| -> HashMap<_, _> {
+        peer_store.blacklist().iter().map(|(entry, expires_at)| (*entry, *expires_at)).collect()
+    };
+
+    let mut peer_store =
+        PeerStore::new(&clock.clock(), store.clone(), &[], config_blacklist.clone()).unwrap();
+    peer_store.blacklist_add(range, None).unwrap();
+    peer_store.blacklist_add(addr, Some(expires_at)).unwrap();
+    // Entries from the config never expire, even if they were added again at runtime.
+    peer_store.blacklist_add(config_entry, Some(expires_at)).unwrap();
+
+    let mut peer_store =
+        PeerStore::new(&clock.clock(), store.clone(), &[], config_blacklist.clone()).unwrap();
+    assert_eq!(
+        blacklist(&peer_store),
+        HashMap::from([(config_entry, None), (range, None), (addr, Some(expires_at))])
+    );
+
+    clock.advance(time::Duration::seconds(10));
+    assert_eq!(peer_store.blacklist_remove_expired(&clock.clock()).unwrap(), vec![addr]);
+    assert!(peer_store.blacklist_remove(&range).unwrap());
+    assert!(!peer_store.blacklist_remove(&range).unwrap());
+
+    let peer_store = PeerStore::new(&clock.clock(), store, &[], config_blacklist).unwrap();
+    assert_eq!(blacklist(&peer_store), HashMap::from([(config_entry, None)]));
+}
+
 #[track_caller]
 fn assert_peers_in_store(opener: &StoreOpener, want: &[PeerId]) {
     let store = store::Store::from(opener.open());
@@ -7,10 +7,12 @@ use crate::peer_manager;
 use crate::peer_manager::peer_manager_actor::Event as PME;
 use crate::peer_manager::testonly::Event;
 use crate::testonly::{make_rng, AsSet as _};
-use crate::types::{PeerMessage, RoutingTableUpdate};
+use crate::types::{
+    PeerManagerMessageRequest, PeerManagerMessageResponse, PeerMessage, RoutingTableUpdate,
+};
 use near_logger_utils::init_test_logger;
 use near_network_primitives::time;
-use near_network_primitives::types::{Ping, RoutedMessageBody};
+use near_network_primitives::types::{BlacklistEntry, Ping, RoutedMessageBody};
 use near_primitives::network::PeerId;
 use pretty_assertions::assert_eq;
 use rand::Rng as _;
@@ -35,6 +37,7 @@ async fn repeated_data_in_sync_routing_table() {
         peers: vec![],
         start_handshake_with: Some(PeerId::new(pm.cfg.node_key.public_key())),
         force_encoding: Some(Encoding::Proto),
+        listen_port: None,
     };
     let stream = TcpStream::connect(pm.cfg.node_addr.unwrap()).await.unwrap();
     let mut peer = peer::testonly::PeerHandle::start_endpoint(clock.clock(), cfg, stream).await;
@@ -102,6 +105,7 @@ async fn ttl() {
         peers: vec![],
         start_handshake_with: Some(PeerId::new(pm.cfg.node_key.public_key())),
         force_encoding: Some(Encoding::Proto),
+        listen_port: None,
     };
     let stream = TcpStream::connect(pm.cfg.node_addr.unwrap()).await.unwrap();
     let mut peer = peer::testonly::PeerHandle::start_endpoint(clock.clock(), cfg, stream).await;
@@ -165,6 +169,7 @@ async fn accounts_data_broadcast() {
             peers: vec![],
             start_handshake_with: Some(PeerId::new(pm.cfg.node_key.public_key())),
             force_encoding: Some(Encoding::Proto),
+            listen_port: None,
         };
         let stream = TcpStream::connect(pm.cfg.node_addr.unwrap()).await.unwrap();
         let mut peer = peer::testonly::PeerHandle::start_endpoint(clock.clock(), cfg, stream).await;
@@ -232,3 +237,42 @@ async fn accounts_data_broadcast() {
     let got1 = peer1.events.recv_until(take_sync).await;
     assert_eq!(got1.accounts_data.as_set(), want.as_set());
 }
+
+// Peers are blacklisted by the address their connection comes from, not only by
+// the address they advertise.
+#[tokio::test]
+async fn blacklist_connection_address() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let port = crate::test_utils::open_port();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+    let pm = peer_manager::testonly::start(chain.clone(), chain.make_config(port)).await;
+    let cfg = peer::testonly::PeerConfig {
+        signer: data::make_signer(rng),
+        chain,
+        peers: vec![],
+        start_handshake_with: Some(PeerId::new(pm.cfg.node_key.public_key())),
+        force_encoding: Some(Encoding::Proto),
+        listen_port: Some(crate::test_utils::open_port()),
+    };
+    let stream = TcpStream::connect(pm.cfg.node_addr.unwrap()).await.unwrap();
+    let conn_addr = stream.local_addr().unwrap();
+    let mut peer = peer::testonly::PeerHandle::start_endpoint(clock.clock(), cfg, stream).await;
+    peer.complete_handshake().await;
+
+    let entry = BlacklistEntry::from_addr(conn_addr);
+    let resp = pm
+        .actix
+        .addr
+        .send(PeerManagerMessageRequest::BlacklistAdd { entry, ttl: None })
+        .await
+        .unwrap();
+    match resp {
+        PeerManagerMessageResponse::BlacklistAdd { disconnected_peers } => {
+            assert_eq!(disconnected_peers, vec![peer.cfg.id()])
+        }
+        resp => panic!("unexpected response: {resp:?}"),
+    }
+}
@@ -15,6 +15,7 @@ use near_rate_limiter::ThrottleController;
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
 
 /// Received new peers from another peer.
@@ -71,6 +72,9 @@ pub enum PeerToManagerMsgResp {
 #[rtype(result = "RegisterPeerResponse")]
 pub(crate) struct RegisterPeer {
     pub actor: actix::Addr<PeerActor>,
+    /// Remote address of the connection.  Unlike `peer_info.addr`, which is
+    /// advertised by the peer, it can't be spoofed.
+    pub peer_addr: SocketAddr,
     pub peer_info: PeerInfo,
     pub peer_type: PeerType,
     pub chain_info: PeerChainInfoV2,
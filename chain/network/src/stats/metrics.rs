@@ -71,6 +71,15 @@ pub(crate) static PEER_CLIENT_MESSAGE_RECEIVED_BY_TYPE_TOTAL: Lazy<IntCounterVec
         )
         .unwrap()
     });
+pub(crate) static PEER_MESSAGE_THROTTLED_BY_TYPE_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_message_throttled_by_type_total",
+        "Number of messages received from peers which exceeded the per message type rate limit, \
+         by message type and the applied policy",
+        &["type", "policy"],
+    )
+    .unwrap()
+});
 pub(crate) static REQUEST_COUNT_BY_TYPE_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
         "near_requests_count_by_type_total",
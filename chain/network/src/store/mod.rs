@@ -1,7 +1,8 @@
 /// Store module defines atomic DB operations on top of schema module.
 /// All transactions should be implemented within this module,
 /// in particular schema::StoreUpdate is not exported.
-use near_network_primitives::types::{Edge, KnownPeerState};
+use near_network_primitives::time;
+use near_network_primitives::types::{BlacklistEntry, Edge, KnownPeerState};
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::AccountId;
 use std::collections::HashSet;
@@ -142,6 +143,34 @@ impl Store {
     }
 }
 
+// Blacklist storage.
+impl Store {
+    /// Inserts (entry,expires_at) to the Blacklist column.
+    pub fn set_blacklist_entry(
+        &mut self,
+        entry: &BlacklistEntry,
+        expires_at: &Option<time::Utc>,
+    ) -> Result<(), Error> {
+        let mut update = self.0.new_update();
+        update.set::<schema::Blacklist>(entry, expires_at);
+        self.0.commit(update).map_err(Error)
+    }
+
+    /// Deletes rows with keys in <entries> from Blacklist column.
+    pub fn delete_blacklist_entries(&mut self, entries: &[BlacklistEntry]) -> Result<(), Error> {
+        let mut update = self.0.new_update();
+        entries.iter().for_each(|e| update.delete::<schema::Blacklist>(e));
+        self.0.commit(update).map_err(Error)
+    }
+
+    /// Reads the whole Blacklist column.
+    pub fn list_blacklist_entries(
+        &self,
+    ) -> Result<Vec<(BlacklistEntry, Option<time::Utc>)>, Error> {
+        self.0.iter::<schema::Blacklist>().collect::<Result<_, _>>().map_err(Error)
+    }
+}
+
 impl From<near_store::Store> for Store {
     fn from(store: near_store::Store) -> Self {
         Self(schema::Store::new(store.into_inner()))
@@ -126,6 +126,37 @@ impl BorshRepr for EdgeRepr {
     }
 }
 
+/// Blacklist entries are stored in the same text form they have in the config.
+pub struct BlacklistEntryFormat;
+impl Format for BlacklistEntryFormat {
+    type T = primitives::BlacklistEntry;
+    fn encode<W: io::Write>(e: &primitives::BlacklistEntry, w: &mut W) -> io::Result<()> {
+        w.write_all(e.to_string().as_bytes())
+    }
+    fn decode(a: &[u8]) -> Result<primitives::BlacklistEntry, Error> {
+        std::str::from_utf8(a).map_err(invalid_data)?.parse().map_err(invalid_data)
+    }
+}
+
+/// Expiration time of a blacklist entry, `None` if it never expires.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct BlacklistExpiryRepr {
+    /// UNIX timestamp in nanos.
+    expires_at: Option<u64>,
+}
+
+impl BorshRepr for BlacklistExpiryRepr {
+    type T = Option<time::Utc>;
+    fn to_repr(expires_at: &Option<time::Utc>) -> Self {
+        Self { expires_at: expires_at.map(|t| t.unix_timestamp_nanos() as u64) }
+    }
+    fn from_repr(s: Self) -> Result<Option<time::Utc>, Error> {
+        s.expires_at
+            .map(|t| time::Utc::from_unix_timestamp_nanos(t as i128).map_err(invalid_data))
+            .transpose()
+    }
+}
+
 /////////////////////////////////////////////
 // Columns
 
@@ -164,6 +195,13 @@ impl Column for LastComponentNonce {
     type Value = Borsh<u64>;
 }
 
+pub struct Blacklist;
+impl Column for Blacklist {
+    const COL: DBCol = DBCol::Blacklist;
+    type Key = BlacklistEntryFormat;
+    type Value = BlacklistExpiryRepr;
+}
+
 ////////////////////////////////////////////////////
 // Storage
 
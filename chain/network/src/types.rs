@@ -7,10 +7,10 @@ use futures::future::BoxFuture;
 use futures::FutureExt;
 use near_network_primitives::time;
 use near_network_primitives::types::{
-    AccountIdOrPeerTrackingShard, AccountOrPeerIdOrHash, KnownProducer, OutboundTcpConnect,
-    PartialEdgeInfo, PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg,
-    PartialEncodedChunkResponseMsg, PeerChainInfoV2, PeerInfo, PeerType, Ping, Pong, ReasonForBan,
-    SetChainInfo, StateResponseInfo,
+    AccountIdOrPeerTrackingShard, AccountOrPeerIdOrHash, BlacklistEntry, KnownProducer,
+    OutboundTcpConnect, PartialEdgeInfo, PartialEncodedChunkForwardMsg,
+    PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg, PeerChainInfoV2, PeerInfo,
+    PeerType, Ping, Pong, ReasonForBan, SetChainInfo, StateResponseInfo,
 };
 use near_primitives::block::{Approval, ApprovalMessage, Block, BlockHeader};
 use near_primitives::challenge::Challenge;
@@ -69,6 +69,16 @@ pub enum PeerManagerMessageRequest {
         nonce: u64,
         target: PeerId,
     },
+    /// Add an entry to the blacklist and disconnect all the connected peers matching it.
+    /// If `ttl` is set, the entry is removed from the blacklist once it elapses.
+    BlacklistAdd {
+        entry: BlacklistEntry,
+        ttl: Option<time::Duration>,
+    },
+    /// Remove an entry from the blacklist.
+    BlacklistRemove(BlacklistEntry),
+    /// Fetch the current blacklist.
+    FetchBlacklist,
 }
 
 impl PeerManagerMessageRequest {
@@ -98,6 +108,15 @@ pub enum PeerManagerMessageResponse {
     SetAdvOptions,
     FetchRoutingTable(RoutingTableInfo),
     PingTo,
+    /// Peers which were disconnected because they matched the added entry.
+    BlacklistAdd {
+        disconnected_peers: Vec<PeerId>,
+    },
+    /// Whether the entry was present on the blacklist.
+    BlacklistRemove {
+        removed: bool,
+    },
+    FetchBlacklist(Vec<(BlacklistEntry, Option<time::Utc>)>),
 }
 
 impl PeerManagerMessageResponse {
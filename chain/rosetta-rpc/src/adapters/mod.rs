@@ -410,6 +410,11 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                     );
                     operations.push(deploy_contract_operation);
                 }
+
+                // Shared contract actions don't move any funds and have no matching Rosetta
+                // operation types.
+                near_primitives::transaction::Action::DeploySharedContract(_)
+                | near_primitives::transaction::Action::UseSharedContract(_) => {}
             }
         }
         operations
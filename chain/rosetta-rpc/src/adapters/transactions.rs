@@ -118,6 +118,10 @@ fn convert_cause_to_transaction_id(
         StateChangeCauseView::Migration => {
             Ok((TransactionIdentifier::block_event("migration", block_hash), None))
         }
+        StateChangeCauseView::PromiseYieldTimeouts => Ok((
+            TransactionIdentifier::block_event("block-promise-yield-timeouts", block_hash),
+            None,
+        )),
         StateChangeCauseView::Resharding => Err(crate::errors::ErrorKind::InternalInvariantError(
             "State Change 'Resharding' should never be observed".to_string(),
         )),
@@ -1,6 +1,7 @@
 //! Chain Client Configuration
 use std::cmp::max;
 use std::cmp::min;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
@@ -40,6 +41,31 @@ pub struct GCConfig {
     /// Number of epochs for which we keep store data.
     #[serde(default = "default_gc_num_epochs_to_keep")]
     pub gc_num_epochs_to_keep: u64,
+
+    /// Columns whose data is kept for longer than `gc_num_epochs_to_keep`.
+    /// Data of all other columns is garbage collected together with the
+    /// blocks it belongs to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub column_policies: Vec<ColumnGCPolicy>,
+}
+
+/// Retention policy of a single store column.
+///
+/// Exactly one of `num_epochs_to_keep` and `max_age` must be set.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ColumnGCPolicy {
+    /// Name of the column, e.g. `"TransactionResult"`.
+    pub column: String,
+
+    /// Number of epochs for which data in the column is kept.  Can't be lower
+    /// than `gc_num_epochs_to_keep`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_epochs_to_keep: Option<u64>,
+
+    /// How long data in the column is kept, measured against the timestamp of
+    /// the chain head.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<Duration>,
 }
 
 impl Default for GCConfig {
@@ -48,6 +74,7 @@ impl Default for GCConfig {
             gc_blocks_limit: 2,
             gc_fork_clean_step: 100,
             gc_num_epochs_to_keep: DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
+            column_policies: vec![],
         }
     }
 }
@@ -70,6 +97,32 @@ impl GCConfig {
     }
 }
 
+/// Configuration of the JSON-lines file the timelines of processed blocks are
+/// written to.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BlockTimelineLogConfig {
+    /// Path of the file.  Relative paths are resolved against the home
+    /// directory of the node.
+    pub path: PathBuf,
+
+    /// Size in bytes after which the file is rotated.
+    #[serde(default = "default_block_timeline_log_max_file_size")]
+    pub max_file_size: u64,
+
+    /// Number of rotated files, named `<path>.1`, `<path>.2` and so on, to
+    /// keep besides the current one.
+    #[serde(default = "default_block_timeline_log_max_rotated_files")]
+    pub max_rotated_files: usize,
+}
+
+fn default_block_timeline_log_max_file_size() -> u64 {
+    100 * 1024 * 1024
+}
+
+fn default_block_timeline_log_max_rotated_files() -> usize {
+    5
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ClientConfig {
     /// Version of the binary.
@@ -156,6 +209,14 @@ pub struct ClientConfig {
     pub max_gas_burnt_view: Option<Gas>,
     /// Re-export storage layer statistics as prometheus metrics.
     pub enable_statistics_export: bool,
+    /// Validate store invariants of every newly finalized block after garbage
+    /// collection.  Violations are reported via logs and metrics.
+    pub enable_online_store_validation: bool,
+    /// If set, the timeline of every processed block is appended to this file.
+    pub block_timeline_log: Option<BlockTimelineLogConfig>,
+    /// Number of threads the chunks of blocks are applied on.  Zero means one
+    /// thread per CPU.
+    pub apply_chunks_threads: usize,
 }
 
 impl ClientConfig {
@@ -215,6 +276,9 @@ impl ClientConfig {
             trie_viewer_state_size_limit: None,
             max_gas_burnt_view: None,
             enable_statistics_export: true,
+            enable_online_store_validation: false,
+            block_timeline_log: None,
+            apply_chunks_threads: 2,
         }
     }
 }
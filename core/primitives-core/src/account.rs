@@ -6,7 +6,7 @@ pub use near_account_id as id;
 
 use crate::hash::CryptoHash;
 use crate::serialize::dec_format;
-use crate::types::{Balance, Nonce, StorageUsage};
+use crate::types::{Balance, BlockHeight, EpochHeight, Nonce, StorageUsage};
 #[derive(
     BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy,
 )]
@@ -164,6 +164,14 @@ impl AccessKey {
     pub fn full_access() -> Self {
         Self { nonce: 0, permission: AccessKeyPermission::FullAccess }
     }
+
+    /// Refills the allowance of a session key as of the given epoch height,
+    /// the way it is refilled when the key is used.  Other keys don't change.
+    pub fn refill_allowance(&mut self, epoch_height: EpochHeight) {
+        if let AccessKeyPermission::SessionFunctionCall(permission) = &mut self.permission {
+            permission.refill_allowance(epoch_height);
+        }
+    }
 }
 
 /// Defines permissions for AccessKey
@@ -177,6 +185,10 @@ pub enum AccessKeyPermission {
     /// Grants full access to the account.
     /// NOTE: It's used to replace account-level public keys.
     FullAccess,
+
+    /// Grants the same permission as `FunctionCall`, but only until a given
+    /// block height and with an allowance which is refilled every few epochs.
+    SessionFunctionCall(SessionFunctionCallPermission),
 }
 
 /// Grants limited permission to make transactions with FunctionCallActions
@@ -209,6 +221,64 @@ pub struct FunctionCallPermission {
     pub method_names: Vec<String>,
 }
 
+/// Permission of a short-lived access key, e.g. a session key of an app.
+///
+/// Like `FunctionCallPermission`, it only allows transactions with a single
+/// function call to the given receiver and methods.  In addition, the key
+/// can't be used after `expires_at` block height, and its allowance is reset to
+/// `allowance_per_period` at the start of every refill period, which is
+/// counted in epochs.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug,
+)]
+pub struct SessionFunctionCallPermission {
+    /// Balance left to use by this access key in the current refill period.
+    #[serde(with = "dec_format")]
+    pub allowance: Balance,
+
+    /// Allowance the key gets at the start of every refill period.
+    #[serde(with = "dec_format")]
+    pub allowance_per_period: Balance,
+
+    /// Length of a refill period in epochs.  Periods start at epoch heights
+    /// which are multiples of it.  Zero means that the allowance is never
+    /// refilled.
+    pub refill_period: EpochHeight,
+
+    /// Refill period `allowance` was last refilled in, that is the epoch
+    /// height divided by `refill_period`.
+    pub allowance_period: u64,
+
+    /// Last block height in which the access key can be used.
+    pub expires_at: BlockHeight,
+
+    /// The access key only allows transactions with the given receiver's account id.
+    pub receiver_id: String,
+
+    /// A list of method names that can be used. The access key only allows transactions with the
+    /// function call of one of the given method names.
+    /// Empty list means any method name can be used.
+    pub method_names: Vec<String>,
+}
+
+impl SessionFunctionCallPermission {
+    /// Resets the allowance if a new refill period started by the given epoch
+    /// height.
+    pub fn refill_allowance(&mut self, epoch_height: EpochHeight) {
+        if let Some(period) = epoch_height.checked_div(self.refill_period) {
+            if period > self.allowance_period {
+                self.allowance = self.allowance_per_period;
+                self.allowance_period = period;
+            }
+        }
+    }
+
+    pub fn is_expired(&self, block_height: BlockHeight) -> bool {
+        block_height > self.expires_at
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use borsh::BorshSerialize;
@@ -225,6 +295,53 @@ mod tests {
         assert_eq!(to_base(&hash(&bytes)), "EVk5UaxBe8LQ8r8iD5EAxVBs6TJcMDKqyH7PBuho6bBJ");
     }
 
+    #[test]
+    fn test_session_permission_refill_allowance() {
+        let mut permission = SessionFunctionCallPermission {
+            allowance: 10,
+            allowance_per_period: 100,
+            refill_period: 50,
+            allowance_period: 2,
+            expires_at: 1000,
+            receiver_id: "test".to_string(),
+            method_names: vec![],
+        };
+        permission.refill_allowance(149);
+        assert_eq!((permission.allowance, permission.allowance_period), (10, 2));
+        permission.refill_allowance(150);
+        assert_eq!((permission.allowance, permission.allowance_period), (100, 3));
+
+        permission.allowance = 0;
+        permission.refill_period = 0;
+        permission.refill_allowance(1000);
+        assert_eq!((permission.allowance, permission.allowance_period), (0, 3));
+        assert!(!permission.is_expired(1000));
+        assert!(permission.is_expired(1001));
+
+        let mut access_key = AccessKey {
+            nonce: 0,
+            permission: AccessKeyPermission::SessionFunctionCall(SessionFunctionCallPermission {
+                allowance: 0,
+                allowance_per_period: 100,
+                refill_period: 50,
+                allowance_period: 0,
+                expires_at: 1000,
+                receiver_id: "test".to_string(),
+                method_names: vec![],
+            }),
+        };
+        access_key.refill_allowance(50);
+        match access_key.permission {
+            AccessKeyPermission::SessionFunctionCall(permission) => {
+                assert_eq!((permission.allowance, permission.allowance_period), (100, 1));
+            }
+            _ => panic!("Incorrect permission"),
+        }
+        let mut access_key = AccessKey::full_access();
+        access_key.refill_allowance(50);
+        assert_eq!(access_key, AccessKey::full_access());
+    }
+
     #[test]
     fn test_account_deserialization() {
         let old_account = LegacyAccount {
@@ -1,4 +1,4 @@
-use crate::types::Gas;
+use crate::types::{BlockHeightDelta, Gas};
 
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
@@ -14,6 +14,10 @@ pub struct VMConfig {
     pub grow_mem_cost: u32,
     /// Gas cost of a regular operation.
     pub regular_op_cost: u32,
+    /// Number of bytes copied or filled by bulk memory operations per one regular operation
+    /// charged. Only used when bulk memory operations are enabled, see [`WasmFeaturesVersion`].
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub bulk_memory_bytes_per_op: Option<u32>,
 
     /// Describes limits for VM and Runtime.
     pub limit_config: VMLimitConfig,
@@ -96,12 +100,23 @@ pub struct VMLimitConfig {
     /// historically.
     #[serde(default = "AccountIdValidityRulesVersion::v0")]
     pub account_id_validity_rules_version: AccountIdValidityRulesVersion,
+    /// Which WebAssembly proposals contracts are allowed to use, see [`WasmFeaturesVersion`].
+    #[serde(default = "WasmFeaturesVersion::v0")]
+    pub wasm_features_version: WasmFeaturesVersion,
+    /// Number of blocks after which a promise yielded by a contract, and not resumed by it, is
+    /// resumed with an error.
+    #[serde(default = "yield_timeout_length_in_blocks_default")]
+    pub yield_timeout_length_in_blocks: BlockHeightDelta,
 }
 
 fn wasmer2_stack_limit_default() -> i32 {
     100 * 1024
 }
 
+fn yield_timeout_length_in_blocks_default() -> BlockHeightDelta {
+    200
+}
+
 /// Our original code for limiting WASM stack was buggy. We fixed that, but we
 /// still have to use old (`V0`) limiter for old protocol versions.
 ///
@@ -157,12 +172,38 @@ impl AccountIdValidityRulesVersion {
     }
 }
 
+/// Set of WebAssembly proposals, on top of the MVP, which contracts are allowed to use.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    serde_repr::Serialize_repr,
+    serde_repr::Deserialize_repr,
+)]
+#[repr(u8)]
+pub enum WasmFeaturesVersion {
+    /// The MVP only.
+    V0,
+    /// Adds the sign-extension operators and the bulk memory operations.
+    V1,
+}
+
+impl WasmFeaturesVersion {
+    fn v0() -> WasmFeaturesVersion {
+        WasmFeaturesVersion::V0
+    }
+}
+
 impl VMConfig {
     pub fn test() -> VMConfig {
         VMConfig {
             ext_costs: ExtCostsConfig::test(),
             grow_mem_cost: 1,
             regular_op_cost: (SAFETY_MULTIPLIER as u32) * 1285457,
+            bulk_memory_bytes_per_op: Some(8),
             limit_config: VMLimitConfig::test(),
         }
     }
@@ -180,6 +221,7 @@ impl VMConfig {
             ext_costs: ExtCostsConfig::free(),
             grow_mem_cost: 0,
             regular_op_cost: 0,
+            bulk_memory_bytes_per_op: None,
             // We shouldn't have any costs in the limit config.
             limit_config: VMLimitConfig { max_gas_burnt: u64::MAX, ..VMLimitConfig::test() },
         }
@@ -238,6 +280,8 @@ impl VMLimitConfig {
             // is 4 bytes worth of code for each local.
             max_locals_per_contract: Some(max_contract_size / 4),
             account_id_validity_rules_version: AccountIdValidityRulesVersion::V1,
+            wasm_features_version: WasmFeaturesVersion::V1,
+            yield_timeout_length_in_blocks: 200,
         }
     }
 }
@@ -258,6 +302,18 @@ pub struct ExtCostsConfig {
     pub contract_loading_base: Gas,
     /// Cost per byte of loading a pre-compiled contract
     pub contract_loading_bytes: Gas,
+    /// Cost per function, defined or imported, of instantiating a loaded contract
+    #[serde(default)]
+    pub contract_loading_function: Gas,
+    /// Cost per global of instantiating a loaded contract
+    #[serde(default)]
+    pub contract_loading_global: Gas,
+    /// Cost per initial table element of instantiating a loaded contract
+    #[serde(default)]
+    pub contract_loading_table_element: Gas,
+    /// Cost per data segment of instantiating a loaded contract
+    #[serde(default)]
+    pub contract_loading_data_segment: Gas,
 
     /// Base cost for guest memory read
     pub read_memory_base: Gas,
@@ -433,6 +489,10 @@ impl ExtCostsConfig {
             base: SAFETY_MULTIPLIER * 88256037,
             contract_loading_base: SAFETY_MULTIPLIER * 11815321,
             contract_loading_bytes: SAFETY_MULTIPLIER * 72250,
+            contract_loading_function: SAFETY_MULTIPLIER * 1_000_000,
+            contract_loading_global: SAFETY_MULTIPLIER * 500_000,
+            contract_loading_table_element: SAFETY_MULTIPLIER * 200_000,
+            contract_loading_data_segment: SAFETY_MULTIPLIER * 3_500_000,
             read_memory_base: SAFETY_MULTIPLIER * 869954400,
             read_memory_byte: SAFETY_MULTIPLIER * 1267111,
             write_memory_base: SAFETY_MULTIPLIER * 934598287,
@@ -500,6 +560,10 @@ impl ExtCostsConfig {
             base: 0,
             contract_loading_base: 0,
             contract_loading_bytes: 0,
+            contract_loading_function: 0,
+            contract_loading_global: 0,
+            contract_loading_table_element: 0,
+            contract_loading_data_segment: 0,
             read_memory_base: 0,
             read_memory_byte: 0,
             write_memory_base: 0,
@@ -625,6 +689,10 @@ pub enum ExtCosts {
     alt_bn128_pairing_check_element,
     alt_bn128_g1_sum_base,
     alt_bn128_g1_sum_element,
+    contract_loading_function,
+    contract_loading_global,
+    contract_loading_table_element,
+    contract_loading_data_segment,
 }
 
 // Type of an action, used in fees logic.
@@ -706,6 +774,10 @@ impl ExtCosts {
             alt_bn128_pairing_check_element => config.alt_bn128_pairing_check_element,
             alt_bn128_g1_sum_base => config.alt_bn128_g1_sum_base,
             alt_bn128_g1_sum_element => config.alt_bn128_g1_sum_element,
+            contract_loading_function => config.contract_loading_function,
+            contract_loading_global => config.contract_loading_global,
+            contract_loading_table_element => config.contract_loading_table_element,
+            contract_loading_data_segment => config.contract_loading_data_segment,
         }
     }
 }
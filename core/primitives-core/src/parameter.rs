@@ -83,10 +83,15 @@ pub enum Parameter {
     // Smart contract dynamic gas costs
     WasmRegularOpCost,
     WasmGrowMemCost,
+    WasmBulkMemoryBytesPerOp,
     /// Base cost for a host function
     WasmBase,
     WasmContractLoadingBase,
     WasmContractLoadingBytes,
+    WasmContractLoadingFunction,
+    WasmContractLoadingGlobal,
+    WasmContractLoadingTableElement,
+    WasmContractLoadingDataSegment,
     WasmReadMemoryBase,
     WasmReadMemoryByte,
     WasmWriteMemoryBase,
@@ -172,6 +177,8 @@ pub enum Parameter {
     Wasmer2StackLimit,
     MaxLocalsPerContract,
     AccountIdValidityRulesVersion,
+    WasmFeaturesVersion,
+    YieldTimeoutLengthInBlocks,
 }
 
 #[derive(
@@ -215,6 +222,10 @@ impl Parameter {
             Parameter::WasmBase,
             Parameter::WasmContractLoadingBase,
             Parameter::WasmContractLoadingBytes,
+            Parameter::WasmContractLoadingFunction,
+            Parameter::WasmContractLoadingGlobal,
+            Parameter::WasmContractLoadingTableElement,
+            Parameter::WasmContractLoadingDataSegment,
             Parameter::WasmReadMemoryBase,
             Parameter::WasmReadMemoryByte,
             Parameter::WasmWriteMemoryBase,
@@ -305,6 +316,8 @@ impl Parameter {
             Parameter::Wasmer2StackLimit,
             Parameter::MaxLocalsPerContract,
             Parameter::AccountIdValidityRulesVersion,
+            Parameter::WasmFeaturesVersion,
+            Parameter::YieldTimeoutLengthInBlocks,
         ]
         .iter()
     }
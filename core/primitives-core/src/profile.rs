@@ -252,6 +252,10 @@ impl Cost {
         Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_pairing_check_element },
         Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_base },
         Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_element },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::contract_loading_function },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::contract_loading_global },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::contract_loading_table_element },
+        Cost::ExtCost { ext_cost_kind: ExtCosts::contract_loading_data_segment },
     ];
 
     pub fn index(self) -> usize {
@@ -326,6 +330,10 @@ impl Cost {
             Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_pairing_check_element } => 67,
             Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_base } => 68,
             Cost::ExtCost { ext_cost_kind: ExtCosts::alt_bn128_g1_sum_element } => 69,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::contract_loading_function } => 70,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::contract_loading_global } => 71,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::contract_loading_table_element } => 72,
+            Cost::ExtCost { ext_cost_kind: ExtCosts::contract_loading_data_segment } => 73,
         }
     }
 }
@@ -1,5 +1,5 @@
 use crate::serialize::dec_format;
-use crate::types::{AccountId, Balance, EpochId, Gas, Nonce};
+use crate::types::{AccountId, Balance, BlockHeight, EpochId, Gas, Nonce, ProtocolVersion};
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::PublicKey;
 use serde::{Deserialize, Serialize};
@@ -168,6 +168,8 @@ pub enum InvalidAccessKeyError {
     },
     /// Having a deposit with a function call action is not allowed with a function call access key.
     DepositWithFunctionCall,
+    /// The access key can't be used after its expiry block height.
+    AccessKeyExpired { account_id: AccountId, public_key: PublicKey, expires_at: BlockHeight },
 }
 
 /// Describes the error for validating a list of actions.
@@ -200,6 +202,8 @@ pub enum ActionsValidationError {
     UnsuitableStakingKey { public_key: PublicKey },
     /// The attached amount of gas in a FunctionCall action has to be a positive number.
     FunctionCallZeroAttachedGas,
+    /// The action requires a protocol feature which is not enabled in the current protocol version.
+    UnsupportedProtocolFeature { protocol_feature: String, version: ProtocolVersion },
 }
 
 /// Describes the error for validating a receipt.
@@ -317,6 +321,11 @@ impl Display for ActionsValidationError {
                 f,
                 "The attached amount of gas in a FunctionCall action has to be a positive number",
             ),
+            ActionsValidationError::UnsupportedProtocolFeature { protocol_feature, version } => write!(
+                f,
+                "Protocol feature {} required by the action is not enabled in protocol version {}",
+                protocol_feature, version,
+            ),
         }
     }
 }
@@ -441,6 +450,8 @@ pub enum ActionErrorKind {
     OnlyImplicitAccountCreationAllowed { account_id: AccountId },
     /// Delete account whose state is large is temporarily banned.
     DeleteAccountWithLargeState { account_id: AccountId },
+    /// The code with the given hash is not in the shared contract registry.
+    SharedContractDoesNotExist { code_hash: CryptoHash },
 }
 
 impl From<ActionErrorKind> for ActionError {
@@ -546,6 +557,13 @@ impl Display for InvalidAccessKeyError {
             InvalidAccessKeyError::DepositWithFunctionCall => {
                 write!(f, "Having a deposit with a function call action is not allowed with a function call access key.")
             }
+            InvalidAccessKeyError::AccessKeyExpired { account_id, public_key, expires_at } => {
+                write!(
+                    f,
+                    "Access key {} of account {:?} expired at block height {}",
+                    public_key, account_id, expires_at
+                )
+            }
         }
     }
 }
@@ -751,6 +769,7 @@ impl Display for ActionErrorKind {
             ActionErrorKind::InsufficientStake { account_id, stake, minimum_stake } => write!(f, "Account {} tries to stake {} but minimum required stake is {}", account_id, stake, minimum_stake),
             ActionErrorKind::OnlyImplicitAccountCreationAllowed { account_id } => write!(f, "CreateAccount action is called on hex-characters account of length 64 {}", account_id),
             ActionErrorKind::DeleteAccountWithLargeState { account_id } => write!(f, "The state of account {} is too large and therefore cannot be deleted", account_id),
+            ActionErrorKind::SharedContractDoesNotExist { code_hash } => write!(f, "Shared contract with code hash {} does not exist", code_hash),
         }
     }
 }
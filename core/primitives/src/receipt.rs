@@ -1,5 +1,6 @@
 use std::borrow::Borrow;
 use std::fmt;
+use std::mem::size_of;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
@@ -10,8 +11,9 @@ use crate::borsh::maybestd::collections::HashMap;
 use crate::hash::CryptoHash;
 use crate::logging;
 use crate::serialize::{dec_format, option_base64_format};
-use crate::transaction::{Action, TransferAction};
-use crate::types::{AccountId, Balance, ShardId};
+use crate::transaction::{Action, DeploySharedContractAction, TransferAction};
+use crate::trie_key::TrieKey;
+use crate::types::{AccountId, Balance, BlockHeight, ShardId, StorageUsage};
 
 /// Receipts are used for a cross-shard communication.
 /// Receipts could be 2 types (determined by a `ReceiptEnum`): `ReceiptEnum::Action` of `ReceiptEnum::Data`.
@@ -87,6 +89,27 @@ impl Receipt {
             }),
         }
     }
+
+    /// Generates a receipt from system which stores the code deployed to the shared contract
+    /// registry of one shard in the registry of the shard of `receiver_id`, without a receipt_id.
+    /// NOTE: The receiver only determines the shard, its account doesn't have to exist and is not
+    /// changed by the receipt.
+    pub fn new_shared_contract_distribution(receiver_id: &AccountId, code: Vec<u8>) -> Self {
+        Receipt {
+            predecessor_id: "system".parse().unwrap(),
+            receiver_id: receiver_id.clone(),
+            receipt_id: CryptoHash::default(),
+
+            receipt: ReceiptEnum::Action(ActionReceipt {
+                signer_id: "system".parse().unwrap(),
+                signer_public_key: PublicKey::empty(KeyType::ED25519),
+                gas_price: 0,
+                output_data_receivers: vec![],
+                input_data_ids: vec![],
+                actions: vec![Action::DeploySharedContract(DeploySharedContractAction { code })],
+            }),
+        }
+    }
 }
 
 /// Receipt could be either ActionReceipt or DataReceipt
@@ -176,5 +199,50 @@ pub struct DelayedReceiptIndices {
     pub next_available_index: u64,
 }
 
+/// Stores indices for a persistent queue of timeouts of promises yielded by contracts.
+#[derive(Default, BorshSerialize, BorshDeserialize, Clone, PartialEq, Debug)]
+pub struct PromiseYieldIndices {
+    // First inclusive index in the queue.
+    pub first_index: u64,
+    // Exclusive end index of the queue
+    pub next_available_index: u64,
+}
+
+/// Entry of the yielded promises timeout queue.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Debug)]
+pub struct PromiseYieldTimeout {
+    /// Account which yielded the promise.
+    pub account_id: AccountId,
+    /// The data the yielded promise is waiting for.
+    pub data_id: CryptoHash,
+    /// Height of the first block in which the promise is resumed with an error, unless it was
+    /// resumed by the account before.
+    pub expires_at: BlockHeight,
+}
+
+impl PromiseYieldTimeout {
+    /// Returns the storage usage of a promise yielded by `account_id` for as long as it can be
+    /// resumed, which is paid for by the account. It covers the `TrieKey::PendingPromiseYield`
+    /// record of the promise and its entry in the timeout queue.
+    pub fn storage_usage(
+        account_id: &AccountId,
+        num_extra_bytes_record: StorageUsage,
+    ) -> StorageUsage {
+        let pending_key = TrieKey::PendingPromiseYield {
+            receiver_id: account_id.clone(),
+            data_id: CryptoHash::default(),
+        };
+        let pending_value = size_of::<BlockHeight>();
+        let timeout_key = TrieKey::PromiseYieldTimeout { index: 0 };
+        // Borsh encodes the account id with its length as `u32`.
+        let timeout_value = size_of::<u32>()
+            + account_id.len()
+            + size_of::<CryptoHash>()
+            + size_of::<BlockHeight>();
+        (pending_key.len() + pending_value + timeout_key.len() + timeout_value) as StorageUsage
+            + 2 * num_extra_bytes_record
+    }
+}
+
 /// Map of shard to list of receipts to send to it.
 pub type ReceiptResult = HashMap<ShardId, Vec<Receipt>>;
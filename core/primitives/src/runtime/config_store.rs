@@ -29,6 +29,13 @@ static CONFIG_DIFFS: &[(ProtocolVersion, &str)] = &[
     (53, include_config!("53.txt")),
     #[cfg(feature = "protocol_feature_account_id_in_function_call_permission")]
     (130, include_config!("130.txt")),
+    // Enabled sign-extension operators and bulk memory operations
+    #[cfg(feature = "protocol_feature_wasm_extensions")]
+    (131, include_config!("131.txt")),
+    // Charged instantiation of contracts by the number of their functions, globals, table
+    // elements and data segments
+    #[cfg(feature = "protocol_feature_structured_loading_cost")]
+    (132, include_config!("132.txt")),
 ];
 
 /// Testnet parameters for versions <= 29, which (incorrectly) differed from mainnet parameters
@@ -58,6 +58,7 @@ impl ParameterTable {
                 "ext_costs": self.json_map(Parameter::ext_costs(), "wasm_"),
                 "grow_mem_cost": self.get(Parameter::WasmGrowMemCost),
                 "regular_op_cost": self.get(Parameter::WasmRegularOpCost),
+                "bulk_memory_bytes_per_op": self.get(Parameter::WasmBulkMemoryBytesPerOp),
                 "limit_config": self.json_map(Parameter::vm_limits(), ""),
             },
             "account_creation_config": {
@@ -246,6 +246,42 @@ pub fn account_id_to_shard_id(account_id: &AccountId, shard_layout: &ShardLayout
     }
 }
 
+/// Returns an account which belongs to the given shard, or `None` if no account can belong to it.
+/// It is used to address receipts which have to be delivered to a shard rather than to a particular
+/// account, the returned account doesn't have to exist.
+pub fn shard_id_to_receiver_account_id(
+    shard_id: ShardId,
+    shard_layout: &ShardLayout,
+) -> Option<AccountId> {
+    if shard_id >= shard_layout.num_shards() {
+        return None;
+    }
+    match shard_layout {
+        // Every shard gets about the same share of the account ids, so this takes about
+        // `num_shards` attempts.
+        ShardLayout::V0(_) => (0u64..)
+            .map(|i| format!("shard{}", i).parse().unwrap())
+            .find(|account_id| account_id_to_shard_id(account_id, shard_layout) == shard_id),
+        // A fixed shard contains its account and any other shard starts at a boundary account,
+        // except for the first one which starts at the lowest valid account id. If the boundary
+        // account itself belongs to a fixed shard, the account right after it is tried instead.
+        ShardLayout::V1(ShardLayoutV1 { fixed_shards, boundary_accounts, .. }) => {
+            let lowest_account_id = "00".to_string();
+            fixed_shards
+                .iter()
+                .map(|account_id| account_id.to_string())
+                .chain(std::iter::once(lowest_account_id))
+                .chain(
+                    boundary_accounts.iter().flat_map(|account_id| {
+                        [account_id.to_string(), format!("{}0", account_id)]
+                    }),
+                )
+                .filter_map(|account_id| account_id.parse().ok())
+                .find(|account_id| account_id_to_shard_id(account_id, shard_layout) == shard_id)
+        }
+    }
+}
+
 /// Maps an account to the shard that it belongs to given a shard_layout
 pub fn account_id_to_shard_uid(account_id: &AccountId, shard_layout: &ShardLayout) -> ShardUId {
     ShardUId::from_shard_id_and_layout(
@@ -332,7 +368,9 @@ pub fn get_block_shard_uid_rev(
 
 #[cfg(test)]
 mod tests {
-    use crate::shard_layout::{account_id_to_shard_id, ShardLayout, ShardUId};
+    use crate::shard_layout::{
+        account_id_to_shard_id, shard_id_to_receiver_account_id, ShardLayout, ShardUId,
+    };
     use rand::distributions::Alphanumeric;
     use rand::rngs::StdRng;
     use rand::{Rng, SeedableRng};
@@ -355,6 +393,12 @@ mod tests {
         let expected_distribution: HashMap<_, _> =
             vec![(0, 246), (1, 252), (2, 230), (3, 272)].into_iter().collect();
         assert_eq!(shard_id_distribution, expected_distribution);
+
+        for shard_id in 0..num_shards {
+            let account_id = shard_id_to_receiver_account_id(shard_id, &shard_layout).unwrap();
+            assert_eq!(account_id_to_shard_id(&account_id, &shard_layout), shard_id);
+        }
+        assert_eq!(shard_id_to_receiver_account_id(num_shards, &shard_layout), None);
     }
 
     #[test]
@@ -397,5 +441,11 @@ mod tests {
         assert_eq!(account_id_to_shard_id(&"foo.goo".parse().unwrap(), &shard_layout), 6);
         assert_eq!(account_id_to_shard_id(&"goo".parse().unwrap(), &shard_layout), 6);
         assert_eq!(account_id_to_shard_id(&"zoo".parse().unwrap(), &shard_layout), 7);
+
+        for shard_id in 0..8 {
+            let account_id = shard_id_to_receiver_account_id(shard_id, &shard_layout).unwrap();
+            assert_eq!(account_id_to_shard_id(&account_id, &shard_layout), shard_id);
+        }
+        assert_eq!(shard_id_to_receiver_account_id(8, &shard_layout), None);
     }
 }
@@ -95,6 +95,14 @@ impl StateRecord {
                 Some(StateRecord::DelayedReceipt(Box::new(receipt)))
             }
             col::DELAYED_RECEIPT_INDICES => None,
+            // Shared contract registry is not owned by any account and can't be represented as
+            // a record.
+            col::SHARED_CONTRACT_CODE => None,
+            // Timeouts are relative to the chain the promises were yielded on, so yielded promises
+            // are exported only as the postponed receipts waiting for them.
+            col::PROMISE_YIELD_INDICES => None,
+            col::PROMISE_YIELD_TIMEOUT => None,
+            col::PENDING_PROMISE_YIELD => None,
             _ => unreachable!(),
         }
     }
@@ -5,7 +5,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use crate::block_header::BlockHeader;
 use crate::epoch_manager::block_info::BlockInfo;
 use crate::epoch_manager::epoch_info::EpochInfo;
-use crate::hash::CryptoHash;
+use crate::hash::{hash, CryptoHash};
 use crate::merkle::{MerklePath, PartialMerkleTree};
 use crate::sharding::{
     ReceiptProof, ShardChunk, ShardChunkHeader, ShardChunkHeaderV1, ShardChunkV1,
@@ -222,6 +222,40 @@ pub struct EpochSyncFinalizationResponse {
     pub next_epoch_info: EpochInfo,
 }
 
+impl EpochSyncFinalizationResponse {
+    /// Hash of the epoch data in the response, it must match `epoch_sync_data_hash` of
+    /// `cur_epoch_header`.
+    pub fn epoch_sync_data_hash(&self) -> CryptoHash {
+        compute_epoch_sync_data_hash(
+            &self.prev_epoch_first_block_info,
+            &self.prev_epoch_prev_last_block_info,
+            &self.prev_epoch_last_block_info,
+            &self.prev_epoch_info,
+            &self.cur_epoch_info,
+            &self.next_epoch_info,
+        )
+    }
+}
+
+/// Hash of the data needed to initialize the epoch manager after Epoch Sync. The first block of
+/// every epoch commits to it in `epoch_sync_data_hash`.
+pub fn compute_epoch_sync_data_hash(
+    prev_epoch_first_block_info: &BlockInfo,
+    prev_epoch_prev_last_block_info: &BlockInfo,
+    prev_epoch_last_block_info: &BlockInfo,
+    prev_epoch_info: &EpochInfo,
+    cur_epoch_info: &EpochInfo,
+    next_epoch_info: &EpochInfo,
+) -> CryptoHash {
+    let mut data = prev_epoch_first_block_info.try_to_vec().unwrap();
+    data.extend(prev_epoch_prev_last_block_info.try_to_vec().unwrap());
+    data.extend(prev_epoch_last_block_info.try_to_vec().unwrap());
+    data.extend(prev_epoch_info.try_to_vec().unwrap());
+    data.extend(cur_epoch_info.try_to_vec().unwrap());
+    data.extend(next_epoch_info.try_to_vec().unwrap());
+    hash(data.as_slice())
+}
+
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(BorshSerialize, BorshDeserialize, Eq, PartialEq, Debug, Clone)]
 pub enum EpochSyncResponse {
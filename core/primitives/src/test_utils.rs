@@ -12,6 +12,7 @@ use crate::hash::CryptoHash;
 use crate::merkle::PartialMerkleTree;
 use crate::num_rational::Ratio;
 use crate::serialize::from_base64;
+use crate::shard_layout::ShardLayout;
 use crate::sharding::ShardChunkHeader;
 use crate::transaction::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
@@ -457,14 +458,23 @@ impl Block {
     }
 }
 
-#[derive(Default)]
 pub struct MockEpochInfoProvider {
     pub validators: HashMap<AccountId, Balance>,
+    pub shard_layout: ShardLayout,
+}
+
+impl Default for MockEpochInfoProvider {
+    fn default() -> Self {
+        MockEpochInfoProvider {
+            validators: HashMap::default(),
+            shard_layout: ShardLayout::v0_single_shard(),
+        }
+    }
 }
 
 impl MockEpochInfoProvider {
     pub fn new(validators: impl Iterator<Item = (AccountId, Balance)>) -> Self {
-        MockEpochInfoProvider { validators: validators.collect() }
+        MockEpochInfoProvider { validators: validators.collect(), ..Default::default() }
     }
 }
 
@@ -489,6 +499,10 @@ impl EpochInfoProvider for MockEpochInfoProvider {
     fn minimum_stake(&self, _prev_block_hash: &CryptoHash) -> Result<Balance, EpochError> {
         Ok(0)
     }
+
+    fn shard_layout(&self, _epoch_id: &EpochId) -> Result<ShardLayout, EpochError> {
+        Ok(self.shard_layout.clone())
+    }
 }
 
 impl FinalExecutionStatus {
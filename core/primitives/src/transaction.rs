@@ -70,6 +70,10 @@ pub enum Action {
     AddKey(AddKeyAction),
     DeleteKey(DeleteKeyAction),
     DeleteAccount(DeleteAccountAction),
+    /// Deploys a Wasm code to the shared contract registry of all shards
+    DeploySharedContract(DeploySharedContractAction),
+    /// Sets a code from the shared contract registry of the receiver's shard to a receiver_id
+    UseSharedContract(UseSharedContractAction),
 }
 
 impl Action {
@@ -220,6 +224,43 @@ impl From<DeleteAccountAction> for Action {
     }
 }
 
+/// Deploy contract to the shared contract registry of all shards action
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct DeploySharedContractAction {
+    /// WebAssembly binary
+    #[serde(with = "base64_format")]
+    pub code: Vec<u8>,
+}
+
+impl From<DeploySharedContractAction> for Action {
+    fn from(deploy_shared_contract_action: DeploySharedContractAction) -> Self {
+        Self::DeploySharedContract(deploy_shared_contract_action)
+    }
+}
+
+impl fmt::Debug for DeploySharedContractAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeploySharedContractAction")
+            .field("code", &format_args!("{}", logging::pretty_utf8(&self.code)))
+            .finish()
+    }
+}
+
+/// Use contract from the shared contract registry action
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct UseSharedContractAction {
+    /// Hash of the code in the shared contract registry
+    pub code_hash: CryptoHash,
+}
+
+impl From<UseSharedContractAction> for Action {
+    fn from(use_shared_contract_action: UseSharedContractAction) -> Self {
+        Self::UseSharedContract(use_shared_contract_action)
+    }
+}
+
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Eq, Debug, Clone)]
 #[borsh_init(init)]
@@ -35,8 +35,19 @@ pub(crate) mod col {
     pub const DELAYED_RECEIPT: u8 = 8;
     /// This column id is used when storing Key-Value data from a contract on an `account_id`.
     pub const CONTRACT_DATA: u8 = 9;
+    /// This column id is used when storing contract blob in the shared contract registry for
+    /// a given `code_hash`.
+    pub const SHARED_CONTRACT_CODE: u8 = 10;
+    /// This column id is used when storing the indices of the yielded promises timeout queue.
+    /// NOTE: It is a singleton per shard.
+    pub const PROMISE_YIELD_INDICES: u8 = 11;
+    /// This column id is used when storing entries of the yielded promises timeout queue.
+    pub const PROMISE_YIELD_TIMEOUT: u8 = 12;
+    /// This column id is used when storing the block height at which a promise yielded by
+    /// a given `account_id` times out, for as long as it may still be resumed.
+    pub const PENDING_PROMISE_YIELD: u8 = 13;
     /// All columns
-    pub const NON_DELAYED_RECEIPT_COLUMNS: [(u8, &str); 8] = [
+    pub const NON_DELAYED_RECEIPT_COLUMNS: [(u8, &str); 10] = [
         (ACCOUNT, "Account"),
         (CONTRACT_CODE, "ContractCode"),
         (ACCESS_KEY, "AccessKey"),
@@ -45,6 +56,8 @@ pub(crate) mod col {
         (PENDING_DATA_COUNT, "PendingDataCount"),
         (POSTPONED_RECEIPT, "PostponedReceipt"),
         (CONTRACT_DATA, "ContractData"),
+        (SHARED_CONTRACT_CODE, "SharedContractCode"),
+        (PENDING_PROMISE_YIELD, "PendingPromiseYield"),
     ];
 }
 
@@ -83,6 +96,23 @@ pub enum TrieKey {
     /// Used to store a key-value record `Vec<u8>` within a contract deployed on a given `AccountId`
     /// and a given key.
     ContractData { account_id: AccountId, key: Vec<u8> },
+    /// Used to store `Vec<u8>` contract code in the shared contract registry for a given hash of
+    /// the code.
+    /// NOTE: Each shard has its own copy of the registry, the code deployed in one shard is
+    /// distributed to the others with receipts. Accounts reference these records by `code_hash`,
+    /// the record is not owned by any of them.
+    SharedContractCode { code_hash: CryptoHash },
+    /// Used to store indices of the yielded promises timeout queue
+    /// (`primitives::receipt::PromiseYieldIndices`).
+    /// NOTE: It is a singleton per shard.
+    PromiseYieldIndices,
+    /// Used to store a `primitives::receipt::PromiseYieldTimeout` for a given index `u64` in the
+    /// yielded promises timeout queue. The queue is unique per shard.
+    PromiseYieldTimeout { index: u64 },
+    /// Used to store the `BlockHeight` at which the promise yielded by a given receiver's
+    /// `AccountId` and waiting for a given `data_id` times out.
+    /// NOTE: The record is removed once the promise is resumed or timed out.
+    PendingPromiseYield { receiver_id: AccountId, data_id: CryptoHash },
 }
 
 /// Provides `len` function.
@@ -140,6 +170,19 @@ impl TrieKey {
                     + ACCOUNT_DATA_SEPARATOR.len()
                     + key.len()
             }
+            TrieKey::SharedContractCode { code_hash } => {
+                col::SHARED_CONTRACT_CODE.len() + code_hash.as_ref().len()
+            }
+            TrieKey::PromiseYieldIndices => col::PROMISE_YIELD_INDICES.len(),
+            TrieKey::PromiseYieldTimeout { .. } => {
+                col::PROMISE_YIELD_TIMEOUT.len() + size_of::<u64>()
+            }
+            TrieKey::PendingPromiseYield { receiver_id, data_id } => {
+                col::PENDING_PROMISE_YIELD.len()
+                    + receiver_id.len()
+                    + ACCOUNT_DATA_SEPARATOR.len()
+                    + data_id.as_ref().len()
+            }
         }
     }
 
@@ -199,6 +242,23 @@ impl TrieKey {
                 buf.push(ACCOUNT_DATA_SEPARATOR);
                 buf.extend(key);
             }
+            TrieKey::SharedContractCode { code_hash } => {
+                buf.push(col::SHARED_CONTRACT_CODE);
+                buf.extend(code_hash.as_ref());
+            }
+            TrieKey::PromiseYieldIndices => {
+                buf.push(col::PROMISE_YIELD_INDICES);
+            }
+            TrieKey::PromiseYieldTimeout { index } => {
+                buf.push(col::PROMISE_YIELD_TIMEOUT);
+                buf.extend(&index.to_le_bytes());
+            }
+            TrieKey::PendingPromiseYield { receiver_id, data_id } => {
+                buf.push(col::PENDING_PROMISE_YIELD);
+                buf.extend(receiver_id.as_ref().as_bytes());
+                buf.push(ACCOUNT_DATA_SEPARATOR);
+                buf.extend(data_id.as_ref());
+            }
         };
         debug_assert_eq!(expected_len, buf.len() - start_len);
     }
@@ -329,6 +389,16 @@ pub mod trie_key_parsers {
         parse_account_id_from_slice(account_id, "ContractCode")
     }
 
+    pub fn is_shared_contract_code_key(raw_key: &[u8]) -> bool {
+        raw_key.first() == Some(&col::SHARED_CONTRACT_CODE)
+    }
+
+    /// Returns whether the key belongs to the yielded promises timeout queue, that is whether it
+    /// is either `TrieKey::PromiseYieldIndices` or `TrieKey::PromiseYieldTimeout`.
+    pub fn is_promise_yield_queue_key(raw_key: &[u8]) -> bool {
+        matches!(raw_key.first(), Some(&(col::PROMISE_YIELD_INDICES | col::PROMISE_YIELD_TIMEOUT)))
+    }
+
     pub fn parse_trie_key_access_key_from_raw_key(
         raw_key: &[u8],
     ) -> Result<TrieKey, std::io::Error> {
@@ -346,6 +416,8 @@ pub mod trie_key_parsers {
                 continue;
             }
             let account_id = match col {
+                // The shared contract registry is not owned by any account.
+                col::SHARED_CONTRACT_CODE => return Ok(None),
                 col::ACCOUNT => parse_account_id_from_account_key(raw_key)?,
                 col::CONTRACT_CODE => parse_account_id_from_contract_code_key(raw_key)?,
                 col::ACCESS_KEY => parse_account_id_from_access_key_key(raw_key)?,
@@ -405,6 +477,11 @@ pub mod trie_key_parsers {
         res
     }
 
+    /// Returns the prefix shared by the keys of the contract codes of all accounts.
+    pub fn get_raw_prefix_for_contract_codes() -> Vec<u8> {
+        vec![col::CONTRACT_CODE]
+    }
+
     pub fn get_raw_prefix_for_contract_data(account_id: &AccountId, prefix: &[u8]) -> Vec<u8> {
         let mut res = Vec::with_capacity(
             col::CONTRACT_DATA.len()
@@ -624,4 +701,41 @@ mod tests {
         let raw_key = key.to_vec();
         assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
     }
+
+    #[test]
+    fn test_key_for_shared_contract_code_consistency() {
+        let key = TrieKey::SharedContractCode { code_hash: CryptoHash::hash_bytes(b"code") };
+        let raw_key = key.to_vec();
+        assert_eq!(raw_key.len(), key.len());
+        assert!(trie_key_parsers::is_shared_contract_code_key(&raw_key));
+        assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
+        let key = TrieKey::ContractCode { account_id: "alice.near".parse().unwrap() };
+        assert!(!trie_key_parsers::is_shared_contract_code_key(&key.to_vec()));
+    }
+
+    #[test]
+    fn test_key_for_promise_yield_consistency() {
+        let key = TrieKey::PromiseYieldIndices;
+        let raw_key = key.to_vec();
+        assert!(trie_key_parsers::is_promise_yield_queue_key(&raw_key));
+        assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
+        let key = TrieKey::PromiseYieldTimeout { index: 0 };
+        let raw_key = key.to_vec();
+        assert_eq!(raw_key.len(), key.len());
+        assert!(trie_key_parsers::is_promise_yield_queue_key(&raw_key));
+        assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
+        for account_id in OK_ACCOUNT_IDS.iter().map(|x| x.parse::<AccountId>().unwrap()) {
+            let key = TrieKey::PendingPromiseYield {
+                receiver_id: account_id.clone(),
+                data_id: CryptoHash::default(),
+            };
+            let raw_key = key.to_vec();
+            assert_eq!(raw_key.len(), key.len());
+            assert!(!trie_key_parsers::is_promise_yield_queue_key(&raw_key));
+            assert_eq!(
+                trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().unwrap(),
+                account_id
+            );
+        }
+    }
 }
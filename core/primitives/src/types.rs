@@ -10,6 +10,7 @@ use crate::challenge::ChallengesResult;
 use crate::errors::EpochError;
 use crate::hash::CryptoHash;
 use crate::serialize::dec_format;
+use crate::shard_layout::ShardLayout;
 use crate::trie_key::TrieKey;
 
 use crate::receipt::Receipt;
@@ -162,6 +163,9 @@ pub enum StateChangeCause {
     Migration,
     /// State changes for building states for re-sharding
     Resharding,
+    /// Promises yielded by contracts which were not resumed in time were resumed with an error,
+    /// and removed from the timeout queue in the state.
+    PromiseYieldTimeouts,
 }
 
 /// This represents the committed changes in the Trie with a change cause.
@@ -345,6 +349,11 @@ impl StateChanges {
                 TrieKey::PostponedReceipt { .. } => {}
                 TrieKey::DelayedReceiptIndices => {}
                 TrieKey::DelayedReceipt { .. } => {}
+                TrieKey::PromiseYieldIndices => {}
+                TrieKey::PromiseYieldTimeout { .. } => {}
+                TrieKey::PendingPromiseYield { .. } => {}
+                // Shared contract registry is not owned by any account
+                TrieKey::SharedContractCode { .. } => {}
             }
         }
 
@@ -914,6 +923,9 @@ pub trait EpochInfoProvider {
     ) -> Result<Balance, EpochError>;
 
     fn minimum_stake(&self, prev_block_hash: &CryptoHash) -> Result<Balance, EpochError>;
+
+    /// Get the shard layout of the given epoch.
+    fn shard_layout(&self, epoch_id: &EpochId) -> Result<ShardLayout, EpochError>;
 }
 
 /// Mode of the trie cache.
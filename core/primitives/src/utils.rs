@@ -270,6 +270,18 @@ pub fn create_data_id(
     )
 }
 
+/// Creates a new Receipt ID for the data receipt sent to a yielded promise waiting for `data_id`
+/// when it times out.
+/// This method is backward compatible, so it takes the current protocol version.
+pub fn create_receipt_id_from_data_id(
+    protocol_version: ProtocolVersion,
+    data_id: &CryptoHash,
+    prev_block_hash: &CryptoHash,
+    block_hash: &CryptoHash,
+) -> CryptoHash {
+    create_hash_upgradable(protocol_version, data_id, prev_block_hash, block_hash, 0)
+}
+
 /// Creates a unique random seed to be provided to `VMContext` from a give `action_hash` and
 /// a given `random_seed`.
 /// This method is backward compatible, so it takes the current protocol version.
@@ -20,7 +20,7 @@ pub struct Version {
 pub type DbVersion = u32;
 
 /// Current version of the database.
-pub const DB_VERSION: DbVersion = 31;
+pub const DB_VERSION: DbVersion = 32;
 
 use crate::upgrade_schedule::{get_protocol_version_internal, ProtocolUpgradeVotingSchedule};
 /// Protocol version type.
@@ -168,6 +168,25 @@ pub enum ProtocolFeature {
     /// Validate account id for function call access keys.
     #[cfg(feature = "protocol_feature_account_id_in_function_call_permission")]
     AccountIdInFunctionCallPermission,
+    /// Allow contracts to use sign-extension operators and bulk memory operations.
+    #[cfg(feature = "protocol_feature_wasm_extensions")]
+    WasmExtensions,
+    /// Charge for instantiating a contract depending on the number of its functions, globals,
+    /// table elements and data segments.
+    #[cfg(feature = "protocol_feature_structured_loading_cost")]
+    StructuredLoadingCost,
+    /// Deploy contract code once to the shared contract registry, which is distributed to all
+    /// shards, and let any account use it by its hash.
+    #[cfg(feature = "protocol_feature_shared_contracts")]
+    SharedContracts,
+    /// Allow contracts to create promises which wait for data supplied by a later call of the
+    /// same contract, see `promise_yield_create` and `promise_yield_resume` host functions.
+    #[cfg(feature = "protocol_feature_yield_resume")]
+    YieldResume,
+    /// Access keys which expire at a given block height and whose allowance is refilled
+    /// periodically, see `AccessKeyPermission::SessionFunctionCall`.
+    #[cfg(feature = "protocol_feature_session_access_keys")]
+    SessionAccessKeys,
 }
 
 /// Both, outgoing and incoming tcp connections to peers, will be rejected if `peer's`
@@ -182,7 +201,7 @@ const STABLE_PROTOCOL_VERSION: ProtocolVersion = 55;
 /// Largest protocol version supported by the current binary.
 pub const PROTOCOL_VERSION: ProtocolVersion = if cfg!(feature = "nightly_protocol") {
     // On nightly, pick big enough version to support all features.
-    130
+    135
 } else if cfg!(feature = "shardnet") {
     // For shardnet, enable `ChunkOnlyProducers` but nothing else.
     100
@@ -256,6 +275,16 @@ impl ProtocolFeature {
             ProtocolFeature::FixContractLoadingCost => 129,
             #[cfg(feature = "protocol_feature_account_id_in_function_call_permission")]
             ProtocolFeature::AccountIdInFunctionCallPermission => 130,
+            #[cfg(feature = "protocol_feature_wasm_extensions")]
+            ProtocolFeature::WasmExtensions => 131,
+            #[cfg(feature = "protocol_feature_structured_loading_cost")]
+            ProtocolFeature::StructuredLoadingCost => 132,
+            #[cfg(feature = "protocol_feature_shared_contracts")]
+            ProtocolFeature::SharedContracts => 133,
+            #[cfg(feature = "protocol_feature_yield_resume")]
+            ProtocolFeature::YieldResume => 134,
+            #[cfg(feature = "protocol_feature_session_access_keys")]
+            ProtocolFeature::SessionAccessKeys => 135,
         }
     }
 }
@@ -12,7 +12,9 @@ use serde::{Deserialize, Serialize};
 
 use near_crypto::{PublicKey, Signature};
 
-use crate::account::{AccessKey, AccessKeyPermission, Account, FunctionCallPermission};
+use crate::account::{
+    AccessKey, AccessKeyPermission, Account, FunctionCallPermission, SessionFunctionCallPermission,
+};
 use crate::block::{Block, BlockHeader, Tip};
 use crate::block_header::{
     BlockHeaderInnerLite, BlockHeaderInnerRest, BlockHeaderInnerRestV2, BlockHeaderInnerRestV3,
@@ -33,8 +35,9 @@ use crate::sharding::{
 };
 use crate::transaction::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithIdAndProof,
-    ExecutionStatus, FunctionCallAction, SignedTransaction, StakeAction, TransferAction,
+    DeployContractAction, DeploySharedContractAction, ExecutionMetadata, ExecutionOutcome,
+    ExecutionOutcomeWithIdAndProof, ExecutionStatus, FunctionCallAction, SignedTransaction,
+    StakeAction, TransferAction, UseSharedContractAction,
 };
 use crate::types::{
     AccountId, AccountWithPublicKey, Balance, BlockHeight, CompiledContractCache, EpochHeight,
@@ -144,6 +147,17 @@ pub enum AccessKeyPermissionView {
         method_names: Vec<String>,
     },
     FullAccess,
+    SessionFunctionCall {
+        #[serde(with = "dec_format")]
+        allowance: Balance,
+        #[serde(with = "dec_format")]
+        allowance_per_period: Balance,
+        refill_period: EpochHeight,
+        allowance_period: u64,
+        expires_at: BlockHeight,
+        receiver_id: String,
+        method_names: Vec<String>,
+    },
 }
 
 impl From<AccessKeyPermission> for AccessKeyPermissionView {
@@ -155,6 +169,17 @@ impl From<AccessKeyPermission> for AccessKeyPermissionView {
                 method_names: func_call.method_names,
             },
             AccessKeyPermission::FullAccess => AccessKeyPermissionView::FullAccess,
+            AccessKeyPermission::SessionFunctionCall(session) => {
+                AccessKeyPermissionView::SessionFunctionCall {
+                    allowance: session.allowance,
+                    allowance_per_period: session.allowance_per_period,
+                    refill_period: session.refill_period,
+                    allowance_period: session.allowance_period,
+                    expires_at: session.expires_at,
+                    receiver_id: session.receiver_id,
+                    method_names: session.method_names,
+                }
+            }
         }
     }
 }
@@ -170,6 +195,23 @@ impl From<AccessKeyPermissionView> for AccessKeyPermission {
                 })
             }
             AccessKeyPermissionView::FullAccess => AccessKeyPermission::FullAccess,
+            AccessKeyPermissionView::SessionFunctionCall {
+                allowance,
+                allowance_per_period,
+                refill_period,
+                allowance_period,
+                expires_at,
+                receiver_id,
+                method_names,
+            } => AccessKeyPermission::SessionFunctionCall(SessionFunctionCallPermission {
+                allowance,
+                allowance_per_period,
+                refill_period,
+                allowance_period,
+                expires_at,
+                receiver_id,
+                method_names,
+            }),
         }
     }
 }
@@ -209,7 +251,57 @@ pub struct StateItem {
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct ViewStateResult {
     pub values: Vec<StateItem>,
+    /// Trie nodes visited to read `values`, if requested.  They prove both the
+    /// values and that there are no other keys in between.
     pub proof: TrieProofPath,
+    /// Key to pass as `start_key_base64` to read the next page, if there are
+    /// more values under the prefix.  Serialized in base64.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_key: Option<String>,
+}
+
+/// Breakdown of the storage used by an account, split by the kind of trie
+/// records the account owns.
+///
+/// Byte counts follow the storage accounting of the runtime, so for a
+/// complete walk their sum equals `storage_usage`.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StorageUsageView {
+    /// Storage usage recorded in the account.
+    pub storage_usage: StorageUsage,
+    /// Amount of the account balance locked to pay for `storage_usage`.
+    #[serde(with = "dec_format")]
+    pub locked_for_storage: Balance,
+    pub account_bytes: StorageUsage,
+    pub code_bytes: StorageUsage,
+    pub access_keys: StoragePrefixUsageView,
+    pub contract_data: StoragePrefixUsageView,
+    /// Contract data grouped by key prefix, largest first.
+    pub contract_data_prefixes: Vec<StoragePrefixUsageView>,
+    /// Largest contract data records, largest first.
+    pub largest_keys: Vec<StorageKeyUsageView>,
+    /// Whether the walk stopped at the size limit of the node, in which case
+    /// access keys and contract data are only partially counted.
+    pub truncated: bool,
+}
+
+/// Number and total size of the records under a key prefix.
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StoragePrefixUsageView {
+    #[serde(rename = "prefix_base64", with = "base64_format")]
+    pub prefix: StoreKey,
+    pub num_records: u64,
+    pub bytes: StorageUsage,
+}
+
+#[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StorageKeyUsageView {
+    #[serde(rename = "key_base64", with = "base64_format")]
+    pub key: StoreKey,
+    pub bytes: StorageUsage,
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -254,6 +346,7 @@ pub enum QueryResponseKind {
     CallResult(CallResult),
     AccessKey(AccessKeyView),
     AccessKeyList(AccessKeyList),
+    StorageUsage(StorageUsageView),
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -270,6 +363,15 @@ pub enum QueryRequest {
         account_id: AccountId,
         #[serde(rename = "prefix_base64", with = "base64_format")]
         prefix: StoreKey,
+        /// Key to start reading from, inclusive.  Setting it or `limit` reads
+        /// the state in pages instead of failing if it is too large.
+        #[serde(default, rename = "start_key_base64", with = "option_base64_format")]
+        start_key: Option<Vec<u8>>,
+        /// Maximum number of values to return.
+        #[serde(default)]
+        limit: Option<u64>,
+        #[serde(default)]
+        include_proof: bool,
     },
     ViewAccessKey {
         account_id: AccountId,
@@ -284,6 +386,9 @@ pub enum QueryRequest {
         #[serde(rename = "args_base64", with = "base64_format")]
         args: FunctionArgs,
     },
+    ViewStorageUsage {
+        account_id: AccountId,
+    },
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -873,6 +978,12 @@ pub enum ActionView {
     DeleteAccount {
         beneficiary_id: AccountId,
     },
+    DeploySharedContract {
+        code: String,
+    },
+    UseSharedContract {
+        code_hash: CryptoHash,
+    },
 }
 
 impl From<Action> for ActionView {
@@ -900,6 +1011,12 @@ impl From<Action> for ActionView {
             Action::DeleteAccount(action) => {
                 ActionView::DeleteAccount { beneficiary_id: action.beneficiary_id }
             }
+            Action::DeploySharedContract(action) => {
+                ActionView::DeploySharedContract { code: to_base64(&hash(&action.code)) }
+            }
+            Action::UseSharedContract(action) => {
+                ActionView::UseSharedContract { code_hash: action.code_hash }
+            }
         }
     }
 }
@@ -934,6 +1051,14 @@ impl TryFrom<ActionView> for Action {
             ActionView::DeleteAccount { beneficiary_id } => {
                 Action::DeleteAccount(DeleteAccountAction { beneficiary_id })
             }
+            ActionView::DeploySharedContract { code } => {
+                Action::DeploySharedContract(DeploySharedContractAction {
+                    code: from_base64(&code)?,
+                })
+            }
+            ActionView::UseSharedContract { code_hash } => {
+                Action::UseSharedContract(UseSharedContractAction { code_hash })
+            }
         })
     }
 }
@@ -1613,6 +1738,7 @@ pub enum StateChangeCauseView {
     ValidatorAccountsUpdate,
     Migration,
     Resharding,
+    PromiseYieldTimeouts,
 }
 
 impl From<StateChangeCause> for StateChangeCauseView {
@@ -1639,6 +1765,7 @@ impl From<StateChangeCause> for StateChangeCauseView {
             StateChangeCause::ValidatorAccountsUpdate => Self::ValidatorAccountsUpdate,
             StateChangeCause::Migration => Self::Migration,
             StateChangeCause::Resharding => Self::Resharding,
+            StateChangeCause::PromiseYieldTimeouts => Self::PromiseYieldTimeouts,
         }
     }
 }
@@ -1731,3 +1858,17 @@ impl From<StateChangeWithCause> for StateChangeWithCauseView {
 }
 
 pub type StateChangesView = Vec<StateChangeWithCauseView>;
+
+/// Progress of precompiling the contracts of the tracked shards ahead of a protocol upgrade.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContractPrecompilationView {
+    /// Protocol version the contracts are compiled for.
+    pub protocol_version: ProtocolVersion,
+    pub shards: Vec<ShardId>,
+    pub num_shards_done: usize,
+    pub num_contracts_compiled: u64,
+    pub num_contracts_already_cached: u64,
+    /// Contracts which failed to compile or couldn't be stored in the cache.
+    pub num_contracts_failed: u64,
+    pub done: bool,
+}
@@ -0,0 +1,318 @@
+//! Backups of the database created while the node is running.
+//!
+//! A backup is a directory with a RocksDB checkpoint in its `data`
+//! subdirectory and a [`BackupManifest`] in `manifest.json`.  The checkpoint
+//! is a regular database which can be opened directly or copied in place of
+//! the node’s `data` directory.
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use near_primitives::block::Tip;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use near_primitives::version::DbVersion;
+use strum::IntoEnumIterator;
+
+use crate::config::Mode;
+use crate::db::{HEAD_KEY, VERSION_KEY};
+use crate::{DBCol, RocksDB, Store, StoreConfig};
+
+/// Name of the file with the manifest inside of a backup directory.
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+/// Name of the directory with the database inside of a backup directory.
+const DATA_DIRNAME: &str = "data";
+/// Name of the directory inside of [`BackupConfig::path`] in which periodic
+/// backups are created before they're renamed after their head height.
+const TMP_DIRNAME: &str = "backup_tmp";
+
+/// Configuration of periodic backups made by a running node.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct BackupConfig {
+    /// Directory in which backups are created, each in a subdirectory named
+    /// after the head height.  If relative, resolved relative to neard home
+    /// directory.
+    pub path: PathBuf,
+    /// How often a backup is created.
+    #[serde(default = "default_backup_period")]
+    pub period: Duration,
+    /// Number of the most recent backups to keep.  Older backups are removed
+    /// after a new one is created.
+    #[serde(default = "default_num_backups_to_keep")]
+    pub num_backups_to_keep: usize,
+}
+
+fn default_backup_period() -> Duration {
+    Duration::from_secs(24 * 60 * 60)
+}
+
+fn default_num_backups_to_keep() -> usize {
+    2
+}
+
+/// Describes the contents of a backup so that it can be verified later.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BackupManifest {
+    pub head_height: BlockHeight,
+    pub head_hash: CryptoHash,
+    pub db_version: DbVersion,
+    /// Number of keys in each column, by column name.  Reference-counted
+    /// columns count keys with non-positive reference count as well.
+    pub key_counts: BTreeMap<String, u64>,
+}
+
+impl BackupManifest {
+    /// Computes the manifest of given database.
+    ///
+    /// This reads every key in the database so it may take a long time.
+    pub fn compute(store: &Store) -> io::Result<Self> {
+        let head = store.get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "database has no HEAD".to_string())
+        })?;
+        let db_version = store
+            .get(DBCol::DbVersion, VERSION_KEY)?
+            .and_then(|value| serde_json::from_slice(&value).ok())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "failed to read database version")
+            })?;
+        let mut key_counts = BTreeMap::new();
+        for col in DBCol::iter() {
+            let mut count = 0;
+            for item in store.iter_raw_bytes(col) {
+                item?;
+                count += 1;
+            }
+            key_counts.insert(col.variant_name().to_string(), count);
+        }
+        Ok(Self {
+            head_height: head.height,
+            head_hash: head.last_block_hash,
+            db_version,
+            key_counts,
+        })
+    }
+
+    /// Reads the manifest of the backup in `backup_dir`.
+    pub fn read(backup_dir: &Path) -> io::Result<Self> {
+        let file = std::fs::File::open(backup_dir.join(MANIFEST_FILENAME))?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    fn write(&self, backup_dir: &Path) -> io::Result<()> {
+        let file = std::fs::File::create(backup_dir.join(MANIFEST_FILENAME))?;
+        Ok(serde_json::to_writer_pretty(file, self)?)
+    }
+
+    /// Describes how `other` differs from this manifest.  Returns an empty
+    /// list if they are the same.
+    pub fn differences(&self, other: &Self) -> Vec<String> {
+        let mut differences = Vec::new();
+        if (self.head_height, self.head_hash) != (other.head_height, other.head_hash) {
+            differences.push(format!(
+                "head is {} at {} rather than {} at {}",
+                other.head_hash, other.head_height, self.head_hash, self.head_height
+            ));
+        }
+        if self.db_version != other.db_version {
+            differences.push(format!(
+                "database version is {} rather than {}",
+                other.db_version, self.db_version
+            ));
+        }
+        for col in DBCol::iter() {
+            let name = col.variant_name();
+            let expected = self.key_counts.get(name).copied().unwrap_or_default();
+            let found = other.key_counts.get(name).copied().unwrap_or_default();
+            if expected != found {
+                differences.push(format!("{name} has {found} keys rather than {expected}"));
+            }
+        }
+        differences
+    }
+}
+
+/// Creates a backup of the database in `backup_dir`, which must not exist.
+///
+/// `config` is used to open the created checkpoint to compute its manifest.
+pub fn create_backup(
+    store: &Store,
+    config: &StoreConfig,
+    backup_dir: &Path,
+) -> io::Result<BackupManifest> {
+    create_backup_checkpoint(store, backup_dir)?;
+    write_backup_manifest(backup_dir, config)
+}
+
+/// Creates the checkpoint of the database of a backup in `backup_dir`, which
+/// must not exist.
+///
+/// This is the only part of creating a backup which needs the database itself
+/// and it’s quick since the checkpoint shares files with the database.
+/// The backup is complete once [`write_backup_manifest`] is called.
+pub fn create_backup_checkpoint(store: &Store, backup_dir: &Path) -> io::Result<()> {
+    if backup_dir.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", backup_dir.display()),
+        ));
+    }
+    std::fs::create_dir_all(backup_dir)?;
+    tracing::info!(target: "store", path = %backup_dir.display(), "Creating database backup");
+    store.create_checkpoint(&backup_dir.join(DATA_DIRNAME))
+}
+
+/// Computes the manifest of the checkpoint created by
+/// [`create_backup_checkpoint`] in `backup_dir` and writes it to the backup.
+pub fn write_backup_manifest(
+    backup_dir: &Path,
+    config: &StoreConfig,
+) -> io::Result<BackupManifest> {
+    // The manifest is computed from the checkpoint since the database keeps
+    // changing while it's being read.
+    let manifest = BackupManifest::compute(&open_backup(backup_dir, config)?)?;
+    manifest.write(backup_dir)?;
+    tracing::info!(target: "store", path = %backup_dir.display(), head_height = manifest.head_height, "Created database backup");
+    Ok(manifest)
+}
+
+/// Opens the database of the backup in `backup_dir` in read-only mode.
+pub fn open_backup(backup_dir: &Path, config: &StoreConfig) -> io::Result<Store> {
+    let db = RocksDB::open(&backup_dir.join(DATA_DIRNAME), config, Mode::ReadOnly)?;
+    Ok(Store::new(Arc::new(db)))
+}
+
+/// Creates a new backup in a subdirectory of `config.path` and removes the
+/// oldest backups so that at most `config.num_backups_to_keep` remain.
+///
+/// Backups are named after the head height of their manifest.  The backup is
+/// created in a temporary subdirectory and renamed once it's complete, since
+/// the head may move before the checkpoint is created.  If a complete backup
+/// at the same head already exists, e.g. because the node is stuck, it's kept
+/// and its manifest returned.
+pub fn create_periodic_backup(
+    store: &Store,
+    store_config: &StoreConfig,
+    config: &BackupConfig,
+) -> io::Result<BackupManifest> {
+    let tmp_dir = config.path.join(TMP_DIRNAME);
+    if tmp_dir.exists() {
+        tracing::info!(target: "store", path = %tmp_dir.display(), "Removing incomplete database backup");
+        std::fs::remove_dir_all(&tmp_dir)?;
+    }
+    let manifest = create_backup(store, store_config, &tmp_dir)?;
+    let backup_dir = config.path.join(format!("backup_{}", manifest.head_height));
+    if backup_dir.exists() {
+        if let Ok(existing) = BackupManifest::read(&backup_dir) {
+            tracing::info!(target: "store", path = %backup_dir.display(), "Database backup at current head already exists");
+            std::fs::remove_dir_all(&tmp_dir)?;
+            return Ok(existing);
+        }
+        tracing::info!(target: "store", path = %backup_dir.display(), "Removing incomplete database backup");
+        std::fs::remove_dir_all(&backup_dir)?;
+    }
+    std::fs::rename(&tmp_dir, &backup_dir)?;
+
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(&config.path)? {
+        let path = entry?.path();
+        if let Ok(manifest) = BackupManifest::read(&path) {
+            backups.push((manifest.head_height, path));
+        }
+    }
+    backups.sort();
+    let num_to_remove = backups.len().saturating_sub(config.num_backups_to_keep);
+    for (_, path) in backups.into_iter().take(num_to_remove) {
+        tracing::info!(target: "store", path = %path.display(), "Removing old database backup");
+        std::fs::remove_dir_all(&path)?;
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::hash::hash;
+
+    #[test]
+    fn test_create_backup() {
+        let (tmp_dir, opener) = Store::test_opener();
+        let store = opener.open();
+        crate::migrations::set_store_version(&store, near_primitives::version::DB_VERSION);
+        let tip = Tip {
+            height: 10,
+            last_block_hash: hash(&[10]),
+            prev_block_hash: hash(&[9]),
+            epoch_id: Default::default(),
+            next_epoch_id: Default::default(),
+        };
+        let mut store_update = store.store_update();
+        store_update.set_ser(DBCol::BlockMisc, HEAD_KEY, &tip).unwrap();
+        store_update.commit().unwrap();
+
+        let backup_dir = tmp_dir.path().join("backup");
+        let manifest = create_backup(&store, &StoreConfig::test_config(), &backup_dir).unwrap();
+        assert_eq!(manifest.head_height, 10);
+        assert_eq!(manifest.key_counts["BlockMisc"], 1);
+        assert_eq!(BackupManifest::read(&backup_dir).unwrap(), manifest);
+
+        // Data written after the backup doesn't end up in it.
+        let mut store_update = store.store_update();
+        store_update.set(DBCol::BlockMisc, b"a", b"1");
+        store_update.commit().unwrap();
+        let backup = open_backup(&backup_dir, &StoreConfig::test_config()).unwrap();
+        let differences = manifest.differences(&BackupManifest::compute(&store).unwrap());
+        assert_eq!(differences, vec!["BlockMisc has 2 keys rather than 1".to_string()]);
+        assert!(manifest.differences(&BackupManifest::compute(&backup).unwrap()).is_empty());
+
+        assert!(create_backup(&store, &StoreConfig::test_config(), &backup_dir).is_err());
+    }
+
+    #[test]
+    fn test_create_periodic_backup() {
+        let (tmp_dir, opener) = Store::test_opener();
+        let store = opener.open();
+        crate::migrations::set_store_version(&store, near_primitives::version::DB_VERSION);
+        let set_head = |height: BlockHeight| {
+            let tip = Tip {
+                height,
+                last_block_hash: hash(&height.to_le_bytes()),
+                prev_block_hash: hash(&(height - 1).to_le_bytes()),
+                epoch_id: Default::default(),
+                next_epoch_id: Default::default(),
+            };
+            let mut store_update = store.store_update();
+            store_update.set_ser(DBCol::BlockMisc, HEAD_KEY, &tip).unwrap();
+            store_update.commit().unwrap();
+        };
+        let config = BackupConfig {
+            path: tmp_dir.path().join("backups"),
+            period: default_backup_period(),
+            num_backups_to_keep: 2,
+        };
+        let store_config = StoreConfig::test_config();
+
+        set_head(10);
+        let manifest = create_periodic_backup(&store, &store_config, &config).unwrap();
+        // Head didn't move, the existing backup is kept.
+        assert_eq!(create_periodic_backup(&store, &store_config, &config).unwrap(), manifest);
+        // An incomplete backup is replaced.
+        std::fs::remove_file(config.path.join("backup_10").join(MANIFEST_FILENAME)).unwrap();
+        assert_eq!(create_periodic_backup(&store, &store_config, &config).unwrap(), manifest);
+
+        // A temporary directory left by a failed attempt is removed.
+        std::fs::create_dir(config.path.join(TMP_DIRNAME)).unwrap();
+        std::fs::write(config.path.join(TMP_DIRNAME).join(MANIFEST_FILENAME), b"{}").unwrap();
+        set_head(11);
+        create_periodic_backup(&store, &store_config, &config).unwrap();
+        set_head(12);
+        create_periodic_backup(&store, &store_config, &config).unwrap();
+        let mut backups: Vec<_> = std::fs::read_dir(&config.path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        backups.sort();
+        assert_eq!(backups, vec!["backup_11".to_string(), "backup_12".to_string()]);
+    }
+}
@@ -219,8 +219,8 @@ pub enum DBCol {
     /// - *Column type*: Receipt
     Receipts = 45,
     /// Precompiled machine code of the contract, used by StoreCompiledContractCache.
-    /// - *Rows*: ContractCacheKey or code hash (not sure)
-    /// - *Column type*: near-vm-runner CacheRecord
+    /// - *Rows*: hash of near-vm-runner ContractCacheKey
+    /// - *Column type*: near-vm-runner CacheEntry
     CachedContractCode = 46,
     /// Epoch validator information used for rpc purposes.
     /// - *Rows*: epoch id (CryptoHash)
@@ -234,6 +234,15 @@ pub enum DBCol {
     /// - *Rows*: BlockShardId (BlockHash || ShardId) - 40 bytes
     /// - *Column type*: StateChangesForSplitStates
     StateChangesForSplitStates = 49,
+    /// Keys of garbage collected blocks' data which is kept longer because of
+    /// a column retention policy, see `near_chain_configs::ColumnGCPolicy`.
+    /// - *Rows*: column (u8) || height (u64, big endian) || block or chunk hash
+    /// - *Column type*: `near_chain::gc_policy::DeferredGCEntry`
+    DeferredGC = 50,
+    /// Network blacklist entries added at runtime through the admin JSON-RPC.
+    /// - *Rows*: blacklist entry as written in the config, e.g. `10.0.0.0/8`
+    /// - *Column type*: expiration time (Option<u64>, UNIX timestamp in nanos)
+    Blacklist = 51,
 }
 
 impl DBCol {
@@ -328,7 +337,9 @@ impl DBCol {
             | DBCol::EpochInfo           // https://github.com/nearprotocol/nearcore/pull/2952
             | DBCol::EpochValidatorInfo  // https://github.com/nearprotocol/nearcore/pull/2952
             | DBCol::EpochStart          // https://github.com/nearprotocol/nearcore/pull/2952
-            | DBCol::CachedContractCode => false,
+            | DBCol::CachedContractCode
+            | DBCol::DeferredGC  // removed by the column retention policies pass
+            | DBCol::Blacklist => false,
             _ => true,
         }
     }
@@ -406,6 +417,8 @@ impl fmt::Display for DBCol {
             Self::EpochValidatorInfo => "epoch validator info",
             Self::HeaderHashesByHeight => "header hashes indexed by their height",
             Self::StateChangesForSplitStates => "state changes indexed by block hash and shard id",
+            Self::DeferredGC => "data retained by column gc policies",
+            Self::Blacklist => "network blacklist entries added at runtime",
         };
         write!(f, "{}", desc)
     }
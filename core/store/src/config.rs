@@ -45,6 +45,11 @@ pub struct StoreConfig {
     /// We're still experimenting with this parameter and it seems decreasing its value can improve
     /// the performance of the storage
     pub trie_cache_capacities: Vec<(ShardUId, usize)>,
+
+    /// If set, the node periodically creates a backup of the database while
+    /// it’s running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<crate::backup::BackupConfig>,
 }
 
 /// Mode in which to open the storage.
@@ -103,6 +108,8 @@ impl Default for StoreConfig {
             block_size: bytesize::ByteSize::kib(16),
 
             trie_cache_capacities: Default::default(),
+
+            backup: None,
         }
     }
 }
@@ -174,16 +181,47 @@ impl<'a> StoreOpener<'a> {
     /// Panics on failure.
     // TODO(mina86): Change it to return Result.
     pub fn open(&self) -> crate::Store {
+        self.try_open().expect("Failed to open the database")
+    }
+
+    /// Opens the RocksDB database.
+    ///
+    /// Unlike [`Self::open`], returns an error on failure, e.g. when the
+    /// database is locked because another process has it open for writing.
+    pub fn try_open(&self) -> std::io::Result<crate::Store> {
         if self.check_if_exists() {
             tracing::info!(target: "near", path=%self.path.display(), "Opening RocksDB database");
         } else if matches!(self.mode, Mode::ReadOnly) {
             tracing::error!(target: "near", path=%self.path.display(), "Database does not exist");
-            panic!("Failed to open non-existent database for reading");
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{}: database does not exist", self.path.display()),
+            ));
         } else {
             tracing::info!(target: "near", path=%self.path.display(), "Creating new RocksDB database");
         }
-        let db = crate::RocksDB::open(&self.path, &self.config, self.mode)
-            .expect("Failed to open the database");
-        crate::Store::new(std::sync::Arc::new(db))
+        let db = crate::RocksDB::open(&self.path, &self.config, self.mode)?;
+        Ok(crate::Store::new(std::sync::Arc::new(db)))
+    }
+
+    /// Opens the RocksDB database as a secondary instance following a node
+    /// which has the database opened for writing.
+    ///
+    /// The secondary instance keeps its logs in `secondary_path` and sees new
+    /// data only after [`crate::Store::try_catch_up_with_primary`] is called.
+    /// Configured mode is ignored since secondary instance is always read-only.
+    pub fn open_secondary(
+        &self,
+        secondary_path: &std::path::Path,
+    ) -> std::io::Result<crate::Store> {
+        if !self.check_if_exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{}: database does not exist", self.path.display()),
+            ));
+        }
+        tracing::info!(target: "near", path=%self.path.display(), secondary_path=%secondary_path.display(), "Opening RocksDB database as secondary instance");
+        let db = crate::RocksDB::open_secondary(&self.path, secondary_path, &self.config)?;
+        Ok(crate::Store::new(std::sync::Arc::new(db)))
     }
 }
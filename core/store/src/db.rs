@@ -24,6 +24,9 @@ pub const GENESIS_STATE_ROOTS_KEY: &[u8; 19] = b"GENESIS_STATE_ROOTS";
 /// Boolean stored in DBCol::BlockMisc indicating whether the database is for an
 /// archival node.  The default value (if missing) is false.
 pub const IS_ARCHIVE_KEY: &[u8; 10] = b"IS_ARCHIVE";
+/// Progress of an unfinished database migration stored in DBCol::DbVersion,
+/// next to the version itself, see [`crate::migrations::MigrationProgress`].
+pub const MIGRATION_PROGRESS_KEY: &[u8; 18] = b"MIGRATION_PROGRESS";
 
 #[derive(Default)]
 pub struct DBTransaction {
@@ -129,6 +132,18 @@ pub trait Database: Sync + Send {
 
     /// Returns statistics about the database if available.
     fn get_store_statistics(&self) -> Option<StoreStatistics>;
+
+    /// Makes data written by the primary instance visible to a secondary
+    /// instance of the database.
+    ///
+    /// This is a no-op for databases which aren’t secondary instances.
+    fn try_catch_up_with_primary(&self) -> io::Result<()>;
+
+    /// Creates a consistent copy of the database in `path`, which must not
+    /// exist.
+    ///
+    /// Databases which aren’t stored on disk return an error.
+    fn create_checkpoint(&self, path: &std::path::Path) -> io::Result<()>;
 }
 
 fn assert_no_overwrite(col: DBCol, key: &[u8], value: &[u8], old_value: &[u8]) {
@@ -21,7 +21,8 @@ use crate::{metrics, DBCol, StoreConfig, StoreStatistics};
 /// List of integer RocskDB properties we’re reading when collecting statistics.
 ///
 /// In the end, they are exported as Prometheus metrics.
-pub const CF_STAT_NAMES: [&'static str; 1] = [::rocksdb::properties::LIVE_SST_FILES_SIZE];
+pub const CF_STAT_NAMES: [&'static str; 2] =
+    [::rocksdb::properties::LIVE_SST_FILES_SIZE, ::rocksdb::properties::ESTIMATE_NUM_KEYS];
 
 pub struct RocksDB {
     db: DB,
@@ -34,6 +35,10 @@ pub struct RocksDB {
     /// want.
     cf_handles: enum_map::EnumMap<DBCol, std::ptr::NonNull<ColumnFamily>>,
 
+    /// Whether this is a secondary instance following a primary instance
+    /// opened by another process, see [`RocksDB::open_secondary`].
+    secondary: bool,
+
     check_free_space_counter: std::sync::atomic::AtomicU16,
     check_free_space_interval: u16,
     free_space_threshold: bytesize::ByteSize,
@@ -94,30 +99,53 @@ impl RocksDB {
     pub fn open(path: &Path, store_config: &StoreConfig, mode: Mode) -> io::Result<RocksDB> {
         ensure_max_open_files_limit(store_config.max_open_files).map_err(other_error)?;
         let (db, db_opt) = Self::open_db(path, store_config, mode)?;
-        let cf_handles = Self::get_cf_handles(&db);
+        Ok(Self::from_db(db, db_opt, false))
+    }
+
+    /// Opens the database as a secondary instance of the database at `path`
+    /// which is opened in read/write mode by another process.
+    ///
+    /// The secondary instance keeps its own logs in `secondary_path`.  It sees
+    /// the data as it was when it was opened; to see data written by the
+    /// primary since then call [`Database::try_catch_up_with_primary`].
+    pub fn open_secondary(
+        path: &Path,
+        secondary_path: &Path,
+        store_config: &StoreConfig,
+    ) -> io::Result<RocksDB> {
+        ensure_max_open_files_limit(store_config.max_open_files).map_err(other_error)?;
+        let mut options = rocksdb_options(store_config, Mode::ReadOnly);
+        // Secondary instance has to keep all the files open, otherwise it may
+        // fail to read files which the primary has already deleted.
+        options.set_max_open_files(-1);
+        let db = DB::open_cf_descriptors_as_secondary(
+            &options,
+            path,
+            secondary_path,
+            rocksdb_cf_descriptors(store_config),
+        )
+        .map_err(into_other)?;
+        Ok(Self::from_db(db, options, true))
+    }
 
-        Ok(Self {
+    fn from_db(db: DB, db_opt: Options, secondary: bool) -> Self {
+        let cf_handles = Self::get_cf_handles(&db);
+        Self {
             db,
             db_opt,
             cf_handles,
+            secondary,
             check_free_space_interval: 256,
             check_free_space_counter: std::sync::atomic::AtomicU16::new(0),
             free_space_threshold: bytesize::ByteSize::mb(16),
             _instance_counter: InstanceCounter::new(),
-        })
+        }
     }
 
     /// Opens the database with all column families configured.
     fn open_db(path: &Path, store_config: &StoreConfig, mode: Mode) -> io::Result<(DB, Options)> {
         let options = rocksdb_options(store_config, mode);
-        let cf_descriptors = DBCol::iter()
-            .map(|col| {
-                rocksdb::ColumnFamilyDescriptor::new(
-                    col_name(col),
-                    rocksdb_column_options(col, store_config),
-                )
-            })
-            .collect::<Vec<_>>();
+        let cf_descriptors = rocksdb_cf_descriptors(store_config);
         let db = match mode {
             Mode::ReadOnly => {
                 DB::open_cf_descriptors_read_only(&options, path, cf_descriptors, false)
@@ -303,6 +331,18 @@ impl Database for RocksDB {
             Some(result)
         }
     }
+
+    fn try_catch_up_with_primary(&self) -> io::Result<()> {
+        if self.secondary {
+            self.db.try_catch_up_with_primary().map_err(into_other)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn create_checkpoint(&self, path: &Path) -> io::Result<()> {
+        self.checkpoint()?.create_checkpoint(path).map_err(into_other)
+    }
 }
 
 /// Returns lowest value following largest value with given prefix.
@@ -394,6 +434,18 @@ fn rocksdb_block_based_options(
     block_opts
 }
 
+/// Descriptors of all column families with their options.
+fn rocksdb_cf_descriptors(store_config: &StoreConfig) -> Vec<rocksdb::ColumnFamilyDescriptor> {
+    DBCol::iter()
+        .map(|col| {
+            rocksdb::ColumnFamilyDescriptor::new(
+                col_name(col),
+                rocksdb_column_options(col, store_config),
+            )
+        })
+        .collect()
+}
+
 fn rocksdb_column_options(col: DBCol, store_config: &StoreConfig) -> Options {
     let mut opts = Options::default();
     set_compression_options(&mut opts);
@@ -712,6 +764,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_secondary_catches_up_with_primary() {
+        let (tmp_dir, opener) = Store::test_opener();
+        let primary = opener.open();
+        let mut store_update = primary.store_update();
+        store_update.set(DBCol::BlockMisc, b"a", b"1");
+        store_update.commit().unwrap();
+        primary.flush().unwrap();
+
+        let secondary = opener.open_secondary(&tmp_dir.path().join("secondary")).unwrap();
+        assert_eq!(secondary.get(DBCol::BlockMisc, b"a").unwrap(), Some(b"1".to_vec()));
+
+        let mut store_update = primary.store_update();
+        store_update.set(DBCol::BlockMisc, b"b", b"2");
+        store_update.commit().unwrap();
+        primary.flush().unwrap();
+        assert_eq!(secondary.get(DBCol::BlockMisc, b"b").unwrap(), None);
+
+        secondary.try_catch_up_with_primary().unwrap();
+        assert_eq!(secondary.get(DBCol::BlockMisc, b"b").unwrap(), Some(b"2".to_vec()));
+    }
+
     #[test]
     fn test_parse_statistics() {
         let statistics = "rocksdb.cold.file.read.count COUNT : 999\n\
@@ -91,4 +91,12 @@ impl Database for TestDB {
     fn get_store_statistics(&self) -> Option<StoreStatistics> {
         None
     }
+
+    fn try_catch_up_with_primary(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn create_checkpoint(&self, _path: &std::path::Path) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "test database can’t be checkpointed"))
+    }
 }
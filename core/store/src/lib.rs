@@ -18,7 +18,7 @@ use near_primitives::account::{AccessKey, Account};
 use near_primitives::contract::ContractCode;
 pub use near_primitives::errors::StorageError;
 use near_primitives::hash::CryptoHash;
-use near_primitives::receipt::{DelayedReceiptIndices, Receipt, ReceivedData};
+use near_primitives::receipt::{DelayedReceiptIndices, PromiseYieldIndices, Receipt, ReceivedData};
 use near_primitives::serialize::to_base;
 pub use near_primitives::shard_layout::ShardUId;
 use near_primitives::trie_key::{trie_key_parsers, TrieKey};
@@ -36,6 +36,7 @@ pub use crate::trie::{
     WrappedTrieChanges,
 };
 
+pub mod backup;
 mod columns;
 mod config;
 pub mod db;
@@ -181,6 +182,18 @@ impl Store {
     pub fn get_store_statistics(&self) -> Option<StoreStatistics> {
         self.storage.get_store_statistics()
     }
+
+    /// If the storage is a secondary instance of a database, makes data
+    /// written by the primary instance since the last call visible.
+    pub fn try_catch_up_with_primary(&self) -> io::Result<()> {
+        self.storage.try_catch_up_with_primary()
+    }
+
+    /// Creates a consistent copy of the database in `path`, which must not
+    /// exist.  The copy can be opened as a regular database.
+    pub fn create_checkpoint(&self, path: &std::path::Path) -> io::Result<()> {
+        self.storage.create_checkpoint(path)
+    }
 }
 
 /// Keeps track of current changes to the database and can commit all of them to the database.
@@ -506,6 +519,12 @@ pub fn get_delayed_receipt_indices(
     Ok(get(state_update, &TrieKey::DelayedReceiptIndices)?.unwrap_or_default())
 }
 
+pub fn get_promise_yield_indices(
+    state_update: &TrieUpdate,
+) -> Result<PromiseYieldIndices, StorageError> {
+    Ok(get(state_update, &TrieKey::PromiseYieldIndices)?.unwrap_or_default())
+}
+
 pub fn set_access_key(
     state_update: &mut TrieUpdate,
     account_id: AccountId,
@@ -549,16 +568,63 @@ pub fn set_code(state_update: &mut TrieUpdate, account_id: AccountId, code: &Con
     state_update.set(TrieKey::ContractCode { account_id }, code.code().to_vec());
 }
 
+/// Returns the code executed for the account: the code deployed on the account
+/// itself or, if there is none, the code with `code_hash` from the shared
+/// contract registry of the shard.
 pub fn get_code(
     state_update: &TrieUpdate,
     account_id: &AccountId,
     code_hash: Option<CryptoHash>,
+) -> Result<Option<ContractCode>, StorageError> {
+    if let Some(code) = get_account_code(state_update, account_id, code_hash)? {
+        return Ok(Some(code));
+    }
+    match code_hash {
+        Some(code_hash) => get_shared_code(state_update, &code_hash),
+        None => Ok(None),
+    }
+}
+
+/// Returns the code deployed on the account itself, ignoring the shared
+/// contract registry.  This is the code the account pays storage for.
+pub fn get_account_code(
+    state_update: &TrieUpdate,
+    account_id: &AccountId,
+    code_hash: Option<CryptoHash>,
 ) -> Result<Option<ContractCode>, StorageError> {
     state_update
         .get(&TrieKey::ContractCode { account_id: account_id.clone() })
         .map(|opt| opt.map(|code| ContractCode::new(code, code_hash)))
 }
 
+pub fn remove_code(state_update: &mut TrieUpdate, account_id: &AccountId) {
+    state_update.remove(TrieKey::ContractCode { account_id: account_id.clone() });
+}
+
+pub fn set_shared_code(state_update: &mut TrieUpdate, code: &ContractCode) {
+    state_update.set(TrieKey::SharedContractCode { code_hash: *code.hash() }, code.code().to_vec());
+}
+
+pub fn get_shared_code(
+    state_update: &TrieUpdate,
+    code_hash: &CryptoHash,
+) -> Result<Option<ContractCode>, StorageError> {
+    state_update
+        .get(&TrieKey::SharedContractCode { code_hash: *code_hash })
+        .map(|opt| opt.map(|code| ContractCode::new(code, Some(*code_hash))))
+}
+
+/// Checks whether code with `code_hash` is in the shared contract registry
+/// without reading the code itself.
+pub fn has_shared_code(
+    state_update: &TrieUpdate,
+    code_hash: &CryptoHash,
+) -> Result<bool, StorageError> {
+    state_update
+        .get_ref(&TrieKey::SharedContractCode { code_hash: *code_hash })
+        .map(|opt| opt.is_some())
+}
+
 /// Removes account, code and all access keys associated to it.
 pub fn remove_account(
     state_update: &mut TrieUpdate,
@@ -625,11 +691,47 @@ pub fn set_genesis_state_roots(store_update: &mut StoreUpdate, genesis_roots: &V
 
 pub struct StoreCompiledContractCache {
     db: Arc<dyn Database>,
+    read_only: bool,
 }
 
 impl StoreCompiledContractCache {
     pub fn new(store: &Store) -> Self {
-        Self { db: store.storage.clone() }
+        Self { db: store.storage.clone(), read_only: false }
+    }
+
+    /// Creates a cache which reads compiled contracts from the store but
+    /// doesn't write newly compiled ones to it.
+    ///
+    /// Used with stores which can't be written to, such as secondary
+    /// instances followed by read-only replicas.  Such nodes rely on the
+    /// contracts compiled by the node owning the database and on the
+    /// in-memory cache of the VM.
+    pub fn read_only(store: &Store) -> Self {
+        Self { db: store.storage.clone(), read_only: true }
+    }
+
+    /// Removes the cached contracts for which `is_stale` returns true when called with the key
+    /// and the value of the entry. Returns the number of removed entries.
+    pub fn remove_stale(&self, is_stale: impl Fn(&[u8], &[u8]) -> bool) -> io::Result<usize> {
+        const BATCH_SIZE: usize = 1000;
+        let mut removed = 0;
+        let mut update = crate::db::DBTransaction::new();
+        let mut batch_len = 0;
+        for item in self.db.iter(DBCol::CachedContractCode) {
+            let (key, value) = item?;
+            if !is_stale(&key, &value) {
+                continue;
+            }
+            update.delete(DBCol::CachedContractCode, key.into_vec());
+            removed += 1;
+            batch_len += 1;
+            if batch_len == BATCH_SIZE {
+                self.db.write(std::mem::take(&mut update))?;
+                batch_len = 0;
+            }
+        }
+        self.db.write(update)?;
+        Ok(removed)
     }
 }
 
@@ -639,6 +741,9 @@ impl StoreCompiledContractCache {
 /// we don't cache non-gas metered binaries, for example.
 impl CompiledContractCache for StoreCompiledContractCache {
     fn put(&self, key: &CryptoHash, value: Vec<u8>) -> io::Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
         let mut update = crate::db::DBTransaction::new();
         update.insert(DBCol::CachedContractCode, key.as_ref().to_vec(), value);
         self.db.write(update)
@@ -773,5 +878,16 @@ mod tests {
         assert_eq!(None, cache.get(&key).unwrap());
         assert_eq!((), cache.put(&key, b"foo".to_vec()).unwrap());
         assert_eq!(Some(&b"foo"[..]), cache.get(&key).unwrap().as_deref());
+
+        let other_key = CryptoHash::hash_bytes(b"other");
+        cache.put(&other_key, b"bar".to_vec()).unwrap();
+        assert_eq!(1, cache.remove_stale(|_key, value| value == b"foo").unwrap());
+        assert_eq!(None, cache.get(&key).unwrap());
+        assert_eq!(Some(&b"bar"[..]), cache.get(&other_key).unwrap().as_deref());
+
+        let read_only_cache = super::StoreCompiledContractCache::read_only(&store);
+        assert_eq!(Some(&b"bar"[..]), read_only_cache.get(&other_key).unwrap().as_deref());
+        read_only_cache.put(&key, b"foo".to_vec()).unwrap();
+        assert_eq!(None, read_only_cache.get(&key).unwrap());
     }
 }
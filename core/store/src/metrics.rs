@@ -1,4 +1,7 @@
-use near_metrics::{try_create_histogram_vec, HistogramVec};
+use near_metrics::{
+    try_create_histogram_vec, try_create_int_counter_vec, try_create_int_gauge, HistogramVec,
+    IntCounterVec, IntGauge,
+};
 use once_cell::sync::Lazy;
 
 pub(crate) static DATABASE_OP_LATENCY_HIST: Lazy<HistogramVec> = Lazy::new(|| {
@@ -10,3 +13,20 @@ pub(crate) static DATABASE_OP_LATENCY_HIST: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub(crate) static MIGRATION_RECORDS_PROCESSED: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_db_migration_records_processed_total",
+        "Number of records rewritten by database migrations by column.",
+        &["column"],
+    )
+    .unwrap()
+});
+
+pub(crate) static MIGRATION_ETA_SECONDS: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_db_migration_eta_seconds",
+        "Estimated time left until the running database migration finishes.",
+    )
+    .unwrap()
+});
@@ -1,20 +1,24 @@
-use std::collections::HashMap;
+use std::io;
+use std::time::{Duration, Instant};
 
 use borsh::{BorshDeserialize, BorshSerialize};
+use tracing::info;
 
-use near_primitives::epoch_manager::epoch_info::{EpochInfo, EpochInfoV1};
-use near_primitives::hash::CryptoHash;
-use near_primitives::types::validator_stake::ValidatorStake;
-use near_primitives::types::AccountId;
+use near_primitives::version::DbVersion;
 
-use crate::{DBCol, Store, StoreOpener, StoreUpdate};
+use crate::db::{StatsValue, MIGRATION_PROGRESS_KEY};
+use crate::{metrics, DBCol, Store, StoreOpener, StoreUpdate};
 
 pub fn set_store_version(store: &Store, db_version: u32) {
     let mut store_update = store.store_update();
+    set_store_version_in_update(&mut store_update, db_version);
+    store_update.commit().expect("Failed to write version to database");
+}
+
+fn set_store_version_in_update(store_update: &mut StoreUpdate, db_version: DbVersion) {
     // Contrary to other integers, we’re using textual representation for
     // storing DbVersion in VERSION_KEY thus to_string rather than to_le_bytes.
     store_update.set(DBCol::DbVersion, crate::db::VERSION_KEY, db_version.to_string().as_bytes());
-    store_update.commit().expect("Failed to write version to database");
 }
 
 pub struct BatchedStoreUpdate<'a> {
@@ -22,21 +26,53 @@ pub struct BatchedStoreUpdate<'a> {
     batch_size: usize,
     store: &'a Store,
     store_update: Option<StoreUpdate>,
+    /// Whether batches are committed only together with migration progress,
+    /// see [`run_batched_migration`].  Otherwise a batch is committed as soon
+    /// as it exceeds `batch_size_limit`.
+    checkpointed: bool,
+    /// Whether batches are discarded instead of being committed.
+    dry_run: bool,
+    num_writes: u64,
 }
 
 impl<'a> BatchedStoreUpdate<'a> {
     pub fn new(store: &'a Store, batch_size_limit: usize) -> Self {
-        Self { batch_size_limit, batch_size: 0, store, store_update: Some(store.store_update()) }
+        Self {
+            batch_size_limit,
+            batch_size: 0,
+            store,
+            store_update: Some(store.store_update()),
+            checkpointed: false,
+            dry_run: false,
+            num_writes: 0,
+        }
+    }
+
+    fn new_checkpointed(store: &'a Store, batch_size_limit: usize, dry_run: bool) -> Self {
+        Self { checkpointed: true, dry_run, ..Self::new(store, batch_size_limit) }
     }
 
     fn commit(&mut self) -> std::io::Result<()> {
         let store_update = self.store_update.take().unwrap();
-        store_update.commit()?;
+        if !self.dry_run {
+            store_update.commit()?;
+        }
         self.store_update = Some(self.store.store_update());
         self.batch_size = 0;
         Ok(())
     }
 
+    fn is_full(&self) -> bool {
+        self.batch_size > self.batch_size_limit
+    }
+
+    fn commit_if_full(&mut self) -> std::io::Result<()> {
+        if !self.checkpointed && self.is_full() {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
     pub fn set_ser<T: BorshSerialize>(
         &mut self,
         col: DBCol,
@@ -45,13 +81,36 @@ impl<'a> BatchedStoreUpdate<'a> {
     ) -> std::io::Result<()> {
         let value_bytes = value.try_to_vec()?;
         self.batch_size += key.as_ref().len() + value_bytes.len() + 8;
+        self.num_writes += 1;
         self.store_update.as_mut().unwrap().set(col, key.as_ref(), &value_bytes);
+        self.commit_if_full()
+    }
 
-        if self.batch_size > self.batch_size_limit {
-            self.commit()?;
-        }
+    pub fn delete(&mut self, col: DBCol, key: &[u8]) -> std::io::Result<()> {
+        self.batch_size += key.len() + 8;
+        self.num_writes += 1;
+        self.store_update.as_mut().unwrap().delete(col, key);
+        self.commit_if_full()
+    }
 
-        Ok(())
+    /// Commits the current batch together with `progress` so that an
+    /// interrupted migration continues right after the last committed record.
+    fn commit_with_progress(&mut self, progress: &MigrationProgress) -> std::io::Result<()> {
+        self.store_update.as_mut().unwrap().set_ser(
+            DBCol::DbVersion,
+            MIGRATION_PROGRESS_KEY,
+            progress,
+        )?;
+        self.commit()
+    }
+
+    /// Commits the last batch of a migration, removes its progress and sets
+    /// the database version to `db_version`, all in one transaction.
+    fn finish_migration(mut self, db_version: DbVersion) -> std::io::Result<()> {
+        let store_update = self.store_update.as_mut().unwrap();
+        store_update.delete(DBCol::DbVersion, MIGRATION_PROGRESS_KEY);
+        set_store_version_in_update(store_update, db_version);
+        self.commit()
     }
 
     pub fn finish(mut self) -> std::io::Result<()> {
@@ -63,43 +122,296 @@ impl<'a> BatchedStoreUpdate<'a> {
     }
 }
 
-fn map_col<T, U, F>(store: &Store, col: DBCol, f: F) -> std::io::Result<()>
+/// Database migration which rewrites records of some columns one by one.
+///
+/// Such migrations are run by [`run_batched_migration`] which commits them in
+/// batches, persists progress and resumes from it if the node was stopped in
+/// the middle of the migration.
+pub trait BatchedMigration {
+    /// Version of the database the migration applies to.
+    fn source_version(&self) -> DbVersion;
+
+    /// Version of the database after the migration.
+    fn target_version(&self) -> DbVersion {
+        self.source_version() + 1
+    }
+
+    /// Columns to migrate, in order in which they are processed.
+    ///
+    /// `DBCol::DbVersion` can't be migrated since it holds the progress of
+    /// the migration.
+    fn columns(&self) -> Vec<DBCol>;
+
+    /// Migrates a single record of column `col`.
+    ///
+    /// Writes made to `store_update` are committed in the same transaction as
+    /// the progress of the migration so a record is never migrated twice.
+    fn migrate_record(
+        &self,
+        col: DBCol,
+        key: &[u8],
+        value: &[u8],
+        store_update: &mut BatchedStoreUpdate,
+    ) -> io::Result<()>;
+}
+
+/// Progress of a [`BatchedMigration`] stored in `DBCol::DbVersion` under
+/// [`MIGRATION_PROGRESS_KEY`] while the migration is running.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MigrationProgress {
+    pub source_version: DbVersion,
+    pub target_version: DbVersion,
+    /// Index of the column being migrated in [`BatchedMigration::columns`].
+    pub col_index: u32,
+    /// Last migrated key of that column.  `None` if none was migrated yet.
+    pub last_key: Option<Vec<u8>>,
+    /// Number of records migrated so far, in all columns.
+    pub num_records: u64,
+}
+
+/// Returns progress of an interrupted migration, if any.
+pub fn get_migration_progress(store: &Store) -> io::Result<Option<MigrationProgress>> {
+    store.get_ser(DBCol::DbVersion, MIGRATION_PROGRESS_KEY)
+}
+
+/// Summary of a [`BatchedMigration`] run.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MigrationStats {
+    /// Number of records migrated by this run.
+    pub num_records: u64,
+    /// Number of records skipped because they were migrated before an
+    /// interruption.
+    pub num_skipped: u64,
+    /// Number of writes and deletions made by this run.
+    pub num_writes: u64,
+}
+
+/// Size of a batch of writes committed together with the migration progress.
+const MIGRATION_BATCH_SIZE_LIMIT: usize = 10_000_000;
+
+/// How often progress of a migration is logged.
+const MIGRATION_PROGRESS_LOG_PERIOD: Duration = Duration::from_secs(10);
+
+/// Runs `migration` on `store`, continuing an interrupted run if there is one.
+///
+/// With `dry_run` nothing is written to the database; the returned stats tell
+/// how many records would be migrated and how many writes it would take.
+pub fn run_batched_migration(
+    store: &Store,
+    migration: &dyn BatchedMigration,
+    dry_run: bool,
+) -> io::Result<MigrationStats> {
+    run_batched_migration_impl(store, migration, dry_run, MIGRATION_BATCH_SIZE_LIMIT)
+}
+
+fn run_batched_migration_impl(
+    store: &Store,
+    migration: &dyn BatchedMigration,
+    dry_run: bool,
+    batch_size_limit: usize,
+) -> io::Result<MigrationStats> {
+    let source_version = migration.source_version();
+    let target_version = migration.target_version();
+    let mut progress = match get_migration_progress(store)? {
+        Some(progress)
+            if progress.source_version == source_version
+                && progress.target_version == target_version =>
+        {
+            info!(target: "store", source_version, target_version, ?progress, "Resuming interrupted DB migration");
+            progress
+        }
+        Some(progress) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "found progress of an unfinished migration from version {} to {} \
+                     while migrating from version {} to {}",
+                    progress.source_version,
+                    progress.target_version,
+                    source_version,
+                    target_version
+                ),
+            ))
+        }
+        None => MigrationProgress {
+            source_version,
+            target_version,
+            col_index: 0,
+            last_key: None,
+            num_records: 0,
+        },
+    };
+
+    let columns = migration.columns();
+    if columns.contains(&DBCol::DbVersion) {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "migration from version {} to {} can't migrate {} column",
+                source_version,
+                target_version,
+                DBCol::DbVersion
+            ),
+        ));
+    }
+    let remaining_columns = &columns[(progress.col_index as usize).min(columns.len())..];
+    let mut reporter = MigrationProgressReporter::new(store, remaining_columns);
+    let mut stats = MigrationStats::default();
+    let mut store_update = BatchedStoreUpdate::new_checkpointed(store, batch_size_limit, dry_run);
+    for (col_index, &col) in columns.iter().enumerate().skip(progress.col_index as usize) {
+        if col_index as u32 != progress.col_index {
+            progress.col_index = col_index as u32;
+            progress.last_key = None;
+        }
+        for item in store.iter(col) {
+            let (key, value) = item?;
+            reporter.record_visited(col, &progress);
+            if let Some(last_key) = &progress.last_key {
+                if key.as_ref() <= last_key.as_slice() {
+                    stats.num_skipped += 1;
+                    continue;
+                }
+            }
+            migration.migrate_record(col, &key, &value, &mut store_update)?;
+            progress.last_key = Some(key.to_vec());
+            progress.num_records += 1;
+            stats.num_records += 1;
+            metrics::MIGRATION_RECORDS_PROCESSED.with_label_values(&[<&str>::from(col)]).inc();
+            if store_update.is_full() {
+                store_update.commit_with_progress(&progress)?;
+            }
+        }
+    }
+    stats.num_writes = store_update.num_writes;
+    store_update.finish_migration(target_version)?;
+    metrics::MIGRATION_ETA_SECONDS.set(0);
+    info!(target: "store", source_version, target_version, dry_run, ?stats, "Finished DB migration");
+    Ok(stats)
+}
+
+/// Logs progress of a migration and estimates the time left using the
+/// approximate number of keys in the migrated columns.
+struct MigrationProgressReporter {
+    start: Instant,
+    last_report: Instant,
+    num_visited: u64,
+    estimated_num_keys: Option<u64>,
+}
+
+impl MigrationProgressReporter {
+    fn new(store: &Store, columns: &[DBCol]) -> Self {
+        let estimated_num_keys = store.get_store_statistics().and_then(|statistics| {
+            let (_, values) = statistics
+                .data
+                .into_iter()
+                .find(|(name, _)| name == ::rocksdb::properties::ESTIMATE_NUM_KEYS)?;
+            let total = values
+                .into_iter()
+                .filter_map(|value| match value {
+                    StatsValue::ColumnValue(col, num_keys) if columns.contains(&col) => {
+                        Some(num_keys.max(0) as u64)
+                    }
+                    _ => None,
+                })
+                .sum();
+            Some(total)
+        });
+        let now = Instant::now();
+        Self { start: now, last_report: now, num_visited: 0, estimated_num_keys }
+    }
+
+    fn record_visited(&mut self, col: DBCol, progress: &MigrationProgress) {
+        self.num_visited += 1;
+        if self.last_report.elapsed() < MIGRATION_PROGRESS_LOG_PERIOD {
+            return;
+        }
+        self.last_report = Instant::now();
+        let rate = self.num_visited as f64 / self.start.elapsed().as_secs_f64();
+        let eta = self.estimated_num_keys.map(|estimated_num_keys| {
+            Duration::from_secs_f64(
+                estimated_num_keys.saturating_sub(self.num_visited) as f64 / rate,
+            )
+        });
+        if let Some(eta) = eta {
+            metrics::MIGRATION_ETA_SECONDS.set(eta.as_secs() as i64);
+        }
+        info!(
+            target: "store",
+            source_version = progress.source_version,
+            target_version = progress.target_version,
+            %col,
+            num_records = progress.num_records,
+            records_per_second = rate as u64,
+            ?eta,
+            "Migrating DB"
+        );
+    }
+}
+
+/// Rewrites a single record deserialized as `T` into `f(T)`.
+fn map_record<T, U, F>(
+    col: DBCol,
+    key: &[u8],
+    value: &[u8],
+    store_update: &mut BatchedStoreUpdate,
+    f: F,
+) -> std::io::Result<()>
 where
     T: BorshDeserialize,
     U: BorshSerialize,
-    F: Fn(T) -> U,
+    F: FnOnce(T) -> U,
 {
-    let mut store_update = BatchedStoreUpdate::new(store, 10_000_000);
-    for (key, value) in store.iter(col).map(Result::unwrap) {
-        let new_value = f(T::try_from_slice(&value).unwrap());
-        store_update.set_ser(col, &key, &new_value)?;
+    let new_value = f(T::try_from_slice(value)?);
+    store_update.set_ser(col, key, &new_value)
+}
+
+/// Deletes `DBCol::_NextBlockWithNewChunk` and `DBCol::_LastBlockWithNewChunk`.
+pub struct Migration28To29;
+
+impl BatchedMigration for Migration28To29 {
+    fn source_version(&self) -> DbVersion {
+        28
+    }
+
+    fn columns(&self) -> Vec<DBCol> {
+        vec![DBCol::_NextBlockWithNewChunk, DBCol::_LastBlockWithNewChunk]
+    }
+
+    fn migrate_record(
+        &self,
+        col: DBCol,
+        key: &[u8],
+        _value: &[u8],
+        store_update: &mut BatchedStoreUpdate,
+    ) -> io::Result<()> {
+        store_update.delete(col, key)
     }
-    store_update.finish()
 }
 
 pub fn migrate_28_to_29(store_opener: &StoreOpener) {
     let store = store_opener.open();
-    let mut store_update = store.store_update();
-    store_update.delete_all(DBCol::_NextBlockWithNewChunk);
-    store_update.delete_all(DBCol::_LastBlockWithNewChunk);
-    store_update.commit().unwrap();
-
-    set_store_version(&store, 29);
+    run_batched_migration(&store, &Migration28To29, false).expect("Failed to migrate");
 }
 
-pub fn migrate_29_to_30(store_opener: &StoreOpener) {
+mod migration_29_to_30 {
+    use std::collections::{BTreeMap, HashMap};
+
+    use borsh::{BorshDeserialize, BorshSerialize};
+
     use near_primitives::epoch_manager::block_info::BlockInfo;
-    use near_primitives::epoch_manager::epoch_info::EpochSummary;
+    use near_primitives::epoch_manager::epoch_info::{EpochInfo, EpochInfoV1, EpochSummary};
     use near_primitives::epoch_manager::AGGREGATOR_KEY;
-    use near_primitives::types::chunk_extra::ChunkExtra;
-    use near_primitives::types::validator_stake::ValidatorStakeV1;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::types::chunk_extra::{ChunkExtra, ChunkExtraV1};
+    use near_primitives::types::validator_stake::{ValidatorStake, ValidatorStakeV1};
     use near_primitives::types::{
-        BlockChunkValidatorStats, EpochId, ProtocolVersion, ShardId, ValidatorId,
+        AccountId, BlockChunkValidatorStats, EpochId, ProtocolVersion, ShardId, ValidatorId,
         ValidatorKickoutReason, ValidatorStats,
     };
-    use std::collections::BTreeMap;
+    use near_primitives::version::DbVersion;
 
-    let store = store_opener.open();
+    use super::{map_record, BatchedMigration, BatchedStoreUpdate};
+    use crate::DBCol;
 
     #[derive(BorshDeserialize)]
     pub struct OldEpochSummary {
@@ -129,49 +441,193 @@ pub fn migrate_29_to_30(store_opener: &StoreOpener) {
         pub last_block_hash: CryptoHash,
     }
 
-    map_col(&store, DBCol::ChunkExtra, ChunkExtra::V1).unwrap();
-
-    map_col(&store, DBCol::BlockInfo, BlockInfo::V1).unwrap();
-
-    map_col(&store, DBCol::EpochValidatorInfo, |info: OldEpochSummary| EpochSummary {
-        prev_epoch_last_block_hash: info.prev_epoch_last_block_hash,
-        all_proposals: info.all_proposals.into_iter().map(ValidatorStake::V1).collect(),
-        validator_kickout: info.validator_kickout,
-        validator_block_chunk_stats: info.validator_block_chunk_stats,
-        next_version: info.next_version,
-    })
-    .unwrap();
-
-    // DBCol::EpochInfo has a special key which contains a different type than all other
-    // values (EpochInfoAggregator), so we cannot use `map_col` on it. We need to handle
-    // the AGGREGATOR_KEY differently from all others.
-    let col = DBCol::EpochInfo;
-    let keys: Vec<_> = store.iter(col).map(Result::unwrap).map(|(key, _)| key).collect();
-    let mut store_update = BatchedStoreUpdate::new(&store, 10_000_000);
-    for key in keys {
-        if key.as_ref() == AGGREGATOR_KEY {
-            let value: OldEpochInfoAggregator = store.get_ser(col, key.as_ref()).unwrap().unwrap();
-            let new_value = NewEpochInfoAggregator {
-                block_tracker: value.block_tracker,
-                shard_tracker: value.shard_tracker,
-                version_tracker: value.version_tracker,
-                epoch_id: value.epoch_id,
-                last_block_hash: value.last_block_hash,
-                all_proposals: value
-                    .all_proposals
-                    .into_iter()
-                    .map(|(account, stake)| (account, ValidatorStake::V1(stake)))
-                    .collect(),
-            };
-            store_update.set_ser(col, key.as_ref(), &new_value).unwrap();
-        } else {
-            let value: EpochInfoV1 = store.get_ser(col, key.as_ref()).unwrap().unwrap();
-            let new_value = EpochInfo::V1(value);
-            store_update.set_ser(col, key.as_ref(), &new_value).unwrap();
-        }
-    }
-
-    store_update.finish().unwrap();
-
-    set_store_version(&store, 30);
+    /// Migrates all structures that use ValidatorStake to versionized version.
+    pub struct Migration29To30;
+
+    impl BatchedMigration for Migration29To30 {
+        fn source_version(&self) -> DbVersion {
+            29
+        }
+
+        fn columns(&self) -> Vec<DBCol> {
+            vec![DBCol::ChunkExtra, DBCol::BlockInfo, DBCol::EpochValidatorInfo, DBCol::EpochInfo]
+        }
+
+        fn migrate_record(
+            &self,
+            col: DBCol,
+            key: &[u8],
+            value: &[u8],
+            store_update: &mut BatchedStoreUpdate,
+        ) -> std::io::Result<()> {
+            match col {
+                DBCol::ChunkExtra => {
+                    map_record(col, key, value, store_update, |extra: ChunkExtraV1| {
+                        ChunkExtra::V1(extra)
+                    })
+                }
+                DBCol::BlockInfo => map_record(col, key, value, store_update, BlockInfo::V1),
+                DBCol::EpochValidatorInfo => {
+                    map_record(col, key, value, store_update, |info: OldEpochSummary| {
+                        EpochSummary {
+                            prev_epoch_last_block_hash: info.prev_epoch_last_block_hash,
+                            all_proposals: info
+                                .all_proposals
+                                .into_iter()
+                                .map(ValidatorStake::V1)
+                                .collect(),
+                            validator_kickout: info.validator_kickout,
+                            validator_block_chunk_stats: info.validator_block_chunk_stats,
+                            next_version: info.next_version,
+                        }
+                    })
+                }
+                // DBCol::EpochInfo has a special key which contains a different type than all
+                // other values (EpochInfoAggregator).
+                DBCol::EpochInfo if key == AGGREGATOR_KEY => {
+                    map_record(col, key, value, store_update, |value: OldEpochInfoAggregator| {
+                        NewEpochInfoAggregator {
+                            block_tracker: value.block_tracker,
+                            shard_tracker: value.shard_tracker,
+                            version_tracker: value.version_tracker,
+                            epoch_id: value.epoch_id,
+                            last_block_hash: value.last_block_hash,
+                            all_proposals: value
+                                .all_proposals
+                                .into_iter()
+                                .map(|(account, stake)| (account, ValidatorStake::V1(stake)))
+                                .collect(),
+                        }
+                    })
+                }
+                DBCol::EpochInfo => {
+                    map_record(col, key, value, store_update, |value: EpochInfoV1| {
+                        EpochInfo::V1(value)
+                    })
+                }
+                _ => unreachable!("column {} is not migrated", col),
+            }
+        }
+    }
+}
+
+pub use migration_29_to_30::Migration29To30;
+
+pub fn migrate_29_to_30(store_opener: &StoreOpener) {
+    let store = store_opener.open();
+    run_batched_migration(&store, &Migration29To30, false).expect("Failed to migrate");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::test_utils::create_test_store;
+
+    /// Increments every value of `DBCol::BlockMisc` and `DBCol::BlockHeight`
+    /// and fails once after `fail_after` records.
+    struct IncrementMigration {
+        fail_after: Cell<Option<u64>>,
+    }
+
+    impl BatchedMigration for IncrementMigration {
+        fn source_version(&self) -> DbVersion {
+            1
+        }
+
+        fn columns(&self) -> Vec<DBCol> {
+            vec![DBCol::BlockMisc, DBCol::BlockHeight]
+        }
+
+        fn migrate_record(
+            &self,
+            col: DBCol,
+            key: &[u8],
+            value: &[u8],
+            store_update: &mut BatchedStoreUpdate,
+        ) -> io::Result<()> {
+            if let Some(fail_after) = self.fail_after.get() {
+                if fail_after == 0 {
+                    self.fail_after.set(None);
+                    return Err(io::Error::new(io::ErrorKind::Other, "interrupted"));
+                }
+                self.fail_after.set(Some(fail_after - 1));
+            }
+            map_record(col, key, value, store_update, |value: u64| value + 1)
+        }
+    }
+
+    fn populate(store: &Store) {
+        let mut store_update = store.store_update();
+        for i in 0..100u64 {
+            store_update.set_ser(DBCol::BlockMisc, &i.to_be_bytes(), &i).unwrap();
+            store_update.set_ser(DBCol::BlockHeight, &i.to_be_bytes(), &i).unwrap();
+        }
+        set_store_version_in_update(&mut store_update, 1);
+        store_update.commit().unwrap();
+    }
+
+    fn get_version(store: &Store) -> Option<Vec<u8>> {
+        store.get(DBCol::DbVersion, crate::db::VERSION_KEY).unwrap()
+    }
+
+    #[test]
+    fn test_batched_migration_resumes() {
+        let store = create_test_store();
+        populate(&store);
+        let migration = IncrementMigration { fail_after: Cell::new(Some(150)) };
+        // With zero batch size limit progress is committed after every record.
+        assert!(run_batched_migration_impl(&store, &migration, false, 0).is_err());
+        let progress = MigrationProgress {
+            source_version: 1,
+            target_version: 2,
+            col_index: 1,
+            last_key: Some(49u64.to_be_bytes().to_vec()),
+            num_records: 150,
+        };
+        assert_eq!(get_migration_progress(&store).unwrap(), Some(progress));
+        assert_eq!(get_version(&store), Some(b"1".to_vec()));
+
+        let stats = run_batched_migration(&store, &migration, false).unwrap();
+        assert_eq!(stats.num_records, 50);
+        assert_eq!(stats.num_skipped, 50);
+        assert_eq!(get_migration_progress(&store).unwrap(), None);
+        assert_eq!(get_version(&store), Some(b"2".to_vec()));
+        for col in [DBCol::BlockMisc, DBCol::BlockHeight] {
+            for i in 0..100u64 {
+                assert_eq!(store.get_ser::<u64>(col, &i.to_be_bytes()).unwrap(), Some(i + 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_batched_migration_dry_run() {
+        let store = create_test_store();
+        populate(&store);
+        let migration = IncrementMigration { fail_after: Cell::new(None) };
+        let stats = run_batched_migration(&store, &migration, true).unwrap();
+        assert_eq!(stats, MigrationStats { num_records: 200, num_skipped: 0, num_writes: 200 });
+        assert_eq!(get_version(&store), Some(b"1".to_vec()));
+        assert_eq!(store.get_ser::<u64>(DBCol::BlockHeight, &7u64.to_be_bytes()).unwrap(), Some(7));
+    }
+
+    #[test]
+    fn test_migrate_28_to_29() {
+        let store = create_test_store();
+        let mut store_update = store.store_update();
+        for i in 0..10u64 {
+            store_update.set_ser(DBCol::_NextBlockWithNewChunk, &i.to_be_bytes(), &i).unwrap();
+            store_update.set_ser(DBCol::_LastBlockWithNewChunk, &i.to_be_bytes(), &i).unwrap();
+        }
+        set_store_version_in_update(&mut store_update, 28);
+        store_update.commit().unwrap();
+
+        let stats = run_batched_migration(&store, &Migration28To29, false).unwrap();
+        assert_eq!(stats.num_records, 20);
+        assert_eq!(get_version(&store), Some(b"29".to_vec()));
+        for col in [DBCol::_NextBlockWithNewChunk, DBCol::_LastBlockWithNewChunk] {
+            assert_eq!(store.iter(col).count(), 0);
+        }
+    }
 }
@@ -9,7 +9,9 @@ use near_primitives::errors::StorageError;
 use near_primitives::receipt::Receipt;
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::state_part::PartId;
-use near_primitives::trie_key::trie_key_parsers::parse_account_id_from_raw_key;
+use near_primitives::trie_key::trie_key_parsers::{
+    is_promise_yield_queue_key, is_shared_contract_code_key, parse_account_id_from_raw_key,
+};
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{
     ConsolidatedStateChange, StateChangeCause, StateChangesForSplitStates, StateRoot,
@@ -78,6 +80,7 @@ impl ShardTries {
                 | TrieKey::PostponedReceiptId { receiver_id: account_id, .. }
                 | TrieKey::PendingDataCount { receiver_id: account_id, .. }
                 | TrieKey::PostponedReceipt { receiver_id: account_id, .. }
+                | TrieKey::PendingPromiseYield { receiver_id: account_id, .. }
                 | TrieKey::ContractData { account_id, .. } => {
                     let new_shard_uid = account_id_to_shard_id(account_id);
                     // we can safely unwrap here because the caller of this function guarantees trie_updates contains all shard_uids for the new shards
@@ -87,6 +90,19 @@ impl ShardTries {
                         None => trie_update.remove(trie_key),
                     }
                 }
+                // The shared contract registry is copied to every new shard. So is the yielded
+                // promises timeout queue: an entry only takes effect in the shard which has the
+                // corresponding `PendingPromiseYield` record.
+                TrieKey::SharedContractCode { .. }
+                | TrieKey::PromiseYieldIndices
+                | TrieKey::PromiseYieldTimeout { .. } => {
+                    for trie_update in trie_updates.values_mut() {
+                        match &value {
+                            Some(value) => trie_update.set(trie_key.clone(), value.clone()),
+                            None => trie_update.remove(trie_key.clone()),
+                        }
+                    }
+                }
             }
         }
         for (_, update) in trie_updates.iter_mut() {
@@ -150,6 +166,17 @@ impl ShardTries {
     ) -> Result<(StoreUpdate, HashMap<ShardUId, StateRoot>), StorageError> {
         let mut changes_by_shard: HashMap<_, Vec<_>> = HashMap::new();
         for (raw_key, value) in values.into_iter() {
+            // The shared contract registry and the yielded promises timeout queue are copied to
+            // every new shard.
+            if is_shared_contract_code_key(&raw_key) || is_promise_yield_queue_key(&raw_key) {
+                for shard_uid in state_roots.keys() {
+                    changes_by_shard
+                        .entry(*shard_uid)
+                        .or_default()
+                        .push((raw_key.clone(), value.clone()));
+                }
+                continue;
+            }
             if let Some(new_shard_uid) = key_to_shard_id(&raw_key)? {
                 changes_by_shard.entry(new_shard_uid).or_default().push((raw_key, value));
             }
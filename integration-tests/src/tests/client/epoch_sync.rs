@@ -0,0 +1,222 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use near_chain::{ChainGenesis, Error, Provenance, RuntimeAdapter};
+use near_chain_configs::Genesis;
+use near_client::sync::EpochSync;
+use near_client::test_utils::TestEnv;
+use near_client::SyncStatus;
+use near_crypto::KeyType;
+use near_logger_utils::init_test_logger;
+use near_network::test_utils::MockPeerManagerAdapter;
+use near_network::types::{FullPeerInfo, NetworkRequests, PeerManagerMessageRequest};
+use near_network_primitives::types::{PartialEdgeInfo, PeerChainInfoV2, PeerInfo};
+use near_primitives::block::{Approval, GenesisId};
+use near_primitives::block_header::ApprovalType;
+use near_primitives::syncing::EpochSyncResponse;
+use near_primitives::types::BlockHeight;
+use near_primitives::validator_signer::InMemoryValidatorSigner;
+use nearcore::config::GenesisExt;
+
+use crate::tests::client::process_blocks::create_nightshade_runtimes;
+
+const EPOCH_LENGTH: BlockHeight = 5;
+
+/// Creates an environment where `test0` is the only block producer and `test1` is a new node.
+fn setup_env() -> TestEnv {
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    genesis.config.epoch_length = EPOCH_LENGTH;
+    TestEnv::builder(ChainGenesis::new(&genesis))
+        .clients_count(2)
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 2))
+        .build()
+}
+
+/// Produces blocks on the first client. Every block contains the approval of its parent, so
+/// that the epochs get light client blocks.
+fn produce_blocks_with_approvals(env: &mut TestEnv, num_blocks: BlockHeight) {
+    let signer =
+        InMemoryValidatorSigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    for height in 1..=num_blocks {
+        let block = env.clients[0].produce_block(height).unwrap().unwrap();
+        env.process_block(0, block.clone(), Provenance::PRODUCED);
+        let approval = Approval::new(*block.hash(), height, height + 1, &signer);
+        env.clients[0].collect_block_approval(&approval, ApprovalType::SelfApproval);
+    }
+}
+
+/// Epoch sync of the second client, which doesn't limit how often the same peer is requested.
+fn new_epoch_sync(env: &TestEnv, network_adapter: Arc<MockPeerManagerAdapter>) -> EpochSync {
+    let genesis = env.clients[1].chain.genesis().clone();
+    let block_producers = env.clients[1]
+        .runtime_adapter
+        .get_epoch_block_producers_ordered(genesis.epoch_id(), genesis.hash())
+        .unwrap()
+        .iter()
+        .map(|(bp, _)| bp.clone())
+        .collect();
+    EpochSync::new(
+        network_adapter,
+        genesis.epoch_id().clone(),
+        block_producers,
+        Duration::from_secs(10),
+        Duration::ZERO,
+    )
+}
+
+fn peer_info(env: &TestEnv) -> FullPeerInfo {
+    FullPeerInfo {
+        peer_info: PeerInfo::random(),
+        chain_info: PeerChainInfoV2 {
+            genesis_id: GenesisId {
+                chain_id: "test".to_string(),
+                hash: *env.clients[0].chain.genesis().hash(),
+            },
+            height: env.clients[0].chain.head().unwrap().height,
+            tracked_shards: vec![],
+            archival: false,
+        },
+        partial_edge_info: PartialEdgeInfo::default(),
+    }
+}
+
+fn pop_request(network_adapter: &MockPeerManagerAdapter) -> NetworkRequests {
+    match network_adapter.pop() {
+        Some(PeerManagerMessageRequest::NetworkRequests(request)) => request,
+        request => panic!("Unexpected request {:?}", request),
+    }
+}
+
+/// A new node proves the block producers of every epoch with light client blocks, initializes
+/// the chain at the latest epoch, and header sync continues from there.
+#[test]
+fn test_epoch_sync() {
+    init_test_logger();
+    let mut env = setup_env();
+    produce_blocks_with_approvals(&mut env, 6 * EPOCH_LENGTH + 3);
+
+    let network_adapter = Arc::new(MockPeerManagerAdapter::default());
+    let mut epoch_sync = new_epoch_sync(&env, network_adapter.clone());
+    let peers = vec![peer_info(&env)];
+    let mut sync_status = SyncStatus::NoSync;
+    let mut num_light_client_blocks = 0;
+    while !epoch_sync.done {
+        epoch_sync.run(&mut sync_status, &env.clients[1].chain, &peers).unwrap();
+        if epoch_sync.done {
+            break;
+        }
+        match pop_request(&network_adapter) {
+            NetworkRequests::EpochSyncRequest { peer_id, epoch_id } => {
+                let response =
+                    env.clients[0].chain.get_epoch_sync_response(&epoch_id).unwrap().unwrap();
+                if let EpochSyncResponse::Advance { .. } = response {
+                    num_light_client_blocks += 1;
+                }
+                epoch_sync.on_response(&peer_id, response).unwrap();
+            }
+            NetworkRequests::EpochSyncFinalizationRequest { peer_id, epoch_id } => {
+                let response = env.clients[0]
+                    .chain
+                    .get_epoch_sync_finalization_response(&epoch_id)
+                    .unwrap()
+                    .unwrap();
+                epoch_sync
+                    .on_finalization_response(&peer_id, response, &mut env.clients[1].chain)
+                    .unwrap();
+            }
+            request => panic!("Unexpected request {:?}", request),
+        }
+        assert!(num_light_client_blocks <= 7);
+    }
+    // One light client block per epoch before the epoch of the head.
+    let head = env.clients[0].chain.head().unwrap();
+    let header = |height| env.clients[0].chain.get_header_by_height(height).unwrap();
+    let num_epoch_switches = (1..=head.height)
+        .filter(|height| header(*height).epoch_id() != header(height - 1).epoch_id())
+        .count();
+    assert_eq!(num_light_client_blocks, num_epoch_switches);
+
+    // Header sync continues from the last block of the previous epoch.
+    let epoch_start_height =
+        env.clients[0].runtime_adapter.get_epoch_start_height(&head.last_block_hash).unwrap();
+    let header_head = env.clients[1].chain.header_head().unwrap();
+    assert_eq!(header_head.height, epoch_start_height - 1);
+    assert_eq!(env.clients[1].chain.head().unwrap().height, 0);
+
+    let headers = (header_head.height + 1..=head.height)
+        .map(|height| env.clients[0].chain.get_header_by_height(height).unwrap())
+        .collect();
+    env.clients[1].sync_block_headers(headers).unwrap();
+    assert_eq!(env.clients[1].chain.header_head().unwrap().last_block_hash, head.last_block_hash);
+}
+
+/// Light client blocks that are not approved by the known block producers or don't match the
+/// next block producers they commit to are rejected.
+#[test]
+fn test_epoch_sync_invalid_light_client_block() {
+    init_test_logger();
+    let mut env = setup_env();
+    produce_blocks_with_approvals(&mut env, 3 * EPOCH_LENGTH);
+
+    let network_adapter = Arc::new(MockPeerManagerAdapter::default());
+    let peers = vec![peer_info(&env)];
+    let request_light_client_block = |epoch_sync: &mut EpochSync| {
+        epoch_sync.run(&mut SyncStatus::NoSync, &env.clients[1].chain, &peers).unwrap();
+        match pop_request(&network_adapter) {
+            NetworkRequests::EpochSyncRequest { peer_id, epoch_id } => {
+                match env.clients[0].chain.get_epoch_sync_response(&epoch_id).unwrap().unwrap() {
+                    EpochSyncResponse::Advance { light_client_block_view } => {
+                        (peer_id, light_client_block_view)
+                    }
+                    response => panic!("Unexpected response {:?}", response),
+                }
+            }
+            request => panic!("Unexpected request {:?}", request),
+        }
+    };
+
+    let mut epoch_sync = new_epoch_sync(&env, network_adapter.clone());
+    let (peer_id, mut light_client_block) = request_light_client_block(&mut epoch_sync);
+    for approval in light_client_block.approvals_after_next.iter_mut() {
+        *approval = None;
+    }
+    assert!(matches!(
+        epoch_sync.on_response(
+            &peer_id,
+            EpochSyncResponse::Advance { light_client_block_view: light_client_block }
+        ),
+        Err(Error::NotEnoughApprovals)
+    ));
+
+    // Approvals missing for some of the block producers are rejected before they are checked.
+    let mut epoch_sync = new_epoch_sync(&env, network_adapter.clone());
+    let (peer_id, mut light_client_block) = request_light_client_block(&mut epoch_sync);
+    light_client_block.approvals_after_next.pop();
+    assert!(matches!(
+        epoch_sync.on_response(
+            &peer_id,
+            EpochSyncResponse::Advance { light_client_block_view: light_client_block }
+        ),
+        Err(Error::InvalidApprovals)
+    ));
+
+    let mut epoch_sync = new_epoch_sync(&env, network_adapter.clone());
+    let (peer_id, mut light_client_block) = request_light_client_block(&mut epoch_sync);
+    light_client_block.next_bps.as_mut().unwrap().pop();
+    assert!(matches!(
+        epoch_sync.on_response(
+            &peer_id,
+            EpochSyncResponse::Advance { light_client_block_view: light_client_block }
+        ),
+        Err(Error::InvalidNextBPHash)
+    ));
+
+    let mut epoch_sync = new_epoch_sync(&env, network_adapter.clone());
+    let (peer_id, light_client_block) = request_light_client_block(&mut epoch_sync);
+    epoch_sync
+        .on_response(
+            &peer_id,
+            EpochSyncResponse::Advance { light_client_block_view: light_client_block },
+        )
+        .unwrap();
+}
@@ -1,6 +1,7 @@
 mod benchmarks;
 mod challenges;
 mod chunks_management;
+mod epoch_sync;
 mod process_blocks;
 mod runtimes;
 #[cfg(feature = "sandbox")]
@@ -16,8 +16,8 @@ use near_chain::chain::ApplyStatePartsRequest;
 use near_chain::types::LatestKnown;
 use near_chain::validate::validate_chunk_with_chunk_extra;
 use near_chain::{
-    Block, BlockProcessingArtifact, ChainGenesis, ChainStore, ChainStoreAccess, Error, Provenance,
-    RuntimeAdapter,
+    Block, BlockProcessingArtifact, ChainGenesis, ChainStore, ChainStoreAccess, Error,
+    OptimisticApplyStats, Provenance, RuntimeAdapter,
 };
 use near_chain_configs::{ClientConfig, Genesis, DEFAULT_GC_NUM_EPOCHS_TO_KEEP};
 use near_chunks::{ChunkStatus, ShardsManager};
@@ -35,6 +35,7 @@ use near_network::types::{
     FullPeerInfo, MsgRecipient as _, NetworkClientMessages, NetworkClientResponses,
     NetworkRequests, NetworkResponses,
 };
+use near_network_primitives::time;
 use near_network_primitives::types::{PeerChainInfoV2, PeerInfo, ReasonForBan};
 use near_primitives::block::{Approval, ApprovalInner};
 use near_primitives::block_header::BlockHeader;
@@ -1043,6 +1044,7 @@ fn test_time_attack() {
     let vs =
         ValidatorSchedule::new().block_producers_per_epoch(vec![vec!["test1".parse().unwrap()]]);
     let mut client = setup_client(
+        time::Clock::real(),
         store,
         vs,
         Some("test1".parse().unwrap()),
@@ -1076,6 +1078,7 @@ fn test_invalid_approvals() {
     let vs =
         ValidatorSchedule::new().block_producers_per_epoch(vec![vec!["test1".parse().unwrap()]]);
     let mut client = setup_client(
+        time::Clock::real(),
         store,
         vs,
         Some("test1".parse().unwrap()),
@@ -1125,6 +1128,7 @@ fn test_invalid_gas_price() {
     let vs =
         ValidatorSchedule::new().block_producers_per_epoch(vec![vec!["test1".parse().unwrap()]]);
     let mut client = setup_client(
+        time::Clock::real(),
         store,
         vs,
         Some("test1".parse().unwrap()),
@@ -1284,6 +1288,7 @@ fn test_bad_chunk_mask() {
                 .num_shards(2)
                 .block_producers_per_epoch(vec![validators.clone()]);
             setup_client(
+                time::Clock::real(),
                 create_test_store(),
                 vs,
                 Some(account_id.clone()),
@@ -1891,6 +1896,7 @@ fn test_incorrect_validator_key_produce_block() {
     let mut config = ClientConfig::test(true, 10, 20, 2, false, true);
     config.epoch_length = chain_genesis.epoch_length;
     let mut client = Client::new(
+        time::Clock::real(),
         config,
         chain_genesis,
         runtime_adapter,
@@ -4057,6 +4063,159 @@ mod access_key_nonce_range_tests {
 
         assert_eq!(env.clients[1].chain.head().unwrap().height, 20);
     }
+
+    /// Test that chunks of a block waiting for a late chunk are applied optimistically and the
+    /// results are reused once the late chunk arrives.
+    /// test0 produces 6 blocks with transfers between test0 and test1, which live in different
+    /// shards.  test1 processes the first 5 blocks normally.  For the last block, test1 receives
+    /// the chunks of shards 0-2 and the partial chunk of shard 3, but not the full chunk of shard
+    /// 3.  Shards 0-2 are then applied optimistically, and when the last chunk arrives, the block
+    /// is processed using these results and ends up with the same chunk extras and execution
+    /// outcomes as on test0.
+    #[test]
+    fn test_apply_chunks_optimistically() {
+        init_test_logger();
+
+        let num_clients = 2;
+        let num_validators = 1;
+        let epoch_length = 10;
+
+        let accounts: Vec<AccountId> =
+            (0..num_clients).map(|i| format!("test{}", i).parse().unwrap()).collect();
+        let mut genesis = Genesis::test(accounts.clone(), num_validators);
+        genesis.config.epoch_length = epoch_length;
+        // make the blockchain to 4 shards
+        genesis.config.shard_layout = ShardLayout::v1_test();
+        genesis.config.num_block_producer_seats_per_shard =
+            vec![num_validators, num_validators, num_validators, num_validators];
+        let chain_genesis = ChainGenesis::new(&genesis);
+        let runtimes: Vec<Arc<dyn RuntimeAdapter>> = (0..2)
+            .map(|_| {
+                Arc::new(nearcore::NightshadeRuntime::test_with_runtime_config_store(
+                    Path::new("."),
+                    create_test_store(),
+                    &genesis,
+                    TrackedConfig::AllShards,
+                    RuntimeConfigStore::test(),
+                )) as Arc<dyn RuntimeAdapter>
+            })
+            .collect();
+        let mut env = TestEnv::builder(chain_genesis)
+            .clients_count(num_clients)
+            .validator_seats(num_validators as usize)
+            .runtime_adapters(runtimes)
+            .build();
+
+        let genesis_hash = *env.clients[0].chain.genesis().hash();
+        let signers: Vec<_> = accounts
+            .iter()
+            .map(|account_id| {
+                InMemorySigner::from_seed(account_id.clone(), KeyType::ED25519, account_id.as_ref())
+            })
+            .collect();
+        let mut blocks = vec![];
+        // produce 6 blocks, every chunk has transactions or receipts to execute
+        for i in 1..=6 {
+            for (sender, receiver) in [(0, 1), (1, 0)] {
+                let tx = SignedTransaction::send_money(
+                    i,
+                    accounts[sender].clone(),
+                    accounts[receiver].clone(),
+                    &signers[sender],
+                    100,
+                    genesis_hash,
+                );
+                env.clients[0].process_tx(tx, false, false);
+            }
+            let block = env.clients[0].produce_block(i).unwrap().unwrap();
+            blocks.push(block.clone());
+            env.process_block(0, block, Provenance::PRODUCED);
+        }
+
+        for block in &blocks[..5] {
+            let _ = env.clients[1].process_block_test(block.clone().into(), Provenance::NONE);
+            env.process_partial_encoded_chunks_requests(1);
+            env.clients[1].finish_blocks_in_processing();
+        }
+        assert_eq!(&env.clients[1].chain.head().unwrap().last_block_hash, blocks[4].hash());
+
+        // deliver all chunks of the last block except the one of shard 3
+        let block = blocks[5].clone();
+        let res = env.clients[1].process_block_test(block.clone().into(), Provenance::NONE);
+        assert_matches!(res.unwrap_err(), near_chain::Error::ChunksMissing(_));
+        let late_chunk_hash = block.chunks()[3].chunk_hash();
+        let mut late_chunk_request = None;
+        while let Some(request) = env.network_adapters[1].pop() {
+            let is_late_chunk = matches!(
+                &request,
+                PeerManagerMessageRequest::NetworkRequests(
+                    NetworkRequests::PartialEncodedChunkRequest { request, .. },
+                ) if request.chunk_hash == late_chunk_hash
+            );
+            if is_late_chunk {
+                late_chunk_request = Some(request);
+            } else {
+                env.process_partial_encoded_chunk_request(1, request);
+            }
+        }
+        env.clients[1].finish_blocks_in_processing();
+        assert_eq!(&env.clients[1].chain.head().unwrap().last_block_hash, blocks[4].hash());
+        assert_eq!(env.clients[1].chain.optimistic_apply_stats(), OptimisticApplyStats::default());
+
+        // the partial chunk of shard 3 has the incoming receipts of the shard, which makes the
+        // other shards applicable
+        let partial_chunk =
+            env.clients[0].chain.store().get_partial_chunk(&late_chunk_hash).unwrap();
+        let mut store_update = env.clients[1].chain.mut_store().store_update();
+        store_update.save_partial_chunk(partial_chunk.as_ref().clone());
+        store_update.commit().unwrap();
+        env.clients[1].process_blocks_with_missing_chunks(Arc::new(|_| {}));
+        let mut stats = env.clients[1].chain.optimistic_apply_stats();
+        for _ in 0..100 {
+            if stats.num_in_progress == 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            stats = env.clients[1].chain.optimistic_apply_stats();
+        }
+        assert_eq!(stats, OptimisticApplyStats { num_in_progress: 0, num_results: 3, num_used: 0 });
+        // checking the block again doesn't apply the chunks again
+        env.clients[1].process_blocks_with_missing_chunks(Arc::new(|_| {}));
+        assert_eq!(env.clients[1].chain.optimistic_apply_stats(), stats);
+
+        env.process_partial_encoded_chunk_request(1, late_chunk_request.unwrap());
+        env.clients[1].finish_blocks_in_processing();
+        assert_eq!(&env.clients[1].chain.head().unwrap().last_block_hash, block.hash());
+        assert_eq!(
+            env.clients[1].chain.optimistic_apply_stats(),
+            OptimisticApplyStats { num_in_progress: 0, num_results: 0, num_used: 3 }
+        );
+
+        let shard_layout = ShardLayout::v1_test();
+        let mut num_outcomes = 0;
+        for shard_id in 0..4 {
+            let shard_uid = ShardUId::from_shard_id_and_layout(shard_id, &shard_layout);
+            assert_eq!(
+                env.clients[1].chain.get_chunk_extra(block.hash(), &shard_uid).unwrap(),
+                env.clients[0].chain.get_chunk_extra(block.hash(), &shard_uid).unwrap(),
+            );
+            let outcome_ids = env.clients[0]
+                .chain
+                .store()
+                .get_outcomes_by_block_hash_and_shard_id(block.hash(), shard_id)
+                .unwrap();
+            assert_eq!(
+                env.clients[1]
+                    .chain
+                    .store()
+                    .get_outcomes_by_block_hash_and_shard_id(block.hash(), shard_id)
+                    .unwrap(),
+                outcome_ids
+            );
+            num_outcomes += outcome_ids.len();
+        }
+        assert!(num_outcomes > 0);
+    }
 }
 
 mod protocol_feature_restore_receipts_after_fix_tests {
@@ -4635,6 +4794,54 @@ mod contract_precompilation_tests {
         // Check that contract is not cached for client 1 because of late state sync.
         assert!(caches[1].get(&contract_key).unwrap().is_none());
     }
+
+    /// Contracts are precompiled in the background before the epoch in which the protocol
+    /// version changes the VM, so that they are already cached when they are called after it.
+    #[test]
+    fn test_precompile_contracts_before_protocol_upgrade() {
+        let old_protocol_version = ProtocolFeature::Wasmer2.protocol_version() - 1;
+        let new_protocol_version = old_protocol_version + 1;
+        let store = create_test_store();
+        let mut genesis =
+            Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+        genesis.config.epoch_length = EPOCH_LENGTH;
+        genesis.config.protocol_version = old_protocol_version;
+        let runtime_adapter = Arc::new(nearcore::NightshadeRuntime::test(
+            Path::new("../../../.."),
+            store.clone(),
+            &genesis,
+        )) as Arc<dyn RuntimeAdapter>;
+        let mut env = TestEnv::builder(ChainGenesis::new(&genesis))
+            .runtime_adapters(vec![runtime_adapter.clone()])
+            .build();
+
+        let wasm_code = near_test_contracts::rs_contract().to_vec();
+        deploy_test_contract(&mut env, "test0".parse().unwrap(), &wasm_code, EPOCH_LENGTH, 1);
+        assert_eq!(runtime_adapter.get_contract_precompilation_progress(), None);
+
+        env.upgrade_protocol(new_protocol_version);
+        let mut progress = runtime_adapter.get_contract_precompilation_progress().unwrap();
+        for _ in 0..100 {
+            if progress.done {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            progress = runtime_adapter.get_contract_precompilation_progress().unwrap();
+        }
+        assert!(progress.done);
+        assert_eq!(progress.protocol_version, new_protocol_version);
+        assert_eq!(progress.num_shards_done, 1);
+        assert_eq!(progress.num_contracts_compiled, 1);
+        assert_eq!(progress.num_contracts_failed, 0);
+
+        let runtime_config = RuntimeConfigStore::new(None).get_config(new_protocol_version).clone();
+        let contract_key = get_contract_cache_key(
+            &ContractCode::new(wasm_code, None),
+            VMKind::for_protocol_version(new_protocol_version),
+            &runtime_config.wasm_config,
+        );
+        assert!(StoreCompiledContractCache::new(&store).get(&contract_key).unwrap().is_some());
+    }
 }
 
 mod chunk_nodes_cache_test {
@@ -0,0 +1,56 @@
+use std::sync::mpsc;
+
+use actix::{Actor, System};
+
+use crate::test_helpers::heavy_test;
+use near_actix_test_utils::run_actix;
+use near_chain_configs::Genesis;
+use near_logger_utils::init_integration_logger;
+use near_network::test_utils::{convert_boot_nodes, open_port, WaitOrTimeoutActor};
+use nearcore::{
+    backup_store, config::GenesisExt, load_test_config, start_with_config, verify_backup,
+};
+
+/// `neard database backup` asks a running node, which holds the database lock,
+/// to create the checkpoint through the admin JSON RPC.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn backup_running_node() {
+    heavy_test(|| {
+        init_integration_logger();
+
+        let genesis = Genesis::test(vec!["test1".parse().unwrap()], 1);
+        let mut near_config = load_test_config("test1", open_port(), genesis);
+        near_config.network_config.boot_nodes = convert_boot_nodes(vec![]);
+        near_config.client_config.min_num_peers = 0;
+        near_config.rpc_config.as_mut().unwrap().enable_admin_rpc = true;
+
+        run_actix(async move {
+            let dir = tempfile::Builder::new().prefix("backup").tempdir().unwrap();
+            start_with_config(dir.path(), near_config.clone()).expect("start_with_config");
+
+            // `backup_store` sends the request from its own actix system, so it
+            // can't run on this thread.
+            let (result_tx, result_rx) = mpsc::channel();
+            let home_dir = dir.path().to_path_buf();
+            std::thread::spawn(move || {
+                let backup_dir = home_dir.join("backup");
+                let result = backup_store(&home_dir, &near_config, &backup_dir)
+                    .and_then(|_| verify_backup(&home_dir, &near_config, &backup_dir));
+                result_tx.send(result.map_err(|err| format!("{:#}", err))).unwrap();
+            });
+
+            WaitOrTimeoutActor::new(
+                Box::new(move |_ctx| {
+                    if let Ok(result) = result_rx.try_recv() {
+                        result.unwrap();
+                        System::current().stop();
+                    }
+                }),
+                100,
+                60000,
+            )
+            .start();
+        });
+    });
+}
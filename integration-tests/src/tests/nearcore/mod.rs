@@ -1,4 +1,6 @@
+mod backup;
 mod node_cluster;
+mod replica;
 mod rpc_error_structs;
 mod rpc_nodes;
 mod run_nodes;
@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use actix::{Actor, System};
+use futures::{future, FutureExt};
+
+use crate::test_helpers::heavy_test;
+use near_actix_test_utils::run_actix;
+use near_chain_configs::Genesis;
+use near_client::{GetBlock, Query};
+use near_logger_utils::init_integration_logger;
+use near_network::test_utils::{convert_boot_nodes, open_port, WaitOrTimeoutActor};
+use near_primitives::contract::ContractCode;
+use near_primitives::state_record::StateRecord;
+use near_primitives::types::BlockReference;
+use near_primitives::views::{QueryRequest, QueryResponseKind};
+use nearcore::replica::{start_replica_with_config, NearReplica};
+use nearcore::{config::GenesisExt, load_test_config, start_with_config};
+
+/// A replica started next to a running node follows the node's database and
+/// answers view queries, including function calls which compile a contract.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn replica_answers_view_queries() {
+    heavy_test(|| {
+        init_integration_logger();
+
+        let mut genesis = Genesis::test(vec!["test1".parse().unwrap()], 1);
+        let code = near_test_contracts::rs_contract();
+        for record in genesis.records.as_mut() {
+            if let StateRecord::Account { account_id, ref mut account } = record {
+                if account_id.as_ref() == "test1" {
+                    account.set_code_hash(*ContractCode::new(code.to_vec(), None).hash());
+                }
+            }
+        }
+        genesis.records.as_mut().push(StateRecord::Contract {
+            account_id: "test1".parse().unwrap(),
+            code: code.to_vec(),
+        });
+
+        let mut near1 = load_test_config("test1", open_port(), genesis.clone());
+        near1.network_config.boot_nodes = convert_boot_nodes(vec![]);
+        near1.client_config.min_num_peers = 0;
+        let mut replica_config = load_test_config("test1", open_port(), genesis);
+        replica_config.rpc_config = None;
+
+        run_actix(async move {
+            let dir = tempfile::Builder::new().prefix("replica").tempdir().unwrap();
+            let nearcore::NearNode { view_client, .. } =
+                start_with_config(dir.path(), near1).expect("start_with_config");
+
+            let replica_holder = Arc::new(RwLock::new(None));
+            let call_succeeded = Arc::new(AtomicBool::new(false));
+            WaitOrTimeoutActor::new(
+                Box::new(move |_ctx| {
+                    let replica = replica_holder.read().unwrap().clone();
+                    let replica = match replica {
+                        Some(replica) => replica,
+                        None => {
+                            let replica_holder = replica_holder.clone();
+                            let dir = dir.path().to_path_buf();
+                            let replica_config = replica_config.clone();
+                            actix::spawn(view_client.send(GetBlock::latest()).then(move |res| {
+                                if let Ok(Ok(block)) = res {
+                                    let mut replica_holder = replica_holder.write().unwrap();
+                                    if block.header.height >= 5 && replica_holder.is_none() {
+                                        let NearReplica { view_client, .. } =
+                                            start_replica_with_config(
+                                                &dir,
+                                                replica_config,
+                                                &dir.join("replica"),
+                                                Duration::from_millis(100),
+                                            )
+                                            .expect("start_replica_with_config");
+                                        *replica_holder = Some(view_client);
+                                    }
+                                }
+                                future::ready(())
+                            }));
+                            return;
+                        }
+                    };
+
+                    let call_succeeded1 = call_succeeded.clone();
+                    actix::spawn(
+                        replica
+                            .send(Query::new(
+                                BlockReference::latest(),
+                                QueryRequest::CallFunction {
+                                    account_id: "test1".parse().unwrap(),
+                                    method_name: "run_test".to_string(),
+                                    args: b"".to_vec().into(),
+                                },
+                            ))
+                            .then(move |res| {
+                                match res.unwrap().unwrap().kind {
+                                    QueryResponseKind::CallResult(result) => {
+                                        assert_eq!(result.result, 10i32.to_le_bytes().to_vec());
+                                        call_succeeded1.store(true, Ordering::SeqCst);
+                                    }
+                                    _ => panic!("wrong return result"),
+                                }
+                                future::ready(())
+                            }),
+                    );
+                    // The replica has to keep up with the node, which only
+                    // started it at height 5.
+                    let call_succeeded2 = call_succeeded.clone();
+                    actix::spawn(replica.send(GetBlock::latest()).then(move |res| {
+                        if let Ok(Ok(block)) = res {
+                            if block.header.height >= 15 && call_succeeded2.load(Ordering::SeqCst) {
+                                System::current().stop();
+                            }
+                        }
+                        future::ready(())
+                    }));
+                }),
+                100,
+                60000,
+            )
+            .start();
+        });
+    });
+}
@@ -1,17 +1,22 @@
 use crate::runtime_utils::{get_runtime_and_trie, get_test_trie_viewer, TEST_SHARD_UID};
+use borsh::BorshSerialize;
+use near_crypto::{KeyType, PublicKey};
+use near_primitives::challenge::PartialState;
+use near_primitives::serialize::{from_base64, to_base64};
 use near_primitives::{
-    account::Account,
+    account::{AccessKey, Account},
     hash::hash as sha256,
     hash::CryptoHash,
-    views::{StateItem, ViewApplyState},
+    views::{StateItem, ViewApplyState, ViewStateResult},
 };
 use near_primitives::{
+    runtime::config::RuntimeConfig,
     test_utils::MockEpochInfoProvider,
     trie_key::TrieKey,
-    types::{EpochId, StateChangeCause},
+    types::{AccountId, EpochId, StateChangeCause},
     version::PROTOCOL_VERSION,
 };
-use near_store::set_account;
+use near_store::{set_access_key, set_account, PartialStorage, Trie, TrieUpdate};
 use node_runtime::state_viewer::errors;
 use node_runtime::state_viewer::*;
 use testlib::runtime_utils::{alice_account, encode_int};
@@ -131,7 +136,8 @@ fn test_view_state() {
 
     let state_update = tries.new_trie_update(shard_uid, new_root);
     let trie_viewer = TrieViewer::default();
-    let result = trie_viewer.view_state(&state_update, &alice_account(), b"").unwrap();
+    let result =
+        trie_viewer.view_state(&state_update, &alice_account(), b"", None, None, false).unwrap();
     assert_eq!(result.proof, Vec::<String>::new());
     assert_eq!(
         result.values,
@@ -140,15 +146,105 @@ fn test_view_state() {
             StateItem { key: "dGVzdDMyMQ==".to_string(), value: "MzIx".to_string(), proof: vec![] }
         ]
     );
-    let result = trie_viewer.view_state(&state_update, &alice_account(), b"xyz").unwrap();
+    let result =
+        trie_viewer.view_state(&state_update, &alice_account(), b"xyz", None, None, false).unwrap();
     assert_eq!(result.values, []);
-    let result = trie_viewer.view_state(&state_update, &alice_account(), b"test123").unwrap();
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test123", None, None, false)
+        .unwrap();
     assert_eq!(
         result.values,
         [StateItem { key: "dGVzdDEyMw==".to_string(), value: "MTIz".to_string(), proof: vec![] }]
     );
 }
 
+#[test]
+fn test_view_state_paginated() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let mut state_update = tries.new_trie_update(TEST_SHARD_UID, root);
+    set_account(
+        &mut state_update,
+        alice_account(),
+        &Account::new(0, 0, CryptoHash::default(), 50_001),
+    );
+    for (key, value) in [("test1", "a"), ("test2", "bb"), ("test3", "ccc")] {
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.as_bytes().to_vec() },
+            value.as_bytes().to_vec(),
+        );
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, TEST_SHARD_UID);
+    db_changes.commit().unwrap();
+    let state_update = tries.new_trie_update(TEST_SHARD_UID, new_root);
+    let keys = |result: &ViewStateResult| {
+        result.values.iter().map(|item| from_base64(&item.key).unwrap()).collect::<Vec<_>>()
+    };
+
+    let trie_viewer = TrieViewer::new(Some(50_000), None);
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test", None, Some(2), false)
+        .unwrap();
+    assert_eq!(keys(&result), [b"test1".to_vec(), b"test2".to_vec()]);
+    assert_eq!(result.next_key, Some(to_base64(b"test3")));
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"test", Some(b"test3"), Some(2), false)
+        .unwrap();
+    assert_eq!(keys(&result), [b"test3".to_vec()]);
+    assert_eq!(result.next_key, None);
+
+    // Each value takes the length of its trie key, 17 bytes, plus its length.
+    let trie_viewer = TrieViewer::new(Some(40), None);
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", Some(b""), None, false)
+        .unwrap();
+    assert_eq!(keys(&result), [b"test1".to_vec(), b"test2".to_vec()]);
+    assert_eq!(result.next_key, Some(to_base64(b"test3")));
+    let trie_viewer = TrieViewer::new(Some(10), None);
+    let result = trie_viewer
+        .view_state(&state_update, &alice_account(), b"", Some(b""), None, false)
+        .unwrap();
+    assert_eq!(keys(&result), [b"test1".to_vec()]);
+    assert_eq!(result.next_key, Some(to_base64(b"test2")));
+
+    // A page without values would never make progress.
+    let result = trie_viewer.view_state(&state_update, &alice_account(), b"", None, Some(0), false);
+    assert!(matches!(result, Err(errors::ViewStateError::InvalidLimit)));
+}
+
+#[test]
+fn test_view_state_with_proof() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let mut state_update = tries.new_trie_update(TEST_SHARD_UID, root);
+    for (key, value) in [("test1", "a"), ("test2", "bb")] {
+        state_update.set(
+            TrieKey::ContractData { account_id: alice_account(), key: key.as_bytes().to_vec() },
+            value.as_bytes().to_vec(),
+        );
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, TEST_SHARD_UID);
+    db_changes.commit().unwrap();
+    let state_update = tries.new_trie_update(TEST_SHARD_UID, new_root);
+
+    let trie_viewer = TrieViewer::default();
+    let result =
+        trie_viewer.view_state(&state_update, &alice_account(), b"", None, None, true).unwrap();
+    assert_eq!(result.values.len(), 2);
+    let nodes = result.proof.iter().map(|node| from_base64(node).unwrap()).collect();
+    let trie = Trie::from_recorded_storage(PartialStorage { nodes: PartialState(nodes) });
+    for item in &result.values {
+        let key = TrieKey::ContractData {
+            account_id: alice_account(),
+            key: from_base64(&item.key).unwrap(),
+        };
+        let value = trie.get(&new_root, &key.to_vec()).unwrap().unwrap();
+        assert_eq!(to_base64(&value), item.value);
+    }
+}
+
 #[test]
 fn test_view_state_too_large() {
     let (_, tries, root) = get_runtime_and_trie();
@@ -159,7 +255,7 @@ fn test_view_state_too_large() {
         &Account::new(0, 0, CryptoHash::default(), 50_001),
     );
     let trie_viewer = TrieViewer::new(Some(50_000), None);
-    let result = trie_viewer.view_state(&state_update, &alice_account(), b"");
+    let result = trie_viewer.view_state(&state_update, &alice_account(), b"", None, None, false);
     assert!(matches!(result, Err(errors::ViewStateError::AccountStateTooLarge { .. })));
 }
 
@@ -175,10 +271,106 @@ fn test_view_state_with_large_contract() {
     );
     state_update.set(TrieKey::ContractCode { account_id: alice_account() }, contract_code);
     let trie_viewer = TrieViewer::new(Some(50_000), None);
-    let result = trie_viewer.view_state(&state_update, &alice_account(), b"");
+    let result = trie_viewer.view_state(&state_update, &alice_account(), b"", None, None, false);
     assert!(result.is_ok());
 }
 
+fn storage_usage_state_update() -> (TrieUpdate, AccountId) {
+    let (_, tries, root) = get_runtime_and_trie();
+    let mut state_update = tries.new_trie_update(TEST_SHARD_UID, root);
+    let account_id: AccountId = "storage".parse().unwrap();
+    set_account(
+        &mut state_update,
+        account_id.clone(),
+        &Account::new(0, 0, CryptoHash::default(), 500),
+    );
+    set_access_key(
+        &mut state_update,
+        account_id.clone(),
+        PublicKey::empty(KeyType::ED25519),
+        &AccessKey::full_access(),
+    );
+    state_update.set(TrieKey::ContractCode { account_id: account_id.clone() }, b"code".to_vec());
+    for (key, value) in [("a1", "x"), ("a2", "yy"), ("b1", "zzzz")] {
+        state_update.set(
+            TrieKey::ContractData { account_id: account_id.clone(), key: key.as_bytes().to_vec() },
+            value.as_bytes().to_vec(),
+        );
+    }
+    state_update.commit(StateChangeCause::InitialState);
+    let trie_changes = state_update.finalize().unwrap().0;
+    let (db_changes, new_root) = tries.apply_all(&trie_changes, TEST_SHARD_UID);
+    db_changes.commit().unwrap();
+    (tries.new_trie_update(TEST_SHARD_UID, new_root), account_id)
+}
+
+#[test]
+fn test_view_storage_usage() {
+    let (state_update, account_id) = storage_usage_state_update();
+    let config = RuntimeConfig::test();
+    let extra_bytes = config.transaction_costs.storage_usage_config.num_extra_bytes_record;
+    let access_key_bytes = (PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap().len()
+        + AccessKey::full_access().try_to_vec().unwrap().len()) as u64
+        + extra_bytes;
+
+    let trie_viewer = TrieViewer::default();
+    let result = trie_viewer.view_storage_usage(&state_update, &account_id, &config, 1, 2).unwrap();
+    assert_eq!(result.storage_usage, 500);
+    assert_eq!(result.locked_for_storage, 500 * config.storage_amount_per_byte);
+    assert_eq!(
+        result.account_bytes,
+        config.transaction_costs.storage_usage_config.num_bytes_account
+    );
+    assert_eq!(result.code_bytes, 4);
+    assert_eq!((result.access_keys.num_records, result.access_keys.bytes), (1, access_key_bytes));
+    assert_eq!(
+        (result.contract_data.num_records, result.contract_data.bytes),
+        (3, 18 + 3 * extra_bytes)
+    );
+    let prefixes: Vec<_> = result
+        .contract_data_prefixes
+        .iter()
+        .map(|prefix| (prefix.prefix.as_ref().to_vec(), prefix.num_records, prefix.bytes))
+        .collect();
+    assert_eq!(
+        prefixes,
+        [(b"a".to_vec(), 2, 7 + 2 * extra_bytes), (b"b".to_vec(), 1, 6 + extra_bytes)]
+    );
+    let largest_keys: Vec<_> =
+        result.largest_keys.iter().map(|key| (key.key.as_ref().to_vec(), key.bytes)).collect();
+    assert_eq!(
+        largest_keys,
+        [(b"b1".to_vec(), 6 + extra_bytes), (b"a2".to_vec(), 5 + extra_bytes)]
+    );
+    assert!(!result.truncated);
+}
+
+#[test]
+fn test_view_storage_usage_truncated() {
+    let (state_update, account_id) = storage_usage_state_update();
+    let config = RuntimeConfig::test();
+    let extra_bytes = config.transaction_costs.storage_usage_config.num_extra_bytes_record;
+    let access_key_bytes = (PublicKey::empty(KeyType::ED25519).try_to_vec().unwrap().len()
+        + AccessKey::full_access().try_to_vec().unwrap().len()) as u64
+        + extra_bytes;
+
+    // Leaves room for the access key and the first data record only.
+    let trie_viewer = TrieViewer::new(Some(access_key_bytes + 3 + extra_bytes), None);
+    let result =
+        trie_viewer.view_storage_usage(&state_update, &account_id, &config, 1, 10).unwrap();
+    assert_eq!(result.access_keys.num_records, 1);
+    assert_eq!(
+        (result.contract_data.num_records, result.contract_data.bytes),
+        (1, 3 + extra_bytes)
+    );
+    assert_eq!(result.largest_keys.len(), 1);
+    assert!(result.truncated);
+
+    let result =
+        trie_viewer.view_storage_usage(&state_update, &"missing".parse().unwrap(), &config, 1, 10);
+    assert!(matches!(result, Err(errors::ViewStateError::AccountDoesNotExist { .. })));
+}
+
 #[test]
 fn test_log_when_panic() {
     let (viewer, root) = get_test_trie_viewer();
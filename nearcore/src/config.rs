@@ -15,8 +15,8 @@ use tempfile::tempdir;
 use tracing::{info, warn};
 
 use near_chain_configs::{
-    get_initial_supply, ClientConfig, GCConfig, Genesis, GenesisConfig, GenesisValidationMode,
-    LogSummaryStyle,
+    get_initial_supply, BlockTimelineLogConfig, ClientConfig, GCConfig, Genesis, GenesisConfig,
+    GenesisValidationMode, LogSummaryStyle,
 };
 use near_crypto::{InMemorySigner, KeyFile, KeyType, PublicKey, Signer};
 #[cfg(feature = "json_rpc")]
@@ -328,6 +328,18 @@ pub struct Config {
     pub db_migration_snapshot_path: Option<PathBuf>,
     /// Different parameters to configure/optimize underlying storage.
     pub store: near_store::StoreConfig,
+    /// Validate store invariants of every newly finalized block while the node
+    /// is running.  Violations are logged and counted in the
+    /// `near_online_store_validation_errors_total` metric.
+    pub enable_online_store_validation: bool,
+    /// If set, the timeline of every processed block (arrival, orphan and
+    /// missing chunks pools, chunk requests and apply durations) is appended
+    /// to a rolling JSON-lines file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_timeline_log: Option<BlockTimelineLogConfig>,
+    /// Number of threads the chunks of blocks are applied on.  Zero means one
+    /// thread per CPU.
+    pub apply_chunks_threads: usize,
 }
 
 impl Default for Config {
@@ -357,6 +369,9 @@ impl Default for Config {
             db_migration_snapshot_path: None,
             use_db_migration_snapshot: true,
             store: near_store::StoreConfig::default(),
+            enable_online_store_validation: false,
+            block_timeline_log: None,
+            apply_chunks_threads: 0,
         }
     }
 }
@@ -571,6 +586,9 @@ impl NearConfig {
                 trie_viewer_state_size_limit: config.trie_viewer_state_size_limit,
                 max_gas_burnt_view: config.max_gas_burnt_view,
                 enable_statistics_export: config.store.enable_statistics_export,
+                enable_online_store_validation: config.enable_online_store_validation,
+                block_timeline_log: config.block_timeline_log,
+                apply_chunks_threads: config.apply_chunks_threads,
             },
             network_config: NetworkConfig::new(
                 config.network,
@@ -1194,7 +1212,13 @@ pub fn load_config(
     dir: &Path,
     genesis_validation: GenesisValidationMode,
 ) -> Result<NearConfig, anyhow::Error> {
-    let config = Config::from_file(&dir.join(CONFIG_FILENAME))?;
+    let mut config = Config::from_file(&dir.join(CONFIG_FILENAME))?;
+    if let Some(block_timeline_log) = &mut config.block_timeline_log {
+        block_timeline_log.path = dir.join(&block_timeline_log.path);
+    }
+    if let Some(backup) = &mut config.store.backup {
+        backup.path = dir.join(&backup.path);
+    }
     let genesis_file = dir.join(&config.genesis_file);
     let validator_file = dir.join(&config.validator_key_file);
     let validator_signer = if validator_file.exists() {
@@ -1318,9 +1342,19 @@ fn test_config_from_file() {
         // values is probably not worth it but there may be some other defaults
         // we want to ensure that they happen.
         let want_gc = if has_gc {
-            GCConfig { gc_blocks_limit: 42, gc_fork_clean_step: 420, gc_num_epochs_to_keep: 24 }
+            GCConfig {
+                gc_blocks_limit: 42,
+                gc_fork_clean_step: 420,
+                gc_num_epochs_to_keep: 24,
+                ..GCConfig::default()
+            }
         } else {
-            GCConfig { gc_blocks_limit: 2, gc_fork_clean_step: 100, gc_num_epochs_to_keep: 5 }
+            GCConfig {
+                gc_blocks_limit: 2,
+                gc_fork_clean_step: 100,
+                gc_num_epochs_to_keep: 5,
+                ..GCConfig::default()
+            }
         };
         assert_eq!(want_gc, config.gc);
 
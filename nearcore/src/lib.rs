@@ -1,11 +1,15 @@
 pub use crate::config::{init_configs, load_config, load_test_config, NearConfig, NEAR_BASE};
-use crate::migrations::migrate_30_to_31;
+use crate::migrations::{migrate_30_to_31, Migration30To31};
 pub use crate::runtime::NightshadeRuntime;
 pub use crate::shard_tracker::TrackedConfig;
 use actix::{Actor, Addr, Arbiter};
 use actix_rt::ArbiterHandle;
 use actix_web;
 use anyhow::Context;
+use futures::channel::mpsc;
+use futures::future;
+use futures::StreamExt;
+use near_chain::store_validator::StoreValidator;
 use near_chain::{Chain, ChainGenesis};
 use near_client::{start_client, start_view_client, ClientActor, ViewClientActor};
 use near_network::types::NetworkRecipient;
@@ -16,9 +20,13 @@ use near_primitives::version::DbVersion;
 use near_rosetta_rpc::start_rosetta_rpc;
 #[cfg(feature = "performance_stats")]
 use near_rust_allocator_proxy::reset_memory_usage_max;
+use near_store::backup::{BackupConfig, BackupManifest};
 use near_store::db::RocksDB;
-use near_store::migrations::{migrate_28_to_29, migrate_29_to_30, set_store_version};
-use near_store::{DBCol, Mode, Store, StoreOpener};
+use near_store::migrations::{
+    get_migration_progress, migrate_28_to_29, migrate_29_to_30, run_batched_migration,
+    set_store_version, Migration28To29, Migration29To30,
+};
+use near_store::{DBCol, Mode, Store, StoreConfig, StoreOpener};
 use near_telemetry::TelemetryActor;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -30,6 +38,7 @@ pub mod config;
 mod download_file;
 mod metrics;
 pub mod migrations;
+pub mod replica;
 mod runtime;
 mod shard_tracker;
 
@@ -122,9 +131,20 @@ fn apply_store_migrations_if_exists(
         );
     }
 
+    // A migration which was interrupted continues from its last saved batch.  The snapshot
+    // created before it started is still the state to recover to if it fails again.
+    let resuming = get_migration_progress(&store_opener.open())?.is_some();
+    let existing_checkpoint_path = db_checkpoint_path(&store_opener.get_path(), near_config);
+
     // Before starting a DB migration, create a consistent snapshot of the database. If a migration
     // fails, it can be used to quickly restore the database to its original state.
-    let checkpoint_path = if near_config.config.use_db_migration_snapshot {
+    let checkpoint_path = if near_config.config.use_db_migration_snapshot
+        && resuming
+        && existing_checkpoint_path.exists()
+    {
+        info!(target: "near", "Resuming an interrupted DB migration, keeping the existing database migration snapshot '{}'", existing_checkpoint_path.display());
+        Some(existing_checkpoint_path)
+    } else if near_config.config.use_db_migration_snapshot {
         let checkpoint_path = create_db_checkpoint(&store_opener.get_path(), near_config).context(
             "Failed to create a database migration snapshot.\n\
              You can change the location of the snapshot by adjusting `config.json`:\n\
@@ -166,6 +186,14 @@ fn apply_store_migrations_if_exists(
         info!(target: "near", "Migrate DB from version 30 to 31");
         migrate_30_to_31(store_opener, &near_config);
     }
+    if db_version <= 31 {
+        // version 31 => 32: add DBCol::DeferredGC and DBCol::Blacklist
+        // Does not need to do anything since open db with option
+        // `create_missing_column_families`.  Nevertheless need to bump db
+        // version, because db_version 31 binary can't open db_version 32 db.
+        info!(target: "near", "Migrate DB from version 31 to 32");
+        set_store_version(&store_opener.open(), 32);
+    }
 
     if cfg!(feature = "nightly") || cfg!(feature = "nightly_protocol") {
         let store = store_opener.open();
@@ -228,11 +256,209 @@ fn init_and_migrate_store(home_dir: &Path, near_config: &NearConfig) -> anyhow::
     Ok(store)
 }
 
+/// Applies all migrations the existing database needs.
+pub fn migrate_store(home_dir: &Path, near_config: &NearConfig) -> anyhow::Result<()> {
+    let opener = Store::opener(home_dir, &near_config.config.store);
+    anyhow::ensure!(
+        apply_store_migrations_if_exists(&opener, near_config)?,
+        "{}: storage doesn’t exist",
+        opener.get_path().display()
+    );
+    Ok(())
+}
+
+/// Reports which migrations the database needs without modifying it.
+///
+/// If the first pending migration is batched, it's run in dry-run mode so that
+/// the number of records it would rewrite is reported.  Later migrations work
+/// on the output of the earlier ones, which isn't written in a dry run, so
+/// they are only listed.
+pub fn dry_run_store_migrations(home_dir: &Path, near_config: &NearConfig) -> anyhow::Result<()> {
+    let opener = Store::opener(home_dir, &near_config.config.store).mode(Mode::ReadOnly);
+    let db_version = match opener.get_version_if_exists()? {
+        None => anyhow::bail!("{}: storage doesn’t exist", opener.get_path().display()),
+        Some(db_version) => db_version,
+    };
+    let store = opener.open();
+    if let Some(progress) = get_migration_progress(&store)? {
+        info!(target: "near", ?progress, "Found an interrupted DB migration");
+    }
+    if db_version >= near_primitives::version::DB_VERSION {
+        info!(target: "near", db_version, "DB is up to date, no migrations are needed");
+        return Ok(());
+    }
+    for version in db_version..near_primitives::version::DB_VERSION {
+        if version != db_version {
+            info!(target: "near", "Migrate DB from version {} to {}: can't be estimated before the previous migrations are applied", version, version + 1);
+            continue;
+        }
+        match version {
+            28 => {
+                let stats = run_batched_migration(&store, &Migration28To29, true)?;
+                info!(target: "near", "Migrate DB from version 28 to 29: {:?}", stats);
+            }
+            29 => {
+                let stats = run_batched_migration(&store, &Migration29To30, true)?;
+                info!(target: "near", "Migrate DB from version 29 to 30: {:?}", stats);
+            }
+            30 => {
+                let migration = Migration30To31::new(&store, near_config);
+                let stats = run_batched_migration(&store, &migration, true)?;
+                info!(target: "near", "Migrate DB from version 30 to 31: {:?}", stats);
+            }
+            _ => {
+                info!(target: "near", "Migrate DB from version {} to {}: not a batched migration, dry run is not supported", version, version + 1);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Creates a backup of the database in `backup_dir`.
+///
+/// A running node holds the database lock.  In that case the node is asked to
+/// create the checkpoint through the `admin_backup_checkpoint` JSON RPC method,
+/// which needs `rpc.enable_admin_rpc` in config.json, and the manifest is then
+/// computed from the checkpoint here.
+pub fn backup_store(
+    home_dir: &Path,
+    near_config: &NearConfig,
+    backup_dir: &Path,
+) -> anyhow::Result<BackupManifest> {
+    let opener = Store::opener(home_dir, &near_config.config.store);
+    anyhow::ensure!(
+        opener.check_if_exists(),
+        "{}: storage doesn’t exist",
+        opener.get_path().display()
+    );
+    match opener.try_open() {
+        Ok(store) => {
+            Ok(near_store::backup::create_backup(&store, &near_config.config.store, backup_dir)?)
+        }
+        Err(err) => {
+            info!(target: "near", %err, "Failed to open the database, requesting a checkpoint from the running node");
+            request_backup_checkpoint(near_config, backup_dir).with_context(|| {
+                format!(
+                    "failed to open {} ({}) and to request a checkpoint from the running node; \
+                     if the node is running, make sure `rpc.enable_admin_rpc` is set in config.json",
+                    opener.get_path().display(),
+                    err
+                )
+            })?;
+            Ok(near_store::backup::write_backup_manifest(backup_dir, &near_config.config.store)?)
+        }
+    }
+}
+
+/// Asks the node running with `near_config` to create the checkpoint of
+/// a backup in `backup_dir`.
+#[cfg(feature = "json_rpc")]
+fn request_backup_checkpoint(near_config: &NearConfig, backup_dir: &Path) -> anyhow::Result<()> {
+    let rpc_addr = near_config.rpc_addr().context("JSON RPC is disabled in config.json")?;
+    let server_addr = format!("http://{}", rpc_addr);
+    // The node may run in a different working directory.
+    let backup_dir = std::env::current_dir()?.join(backup_dir);
+    let request = near_jsonrpc_primitives::types::backup::RpcBackupCheckpointRequest { backup_dir };
+    actix::System::new()
+        .block_on(async move {
+            near_jsonrpc::client::new_client(&server_addr).admin_backup_checkpoint(request).await
+        })
+        .map_err(|err| anyhow::anyhow!("{}", err))
+}
+
+#[cfg(not(feature = "json_rpc"))]
+fn request_backup_checkpoint(_near_config: &NearConfig, _backup_dir: &Path) -> anyhow::Result<()> {
+    anyhow::bail!("neard was built without JSON RPC support")
+}
+
+/// Verifies the backup in `backup_dir`.
+///
+/// Checks that the backup matches its manifest and runs the store validator
+/// Head-Tail and refcount checks on it.  The backup isn't modified.
+pub fn verify_backup(
+    home_dir: &Path,
+    near_config: &NearConfig,
+    backup_dir: &Path,
+) -> anyhow::Result<()> {
+    let manifest = BackupManifest::read(backup_dir)
+        .with_context(|| format!("failed to read manifest of {}", backup_dir.display()))?;
+    anyhow::ensure!(
+        manifest.db_version == near_primitives::version::DB_VERSION,
+        "backup DB version {} doesn’t match version {} of this neard",
+        manifest.db_version,
+        near_primitives::version::DB_VERSION
+    );
+    let store = near_store::backup::open_backup(backup_dir, &near_config.config.store)?;
+    let differences = manifest.differences(&BackupManifest::compute(&store)?);
+    for difference in &differences {
+        error!(target: "near", "Backup doesn’t match its manifest: {}", difference);
+    }
+
+    let runtime = Arc::new(NightshadeRuntime::from_config(home_dir, store.clone(), near_config));
+    let mut store_validator = StoreValidator::new(
+        None,
+        near_config.genesis.config.clone(),
+        runtime,
+        store,
+        near_config.client_config.archive,
+    );
+    store_validator.validate_refcounts();
+    for error in &store_validator.errors {
+        error!(target: "near", "{}: {}: {}", error.col, error.key, error.err);
+    }
+
+    anyhow::ensure!(
+        differences.is_empty() && !store_validator.is_failed(),
+        "backup verification failed: {} manifest mismatches, {} store validator errors",
+        differences.len(),
+        store_validator.num_failed()
+    );
+    info!(target: "near", head_height = manifest.head_height, tests = store_validator.tests_done(), "Backup verified");
+    Ok(())
+}
+
+/// Creates a backup of the database every `backup_config.period` and whenever
+/// requested through the returned sender.  Has to be called from within an
+/// actix system.
+fn spawn_periodic_backups(
+    store: Store,
+    store_config: StoreConfig,
+    backup_config: BackupConfig,
+) -> mpsc::UnboundedSender<()> {
+    let (requests_tx, mut requests_rx) = mpsc::unbounded();
+    // The task keeps a sender so that the channel is never closed and waiting
+    // for a request doesn't return before one arrives.
+    let task_requests_tx = requests_tx.clone();
+    actix::spawn(async move {
+        let _requests_tx = task_requests_tx;
+        loop {
+            let sleep = Box::pin(actix_rt::time::sleep(backup_config.period));
+            future::select(sleep, requests_rx.next()).await;
+            let store = store.clone();
+            let store_config = store_config.clone();
+            let backup_config = backup_config.clone();
+            let result = actix_rt::task::spawn_blocking(move || {
+                near_store::backup::create_periodic_backup(&store, &store_config, &backup_config)
+            })
+            .await;
+            match result {
+                Ok(Ok(_)) => {}
+                Ok(Err(err)) => error!(target: "near", ?err, "Failed to back up the database"),
+                Err(err) => error!(target: "near", ?err, "Database backup task failed"),
+            }
+        }
+    });
+    requests_tx
+}
+
 pub struct NearNode {
     pub client: Addr<ClientActor>,
     pub view_client: Addr<ViewClientActor>,
     pub arbiters: Vec<ArbiterHandle>,
     pub rpc_servers: Vec<(&'static str, actix_web::dev::ServerHandle)>,
+    /// Sending to it makes the node create a backup of the database right
+    /// away.  `None` if backups aren't configured with `store.backup`.
+    pub backup_requests: Option<mpsc::UnboundedSender<()>>,
 }
 
 pub fn start_with_config(home_dir: &Path, config: NearConfig) -> anyhow::Result<NearNode> {
@@ -249,6 +475,18 @@ pub fn start_with_config_and_synchronization(
     let store = init_and_migrate_store(home_dir, &config)?;
 
     let runtime = Arc::new(NightshadeRuntime::from_config(home_dir, store.clone(), &config));
+    // Stale compiled contracts are removed in the background so that they don't delay the start.
+    std::thread::spawn({
+        let runtime = runtime.clone();
+        move || {
+            if let Err(err) = runtime.remove_stale_compiled_contracts() {
+                error!(target: "near", ?err, "Failed to remove stale compiled contracts");
+            }
+        }
+    });
+    let backup_requests = config.config.store.backup.clone().map(|backup_config| {
+        spawn_periodic_backups(store.clone(), config.config.store.clone(), backup_config)
+    });
 
     let telemetry = TelemetryActor::new(config.telemetry_config.clone()).start();
     let chain_genesis = ChainGenesis::new(&config.genesis);
@@ -309,6 +547,7 @@ pub fn start_with_config_and_synchronization(
             config.genesis.config.clone(),
             client_actor.clone(),
             view_client.clone(),
+            Some(network_actor.clone()),
         ));
     }
 
@@ -338,6 +577,7 @@ pub fn start_with_config_and_synchronization(
         view_client,
         rpc_servers,
         arbiters: vec![client_arbiter_handle, arbiter.handle()],
+        backup_requests,
     })
 }
 
@@ -1,40 +1,100 @@
+use std::io;
+
+use borsh::BorshDeserialize;
+use tracing::info;
+
 use near_chain::{ChainStore, ChainStoreAccess};
+use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::ReceiptResult;
 use near_primitives::runtime::migration_data::MigrationData;
-use near_primitives::types::Gas;
+use near_primitives::types::{BlockHeight, Gas};
 use near_primitives::utils::index_to_bytes;
-use near_store::migrations::{set_store_version, BatchedStoreUpdate};
-use near_store::DBCol;
+use near_primitives::version::DbVersion;
+use near_store::migrations::{run_batched_migration, BatchedMigration, BatchedStoreUpdate};
+use near_store::{DBCol, Store};
 
-/// Fix an issue with block ordinal (#5761)
+/// Fixes the block ordinal to block hash mapping of mainnet archival nodes (#5761).
+///
+/// Goes through the canonical blocks from `FIRST_INCONSISTENT_HEIGHT` to the head and rewrites
+/// the block hash of the ordinal of each one where it doesn't match.  On other nodes there's
+/// nothing to fix.
 // This migration takes at least 3 hours to complete on mainnet
-pub fn migrate_30_to_31(store_opener: &near_store::StoreOpener, near_config: &crate::NearConfig) {
-    let store = store_opener.open();
-    if near_config.client_config.archive && &near_config.genesis.config.chain_id == "mainnet" {
+pub struct Migration30To31 {
+    chain_store: ChainStore,
+    /// Height of the head if the mapping needs to be fixed.
+    head_height: Option<BlockHeight>,
+}
+
+impl Migration30To31 {
+    /// We manually checked mainnet archival data and the first block where the discrepancy
+    /// happened is `47443088`.
+    const FIRST_INCONSISTENT_HEIGHT: BlockHeight = 47443088;
+
+    pub fn new(store: &Store, near_config: &crate::NearConfig) -> Self {
         let genesis_height = near_config.genesis.config.genesis_height;
         let chain_store = ChainStore::new(store.clone(), genesis_height, false);
-        let head = chain_store.head().unwrap();
-        let mut store_update = BatchedStoreUpdate::new(&store, 10_000_000);
-        let mut count = 0;
-        // we manually checked mainnet archival data and the first block where the discrepancy happened is `47443088`.
-        for height in 47443088..=head.height {
-            if let Ok(block_hash) = chain_store.get_block_hash_by_height(height) {
-                let block_ordinal = chain_store.get_block_merkle_tree(&block_hash).unwrap().size();
-                let block_hash_from_block_ordinal =
-                    chain_store.get_block_hash_from_ordinal(block_ordinal).unwrap();
-                if block_hash_from_block_ordinal != block_hash {
-                    println!("Inconsistency in block ordinal to block hash mapping found at block height {}", height);
-                    count += 1;
-                    store_update
-                        .set_ser(DBCol::BlockOrdinal, &index_to_bytes(block_ordinal), &block_hash)
-                        .expect("BorshSerialize should not fail");
-                }
-            }
+        let head_height = if near_config.client_config.archive
+            && near_config.genesis.config.chain_id == "mainnet"
+        {
+            Some(chain_store.head().expect("Failed to read the head").height)
+        } else {
+            None
+        };
+        Self { chain_store, head_height }
+    }
+}
+
+impl BatchedMigration for Migration30To31 {
+    fn source_version(&self) -> DbVersion {
+        30
+    }
+
+    fn columns(&self) -> Vec<DBCol> {
+        match self.head_height {
+            Some(_) => vec![DBCol::BlockHeight],
+            None => vec![],
+        }
+    }
+
+    fn migrate_record(
+        &self,
+        _col: DBCol,
+        key: &[u8],
+        value: &[u8],
+        store_update: &mut BatchedStoreUpdate,
+    ) -> io::Result<()> {
+        let to_io_error =
+            |err: near_chain::Error| io::Error::new(io::ErrorKind::Other, err.to_string());
+        let height = BlockHeight::from_le_bytes(key.try_into().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid block height key {:?}", key),
+            )
+        })?);
+        if height < Self::FIRST_INCONSISTENT_HEIGHT || Some(height) > self.head_height {
+            return Ok(());
         }
-        println!("total inconsistency count: {}", count);
-        store_update.finish().expect("Failed to migrate");
+        let block_hash = CryptoHash::try_from_slice(value)?;
+        let block_ordinal =
+            self.chain_store.get_block_merkle_tree(&block_hash).map_err(to_io_error)?.size();
+        let block_hash_from_block_ordinal =
+            self.chain_store.get_block_hash_from_ordinal(block_ordinal).map_err(to_io_error)?;
+        if block_hash_from_block_ordinal != block_hash {
+            info!(target: "near", height, "Inconsistency in block ordinal to block hash mapping found");
+            store_update.set_ser(
+                DBCol::BlockOrdinal,
+                &index_to_bytes(block_ordinal),
+                &block_hash,
+            )?;
+        }
+        Ok(())
     }
-    set_store_version(&store, 31);
+}
+
+pub fn migrate_30_to_31(store_opener: &near_store::StoreOpener, near_config: &crate::NearConfig) {
+    let store = store_opener.open();
+    run_batched_migration(&store, &Migration30To31::new(&store, near_config), false)
+        .expect("Failed to migrate");
 }
 
 /// In test runs reads and writes here used 442 TGas, but in test on live net migration take
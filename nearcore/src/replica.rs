@@ -0,0 +1,134 @@
+//! Read-only replica of a node running on the same machine.
+//!
+//! A replica opens the database of the primary node as a RocksDB secondary
+//! instance and periodically catches up with it.  It serves view client
+//! queries and JSON-RPC without running consensus or networking, which makes
+//! it a cheap way of scaling RPC.
+//!
+//! The replica has no client, so JSON-RPC methods handled by the client fail.
+//! Besides sending transactions, these include `status`, `health` and their
+//! `/status` and `/health` HTTP endpoints, which therefore can't be used to
+//! check whether a replica is up.  Use a view method, e.g. `block` with
+//! `"finality": "final"`, instead.
+use crate::{NearConfig, NightshadeRuntime};
+use actix::Addr;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use near_chain::ChainGenesis;
+use near_client::{start_view_client, ViewClientActor};
+use near_network::types::{MsgRecipient, PeerManagerMessageRequest, PeerManagerMessageResponse};
+use near_network_primitives::types::SetChainInfo;
+use near_store::Store;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+pub struct NearReplica {
+    pub view_client: Addr<ViewClientActor>,
+    pub rpc_servers: Vec<(&'static str, actix_web::dev::ServerHandle)>,
+}
+
+/// Network adapter of a replica, which has no network.  Requests to peers,
+/// e.g. for transaction statuses the replica doesn't know about, fail.
+struct NoNetwork;
+
+impl MsgRecipient<PeerManagerMessageRequest> for NoNetwork {
+    fn send(
+        &self,
+        _msg: PeerManagerMessageRequest,
+    ) -> BoxFuture<'static, Result<PeerManagerMessageResponse, actix::MailboxError>> {
+        futures::future::err(actix::MailboxError::Closed).boxed()
+    }
+
+    fn do_send(&self, _msg: PeerManagerMessageRequest) {}
+}
+
+impl MsgRecipient<SetChainInfo> for NoNetwork {
+    fn send(&self, _msg: SetChainInfo) -> BoxFuture<'static, Result<(), actix::MailboxError>> {
+        futures::future::err(actix::MailboxError::Closed).boxed()
+    }
+
+    fn do_send(&self, _msg: SetChainInfo) {}
+}
+
+/// Opens the database of the primary node as a secondary instance keeping its
+/// logs in `secondary_path`.
+fn open_secondary_store(
+    home_dir: &Path,
+    config: &NearConfig,
+    secondary_path: &Path,
+) -> anyhow::Result<Store> {
+    let opener = Store::opener(home_dir, &config.config.store);
+    match opener.get_version_if_exists()? {
+        None => anyhow::bail!("{}: storage doesn’t exist", opener.get_path().display()),
+        Some(near_primitives::version::DB_VERSION) => {}
+        Some(db_version) => anyhow::bail!(
+            "DB version {db_version} doesn’t match version {} of this neard; \
+             the primary node has to run the same version of neard",
+            near_primitives::version::DB_VERSION
+        ),
+    }
+    Ok(opener.open_secondary(secondary_path)?)
+}
+
+/// Starts a replica following the node whose home directory is `home_dir`.
+///
+/// The replica keeps catching up with the primary every `catch_up_interval`.
+/// Has to be called from within an actix system.  See the module documentation
+/// for the JSON-RPC methods a replica doesn't support.
+pub fn start_replica_with_config(
+    home_dir: &Path,
+    config: NearConfig,
+    secondary_path: &Path,
+    catch_up_interval: Duration,
+) -> anyhow::Result<NearReplica> {
+    let store = open_secondary_store(home_dir, &config, secondary_path)?;
+    // The task, and with it the store, is dropped when the actix system stops.
+    actix::spawn({
+        let store = store.clone();
+        async move {
+            let mut interval = actix_rt::time::interval(catch_up_interval);
+            loop {
+                interval.tick().await;
+                if let Err(err) = store.try_catch_up_with_primary() {
+                    error!(target: "near", ?err, "Failed to catch up with the primary database");
+                }
+            }
+        }
+    });
+
+    // The secondary instance can't be written to, so contracts compiled by view
+    // calls are only kept in memory.
+    let runtime = Arc::new(NightshadeRuntime::read_only_from_config(home_dir, store, &config));
+    let chain_genesis = ChainGenesis::new(&config.genesis);
+    let view_client = start_view_client(
+        None,
+        chain_genesis,
+        runtime,
+        Arc::new(NoNetwork),
+        config.client_config.clone(),
+        near_client::adversarial::Controls::new(config.client_config.archive),
+    );
+
+    #[allow(unused_mut)]
+    let mut rpc_servers = Vec::new();
+    #[cfg(feature = "json_rpc")]
+    if let Some(rpc_config) = config.rpc_config {
+        // The context is dropped right away so all messages sent to the client
+        // fail with `MailboxError::Closed` and the methods handled by it, such
+        // as `status` and `health`, return an error.
+        let client_actor =
+            actix::AsyncContext::address(&actix::Context::<near_client::ClientActor>::new());
+        rpc_servers.extend(near_jsonrpc::start_http(
+            rpc_config,
+            config.genesis.config.clone(),
+            client_actor,
+            view_client.clone(),
+            None,
+        ));
+    }
+
+    info!(target: "near", path = %secondary_path.display(), "Started read-only replica");
+    Ok(NearReplica { view_client, rpc_servers })
+}
@@ -0,0 +1,127 @@
+//! Precompilation of contracts ahead of protocol upgrades which change the VM or its
+//! configuration, so that the first call of each contract after the upgrade doesn't pay for its
+//! compilation while the chunk is being applied.
+
+use near_primitives::contract::ContractCode;
+use near_primitives::errors::StorageError;
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::trie_key::trie_key_parsers::get_raw_prefix_for_contract_codes;
+use near_primitives::types::{ShardId, StateRoot};
+use near_primitives::version::ProtocolVersion;
+use near_primitives::views::ContractPrecompilationView;
+use near_store::{ShardTries, Store, StoreCompiledContractCache};
+use near_vm_runner::{precompile_contract, ContractPrecompilatonResult};
+use node_runtime::config::RuntimeConfig;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info};
+
+/// Runs the precompilation in a background thread and keeps track of its progress.
+#[derive(Default)]
+pub(crate) struct ContractPrecompiler {
+    progress: Arc<Mutex<Option<ContractPrecompilationView>>>,
+}
+
+impl ContractPrecompiler {
+    pub(crate) fn progress(&self) -> Option<ContractPrecompilationView> {
+        self.progress.lock().unwrap().clone()
+    }
+
+    /// Starts precompiling the contracts of the shards with given state roots for the protocol
+    /// version, unless it has already been started for this protocol version.
+    pub(crate) fn start(
+        &self,
+        tries: ShardTries,
+        store: Store,
+        config: Arc<RuntimeConfig>,
+        protocol_version: ProtocolVersion,
+        state_roots: Vec<(ShardUId, StateRoot)>,
+    ) {
+        {
+            let mut progress = self.progress.lock().unwrap();
+            if progress.as_ref().map(|progress| progress.protocol_version) == Some(protocol_version)
+            {
+                return;
+            }
+            *progress = Some(ContractPrecompilationView {
+                protocol_version,
+                shards: state_roots
+                    .iter()
+                    .map(|(shard_uid, _)| shard_uid.shard_id as ShardId)
+                    .collect(),
+                num_shards_done: 0,
+                num_contracts_compiled: 0,
+                num_contracts_already_cached: 0,
+                num_contracts_failed: 0,
+                done: false,
+            });
+        }
+        info!(target: "runtime", protocol_version, num_shards = state_roots.len(), "Precompiling contracts for the next protocol version");
+        let progress = self.progress.clone();
+        std::thread::spawn(move || {
+            let cache = StoreCompiledContractCache::new(&store);
+            for (shard_uid, state_root) in state_roots {
+                if let Err(err) = precompile_shard(
+                    &tries,
+                    shard_uid,
+                    &state_root,
+                    &cache,
+                    &config,
+                    protocol_version,
+                    &progress,
+                ) {
+                    error!(target: "runtime", ?shard_uid, ?err, "Failed to precompile contracts");
+                }
+                update_progress(&progress, protocol_version, |progress| {
+                    progress.num_shards_done += 1
+                });
+            }
+            update_progress(&progress, protocol_version, |progress| progress.done = true);
+            info!(target: "runtime", protocol_version, "Finished precompiling contracts");
+        });
+    }
+}
+
+/// Updates the progress unless a precompilation for another protocol version has been started
+/// in the meantime.
+fn update_progress(
+    progress: &Mutex<Option<ContractPrecompilationView>>,
+    protocol_version: ProtocolVersion,
+    update: impl FnOnce(&mut ContractPrecompilationView),
+) {
+    if let Some(progress) = progress.lock().unwrap().as_mut() {
+        if progress.protocol_version == protocol_version {
+            update(progress);
+        }
+    }
+}
+
+fn precompile_shard(
+    tries: &ShardTries,
+    shard_uid: ShardUId,
+    state_root: &StateRoot,
+    cache: &StoreCompiledContractCache,
+    config: &RuntimeConfig,
+    protocol_version: ProtocolVersion,
+    progress: &Mutex<Option<ContractPrecompilationView>>,
+) -> Result<(), StorageError> {
+    let prefix = get_raw_prefix_for_contract_codes();
+    let trie = tries.get_view_trie_for_shard(shard_uid);
+    let mut iter = trie.iter(state_root)?;
+    iter.seek(&prefix)?;
+    for item in iter {
+        let (key, code) = item?;
+        if !key.starts_with(&prefix) {
+            break;
+        }
+        let code = ContractCode::new(code, None);
+        let result = precompile_contract(&code, &config.wasm_config, protocol_version, Some(cache));
+        update_progress(progress, protocol_version, |progress| match result {
+            Ok(Ok(ContractPrecompilatonResult::ContractAlreadyInCache)) => {
+                progress.num_contracts_already_cached += 1
+            }
+            Ok(Ok(_)) => progress.num_contracts_compiled += 1,
+            Ok(Err(_)) | Err(_) => progress.num_contracts_failed += 1,
+        });
+    }
+    Ok(())
+}
@@ -80,6 +80,9 @@ impl QueryError {
             node_runtime::state_viewer::errors::ViewStateError::AccountStateTooLarge {
                 requested_account_id,
             } => Self::TooLargeContractState { requested_account_id, block_height, block_hash },
+            node_runtime::state_viewer::errors::ViewStateError::InvalidLimit => {
+                Self::InvalidStateLimit { block_height, block_hash }
+            }
         }
     }
 
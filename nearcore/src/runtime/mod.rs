@@ -4,6 +4,7 @@ use crate::shard_tracker::{ShardTracker, TrackedConfig};
 use crate::NearConfig;
 use borsh::ser::BorshSerialize;
 use borsh::BorshDeserialize;
+use contract_precompilation::ContractPrecompiler;
 use errors::FromStateViewerErrors;
 use near_chain::types::{
     ApplySplitStateResult, ApplyTransactionResult, BlockHeaderInfo, ValidatorInfoIdentifier,
@@ -17,8 +18,9 @@ use near_crypto::{PublicKey, Signature};
 use near_epoch_manager::EpochManager;
 use near_pool::types::PoolIterator;
 use near_primitives::account::{AccessKey, Account};
-use near_primitives::block::{Approval, ApprovalInner};
+use near_primitives::block::{Approval, ApprovalInner, Tip};
 use near_primitives::challenge::ChallengesResult;
+use near_primitives::config::VMConfig;
 use near_primitives::contract::ContractCode;
 use near_primitives::epoch_manager::block_info::BlockInfo;
 use near_primitives::epoch_manager::epoch_info::EpochInfo;
@@ -35,7 +37,9 @@ use near_primitives::shard_layout::{
 use near_primitives::sharding::ChunkHash;
 use near_primitives::state_part::PartId;
 use near_primitives::state_record::{state_record_to_account_id, StateRecord};
-use near_primitives::syncing::{get_num_state_parts, STATE_PART_MEMORY_LIMIT};
+use near_primitives::syncing::{
+    compute_epoch_sync_data_hash, get_num_state_parts, STATE_PART_MEMORY_LIMIT,
+};
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::validator_stake::{ValidatorStake, ValidatorStakeIter};
 use near_primitives::types::{
@@ -43,18 +47,18 @@ use near_primitives::types::{
     EpochInfoProvider, Gas, MerkleHash, NumShards, ShardId, StateChangeCause,
     StateChangesForSplitStates, StateRoot, StateRootNode,
 };
-use near_primitives::version::ProtocolVersion;
+use near_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
 use near_primitives::views::{
-    AccessKeyInfoView, CallResult, EpochValidatorInfo, QueryRequest, QueryResponse,
-    QueryResponseKind, ViewApplyState, ViewStateResult,
+    AccessKeyInfoView, CallResult, ContractPrecompilationView, EpochValidatorInfo, QueryRequest,
+    QueryResponse, QueryResponseKind, StorageUsageView, ViewApplyState, ViewStateResult,
 };
 use near_store::split_state::get_delayed_receipts;
 use near_store::{
     get_genesis_hash, get_genesis_state_roots, set_genesis_hash, set_genesis_state_roots,
     ApplyStatePartResult, DBCol, PartialStorage, ShardTries, Store, StoreCompiledContractCache,
-    StoreUpdate, Trie, TrieCacheFactory, WrappedTrieChanges,
+    StoreUpdate, Trie, TrieCacheFactory, WrappedTrieChanges, HEAD_KEY,
 };
-use near_vm_runner::precompile_contract;
+use near_vm_runner::{is_compiled_code_reusable, is_stale_cache_entry, precompile_contract};
 use node_runtime::adapter::ViewRuntimeAdapter;
 use node_runtime::config::RuntimeConfig;
 use node_runtime::near_primitives::shard_layout::ShardLayoutError;
@@ -71,6 +75,7 @@ use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::time::Instant;
 use tracing::{debug, error, info, warn};
 
+mod contract_precompilation;
 pub mod errors;
 
 const POISONED_LOCK_ERR: &str = "The lock was poisoned.";
@@ -125,6 +130,11 @@ impl EpochInfoProvider for SafeEpochManager {
         let epoch_manager = self.read();
         epoch_manager.minimum_stake(prev_block_hash)
     }
+
+    fn shard_layout(&self, epoch_id: &EpochId) -> Result<ShardLayout, EpochError> {
+        let epoch_manager = self.read();
+        Ok(epoch_manager.get_shard_layout(epoch_id)?.clone())
+    }
 }
 
 /// Defines Nightshade state transition and validator rotation.
@@ -142,6 +152,10 @@ pub struct NightshadeRuntime {
     genesis_state_roots: Vec<StateRoot>,
     migration_data: Arc<MigrationData>,
     gc_num_epochs_to_keep: u64,
+    contract_precompiler: ContractPrecompiler,
+    /// Whether the store can't be written to, in which case compiled contracts
+    /// aren't cached in it.
+    read_only: bool,
 }
 
 impl NightshadeRuntime {
@@ -159,6 +173,13 @@ impl NightshadeRuntime {
         )
     }
 
+    /// Like [`Self::from_config`] but for a store which can't be written to,
+    /// such as the secondary instance of a read-only replica.  The runtime
+    /// can then only be used for view calls.
+    pub fn read_only_from_config(home_dir: &Path, store: Store, config: &NearConfig) -> Self {
+        Self { read_only: true, ..Self::from_config(home_dir, store, config) }
+    }
+
     fn new(
         home_dir: &Path,
         store: Store,
@@ -210,6 +231,8 @@ impl NightshadeRuntime {
             genesis_state_roots: state_roots,
             migration_data: Arc::new(load_migration_data(&genesis.config.chain_id)),
             gc_num_epochs_to_keep: gc_num_epochs_to_keep.max(MIN_GC_NUM_EPOCHS_TO_KEEP),
+            contract_precompiler: ContractPrecompiler::default(),
+            read_only: false,
         }
     }
 
@@ -248,6 +271,44 @@ impl NightshadeRuntime {
         epoch_manager.get_epoch_id(hash).map_err(Error::from)
     }
 
+    fn compiled_contract_cache(&self) -> Box<dyn CompiledContractCache> {
+        if self.read_only {
+            Box::new(StoreCompiledContractCache::read_only(&self.store))
+        } else {
+            Box::new(StoreCompiledContractCache::new(&self.store))
+        }
+    }
+
+    /// Removes compiled contracts which can't be used anymore, because they were compiled by
+    /// another version of the VM or for a `VMConfig` which none of the protocol versions from
+    /// the one of the head up to the next scheduled one uses. The next scheduled version is the
+    /// one of the next epoch or the latest supported one, whichever is higher. Returns the
+    /// number of removed contracts.
+    pub fn remove_stale_compiled_contracts(&self) -> Result<usize, Error> {
+        let (head_protocol_version, next_protocol_version) = match self
+            .store
+            .get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?
+        {
+            Some(head) => (
+                self.get_epoch_protocol_version(&head.epoch_id)?,
+                self.get_epoch_protocol_version(&head.next_epoch_id)?,
+            ),
+            None => (self.genesis_config.protocol_version, self.genesis_config.protocol_version),
+        };
+        let max_protocol_version = std::cmp::max(next_protocol_version, PROTOCOL_VERSION);
+        let mut configs: Vec<&VMConfig> = vec![];
+        for protocol_version in head_protocol_version..=max_protocol_version {
+            let config = &self.runtime_config_store.get_config(protocol_version).wasm_config;
+            if !configs.iter().any(|known| std::ptr::eq(*known, config)) {
+                configs.push(config);
+            }
+        }
+        let removed = StoreCompiledContractCache::new(&self.store)
+            .remove_stale(|key, value| is_stale_cache_entry(key, value, &configs))?;
+        info!(target: "runtime", removed, "Removed stale compiled contracts");
+        Ok(removed)
+    }
+
     /// Create store of runtime configs for the given chain id.
     ///
     /// For mainnet and other chains except testnet we don't need to override runtime config for
@@ -542,7 +603,7 @@ impl NightshadeRuntime {
             random_seed,
             current_protocol_version,
             config: self.runtime_config_store.get_config(current_protocol_version).clone(),
-            cache: Some(Box::new(StoreCompiledContractCache::new(&self.store))),
+            cache: Some(self.compiled_contract_cache()),
             is_new_chunk,
             migration_data: Arc::clone(&self.migration_data),
             migration_flags: MigrationFlags {
@@ -633,8 +694,7 @@ impl NightshadeRuntime {
         .entered();
         let protocol_version = self.get_epoch_protocol_version(epoch_id)?;
         let runtime_config = self.runtime_config_store.get_config(protocol_version);
-        let compiled_contract_cache: Option<Box<dyn CompiledContractCache>> =
-            Some(Box::new(StoreCompiledContractCache::new(&self.store)));
+        let compiled_contract_cache = Some(self.compiled_contract_cache());
         // Execute precompile_contract in parallel but prevent it from using more than half of all
         // threads so that node will still function normally.
         rayon::scope(|scope| {
@@ -757,6 +817,7 @@ impl RuntimeAdapter for NightshadeRuntime {
         state_root: Option<StateRoot>,
         transaction: &SignedTransaction,
         verify_signature: bool,
+        min_block_height: BlockHeight,
         epoch_id: &EpochId,
         current_protocol_version: ProtocolVersion,
     ) -> Result<Option<InvalidTxError>, Error> {
@@ -766,6 +827,7 @@ impl RuntimeAdapter for NightshadeRuntime {
             let shard_uid =
                 self.account_id_to_shard_uid(&transaction.transaction.signer_id, epoch_id)?;
             let mut state_update = self.tries.new_trie_update(shard_uid, state_root);
+            let epoch_height = self.epoch_manager.read().get_epoch_info(epoch_id)?.epoch_height();
 
             match verify_and_charge_transaction(
                 runtime_config,
@@ -776,6 +838,8 @@ impl RuntimeAdapter for NightshadeRuntime {
                 // here we do not know which block the transaction will be included
                 // and therefore skip the check on the nonce upper bound.
                 None,
+                min_block_height,
+                epoch_height,
                 current_protocol_version,
             ) {
                 Ok(_) => Ok(None),
@@ -820,6 +884,7 @@ impl RuntimeAdapter for NightshadeRuntime {
     ) -> Result<Vec<SignedTransaction>, Error> {
         let shard_uid = self.get_shard_uid_from_epoch_id(shard_id, epoch_id)?;
         let mut state_update = self.tries.new_trie_update(shard_uid, state_root);
+        let epoch_height = self.epoch_manager.read().get_epoch_info(epoch_id)?.epoch_height();
 
         // Total amount of gas burnt for converting transactions towards receipts.
         let mut total_gas_burnt = 0;
@@ -856,6 +921,8 @@ impl RuntimeAdapter for NightshadeRuntime {
                             &tx,
                             false,
                             Some(next_block_height),
+                            next_block_height,
+                            epoch_height,
                             current_protocol_version,
                         ) {
                             Ok(verification_result) => {
@@ -1281,7 +1348,6 @@ impl RuntimeAdapter for NightshadeRuntime {
         Ok(epoch_manager.get_epoch_info(epoch_id)?.minted_amount())
     }
 
-    // TODO #3488 this likely to be updated
     fn get_epoch_sync_data_hash(
         &self,
         prev_epoch_last_block_hash: &CryptoHash,
@@ -1296,16 +1362,16 @@ impl RuntimeAdapter for NightshadeRuntime {
             cur_epoch_info,
             next_epoch_info,
         ) = self.get_epoch_sync_data(prev_epoch_last_block_hash, epoch_id, next_epoch_id)?;
-        let mut data = prev_epoch_first_block_info.try_to_vec().unwrap();
-        data.extend(prev_epoch_prev_last_block_info.try_to_vec().unwrap());
-        data.extend(prev_epoch_last_block_info.try_to_vec().unwrap());
-        data.extend(prev_epoch_info.try_to_vec().unwrap());
-        data.extend(cur_epoch_info.try_to_vec().unwrap());
-        data.extend(next_epoch_info.try_to_vec().unwrap());
-        Ok(hash(data.as_slice()))
+        Ok(compute_epoch_sync_data_hash(
+            &prev_epoch_first_block_info,
+            &prev_epoch_prev_last_block_info,
+            &prev_epoch_last_block_info,
+            &prev_epoch_info,
+            &cur_epoch_info,
+            &next_epoch_info,
+        ))
     }
 
-    // TODO #3488 this likely to be updated
     fn get_epoch_sync_data(
         &self,
         prev_epoch_last_block_hash: &CryptoHash,
@@ -1576,9 +1642,17 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
-            QueryRequest::ViewState { account_id, prefix } => {
+            QueryRequest::ViewState { account_id, prefix, start_key, limit, include_proof } => {
                 let view_state_result = self
-                    .view_state(&shard_uid, *state_root, account_id, prefix.as_ref())
+                    .view_state(
+                        &shard_uid,
+                        *state_root,
+                        account_id,
+                        prefix.as_ref(),
+                        start_key.as_deref(),
+                        *limit,
+                        *include_proof,
+                    )
                     .map_err(|err| {
                         near_chain::near_chain_primitives::error::QueryError::from_view_state_error(
                             err,
@@ -1592,6 +1666,41 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
+            QueryRequest::ViewStorageUsage { account_id } => {
+                let current_protocol_version = self
+                    .epoch_manager
+                    .read()
+                    .get_epoch_info(epoch_id)
+                    .map_err(|err| {
+                        near_chain::near_chain_primitives::error::QueryError::from_epoch_error(
+                            err,
+                            block_height,
+                            *block_hash,
+                        )
+                    })?
+                    .protocol_version();
+                let storage_usage = self
+                    .view_storage_usage(
+                        &shard_uid,
+                        *state_root,
+                        account_id,
+                        node_runtime::state_viewer::STORAGE_USAGE_PREFIX_LEN,
+                        node_runtime::state_viewer::STORAGE_USAGE_NUM_LARGEST_KEYS,
+                        current_protocol_version,
+                    )
+                    .map_err(|err| {
+                        near_chain::near_chain_primitives::error::QueryError::from_view_state_error(
+                            err,
+                            block_height,
+                            *block_hash,
+                        )
+                    })?;
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::StorageUsage(storage_usage),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
             QueryRequest::ViewAccessKeyList { account_id } => {
                 let access_key_list =
                     self.view_access_keys(&shard_uid, *state_root, account_id).map_err(|err| {
@@ -1601,13 +1710,25 @@ impl RuntimeAdapter for NightshadeRuntime {
                             *block_hash,
                         )
                     })?;
+                let epoch_height = self
+                    .epoch_manager
+                    .read()
+                    .get_epoch_info(epoch_id)
+                    .map_err(|err| {
+                        near_chain::near_chain_primitives::error::QueryError::from_epoch_error(
+                            err,
+                            block_height,
+                            *block_hash,
+                        )
+                    })?
+                    .epoch_height();
                 Ok(QueryResponse {
                     kind: QueryResponseKind::AccessKeyList(
                         access_key_list
                             .into_iter()
-                            .map(|(public_key, access_key)| AccessKeyInfoView {
-                                public_key,
-                                access_key: access_key.into(),
+                            .map(|(public_key, mut access_key)| {
+                                access_key.refill_allowance(epoch_height);
+                                AccessKeyInfoView { public_key, access_key: access_key.into() }
                             })
                             .collect(),
                     ),
@@ -1616,7 +1737,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                 })
             }
             QueryRequest::ViewAccessKey { account_id, public_key } => {
-                let access_key = self
+                let mut access_key = self
                     .view_access_key(&shard_uid, *state_root, account_id, public_key)
                     .map_err(|err| {
                         near_chain::near_chain_primitives::error::QueryError::from_view_access_key_error(
@@ -1625,6 +1746,20 @@ impl RuntimeAdapter for NightshadeRuntime {
                             *block_hash,
                         )
                     })?;
+                // Show the allowance which the next transaction signed with the key would see.
+                let epoch_height = self
+                    .epoch_manager
+                    .read()
+                    .get_epoch_info(epoch_id)
+                    .map_err(|err| {
+                        near_chain::near_chain_primitives::error::QueryError::from_epoch_error(
+                            err,
+                            block_height,
+                            *block_hash,
+                        )
+                    })?
+                    .epoch_height();
+                access_key.refill_allowance(epoch_height);
                 Ok(QueryResponse {
                     kind: QueryResponseKind::AccessKey(access_key.into()),
                     block_height,
@@ -1784,6 +1919,38 @@ impl RuntimeAdapter for NightshadeRuntime {
         Ok(store_update.commit()?)
     }
 
+    fn precompile_contracts_for_next_epoch(
+        &self,
+        epoch_id: &EpochId,
+        next_epoch_id: &EpochId,
+        state_roots: Vec<(ShardUId, StateRoot)>,
+    ) -> Result<(), Error> {
+        let protocol_version = self.get_epoch_protocol_version(epoch_id)?;
+        let next_protocol_version = self.get_epoch_protocol_version(next_epoch_id)?;
+        let config = self.runtime_config_store.get_config(protocol_version);
+        let next_config = self.runtime_config_store.get_config(next_protocol_version);
+        if is_compiled_code_reusable(
+            protocol_version,
+            &config.wasm_config,
+            next_protocol_version,
+            &next_config.wasm_config,
+        ) {
+            return Ok(());
+        }
+        self.contract_precompiler.start(
+            self.tries.clone(),
+            self.store.clone(),
+            next_config.clone(),
+            next_protocol_version,
+            state_roots,
+        );
+        Ok(())
+    }
+
+    fn get_contract_precompilation_progress(&self) -> Option<ContractPrecompilationView> {
+        self.contract_precompiler.progress()
+    }
+
     /// `block_hash` is a block whose `prev_state_root` is `state_root`
     fn get_state_root_node(
         &self,
@@ -1956,7 +2123,7 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
             epoch_height,
             block_timestamp,
             current_protocol_version,
-            cache: Some(Box::new(StoreCompiledContractCache::new(&self.tries.get_store()))),
+            cache: Some(self.compiled_contract_cache()),
         };
         self.trie_viewer.call_function(
             state_update,
@@ -1997,9 +2164,39 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         state_root: MerkleHash,
         account_id: &AccountId,
         prefix: &[u8],
+        start_key: Option<&[u8]>,
+        limit: Option<u64>,
+        include_proof: bool,
     ) -> Result<ViewStateResult, node_runtime::state_viewer::errors::ViewStateError> {
         let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
-        self.trie_viewer.view_state(&state_update, account_id, prefix)
+        self.trie_viewer.view_state(
+            &state_update,
+            account_id,
+            prefix,
+            start_key,
+            limit,
+            include_proof,
+        )
+    }
+
+    fn view_storage_usage(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        prefix_len: usize,
+        num_largest_keys: usize,
+        current_protocol_version: ProtocolVersion,
+    ) -> Result<StorageUsageView, node_runtime::state_viewer::errors::ViewStateError> {
+        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        let config = self.runtime_config_store.get_config(current_protocol_version);
+        self.trie_viewer.view_storage_usage(
+            &state_update,
+            account_id,
+            config,
+            prefix_len,
+            num_largest_keys,
+        )
     }
 }
 
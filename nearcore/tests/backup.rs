@@ -0,0 +1,83 @@
+/// Test creating and verifying database backups.
+use std::path::Path;
+use std::sync::Arc;
+
+use near_chain::{Chain, ChainGenesis, DoomslugThresholdMode};
+use near_chain_configs::Genesis;
+use near_logger_utils::init_test_logger;
+use near_primitives::hash::CryptoHash;
+use near_primitives::version::DB_VERSION;
+use near_store::migrations::set_store_version;
+use near_store::{DBCol, Store};
+use nearcore::config::GenesisExt;
+use nearcore::{backup_store, load_test_config, verify_backup, NearConfig, NightshadeRuntime};
+
+/// Creates a database with the genesis block in `home_dir`.  Returns the store
+/// and the hash of the genesis block.
+fn init_store(home_dir: &Path, near_config: &NearConfig) -> (Store, CryptoHash) {
+    let store = Store::opener(home_dir, &near_config.config.store).open();
+    set_store_version(&store, DB_VERSION);
+    let runtime = Arc::new(NightshadeRuntime::from_config(home_dir, store.clone(), near_config));
+    let chain = Chain::new(
+        runtime,
+        &ChainGenesis::new(&near_config.genesis),
+        DoomslugThresholdMode::TwoThirds,
+        near_config.client_config.archive,
+    )
+    .unwrap();
+    (store, *chain.genesis().hash())
+}
+
+/// Sets the reference count of the genesis block in the database of a backup.
+fn set_genesis_refcount(
+    backup_dir: &Path,
+    near_config: &NearConfig,
+    genesis_hash: &CryptoHash,
+    refcount: u64,
+) {
+    let mut store_config = near_config.config.store.clone();
+    store_config.path = Some(backup_dir.join("data"));
+    let backup = Store::opener(backup_dir, &store_config).open();
+    let mut store_update = backup.store_update();
+    store_update.set_ser(DBCol::BlockRefCount, genesis_hash.as_ref(), &refcount).unwrap();
+    store_update.commit().unwrap();
+}
+
+#[test]
+fn test_backup_and_verify() {
+    init_test_logger();
+    let genesis = Genesis::test(vec!["test0".parse().unwrap()], 1);
+    let near_config = load_test_config("test0", 0, genesis);
+    let home_dir = tempfile::tempdir().unwrap();
+    let backup_dir = home_dir.path().join("backup");
+    let (store, genesis_hash) = init_store(home_dir.path(), &near_config);
+
+    // The database is locked while it's open, as if the node was running, but
+    // there's no node to request the checkpoint from.
+    let err = backup_store(home_dir.path(), &near_config, &backup_dir).unwrap_err();
+    assert!(format!("{err:#}").contains("enable_admin_rpc"), "{err:#}");
+    assert!(!backup_dir.exists());
+    drop(store);
+
+    let manifest = backup_store(home_dir.path(), &near_config, &backup_dir).unwrap();
+    assert_eq!(manifest.db_version, DB_VERSION);
+    assert_eq!(manifest.head_hash, genesis_hash);
+    verify_backup(home_dir.path(), &near_config, &backup_dir).unwrap();
+
+    // A broken refcount doesn't change the number of keys, so the manifest
+    // still matches, but the store validator catches it.
+    set_genesis_refcount(&backup_dir, &near_config, &genesis_hash, 2);
+    assert!(verify_backup(home_dir.path(), &near_config, &backup_dir).is_err());
+    set_genesis_refcount(&backup_dir, &near_config, &genesis_hash, 1);
+    verify_backup(home_dir.path(), &near_config, &backup_dir).unwrap();
+
+    // Data missing from the backup doesn't match the manifest.
+    let mut store_config = near_config.config.store.clone();
+    store_config.path = Some(backup_dir.join("data"));
+    let backup = Store::opener(&backup_dir, &store_config).open();
+    let mut store_update = backup.store_update();
+    store_update.delete(DBCol::BlockRefCount, genesis_hash.as_ref());
+    store_update.commit().unwrap();
+    drop(backup);
+    assert!(verify_backup(home_dir.path(), &near_config, &backup_dir).is_err());
+}
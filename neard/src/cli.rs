@@ -1,6 +1,7 @@
 use crate::log_config_watcher::{LogConfigWatcher, UpdateBehavior};
 use actix::SystemRunner;
 use clap::{Args, Parser};
+use futures::channel::mpsc::UnboundedSender;
 use near_chain_configs::GenesisValidationMode;
 use near_o11y::{
     default_subscriber, BuildEnvFilterError, DefaultSubscriberGuard, EnvFilterBuilder,
@@ -78,6 +79,15 @@ impl NeardCmd {
             NeardSubCommand::RecompressStorage(cmd) => {
                 cmd.run(&home_dir);
             }
+            NeardSubCommand::MigrateDb(cmd) => {
+                cmd.run(&home_dir, genesis_validation);
+            }
+            NeardSubCommand::Replica(cmd) => {
+                cmd.run(&home_dir, genesis_validation, runtime);
+            }
+            NeardSubCommand::Database(cmd) => {
+                cmd.run(&home_dir, genesis_validation);
+            }
         };
         Ok(())
     }
@@ -187,6 +197,18 @@ pub(super) enum NeardSubCommand {
     /// tool, it is planned to be removed by the end of 2022.
     #[clap(alias = "recompress_storage")]
     RecompressStorage(RecompressStorageSubCommand),
+    /// Applies database migrations needed by this version of neard.  The node
+    /// does it on start as well.  Batched migrations save their progress and
+    /// continue from it if they are interrupted.
+    #[clap(alias = "migrate_db")]
+    MigrateDb(MigrateDbCmd),
+    /// Runs a read-only replica of a node running on the same machine.  The
+    /// replica follows the node’s database and serves view queries over
+    /// JSON-RPC without running consensus or networking.  Sending transactions,
+    /// `status` and `health` aren’t supported.
+    Replica(ReplicaCmd),
+    /// Backs up and verifies backups of the database.
+    Database(DatabaseCmd),
 }
 
 #[derive(Parser)]
@@ -413,11 +435,11 @@ impl RunCmd {
         let (tx, rx) = oneshot::channel::<()>();
         let sys = new_actix_system(runtime);
         sys.block_on(async move {
-            let nearcore::NearNode { rpc_servers, .. } =
+            let nearcore::NearNode { rpc_servers, backup_requests, .. } =
                 nearcore::start_with_config_and_synchronization(home_dir, near_config, Some(tx))
                     .expect("start_with_config");
 
-            let sig = wait_for_interrupt_signal(home_dir, rx).await;
+            let sig = wait_for_interrupt_signal(home_dir, rx, backup_requests).await;
             warn!(target: "neard", "{}, stopping... this may take a few minutes.", sig);
             futures::future::join_all(rpc_servers.iter().map(|(name, server)| async move {
                 server.stop(true).await;
@@ -447,14 +469,26 @@ fn new_actix_system(runtime: Runtime) -> SystemRunner {
 }
 
 #[cfg(not(unix))]
-async fn wait_for_interrupt_signal(_home_dir: &Path, mut _rx_crash: Receiver<()>) -> &str {
+async fn wait_for_interrupt_signal(
+    _home_dir: &Path,
+    mut _rx_crash: Receiver<()>,
+    _backup_requests: Option<UnboundedSender<()>>,
+) -> &str {
     // TODO(#6372): Support graceful shutdown on windows.
     tokio::signal::ctrl_c().await.unwrap();
     "Ctrl+C"
 }
 
+/// Waits for a signal which stops the node.
+///
+/// SIGHUP reloads `log_config.json` and SIGUSR1 makes the node create
+/// a backup of the database, if backups are configured.
 #[cfg(unix)]
-async fn wait_for_interrupt_signal(home_dir: &Path, mut rx_crash: Receiver<()>) -> &str {
+async fn wait_for_interrupt_signal(
+    home_dir: &Path,
+    mut rx_crash: Receiver<()>,
+    backup_requests: Option<UnboundedSender<()>>,
+) -> &str {
     let watched_path = home_dir.join("log_config.json");
     let log_config_watcher = LogConfigWatcher { watched_path };
     // Apply the logging config file if it exists.
@@ -464,6 +498,7 @@ async fn wait_for_interrupt_signal(home_dir: &Path, mut rx_crash: Receiver<()>)
     let mut sigint = signal(SignalKind::interrupt()).unwrap();
     let mut sigterm = signal(SignalKind::terminate()).unwrap();
     let mut sighup = signal(SignalKind::hangup()).unwrap();
+    let mut sigusr1 = signal(SignalKind::user_defined1()).unwrap();
 
     loop {
         break tokio::select! {
@@ -473,6 +508,16 @@ async fn wait_for_interrupt_signal(home_dir: &Path, mut rx_crash: Receiver<()>)
                 log_config_watcher.update(UpdateBehavior::UpdateOrReset);
                 continue;
              },
+             _ = sigusr1.recv() => {
+                match &backup_requests {
+                    Some(backup_requests) => {
+                        info!(target: "neard", "Received SIGUSR1, creating a database backup");
+                        let _ = backup_requests.unbounded_send(());
+                    }
+                    None => warn!(target: "neard", "Received SIGUSR1 but database backups aren’t configured, set `store.backup` in config.json"),
+                }
+                continue;
+             },
              _ = &mut rx_crash => "ClientActor died",
         };
     }
@@ -557,6 +602,161 @@ impl RecompressStorageSubCommand {
     }
 }
 
+#[derive(Parser)]
+pub(super) struct MigrateDbCmd {
+    /// Only report which migrations are needed and how many records the first
+    /// of them would rewrite, without modifying the database.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl MigrateDbCmd {
+    pub(super) fn run(self, home_dir: &Path, genesis_validation: GenesisValidationMode) {
+        let near_config = nearcore::config::load_config(&home_dir, genesis_validation)
+            .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+        let result = if self.dry_run {
+            nearcore::dry_run_store_migrations(home_dir, &near_config)
+        } else {
+            nearcore::migrate_store(home_dir, &near_config)
+        };
+        if let Err(err) = result {
+            error!("{:#}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[derive(Parser)]
+pub(super) struct ReplicaCmd {
+    /// Directory where the replica keeps its own RocksDB logs.  Relative paths
+    /// are resolved against the home directory.
+    #[clap(long, parse(from_os_str), default_value = "replica")]
+    secondary_path: PathBuf,
+    /// How often, in milliseconds, the replica catches up with the node’s
+    /// database.
+    #[clap(long, default_value = "500")]
+    catch_up_interval_ms: u64,
+    /// Customize RPC listening address.  The node running on the same machine
+    /// usually already listens on the address from config.json.
+    #[cfg(feature = "json_rpc")]
+    #[clap(long)]
+    rpc_addr: Option<String>,
+    /// Export prometheus metrics on an additional listening address.
+    #[cfg(feature = "json_rpc")]
+    #[clap(long)]
+    rpc_prometheus_addr: Option<String>,
+}
+
+impl ReplicaCmd {
+    pub(super) fn run(
+        self,
+        home_dir: &Path,
+        genesis_validation: GenesisValidationMode,
+        runtime: Runtime,
+    ) {
+        #[allow(unused_mut)]
+        let mut near_config = nearcore::config::load_config(&home_dir, genesis_validation)
+            .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+        near_config.client_config.version = crate::neard_version();
+        #[cfg(feature = "json_rpc")]
+        {
+            if let Some(rpc_addr) = self.rpc_addr {
+                near_config.rpc_config.get_or_insert(Default::default()).addr = rpc_addr;
+            }
+            if let Some(rpc_prometheus_addr) = self.rpc_prometheus_addr {
+                near_config.rpc_config.get_or_insert(Default::default()).prometheus_addr =
+                    Some(rpc_prometheus_addr);
+            }
+        }
+        let secondary_path = home_dir.join(&self.secondary_path);
+        let catch_up_interval = std::time::Duration::from_millis(self.catch_up_interval_ms);
+
+        // The replica has no client actor which could die so the sender is
+        // only kept alive until the replica is stopped.
+        let (_tx, rx) = oneshot::channel::<()>();
+        let sys = new_actix_system(runtime);
+        sys.block_on(async move {
+            let nearcore::replica::NearReplica { rpc_servers, .. } =
+                nearcore::replica::start_replica_with_config(
+                    home_dir,
+                    near_config,
+                    &secondary_path,
+                    catch_up_interval,
+                )
+                .unwrap_or_else(|err| panic!("Failed to start replica: {:#}", err));
+
+            let sig = wait_for_interrupt_signal(home_dir, rx, None).await;
+            warn!(target: "neard", "{}, stopping...", sig);
+            futures::future::join_all(rpc_servers.iter().map(|(name, server)| async move {
+                server.stop(true).await;
+                debug!(target: "neard", "{} server stopped", name);
+            }))
+            .await;
+            actix::System::current().stop();
+            opentelemetry::global::shutdown_tracer_provider(); // Finish sending spans.
+        });
+        sys.run().unwrap();
+        RocksDB::block_until_all_instances_are_dropped();
+    }
+}
+
+#[derive(Parser)]
+pub(super) struct DatabaseCmd {
+    #[clap(subcommand)]
+    subcmd: DatabaseSubCommand,
+}
+
+#[derive(Parser)]
+enum DatabaseSubCommand {
+    /// Creates a backup of the database: a RocksDB checkpoint together with
+    /// a manifest listing head height, DB version and number of keys in each
+    /// column.
+    ///
+    /// If the node is running, it's asked to create the checkpoint through
+    /// the admin JSON RPC, which has to be enabled with `rpc.enable_admin_rpc`
+    /// in config.json.  A running node configured with the `store.backup`
+    /// option creates backups periodically and whenever it receives SIGUSR1.
+    Backup(BackupCmd),
+    /// Verifies a backup: checks it against its manifest and runs Head-Tail
+    /// and refcount checks of the store validator on it.
+    Verify(VerifyBackupCmd),
+}
+
+#[derive(Parser)]
+pub(super) struct BackupCmd {
+    /// Directory to create the backup in.  Must not exist.
+    #[clap(long, parse(from_os_str))]
+    path: PathBuf,
+}
+
+#[derive(Parser)]
+pub(super) struct VerifyBackupCmd {
+    /// Directory of the backup to verify.
+    #[clap(long, parse(from_os_str))]
+    path: PathBuf,
+}
+
+impl DatabaseCmd {
+    pub(super) fn run(self, home_dir: &Path, genesis_validation: GenesisValidationMode) {
+        let near_config = nearcore::config::load_config(&home_dir, genesis_validation)
+            .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+        let result = match self.subcmd {
+            DatabaseSubCommand::Backup(cmd) => {
+                nearcore::backup_store(home_dir, &near_config, &cmd.path).map(|manifest| {
+                    info!(target: "neard", head_height = manifest.head_height, path = %cmd.path.display(), "Created database backup");
+                })
+            }
+            DatabaseSubCommand::Verify(cmd) => {
+                nearcore::verify_backup(home_dir, &near_config, &cmd.path)
+            }
+        };
+        if let Err(err) = result {
+            error!("{:#}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -585,4 +785,28 @@ mod tests {
         ])
         .is_err());
     }
+
+    #[test]
+    fn replica_default_values() {
+        let cmd = NeardCmd::parse_from(&["test", "replica"]);
+        if let NeardSubCommand::Replica(scmd) = cmd.subcmd {
+            assert_eq!(scmd.secondary_path, PathBuf::from("replica"));
+            assert_eq!(scmd.catch_up_interval_ms, 500);
+        } else {
+            panic!("incorrect subcommand");
+        }
+    }
+
+    #[test]
+    fn database_backup_requires_path() {
+        assert!(NeardCmd::try_parse_from(&["test", "database", "backup"]).is_err());
+        let cmd = NeardCmd::parse_from(&["test", "database", "verify", "--path", "backup"]);
+        if let NeardSubCommand::Database(DatabaseCmd { subcmd: DatabaseSubCommand::Verify(scmd) }) =
+            cmd.subcmd
+        {
+            assert_eq!(scmd.path, PathBuf::from("backup"));
+        } else {
+            panic!("incorrect subcommand");
+        }
+    }
 }
@@ -224,6 +224,10 @@ pub enum HostError {
     /// Invalid input to alt_bn128 familiy of functions (e.g., point which isn't
     /// on the curve).
     AltBn128InvalidInput { msg: String },
+    /// `data_id` passed to `promise_yield_resume` is not a valid hash.
+    DataIdMalformed,
+    /// The payload passed to `promise_yield_resume` exceeded the limit.
+    YieldPayloadLengthExceeded { length: u64, limit: u64 },
 }
 
 #[derive(Debug, PartialEq)]
@@ -421,6 +425,8 @@ impl std::fmt::Display for HostError {
             Deprecated {method_name}=> write!(f, "Attempted to call deprecated host function {}", method_name),
             AltBn128InvalidInput { msg } => write!(f, "AltBn128 invalid input: {}", msg),
             ECRecoverError { msg } => write!(f, "ECDSA recover error: {}", msg),
+            DataIdMalformed => write!(f, "The data id passed to promise_yield_resume is malformed"),
+            YieldPayloadLengthExceeded { length, limit } => write!(f, "The length of a yield resume payload {} exceeds the limit {}", length, limit),
         }
     }
 }
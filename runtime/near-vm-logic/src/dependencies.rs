@@ -165,6 +165,12 @@ pub trait External {
 
     fn generate_data_id(&mut self) -> CryptoHash;
 
+    /// Marks the promise yielded by the current account and waiting for `data_id` as resumed.
+    ///
+    /// Returns `false` if there is no such promise waiting, either because it wasn't yielded in
+    /// a previous execution, or because it was already resumed or timed out.
+    fn yield_resume(&mut self, data_id: CryptoHash) -> Result<bool>;
+
     /// Returns amount of touched trie nodes by storage operations
     fn get_trie_nodes_count(&self) -> TrieNodesCount;
 
@@ -2,16 +2,18 @@ use crate::context::VMContext;
 use crate::dependencies::{External, MemoryLike};
 use crate::gas_counter::{FastGasCounter, GasCounter};
 use crate::receipt_manager::ReceiptManager;
-use crate::types::{PromiseIndex, PromiseResult, ReceiptIndex, ReturnData};
+use crate::types::{ModuleSummary, PromiseIndex, PromiseResult, ReceiptIndex, ReturnData};
 use crate::utils::split_method_names;
 use crate::{ReceiptMetadata, ValuePtr};
 use byteorder::ByteOrder;
 use near_crypto::Secp256K1Signature;
 use near_primitives::checked_feature;
 use near_primitives::config::ViewConfig;
+use near_primitives::receipt::PromiseYieldTimeout;
 use near_primitives::version::is_implicit_account_creation_enabled;
 use near_primitives_core::config::ExtCosts::*;
 use near_primitives_core::config::{ActionCosts, ExtCosts, VMConfig};
+use near_primitives_core::hash::CryptoHash;
 use near_primitives_core::profile::ProfileData;
 use near_primitives_core::runtime::fees::{
     transfer_exec_fee, transfer_send_fee, RuntimeFeesConfig,
@@ -2086,6 +2088,167 @@ impl<'a> VMLogic<'a> {
         }
     }
 
+    /// Creates a promise that will execute a method on the current account with given arguments
+    /// once the contract resumes it with `promise_yield_resume`, or once it times out. The id of
+    /// the data the promise waits for is written to `register_id`.
+    ///
+    /// The callback gets the payload given to `promise_yield_resume` as its only promise result.
+    /// If the promise isn't resumed within `yield_timeout_length_in_blocks` blocks, the callback
+    /// is executed with a failed promise result instead. Until then the storage taken by the
+    /// promise is added to the storage usage of the account.
+    ///
+    /// # Errors
+    ///
+    /// * If `method_name_len + method_name_ptr` or `arguments_len + arguments_ptr` points outside
+    /// the memory of the guest or host returns `MemoryAccessViolation`.
+    /// * If `method_name` is empty returns `EmptyMethodName`.
+    /// * If called as view function returns `ProhibitedInView`.
+    ///
+    /// # Returns
+    ///
+    /// Index of the new promise that uniquely identifies it within the current execution of the
+    /// method.
+    ///
+    /// # Cost
+    ///
+    /// `base + dispatch&execution cost of the receipt + dispatch&execution base cost of the data
+    ///  dependency + function call action fees + cost of writing the data id to the register`
+    pub fn promise_yield_create(
+        &mut self,
+        method_name_len: u64,
+        method_name_ptr: u64,
+        arguments_len: u64,
+        arguments_ptr: u64,
+        gas: Gas,
+        gas_weight: u64,
+        register_id: u64,
+    ) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        if self.context.is_view() {
+            return Err(HostError::ProhibitedInView {
+                method_name: "promise_yield_create".to_string(),
+            }
+            .into());
+        }
+        let method_name = self.get_vec_from_memory_or_register(method_name_ptr, method_name_len)?;
+        if method_name.is_empty() {
+            return Err(HostError::EmptyMethodName.into());
+        }
+        let arguments = self.get_vec_from_memory_or_register(arguments_ptr, arguments_len)?;
+
+        // The receipt is always sent to the current account, and so is the data it waits for.
+        self.pay_gas_for_new_receipt(true, &[true])?;
+        let num_bytes = method_name.len() as u64 + arguments.len() as u64;
+        self.gas_counter.pay_action_base(
+            &self.fees_config.action_creation_config.function_call_cost,
+            true,
+            ActionCosts::function_call,
+        )?;
+        self.gas_counter.pay_action_per_byte(
+            &self.fees_config.action_creation_config.function_call_cost_per_byte,
+            num_bytes,
+            true,
+            ActionCosts::function_call,
+        )?;
+        self.gas_counter.prepay_gas(gas)?;
+
+        // The account pays for the storage of the yielded promise until it's resumed or times out.
+        self.current_storage_usage = self
+            .current_storage_usage
+            .checked_add(self.promise_yield_storage_usage())
+            .ok_or(InconsistentStateError::IntegerOverflow)?;
+        let (receipt_idx, data_id) = self
+            .receipt_manager
+            .create_promise_yield_receipt(self.ext, self.context.current_account_id.clone());
+        self.receipt_manager.append_action_function_call_weight(
+            receipt_idx,
+            method_name,
+            arguments,
+            0,
+            gas,
+            GasWeight(gas_weight),
+        )?;
+        self.internal_write_register(register_id, data_id.as_bytes().to_vec())?;
+
+        self.checked_push_promise(Promise::Receipt(receipt_idx))
+    }
+
+    /// Resumes the promise created with `promise_yield_create` by the current account in one of
+    /// its previous executions, sending it `payload` as its promise result.
+    ///
+    /// # Errors
+    ///
+    /// * If `data_id_len + data_id_ptr` or `payload_len + payload_ptr` points outside the memory
+    /// of the guest or host returns `MemoryAccessViolation`.
+    /// * If the data id isn't 32 bytes long returns `DataIdMalformed`.
+    /// * If the length of the payload exceeds `max_length_returned_data` returns
+    /// `YieldPayloadLengthExceeded`.
+    /// * If called as view function returns `ProhibitedInView`.
+    ///
+    /// # Returns
+    ///
+    /// `1` if the promise was resumed, or `0` if there is no promise waiting for the data id,
+    /// e.g. because it was already resumed or has timed out.
+    ///
+    /// # Cost
+    ///
+    /// `base + cost of reading the data id and the payload from memory or register +
+    ///  dispatch&exec cost per byte of the payload`
+    pub fn promise_yield_resume(
+        &mut self,
+        data_id_len: u64,
+        data_id_ptr: u64,
+        payload_len: u64,
+        payload_ptr: u64,
+    ) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        if self.context.is_view() {
+            return Err(HostError::ProhibitedInView {
+                method_name: "promise_yield_resume".to_string(),
+            }
+            .into());
+        }
+        let data_id = self.get_vec_from_memory_or_register(data_id_ptr, data_id_len)?;
+        let data_id =
+            CryptoHash::try_from(data_id.as_slice()).map_err(|_| HostError::DataIdMalformed)?;
+        let payload = self.get_vec_from_memory_or_register(payload_ptr, payload_len)?;
+        let num_bytes = payload.len() as u64;
+        if num_bytes > self.config.limit_config.max_length_returned_data {
+            return Err(HostError::YieldPayloadLengthExceeded {
+                length: num_bytes,
+                limit: self.config.limit_config.max_length_returned_data,
+            }
+            .into());
+        }
+        // Like with `value_return`, the data receipt is paid for upfront by the sender.
+        let data_cfg = &self.fees_config.data_receipt_creation_config;
+        let burn_gas = data_cfg
+            .cost_per_byte
+            .send_fee(true)
+            .checked_add(data_cfg.cost_per_byte.exec_fee())
+            .ok_or(HostError::IntegerOverflow)?
+            .checked_mul(num_bytes)
+            .ok_or(HostError::IntegerOverflow)?;
+        self.gas_counter.pay_action_accumulated(burn_gas, burn_gas, ActionCosts::new_receipt)?;
+
+        if !self.ext.yield_resume(data_id)? {
+            return Ok(0);
+        }
+        self.current_storage_usage = self
+            .current_storage_usage
+            .checked_sub(self.promise_yield_storage_usage())
+            .ok_or(InconsistentStateError::IntegerOverflow)?;
+        self.receipt_manager.create_data_receipt(data_id, payload);
+        Ok(1)
+    }
+
+    fn promise_yield_storage_usage(&self) -> StorageUsage {
+        PromiseYieldTimeout::storage_usage(
+            &self.context.current_account_id,
+            self.fees_config.storage_usage_config.num_extra_bytes_record,
+        )
+    }
+
     // #####################
     // # Miscellaneous API #
     // #####################
@@ -2704,21 +2867,40 @@ impl<'a> VMLogic<'a> {
             logs: self.logs,
             profile,
             action_receipts: self.receipt_manager.action_receipts,
+            data_receipts: self.receipt_manager.data_receipts,
         }
     }
 
     /// Add a cost for loading the contract code in the VM.
     ///
     /// This cost does not consider the structure of the contract code, only the
-    /// size. This is currently the only loading fee. A fee that takes the code
-    /// structure into consideration could be added. But since that would have
-    /// to happen after loading, we cannot pre-charge it. This is the main
-    /// motivation to (only) have this simple fee.
+    /// size, so that it can be pre-charged before the code is loaded. The
+    /// structure of the code is charged for separately by
+    /// `add_contract_module_fee`, once it is known.
     pub fn add_contract_loading_fee(&mut self, code_len: u64) -> Result<()> {
         self.gas_counter.pay_per(contract_loading_bytes, code_len)?;
         self.gas_counter.pay_base(contract_loading_base)
     }
 
+    /// Add a cost for instantiating the loaded contract, which depends on the
+    /// shape of its module rather than on the size of its code.
+    ///
+    /// The `summary` is stored along with the compiled contract, so this fee
+    /// is charged after the executable is loaded but before it's instantiated.
+    pub fn add_contract_module_fee(&mut self, summary: &ModuleSummary) -> Result<()> {
+        self.gas_counter.pay_per(contract_loading_function, summary.functions)?;
+        self.gas_counter.pay_per(contract_loading_global, summary.globals)?;
+        self.gas_counter.pay_per(contract_loading_table_element, summary.table_elements)?;
+        self.gas_counter.pay_per(contract_loading_data_segment, summary.data_segments)
+    }
+
+    /// Gas burnt and used by the execution so far, as `(burnt_gas, used_gas)`.
+    ///
+    /// Unlike the `used_gas` host function, this is free and meant to be used by the host.
+    pub fn gas_spent(&self) -> (Gas, Gas) {
+        (self.gas_counter.burnt_gas(), self.gas_counter.used_gas())
+    }
+
     /// Gets pointer to the fast gas counter.
     pub fn gas_counter_pointer(&mut self) -> *mut FastGasCounter {
         self.gas_counter.gas_counter_raw_ptr()
@@ -2765,11 +2947,16 @@ impl<'a> VMLogic<'a> {
         Ok(())
     }
 
-    /// Legacy code to preserve old gas charging behaviour in old protocol versions.
+    /// VM independent setup after loading the executable.
+    ///
+    /// Charges for the structure of the loaded module before it's instantiated.
+    /// In old protocol versions, this is also where the legacy gas charging
+    /// for the size of the code happens.
     pub fn after_loading_executable(
         &mut self,
         current_protocol_version: u32,
         wasm_code_bytes: usize,
+        module_summary: &ModuleSummary,
     ) -> std::result::Result<(), VMError> {
         if !checked_feature!(
             "protocol_feature_fix_contract_loading_cost",
@@ -2784,6 +2971,19 @@ impl<'a> VMLogic<'a> {
                 ));
             }
         }
+        if checked_feature!(
+            "protocol_feature_structured_loading_cost",
+            StructuredLoadingCost,
+            current_protocol_version
+        ) {
+            if self.add_contract_module_fee(module_summary).is_err() {
+                return Err(VMError::FunctionCallError(
+                    near_vm_errors::FunctionCallError::HostError(
+                        near_vm_errors::HostError::GasExceeded,
+                    ),
+                ));
+            }
+        }
         Ok(())
     }
 }
@@ -2799,6 +2999,8 @@ pub struct VMOutcome {
     /// Data collected from making a contract call
     pub profile: ProfileData,
     pub action_receipts: Vec<(AccountId, ReceiptMetadata)>,
+    /// Data sent to promises yielded by the current account, as `(data_id, data)` pairs.
+    pub data_receipts: Vec<(CryptoHash, Vec<u8>)>,
 }
 
 impl std::fmt::Debug for VMOutcome {
@@ -2,13 +2,15 @@ use crate::{External, ValuePtr};
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::types::TrieNodesCount;
 use near_primitives_core::types::{AccountId, Balance};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Default, Clone)]
 /// Emulates the trie and the mock handling code.
 pub struct MockedExternal {
     pub fake_trie: HashMap<Vec<u8>, Vec<u8>>,
     pub validators: HashMap<AccountId, Balance>,
+    /// Data ids of yielded promises which can be resumed.
+    pub pending_yields: HashSet<CryptoHash>,
     data_count: u64,
 }
 
@@ -78,6 +80,10 @@ impl External for MockedExternal {
         data_id
     }
 
+    fn yield_resume(&mut self, data_id: CryptoHash) -> Result<bool> {
+        Ok(self.pending_yields.remove(&data_id))
+    }
+
     fn get_trie_nodes_count(&self) -> TrieNodesCount {
         TrieNodesCount { db_reads: 0, mem_reads: 0 }
     }
@@ -29,11 +29,17 @@ pub struct ReceiptMetadata {
     pub input_data_ids: Vec<CryptoHash>,
     /// A list of actions to process when all input_data_ids are filled
     pub actions: Vec<Action>,
+    /// Whether the receipt was created with `promise_yield_create`. Its only input data is then
+    /// supplied with `promise_yield_resume`, or as a failure once the yield times out.
+    pub is_promise_yield: bool,
 }
 
 #[derive(Default, Clone, PartialEq)]
 pub(crate) struct ReceiptManager {
     pub(crate) action_receipts: ActionReceipts,
+    /// Data sent with `promise_yield_resume` to promises yielded by the current account, as
+    /// `(data_id, data)` pairs.
+    pub(crate) data_receipts: Vec<(CryptoHash, Vec<u8>)>,
     gas_weights: Vec<(FunctionCallActionIndex, GasWeight)>,
 }
 
@@ -116,13 +122,50 @@ impl ReceiptManager {
             input_data_ids.push(data_id);
         }
 
-        let new_receipt =
-            ReceiptMetadata { output_data_receivers: vec![], input_data_ids, actions: vec![] };
+        let new_receipt = ReceiptMetadata {
+            output_data_receivers: vec![],
+            input_data_ids,
+            actions: vec![],
+            is_promise_yield: false,
+        };
         let new_receipt_index = self.action_receipts.len() as ReceiptIndex;
         self.action_receipts.push((receiver_id, new_receipt));
         Ok(new_receipt_index)
     }
 
+    /// Create a receipt which will be executed once the data identified by the returned data id
+    /// is sent with [`create_data_receipt`](Self::create_data_receipt), or once the yield times
+    /// out.
+    ///
+    /// # Arguments
+    ///
+    /// * `ext` - used to generate the data id the new receipt waits for
+    /// * `receiver_id` - account id of the receiver of the receipt created, which is always the
+    ///   current account
+    pub(crate) fn create_promise_yield_receipt(
+        &mut self,
+        ext: &mut dyn External,
+        receiver_id: AccountId,
+    ) -> (ReceiptIndex, CryptoHash) {
+        let data_id = ext.generate_data_id();
+        let new_receipt = ReceiptMetadata {
+            output_data_receivers: vec![],
+            input_data_ids: vec![data_id],
+            actions: vec![],
+            is_promise_yield: true,
+        };
+        let new_receipt_index = self.action_receipts.len() as ReceiptIndex;
+        self.action_receipts.push((receiver_id, new_receipt));
+        (new_receipt_index, data_id)
+    }
+
+    /// Send `data` to the receipt created by
+    /// [`create_promise_yield_receipt`](Self::create_promise_yield_receipt) and waiting for
+    /// `data_id`.
+    pub(crate) fn create_data_receipt(&mut self, data_id: CryptoHash, data: Vec<u8>) {
+        self.data_receipts.push((data_id, data));
+    }
+
     /// Attach the [`CreateAccountAction`] action to an existing receipt.
     ///
     /// # Arguments
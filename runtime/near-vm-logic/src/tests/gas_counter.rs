@@ -2,8 +2,8 @@ use crate::receipt_manager::ReceiptMetadata;
 use crate::tests::fixtures::get_context;
 use crate::tests::helpers::*;
 use crate::tests::vm_logic_builder::VMLogicBuilder;
-use crate::types::Gas;
-use crate::{VMConfig, VMLogic};
+use crate::types::{Gas, ModuleSummary};
+use crate::{ExtCosts, VMConfig, VMLogic};
 use near_primitives::transaction::{Action, FunctionCallAction};
 
 #[test]
@@ -219,6 +219,29 @@ fn function_call_no_weight_refund() {
     assert!(outcome.used_gas < gas_limit);
 }
 
+#[test]
+fn test_contract_module_fee() {
+    let gas_limit = 10u64.pow(14);
+    let mut logic_builder = VMLogicBuilder::default();
+    let ext_costs = logic_builder.config.ext_costs.clone();
+    let mut logic = logic_builder.build_with_prepaid_gas(gas_limit);
+
+    let summary = ModuleSummary { functions: 10, globals: 3, table_elements: 20, data_segments: 2 };
+    logic.add_contract_module_fee(&summary).expect("should have enough gas");
+    let outcome = logic.compute_outcome_and_distribute_gas();
+
+    let expected = [
+        (ExtCosts::contract_loading_function, 10 * ext_costs.contract_loading_function),
+        (ExtCosts::contract_loading_global, 3 * ext_costs.contract_loading_global),
+        (ExtCosts::contract_loading_table_element, 20 * ext_costs.contract_loading_table_element),
+        (ExtCosts::contract_loading_data_segment, 2 * ext_costs.contract_loading_data_segment),
+    ];
+    for (cost, gas) in expected {
+        assert_eq!(outcome.profile.get_ext_cost(cost), gas, "{}", cost);
+    }
+    assert_eq!(outcome.burnt_gas, expected.iter().map(|(_, gas)| gas).sum::<Gas>());
+}
+
 impl VMLogicBuilder {
     fn max_gas_burnt(mut self, max_gas_burnt: Gas) -> Self {
         self.config.limit_config.max_gas_burnt = max_gas_burnt;
@@ -6,6 +6,8 @@ use crate::VMLogic;
 use borsh::BorshSerialize;
 use near_account_id::AccountId;
 use near_crypto::PublicKey;
+use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::PromiseYieldTimeout;
 use near_primitives::transaction::Action;
 use serde::Serialize;
 use serde_json;
@@ -421,3 +423,112 @@ fn test_promise_batch_then() {
     ]);
     assert_eq!(&serde_json::to_string(&vm_receipts(&logic)).unwrap(), &expected.to_string());
 }
+
+#[test]
+fn test_promise_yield_create() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let context = get_context(vec![], false);
+    let yield_storage_usage = PromiseYieldTimeout::storage_usage(
+        &context.current_account_id,
+        logic_builder.fees_config.storage_usage_config.num_extra_bytes_record,
+    );
+    let mut logic = logic_builder.build(context);
+
+    let method_name = b"callback";
+    let args = b"args";
+    logic
+        .promise_yield_create(0, method_name.as_ptr() as _, 0, args.as_ptr() as _, 0, 0, 0)
+        .expect_err("shouldn't accept an empty method name");
+    let index = logic
+        .promise_yield_create(
+            method_name.len() as _,
+            method_name.as_ptr() as _,
+            args.len() as _,
+            args.as_ptr() as _,
+            0,
+            1,
+            0,
+        )
+        .expect("should create a yielded promise");
+    assert_eq!(index, 0);
+
+    let data_id = [0u8; 32];
+    logic.read_register(0, data_id.as_ptr() as u64).unwrap();
+    let (receiver_id, receipt) = &logic.receipt_manager().action_receipts[0];
+    assert_eq!(receiver_id.as_ref(), "alice.near");
+    assert!(receipt.is_promise_yield);
+    assert_eq!(receipt.input_data_ids, vec![CryptoHash(data_id)]);
+    let expected = serde_json::json!([
+        {
+            "receiver_id": "alice.near",
+            "actions": [
+                {
+                    "FunctionCall": {
+                        "method_name": "callback",
+                        "args": "YXJncw==",
+                        "gas": 0,
+                        "deposit": "0"
+                    }
+                }
+            ]
+        }
+    ]);
+    assert_eq!(&serde_json::to_string(&vm_receipts(&logic)).unwrap(), &expected.to_string());
+    // The account pays for the storage of the yielded promise.
+    assert_eq!(logic.storage_usage().unwrap(), yield_storage_usage);
+}
+
+#[test]
+fn test_promise_yield_resume() {
+    let data_id = [1u8; 32];
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.ext.pending_yields.insert(CryptoHash(data_id));
+    logic_builder.config.limit_config.max_length_returned_data = 8;
+    let mut context = get_context(vec![], false);
+    let yield_storage_usage = PromiseYieldTimeout::storage_usage(
+        &context.current_account_id,
+        logic_builder.fees_config.storage_usage_config.num_extra_bytes_record,
+    );
+    context.storage_usage = 100 + yield_storage_usage;
+    let mut logic = logic_builder.build(context);
+
+    let payload = b"payload";
+    let long_payload = b"too long payload";
+    logic
+        .promise_yield_resume(31, data_id.as_ptr() as _, payload.len() as _, payload.as_ptr() as _)
+        .expect_err("shouldn't accept a malformed data id");
+    logic
+        .promise_yield_resume(
+            data_id.len() as _,
+            data_id.as_ptr() as _,
+            long_payload.len() as _,
+            long_payload.as_ptr() as _,
+        )
+        .expect_err("shouldn't accept a payload longer than the limit");
+    assert_eq!(
+        logic.promise_yield_resume(
+            data_id.len() as _,
+            data_id.as_ptr() as _,
+            payload.len() as _,
+            payload.as_ptr() as _,
+        ),
+        Ok(1),
+        "Pending promise must be resumed"
+    );
+    assert_eq!(
+        logic.promise_yield_resume(
+            data_id.len() as _,
+            data_id.as_ptr() as _,
+            payload.len() as _,
+            payload.as_ptr() as _,
+        ),
+        Ok(0),
+        "Promise can be resumed only once"
+    );
+    assert_eq!(
+        logic.receipt_manager().data_receipts,
+        vec![(CryptoHash(data_id), payload.to_vec())]
+    );
+    // The storage of the resumed promise is released.
+    assert_eq!(logic.storage_usage().unwrap(), 100);
+}
@@ -1,3 +1,4 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 pub use near_primitives_core::types::*;
 
 pub type PublicKey = Vec<u8>;
@@ -28,6 +29,23 @@ impl ReturnData {
     }
 }
 
+/// Shape of a contract module which determines the cost of its instantiation, on top of the
+/// size of its code.
+///
+/// It is computed when the contract is compiled and stored next to the compiled code, so that
+/// it's known before the contract is instantiated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ModuleSummary {
+    /// Number of functions, both defined in the module and imported.
+    pub functions: u64,
+    /// Number of globals defined in the module.
+    pub globals: u64,
+    /// Sum of the initial sizes of the tables defined in the module.
+    pub table_elements: u64,
+    /// Number of data segments.
+    pub data_segments: u64,
+}
+
 /// When there is a callback attached to one or more contract calls the execution results of these
 /// calls are available to the contract invoked through the callback.
 #[derive(Debug, PartialEq)]
@@ -0,0 +1,61 @@
+#![no_main]
+
+use near_primitives::contract::ContractCode;
+use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_primitives::version::PROTOCOL_VERSION;
+use near_vm_logic::mocks::mock_external::MockedExternal;
+use near_vm_logic::VMConfig;
+use near_vm_runner::internal::VMKind;
+use near_vm_runner::{with_execution_trace, ExecutionTrace};
+use near_vm_runner_fuzz::{create_context, find_entry_point, ArbitraryModule};
+
+libfuzzer_sys::fuzz_target!(|module: ArbitraryModule| {
+    let code = ContractCode::new(module.0.module.to_bytes(), None);
+    let traces: Vec<_> = [VMKind::Wasmer0, VMKind::Wasmtime, VMKind::Wasmer2]
+        .into_iter()
+        .filter_map(|vm_kind| Some((vm_kind, run_fuzz(&code, vm_kind)?)))
+        .collect();
+    // There is nothing to compare unless at least one VM is enabled.
+    let (first_vm_kind, first_trace) = match traces.first() {
+        Some(first) => first,
+        None => return,
+    };
+    for (vm_kind, trace) in &traces[1..] {
+        assert_eq!(
+            trace.to_string(),
+            first_trace.to_string(),
+            "{:?} and {:?} executions differ",
+            first_vm_kind,
+            vm_kind
+        );
+    }
+});
+
+/// Runs the contract on the given VM, if it's enabled, and returns the trace
+/// of the execution.
+fn run_fuzz(code: &ContractCode, vm_kind: VMKind) -> Option<ExecutionTrace> {
+    let mut fake_external = MockedExternal::new();
+    let mut context = create_context(vec![]);
+    context.prepaid_gas = 10u64.pow(14);
+    let mut config = VMConfig::test();
+    config.limit_config.wasmer2_stack_limit = i32::MAX; // If we can crash wasmer2 even without the secondary stack limit it's still good to know
+    let fees = RuntimeFeesConfig::test();
+
+    let promise_results = vec![];
+
+    let method_name = find_entry_point(code).unwrap_or_else(|| "main".to_string());
+    let runtime = vm_kind.runtime(config)?;
+    let (_, trace) = with_execution_trace(|| {
+        runtime.run(
+            code,
+            &method_name,
+            &mut fake_external,
+            context,
+            &fees,
+            &promise_results,
+            PROTOCOL_VERSION,
+            None,
+        )
+    });
+    Some(trace)
+}
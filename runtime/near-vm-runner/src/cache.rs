@@ -5,22 +5,23 @@ use near_primitives::contract::ContractCode;
 use near_primitives::hash::CryptoHash;
 use near_primitives::types::CompiledContractCache;
 use near_vm_errors::{CacheError, CompilationError};
-use near_vm_logic::{ProtocolVersion, VMConfig};
+use near_vm_logic::{ModuleSummary, ProtocolVersion, VMConfig};
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::{Arc, Mutex};
 
 #[cfg(target_arch = "x86_64")]
-use crate::prepare;
+use crate::{metrics, prepare};
 #[cfg(target_arch = "x86_64")]
 use near_vm_errors::{FunctionCallError, VMError};
 
-#[derive(Debug, Clone, BorshSerialize)]
-enum ContractCacheKey {
+#[derive(Debug, Clone, PartialEq, BorshDeserialize, BorshSerialize)]
+pub(crate) enum ContractCacheKey {
     _Version1,
     _Version2,
     _Version3,
-    Version4 {
+    _Version4,
+    Version5 {
         code_hash: CryptoHash,
         vm_config_non_crypto_hash: u64,
         vm_kind: VMKind,
@@ -28,10 +29,51 @@ enum ContractCacheKey {
     },
 }
 
+impl ContractCacheKey {
+    pub(crate) fn new(code_hash: CryptoHash, vm_kind: VMKind, config: &VMConfig) -> Self {
+        ContractCacheKey::Version5 {
+            code_hash,
+            vm_config_non_crypto_hash: config.non_crypto_hash(),
+            vm_kind,
+            vm_hash: vm_hash(vm_kind),
+        }
+    }
+
+    /// Key under which the entry is stored in the `CompiledContractCache`.
+    pub(crate) fn hash(&self) -> CryptoHash {
+        near_primitives::hash::hash(&self.try_to_vec().unwrap())
+    }
+}
+
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
 enum CacheRecord {
     CompileModuleError(CompilationError),
-    Code(Vec<u8>),
+    /// The compiled code along with the summary of the module it was compiled from.
+    Code {
+        code: Vec<u8>,
+        summary: ModuleSummary,
+    },
+}
+
+/// Value stored in the `CompiledContractCache`. The record is preceded by the key it is stored
+/// under, so that entries of an outdated VM or `VMConfig` can be found and garbage collected.
+#[derive(BorshDeserialize, BorshSerialize)]
+struct CacheEntry {
+    key: ContractCacheKey,
+    record: CacheRecord,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl CacheEntry {
+    fn serialize(key: &ContractCacheKey, record: CacheRecord) -> Vec<u8> {
+        CacheEntry { key: key.clone(), record }.try_to_vec().unwrap()
+    }
+
+    fn deserialize_record(serialized: &[u8]) -> Result<CacheRecord, CacheError> {
+        let entry = CacheEntry::try_from_slice(serialized)
+            .map_err(|_e| CacheError::DeserializationError)?;
+        Ok(entry.record)
+    }
 }
 
 fn vm_hash(vm_kind: VMKind) -> u64 {
@@ -51,31 +93,62 @@ fn vm_hash(vm_kind: VMKind) -> u64 {
     }
 }
 
+fn is_vm_enabled(vm_kind: VMKind) -> bool {
+    match vm_kind {
+        VMKind::Wasmer0 => cfg!(all(feature = "wasmer0_vm", target_arch = "x86_64")),
+        VMKind::Wasmer2 => cfg!(all(feature = "wasmer2_vm", target_arch = "x86_64")),
+        VMKind::Wasmtime => cfg!(feature = "wasmtime_vm"),
+    }
+}
+
 pub fn get_contract_cache_key(
     code: &ContractCode,
     vm_kind: VMKind,
     config: &VMConfig,
 ) -> CryptoHash {
     let _span = tracing::debug_span!(target: "vm", "get_key").entered();
-    let key = ContractCacheKey::Version4 {
-        code_hash: *code.hash(),
-        vm_config_non_crypto_hash: config.non_crypto_hash(),
-        vm_kind,
-        vm_hash: vm_hash(vm_kind),
+    ContractCacheKey::new(*code.hash(), vm_kind, config).hash()
+}
+
+/// Returns whether an entry of the compiled contract cache can be removed, because it was
+/// produced by another version of the VM or for a `VMConfig` other than the `current_configs`.
+pub fn is_stale_cache_entry(key: &[u8], value: &[u8], current_configs: &[&VMConfig]) -> bool {
+    // Only the key in front of the entry is read, the compiled code doesn't need to be copied.
+    let entry_key = match ContractCacheKey::deserialize(&mut &value[..]) {
+        Ok(entry_key) => entry_key,
+        Err(_) => return true,
     };
-    near_primitives::hash::hash(&key.try_to_vec().unwrap())
+    if entry_key.hash().as_ref() != key {
+        return true;
+    }
+    match entry_key {
+        ContractCacheKey::Version5 {
+            vm_config_non_crypto_hash,
+            vm_kind,
+            vm_hash: entry_vm_hash,
+            ..
+        } => {
+            !is_vm_enabled(vm_kind)
+                || vm_hash(vm_kind) != entry_vm_hash
+                || current_configs
+                    .iter()
+                    .all(|config| config.non_crypto_hash() != vm_config_non_crypto_hash)
+        }
+        _ => true,
+    }
 }
 
+/// Stores the compilation error in the cache and returns the size of the stored entry.
 #[cfg(target_arch = "x86_64")]
 fn cache_error(
     error: &CompilationError,
-    key: &CryptoHash,
+    key: &ContractCacheKey,
     cache: &dyn CompiledContractCache,
-) -> Result<(), CacheError> {
-    let record = CacheRecord::CompileModuleError(error.clone());
-    let record = record.try_to_vec().unwrap();
-    cache.put(key, record).map_err(|_io_err| CacheError::ReadError)?;
-    Ok(())
+) -> Result<usize, CacheError> {
+    let record = CacheEntry::serialize(key, CacheRecord::CompileModuleError(error.clone()));
+    let size = record.len();
+    cache.put(&key.hash(), record).map_err(|_io_err| CacheError::ReadError)?;
+    Ok(size)
 }
 
 #[cfg(target_arch = "x86_64")]
@@ -119,21 +192,29 @@ impl fmt::Debug for MockCompiledContractCache {
     }
 }
 
-#[cfg(all(not(feature = "no_cache"), target_arch = "x86_64"))]
+#[cfg(all(feature = "wasmer0_vm", not(feature = "no_cache"), target_arch = "x86_64"))]
 const CACHE_SIZE: usize = 128;
 
+/// Limit on the total size of the loaded Wasmer2 artifacts kept in memory. The size of an
+/// artifact is approximated by the size of its serialized form.
+#[cfg(all(feature = "wasmer2_vm", not(feature = "no_cache"), target_arch = "x86_64"))]
+const WASMER2_CACHE_SIZE_BYTES: usize = 512 * 1024 * 1024;
+
 #[cfg(all(feature = "wasmer0_vm", not(feature = "no_cache"), target_arch = "x86_64"))]
 static WASMER_CACHE: once_cell::sync::Lazy<
-    near_cache::SyncLruCache<CryptoHash, Result<wasmer_runtime::Module, CompilationError>>,
+    near_cache::SyncLruCache<
+        CryptoHash,
+        Result<(wasmer_runtime::Module, ModuleSummary), CompilationError>,
+    >,
 > = once_cell::sync::Lazy::new(|| near_cache::SyncLruCache::new(CACHE_SIZE));
 
 #[cfg(all(feature = "wasmer2_vm", not(feature = "no_cache"), target_arch = "x86_64"))]
 static WASMER2_CACHE: once_cell::sync::Lazy<
-    near_cache::SyncLruCache<
+    near_cache::SyncSizedLruCache<
         CryptoHash,
-        Result<crate::wasmer2_runner::VMArtifact, CompilationError>,
+        Result<(crate::wasmer2_runner::VMArtifact, ModuleSummary), CompilationError>,
     >,
-> = once_cell::sync::Lazy::new(|| near_cache::SyncLruCache::new(CACHE_SIZE));
+> = once_cell::sync::Lazy::new(|| near_cache::SyncSizedLruCache::new(WASMER2_CACHE_SIZE_BYTES));
 
 #[cfg(all(feature = "wasmer0_vm", target_arch = "x86_64"))]
 pub mod wasmer0_cache {
@@ -146,12 +227,13 @@ pub mod wasmer0_cache {
     pub(crate) fn compile_module(
         code: &[u8],
         config: &VMConfig,
-    ) -> Result<wasmer_runtime::Module, CompilationError> {
+    ) -> Result<(wasmer_runtime::Module, ModuleSummary), CompilationError> {
         let _span = tracing::debug_span!(target: "vm", "compile_module").entered();
 
         let prepared_code =
             prepare::prepare_contract(code, config).map_err(CompilationError::PrepareError)?;
-        wasmer_runtime::compile(&prepared_code).map_err(|err| match err {
+        let summary = prepare::module_summary(code).map_err(CompilationError::PrepareError)?;
+        let module = wasmer_runtime::compile(&prepared_code).map_err(|err| match err {
             wasmer_runtime::error::CompileError::ValidationError { .. } => {
                 CompilationError::WasmerCompileError { msg: err.to_string() }
             }
@@ -160,19 +242,20 @@ pub mod wasmer0_cache {
             wasmer_runtime::error::CompileError::InternalError { .. } => {
                 CompilationError::WasmerCompileError { msg: err.to_string() }
             }
-        })
+        })?;
+        Ok((module, summary))
     }
 
     pub(crate) fn compile_and_serialize_wasmer(
         wasm_code: &[u8],
         config: &VMConfig,
-        key: &CryptoHash,
+        key: &ContractCacheKey,
         cache: &dyn CompiledContractCache,
-    ) -> Result<Result<wasmer_runtime::Module, CompilationError>, CacheError> {
+    ) -> Result<Result<(wasmer_runtime::Module, ModuleSummary), CompilationError>, CacheError> {
         let _span = tracing::debug_span!(target: "vm", "compile_and_serialize_wasmer").entered();
 
-        let module = match compile_module(wasm_code, config) {
-            Ok(module) => module,
+        let (module, summary) = match compile_module(wasm_code, config) {
+            Ok(it) => it,
             Err(err) => {
                 cache_error(&err, key, cache)?;
                 return Ok(Err(err));
@@ -182,10 +265,10 @@ pub mod wasmer0_cache {
         let code = module
             .cache()
             .and_then(|it| it.serialize())
-            .map_err(|_e| CacheError::SerializationError { hash: key.0 })?;
-        let serialized = CacheRecord::Code(code).try_to_vec().unwrap();
-        cache.put(key, serialized).map_err(|_io_err| CacheError::WriteError)?;
-        Ok(Ok(module))
+            .map_err(|_e| CacheError::SerializationError { hash: key.hash().0 })?;
+        let serialized = CacheEntry::serialize(key, CacheRecord::Code { code, summary });
+        cache.put(&key.hash(), serialized).map_err(|_io_err| CacheError::WriteError)?;
+        Ok(Ok((module, summary)))
     }
 
     /// Deserializes contract or error from the binary data. Signature means that we could either
@@ -193,37 +276,45 @@ pub mod wasmer0_cache {
     /// the deserialization process.
     fn deserialize_wasmer(
         serialized: &[u8],
-    ) -> Result<Result<wasmer_runtime::Module, CompilationError>, CacheError> {
+    ) -> Result<Result<(wasmer_runtime::Module, ModuleSummary), CompilationError>, CacheError> {
         let _span = tracing::debug_span!(target: "vm", "deserialize_wasmer").entered();
 
-        let record = CacheRecord::try_from_slice(serialized)
-            .map_err(|_e| CacheError::DeserializationError)?;
-        let serialized_artifact = match record {
+        let record = CacheEntry::deserialize_record(serialized)?;
+        let (serialized_artifact, summary) = match record {
             CacheRecord::CompileModuleError(err) => return Ok(Err(err)),
-            CacheRecord::Code(code) => code,
+            CacheRecord::Code { code, summary } => (code, summary),
         };
         let artifact = Artifact::deserialize(serialized_artifact.as_slice())
             .map_err(|_e| CacheError::DeserializationError)?;
         unsafe {
             let compiler = compiler_for_backend(Backend::Singlepass).unwrap();
             match load_cache_with(artifact, compiler.as_ref()) {
-                Ok(module) => Ok(Ok(module)),
+                Ok(module) => Ok(Ok((module, summary))),
                 Err(_) => Err(CacheError::DeserializationError),
             }
         }
     }
 
     fn compile_module_cached_wasmer_impl(
-        key: CryptoHash,
+        key: ContractCacheKey,
         wasm_code: &[u8],
         config: &VMConfig,
         cache: Option<&dyn CompiledContractCache>,
-    ) -> Result<Result<wasmer_runtime::Module, CompilationError>, CacheError> {
+    ) -> Result<Result<(wasmer_runtime::Module, ModuleSummary), CompilationError>, CacheError> {
         match cache {
-            None => Ok(compile_module(wasm_code, config)),
-            Some(cache) => match cache.get(&key).map_err(|_io_err| CacheError::ReadError)? {
-                Some(serialized) => deserialize_wasmer(&serialized[..]),
-                None => compile_and_serialize_wasmer(wasm_code, config, &key, cache),
+            None => {
+                metrics::record_miss(VMKind::Wasmer0);
+                Ok(compile_module(wasm_code, config))
+            }
+            Some(cache) => match cache.get(&key.hash()).map_err(|_io_err| CacheError::ReadError)? {
+                Some(serialized) => {
+                    metrics::record_disk_hit(VMKind::Wasmer0);
+                    deserialize_wasmer(&serialized[..])
+                }
+                None => {
+                    metrics::record_miss(VMKind::Wasmer0);
+                    compile_and_serialize_wasmer(wasm_code, config, &key, cache)
+                }
             },
         }
     }
@@ -232,17 +323,32 @@ pub mod wasmer0_cache {
         code: &ContractCode,
         config: &VMConfig,
         cache: Option<&dyn CompiledContractCache>,
-    ) -> Result<Result<wasmer_runtime::Module, CompilationError>, CacheError> {
-        let key = get_contract_cache_key(code, VMKind::Wasmer0, config);
+    ) -> Result<Result<(wasmer_runtime::Module, ModuleSummary), CompilationError>, CacheError> {
+        let key = ContractCacheKey::new(*code.hash(), VMKind::Wasmer0, config);
 
         #[cfg(not(feature = "no_cache"))]
-        return WASMER_CACHE.get_or_try_put(key, |key| {
-            compile_module_cached_wasmer_impl(*key, code.code(), config, cache)
-        });
+        return compile_module_memory_cached_wasmer0(key, code, config, cache);
 
         #[cfg(feature = "no_cache")]
         return compile_module_cached_wasmer_impl(key, code.code(), config, cache);
     }
+
+    #[cfg(not(feature = "no_cache"))]
+    fn compile_module_memory_cached_wasmer0(
+        key: ContractCacheKey,
+        code: &ContractCode,
+        config: &VMConfig,
+        cache: Option<&dyn CompiledContractCache>,
+    ) -> Result<Result<(wasmer_runtime::Module, ModuleSummary), CompilationError>, CacheError> {
+        let hash = key.hash();
+        if let Some(module) = WASMER_CACHE.get(&hash) {
+            metrics::record_memory_hit(VMKind::Wasmer0);
+            return Ok(module);
+        }
+        let module = compile_module_cached_wasmer_impl(key, code.code(), config, cache)?;
+        WASMER_CACHE.put(hash, module.clone());
+        Ok(module)
+    }
 }
 
 #[cfg(all(feature = "wasmer2_vm", target_arch = "x86_64"))]
@@ -257,38 +363,53 @@ pub mod wasmer2_cache {
         vm: &Wasmer2VM,
         code: &[u8],
         config: &VMConfig,
-    ) -> Result<wasmer_engine_universal::UniversalExecutable, CompilationError> {
+    ) -> Result<(wasmer_engine_universal::UniversalExecutable, ModuleSummary), CompilationError>
+    {
         let _span = tracing::debug_span!(target: "vm", "compile_module_wasmer2").entered();
         let prepared_code =
             prepare::prepare_contract(code, config).map_err(CompilationError::PrepareError)?;
-        vm.compile_uncached(&prepared_code)
+        let summary = prepare::module_summary(code).map_err(CompilationError::PrepareError)?;
+        Ok((vm.compile_uncached(&prepared_code)?, summary))
     }
 
     pub(crate) fn compile_and_serialize_wasmer2(
         wasm_code: &[u8],
-        key: &CryptoHash,
+        key: &ContractCacheKey,
+        config: &VMConfig,
+        cache: &dyn CompiledContractCache,
+    ) -> Result<Result<(VMArtifact, ModuleSummary), CompilationError>, CacheError> {
+        Ok(compile_and_serialize_wasmer2_sized(wasm_code, key, config, cache)?.0)
+    }
+
+    /// Like [`compile_and_serialize_wasmer2`], but also returns the size of the entry stored in
+    /// the cache.
+    fn compile_and_serialize_wasmer2_sized(
+        wasm_code: &[u8],
+        key: &ContractCacheKey,
         config: &VMConfig,
         cache: &dyn CompiledContractCache,
-    ) -> Result<Result<VMArtifact, CompilationError>, CacheError> {
+    ) -> Result<(Result<(VMArtifact, ModuleSummary), CompilationError>, usize), CacheError> {
         let _span = tracing::debug_span!(target: "vm", "compile_and_serialize_wasmer2").entered();
         let vm = Wasmer2VM::new(config.clone());
-        let executable = match compile_module_wasmer2(&vm, wasm_code, config) {
-            Ok(module) => module,
+        let (executable, summary) = match compile_module_wasmer2(&vm, wasm_code, config) {
+            Ok(it) => it,
             Err(err) => {
-                cache_error(&err, key, cache)?;
-                return Ok(Err(err));
+                let size = cache_error(&err, key, cache)?;
+                return Ok((Err(err), size));
             }
         };
-        let code =
-            executable.serialize().map_err(|_e| CacheError::SerializationError { hash: key.0 })?;
-        let serialized = CacheRecord::Code(code).try_to_vec().unwrap();
-        cache.put(key, serialized).map_err(|_io_err| CacheError::WriteError)?;
+        let code = executable
+            .serialize()
+            .map_err(|_e| CacheError::SerializationError { hash: key.hash().0 })?;
+        let serialized = CacheEntry::serialize(key, CacheRecord::Code { code, summary });
+        let size = serialized.len();
+        cache.put(&key.hash(), serialized).map_err(|_io_err| CacheError::WriteError)?;
         match vm.engine.load_universal_executable(&executable) {
-            Ok(artifact) => Ok(Ok(Arc::new(artifact) as _)),
+            Ok(artifact) => Ok((Ok((Arc::new(artifact) as _, summary)), size)),
             Err(err) => {
                 let err = CompilationError::WasmerCompileError { msg: err.to_string() };
-                cache_error(&err, key, cache)?;
-                Ok(Err(err))
+                let size = cache_error(&err, key, cache)?;
+                Ok((Err(err), size))
             }
         }
     }
@@ -296,14 +417,13 @@ pub mod wasmer2_cache {
     fn deserialize_wasmer2(
         serialized: &[u8],
         config: &VMConfig,
-    ) -> Result<Result<VMArtifact, CompilationError>, CacheError> {
+    ) -> Result<Result<(VMArtifact, ModuleSummary), CompilationError>, CacheError> {
         let _span = tracing::debug_span!(target: "vm", "deserialize_wasmer2").entered();
 
-        let record = CacheRecord::try_from_slice(serialized)
-            .map_err(|_e| CacheError::DeserializationError)?;
-        let serialized_module = match record {
+        let record = CacheEntry::deserialize_record(serialized)?;
+        let (serialized_module, summary) = match record {
             CacheRecord::CompileModuleError(err) => return Ok(Err(err)),
-            CacheRecord::Code(code) => code,
+            CacheRecord::Code { code, summary } => (code, summary),
         };
         unsafe {
             // (UN-)SAFETY: the `serialized_module` must have been produced by a prior call to
@@ -318,27 +438,44 @@ pub mod wasmer2_cache {
             let artifact = Wasmer2VM::new(config.clone())
                 .deserialize(&serialized_module)
                 .map_err(|_| CacheError::DeserializationError)?;
-            Ok(Ok(artifact))
+            Ok(Ok((artifact, summary)))
         }
     }
 
+    /// Loads the artifact from the `cache` or compiles it. Also returns the approximate size of
+    /// the artifact for the in-memory cache.
     fn compile_module_cached_wasmer2_impl(
-        key: CryptoHash,
+        key: ContractCacheKey,
         code: &ContractCode,
         config: &VMConfig,
         cache: Option<&dyn CompiledContractCache>,
-    ) -> Result<Result<VMArtifact, CompilationError>, CacheError> {
+    ) -> Result<(Result<(VMArtifact, ModuleSummary), CompilationError>, usize), CacheError> {
         let vm = Wasmer2VM::new(config.clone());
         match cache {
-            None => Ok(compile_module_wasmer2(&vm, code.code(), config).and_then(|executable| {
-                vm.engine
-                    .load_universal_executable(&executable)
-                    .map(|v| Arc::new(v) as _)
-                    .map_err(|err| panic!("could not load the executable: {}", err.to_string()))
-            })),
-            Some(cache) => match cache.get(&key).map_err(|_io_err| CacheError::ReadError)? {
-                Some(serialized) => deserialize_wasmer2(&serialized[..], config),
-                None => compile_and_serialize_wasmer2(code.code(), &key, config, cache),
+            None => {
+                metrics::record_miss(VMKind::Wasmer2);
+                let artifact = compile_module_wasmer2(&vm, code.code(), config).and_then(
+                    |(executable, summary)| {
+                        vm.engine
+                            .load_universal_executable(&executable)
+                            .map(|v| (Arc::new(v) as _, summary))
+                            .map_err(|err| {
+                                panic!("could not load the executable: {}", err.to_string())
+                            })
+                    },
+                );
+                // Nothing is serialized without a cache, so the size of the contract code is used.
+                Ok((artifact, code.code().len()))
+            }
+            Some(cache) => match cache.get(&key.hash()).map_err(|_io_err| CacheError::ReadError)? {
+                Some(serialized) => {
+                    metrics::record_disk_hit(VMKind::Wasmer2);
+                    Ok((deserialize_wasmer2(&serialized[..], config)?, serialized.len()))
+                }
+                None => {
+                    metrics::record_miss(VMKind::Wasmer2);
+                    compile_and_serialize_wasmer2_sized(code.code(), &key, config, cache)
+                }
             },
         }
     }
@@ -347,16 +484,33 @@ pub mod wasmer2_cache {
         code: &ContractCode,
         config: &VMConfig,
         cache: Option<&dyn CompiledContractCache>,
-    ) -> Result<Result<VMArtifact, CompilationError>, CacheError> {
-        let key = get_contract_cache_key(code, VMKind::Wasmer2, config);
+    ) -> Result<Result<(VMArtifact, ModuleSummary), CompilationError>, CacheError> {
+        let key = ContractCacheKey::new(*code.hash(), VMKind::Wasmer2, config);
 
         #[cfg(not(feature = "no_cache"))]
-        return WASMER2_CACHE.get_or_try_put(key, |key| {
-            compile_module_cached_wasmer2_impl(*key, code, config, cache)
-        });
+        return compile_module_memory_cached_wasmer2(key, code, config, cache);
 
         #[cfg(feature = "no_cache")]
-        return compile_module_cached_wasmer2_impl(key, code, config, cache);
+        return compile_module_cached_wasmer2_impl(key, code, config, cache)
+            .map(|(artifact, _size)| artifact);
+    }
+
+    #[cfg(not(feature = "no_cache"))]
+    fn compile_module_memory_cached_wasmer2(
+        key: ContractCacheKey,
+        code: &ContractCode,
+        config: &VMConfig,
+        cache: Option<&dyn CompiledContractCache>,
+    ) -> Result<Result<(VMArtifact, ModuleSummary), CompilationError>, CacheError> {
+        let hash = key.hash();
+        if let Some(artifact) = WASMER2_CACHE.get(&hash) {
+            metrics::record_memory_hit(VMKind::Wasmer2);
+            return Ok(artifact);
+        }
+        let (artifact, size) = compile_module_cached_wasmer2_impl(key, code, config, cache)?;
+        WASMER2_CACHE.put(hash, artifact.clone(), size);
+        metrics::COMPILED_CONTRACT_CACHE_MEMORY_BYTES.set(WASMER2_CACHE.size() as i64);
+        Ok(artifact)
     }
 }
 
@@ -370,9 +524,9 @@ pub fn precompile_contract_vm(
         None => return Ok(Ok(ContractPrecompilatonResult::CacheNotAvailable)),
         Some(it) => it,
     };
-    let key = get_contract_cache_key(wasm_code, vm_kind, config);
+    let key = ContractCacheKey::new(*wasm_code.hash(), vm_kind, config);
     // Check if we already cached with such a key.
-    match cache.get(&key).map_err(|_io_error| CacheError::ReadError)? {
+    match cache.get(&key.hash()).map_err(|_io_error| CacheError::ReadError)? {
         // If so - do not override.
         Some(_) => return Ok(Ok(ContractPrecompilatonResult::ContractAlreadyInCache)),
         None => {}
@@ -396,6 +550,19 @@ pub fn precompile_contract_vm(
     }
 }
 
+/// Returns whether contracts compiled for `protocol_version` with `config` are reused without
+/// recompilation for `other_protocol_version` with `other_config`.
+pub fn is_compiled_code_reusable(
+    protocol_version: ProtocolVersion,
+    config: &VMConfig,
+    other_protocol_version: ProtocolVersion,
+    other_config: &VMConfig,
+) -> bool {
+    VMKind::for_protocol_version(protocol_version)
+        == VMKind::for_protocol_version(other_protocol_version)
+        && config.non_crypto_hash() == other_config.non_crypto_hash()
+}
+
 /// Precompiles contract for the current default VM, and stores result to the cache.
 /// Returns `Ok(true)` if compiled code was added to the cache, and `Ok(false)` if element
 /// is already in the cache, or if cache is `None`.
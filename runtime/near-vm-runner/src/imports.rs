@@ -205,6 +205,21 @@ imports! {
     promise_results_count<[] -> [u64]>,
     promise_result<[result_idx: u64, register_id: u64] -> [u64]>,
     promise_return<[promise_idx: u64] -> []>,
+    #["protocol_feature_yield_resume", YieldResume] promise_yield_create<[
+        method_name_len: u64,
+        method_name_ptr: u64,
+        arguments_len: u64,
+        arguments_ptr: u64,
+        gas: u64,
+        gas_weight: u64,
+        register_id: u64
+    ] -> [u64]>,
+    #["protocol_feature_yield_resume", YieldResume] promise_yield_resume<[
+        data_id_len: u64,
+        data_id_ptr: u64,
+        payload_len: u64,
+        payload_ptr: u64
+    ] -> [u64]>,
     // ###############
     // # Storage API #
     // ###############
@@ -274,7 +289,12 @@ pub(crate) mod wasmer {
                         Some(tracing::trace_span!(target: "host-function", stringify!($func)).entered())
                     };
                     let logic: &mut VMLogic<'_> = unsafe { &mut *(ctx.data as *mut VMLogic<'_>) };
-                    logic.$func( $( $arg_name, )* )
+                    let result = logic.$func( $( $arg_name, )* );
+                    #[cfg(any(test, feature = "execution_trace"))]
+                    if !IS_GAS {
+                        crate::trace::record_host_call(stringify!($func), &[$( $arg_name as u64 ),*], &result, logic);
+                    }
+                    result
                 }
 
                 ns.insert(stringify!($func), wasmer_runtime::func!($func));
@@ -378,7 +398,18 @@ pub(crate) mod wasmer2 {
                             // lifetime and so it is safe to dereference the `env` pointer which is
                             // known to be derived from a valid `&'vmlogic mut VMLogic<'_>` in the
                             // first place.
-                            unsafe { (*env).$func( $( $arg_name, )* ) }
+                            let logic = unsafe { &mut *env };
+                            let result = logic.$func( $( $arg_name, )* );
+                            #[cfg(any(test, feature = "execution_trace"))]
+                            if !IS_GAS {
+                                crate::trace::record_host_call(
+                                    stringify!($func),
+                                    &[$( $arg_name as u64 ),*],
+                                    &result,
+                                    logic,
+                                );
+                            }
+                            result
                         }));
                         // We want to ensure that the only kind of error that host function calls
                         // return are VMLogicError. This is important because we later attempt to
@@ -498,7 +529,12 @@ pub(crate) mod wasmtime {
                         crate::wasmtime_runner::CALLER.with(|runner_caller| *runner_caller.borrow_mut() = std::mem::transmute(caller));
                     }
                     let logic: &mut VMLogic<'_> = unsafe { &mut *(data as *mut VMLogic<'_>) };
-                    match logic.$func( $( $arg_name as $arg_type, )* ) {
+                    let result = logic.$func( $( $arg_name as $arg_type, )* );
+                    #[cfg(any(test, feature = "execution_trace"))]
+                    if !IS_GAS {
+                        crate::trace::record_host_call(stringify!($func), &[$( $arg_name as u64 ),*], &result, logic);
+                    }
+                    match result {
                         Ok(result) => Ok(result as ($( $returns ),* ) ),
                         Err(err) => {
                             // Wasmtime doesn't have proper mechanism for wrapping custom errors
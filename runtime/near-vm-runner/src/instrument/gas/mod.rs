@@ -265,6 +265,76 @@ fn add_grow_counter<R: Rules>(
     b.build()
 }
 
+/// Inserts a call to the bulk memory counter before every bulk operation which copies or fills a
+/// number of bytes (or table elements) given by its last operand.
+fn inject_bulk_counter(instructions: &mut elements::Instructions, bulk_counter_func: u32) -> usize {
+    use parity_wasm::elements::BulkInstruction::*;
+    use parity_wasm::elements::Instruction::*;
+    let mut counter = 0;
+    let original_instrs = mem::take(instructions.elements_mut());
+    let new_instrs = instructions.elements_mut();
+    new_instrs.reserve(original_instrs.len());
+    for instruction in original_instrs {
+        if let Bulk(MemoryInit(_) | MemoryCopy | MemoryFill | TableInit(_) | TableCopy) =
+            instruction
+        {
+            new_instrs.push(Call(bulk_counter_func));
+            counter += 1;
+        }
+        new_instrs.push(instruction);
+    }
+    counter
+}
+
+fn add_bulk_counter<R: Rules>(
+    module: elements::Module,
+    rules: &R,
+    gas_func: u32,
+) -> elements::Module {
+    use parity_wasm::elements::Instruction::*;
+    use rules::BulkMemoryCost;
+
+    let bytes_per_unit = match rules.bulk_memory_cost() {
+        None => return module,
+        Some(BulkMemoryCost::BytesPerUnit(val)) => val.get(),
+    };
+
+    // Takes the length operand of the bulk operation, charges for it and leaves it on the stack.
+    // The charge is `len / bytes_per_unit` rounded up, and at least one unit even for an empty
+    // operation, computed as `len / bytes_per_unit + (len % bytes_per_unit != 0 | len == 0)` so
+    // that it can't overflow.
+    let mut b = builder::from_module(module);
+    b.push_function(
+        builder::function()
+            .signature()
+            .with_param(ValueType::I32)
+            .with_result(ValueType::I32)
+            .build()
+            .body()
+            .with_instructions(elements::Instructions::new(vec![
+                GetLocal(0),
+                GetLocal(0),
+                I32Const(bytes_per_unit as i32),
+                I32DivU,
+                GetLocal(0),
+                I32Const(bytes_per_unit as i32),
+                I32RemU,
+                I32Const(0),
+                I32Ne,
+                GetLocal(0),
+                I32Eqz,
+                I32Or,
+                I32Add,
+                Call(gas_func),
+                End,
+            ]))
+            .build()
+            .build(),
+    );
+
+    b.build()
+}
+
 pub(crate) fn determine_metered_blocks<R: Rules>(
     instructions: &elements::Instructions,
     rules: &R,
@@ -417,7 +487,8 @@ fn insert_metering_calls(
 /// Additionally, each `memory.grow` instruction found in the module is instrumented to first make
 /// a call to charge gas for the additional pages requested. This cannot be done as part of the
 /// block level gas charges as the gas cost is not static and depends on the stack argument to
-/// `memory.grow`.
+/// `memory.grow`. In the same way, bulk memory operations which copy or fill memory (or tables)
+/// are instrumented to charge gas proportional to their length operand.
 ///
 /// The above transformations are performed for every function body defined in the module. This
 /// function also rewrites all function indices references by code, table elements, etc., since
@@ -449,7 +520,18 @@ pub fn inject_gas_counter<R: Rules>(
 
     let gas_func = module.import_count(elements::ImportCountType::Function) as u32 - 1;
     let total_func = module.functions_space() as u32;
-    let mut need_grow_counter = false;
+    // The counter functions are appended after all the existing functions, the grow counter first.
+    let need_grow_counter =
+        rules.memory_grow_cost().is_some()
+            && module.code_section().map_or(false, |code_section| {
+                code_section.bodies().iter().any(|func_body| {
+                    func_body.code().elements().iter().any(|instruction| {
+                        matches!(instruction, elements::Instruction::GrowMemory(_))
+                    })
+                })
+            });
+    let bulk_counter_func = total_func + need_grow_counter as u32;
+    let mut need_bulk_counter = false;
     let mut error = false;
 
     // Updating calling addresses (all calls to function index >= `gas_func` should be incremented)
@@ -462,10 +544,13 @@ pub fn inject_gas_counter<R: Rules>(
                         error = true;
                         break;
                     }
-                    if rules.memory_grow_cost().is_some()
-                        && inject_grow_counter(func_body.code_mut(), total_func) > 0
+                    if need_grow_counter {
+                        inject_grow_counter(func_body.code_mut(), total_func);
+                    }
+                    if rules.bulk_memory_cost().is_some()
+                        && inject_bulk_counter(func_body.code_mut(), bulk_counter_func) > 0
                     {
-                        need_grow_counter = true;
+                        need_bulk_counter = true;
                     }
                 }
             }
@@ -504,10 +589,12 @@ pub fn inject_gas_counter<R: Rules>(
     }
 
     if need_grow_counter {
-        Ok(add_grow_counter(module, rules, gas_func))
-    } else {
-        Ok(module)
+        module = add_grow_counter(module, rules, gas_func);
     }
+    if need_bulk_counter {
+        module = add_bulk_counter(module, rules, gas_func);
+    }
+    Ok(module)
 }
 
 #[cfg(test)]
@@ -598,6 +685,146 @@ mod tests {
         wasmparser::validate(&binary).unwrap();
     }
 
+    #[test]
+    fn simple_bulk_memory() {
+        let module = builder::module()
+            .memory()
+            .build()
+            .function()
+            .signature()
+            .with_params(vec![ValueType::I32; 3])
+            .build()
+            .body()
+            .with_instructions(elements::Instructions::new(vec![
+                GetLocal(0),
+                GetLocal(1),
+                GetLocal(2),
+                Bulk(elements::BulkInstruction::MemoryFill),
+                End,
+            ]))
+            .build()
+            .build()
+            .build();
+
+        let injected_module =
+            inject_gas_counter(module, &rules::Set::default().with_bulk_memory_cost(8), "env")
+                .unwrap();
+
+        assert_eq!(
+            get_function_body(&injected_module, 0).unwrap(),
+            &vec![
+                I32Const(4),
+                Call(0),
+                GetLocal(0),
+                GetLocal(1),
+                GetLocal(2),
+                Call(2),
+                Bulk(elements::BulkInstruction::MemoryFill),
+                End
+            ][..]
+        );
+        assert_eq!(
+            get_function_body(&injected_module, 1).unwrap(),
+            &vec![
+                GetLocal(0),
+                GetLocal(0),
+                I32Const(8),
+                I32DivU,
+                GetLocal(0),
+                I32Const(8),
+                I32RemU,
+                I32Const(0),
+                I32Ne,
+                GetLocal(0),
+                I32Eqz,
+                I32Or,
+                I32Add,
+                Call(0),
+                End
+            ][..]
+        );
+
+        let binary = serialize(injected_module).expect("serialization failed");
+        wasmparser::validate(&binary).unwrap();
+    }
+
+    #[test]
+    fn grow_and_bulk_memory() {
+        let module = builder::module()
+            .memory()
+            .build()
+            .function()
+            .signature()
+            .with_params(vec![ValueType::I32; 3])
+            .build()
+            .body()
+            .with_instructions(elements::Instructions::new(vec![
+                GetLocal(0),
+                GetLocal(1),
+                GetLocal(2),
+                Bulk(elements::BulkInstruction::MemoryFill),
+                GetLocal(0),
+                GrowMemory(0),
+                Drop,
+                End,
+            ]))
+            .build()
+            .build()
+            .build();
+
+        let injected_module = inject_gas_counter(
+            module,
+            &rules::Set::default().with_grow_cost(10000).with_bulk_memory_cost(8),
+            "env",
+        )
+        .unwrap();
+
+        assert_eq!(
+            get_function_body(&injected_module, 0).unwrap(),
+            &vec![
+                I32Const(7),
+                Call(0),
+                GetLocal(0),
+                GetLocal(1),
+                GetLocal(2),
+                Call(3),
+                Bulk(elements::BulkInstruction::MemoryFill),
+                GetLocal(0),
+                Call(2),
+                Drop,
+                End
+            ][..]
+        );
+        assert_eq!(
+            get_function_body(&injected_module, 1).unwrap(),
+            &vec![GetLocal(0), GetLocal(0), I32Const(10000), I32Mul, Call(0), GrowMemory(0), End,]
+                [..]
+        );
+        assert_eq!(
+            get_function_body(&injected_module, 2).unwrap(),
+            &vec![
+                GetLocal(0),
+                GetLocal(0),
+                I32Const(8),
+                I32DivU,
+                GetLocal(0),
+                I32Const(8),
+                I32RemU,
+                I32Const(0),
+                I32Ne,
+                GetLocal(0),
+                I32Eqz,
+                I32Or,
+                I32Add,
+                Call(0),
+                End
+            ][..]
+        );
+
+        let binary = serialize(injected_module).expect("serialization failed");
+        wasmparser::validate(&binary).unwrap();
+    }
+
     #[test]
     fn call_index() {
         let module = builder::module()
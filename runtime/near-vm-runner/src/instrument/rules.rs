@@ -23,6 +23,15 @@ pub trait Rules {
     /// those costs depend on the stack and must be injected as code into the function calling
     /// `memory.grow`. Therefore returning `Some` comes with a performance cost.
     fn memory_grow_cost(&self) -> Option<MemoryGrowCost>;
+
+    /// Returns the costs for copying and filling memory or tables using the bulk memory
+    /// operations.
+    ///
+    /// Similarly to `memory_grow_cost`, these costs are in addition to the costs specified by
+    /// `instruction_cost` and depend on the length operand of the operation, so they are charged
+    /// by code injected before each such operation. Specifying `None` leads to no additional
+    /// charge.
+    fn bulk_memory_cost(&self) -> Option<BulkMemoryCost>;
 }
 
 /// Dynamic costs for memory growth.
@@ -32,6 +41,14 @@ pub enum MemoryGrowCost {
     Linear(NonZeroU32),
 }
 
+/// Dynamic costs for bulk memory operations.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum BulkMemoryCost {
+    /// Charge one unit for each specified number of bytes (or table elements) that are copied or
+    /// filled, rounded up. At least one unit is charged for every operation.
+    BytesPerUnit(NonZeroU32),
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 #[allow(unused)]
 pub enum Metering {
@@ -63,6 +80,7 @@ pub enum InstructionType {
     Nop,
     CurrentMemory,
     GrowMemory,
+    BulkMemory,
 }
 
 impl FromStr for InstructionType {
@@ -90,6 +108,7 @@ impl FromStr for InstructionType {
             "nop" => Ok(InstructionType::Nop),
             "current_mem" => Ok(InstructionType::CurrentMemory),
             "grow_mem" => Ok(InstructionType::GrowMemory),
+            "bulk_mem" => Ok(InstructionType::BulkMemory),
             _ => Err(UnknownInstruction),
         }
     }
@@ -288,6 +307,10 @@ impl InstructionType {
             I64ReinterpretF64 => InstructionType::Reinterpretation,
             F32ReinterpretI32 => InstructionType::Reinterpretation,
             F64ReinterpretI64 => InstructionType::Reinterpretation,
+
+            SignExt(_) => InstructionType::Conversion,
+
+            Bulk(_) => InstructionType::BulkMemory,
         }
     }
 }
@@ -297,23 +320,29 @@ pub struct Set {
     regular: u32,
     entries: Map<InstructionType, Metering>,
     grow: u32,
+    bulk: u32,
 }
 
 impl Default for Set {
     fn default() -> Self {
-        Set { regular: 1, entries: Map::new(), grow: 0 }
+        Set { regular: 1, entries: Map::new(), grow: 0, bulk: 0 }
     }
 }
 
 impl Set {
     pub fn new(regular: u32, entries: Map<InstructionType, Metering>) -> Self {
-        Set { regular, entries, grow: 0 }
+        Set { regular, entries, grow: 0, bulk: 0 }
     }
 
     pub fn with_grow_cost(mut self, val: u32) -> Self {
         self.grow = val;
         self
     }
+
+    pub fn with_bulk_memory_cost(mut self, bytes_per_unit: u32) -> Self {
+        self.bulk = bytes_per_unit;
+        self
+    }
 }
 
 impl Rules for Set {
@@ -328,4 +357,8 @@ impl Rules for Set {
     fn memory_grow_cost(&self) -> Option<MemoryGrowCost> {
         NonZeroU32::new(self.grow).map(MemoryGrowCost::Linear)
     }
+
+    fn bulk_memory_cost(&self) -> Option<BulkMemoryCost> {
+        NonZeroU32::new(self.bulk).map(BulkMemoryCost::BytesPerUnit)
+    }
 }
@@ -120,6 +120,7 @@ impl Stack {
 
 /// This function expects the function to be validated.
 pub(crate) fn compute(func_idx: u32, module_ctx: &ModuleCtx<'_>) -> Result<u32, Error> {
+    use parity_wasm::elements::BulkInstruction::*;
     use parity_wasm::elements::Instruction::*;
 
     let module = module_ctx.module;
@@ -394,6 +395,19 @@ pub(crate) fn compute(func_idx: u32, module_ctx: &ModuleCtx<'_>) -> Result<u32,
                 stack.pop_values(1)?;
                 stack.push_values(1)?;
             }
+
+            SignExt(_) => {
+                // Sign-extension operators take one value and produce one result.
+                stack.pop_values(1)?;
+                stack.push_values(1)?;
+            }
+
+            Bulk(MemoryInit(_) | MemoryCopy | MemoryFill | TableInit(_) | TableCopy) => {
+                // These instructions pop the destination, the source (or the value) and the
+                // length.
+                stack.pop_values(3)?;
+            }
+            Bulk(MemoryDrop(_) | TableDrop(_)) => {}
         }
         pc += 1;
     }
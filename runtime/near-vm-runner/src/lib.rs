@@ -6,10 +6,15 @@ mod imports;
 mod instrument;
 #[cfg(all(feature = "wasmer0_vm", target_arch = "x86_64"))]
 mod memory;
+#[cfg(target_arch = "x86_64")]
+mod metrics;
 pub mod prepare;
 mod runner;
 #[cfg(test)]
 mod tests;
+// Traces are always recorded in tests, so that they are covered without the feature.
+#[cfg(any(test, feature = "execution_trace"))]
+mod trace;
 mod vm_kind;
 #[cfg(all(feature = "wasmer2_vm", target_arch = "x86_64"))]
 mod wasmer2_runner;
@@ -18,13 +23,17 @@ mod wasmer_runner;
 #[cfg(feature = "wasmtime_vm")]
 mod wasmtime_runner;
 
+pub use errors::ContractPrecompilatonResult;
 pub use near_vm_errors::VMError;
 pub use near_vm_logic::with_ext_cost_counter;
 
 pub use cache::{
-    get_contract_cache_key, precompile_contract, precompile_contract_vm, MockCompiledContractCache,
+    get_contract_cache_key, is_compiled_code_reusable, is_stale_cache_entry, precompile_contract,
+    precompile_contract_vm, MockCompiledContractCache,
 };
 pub use runner::{run, VMResult, VM};
+#[cfg(feature = "execution_trace")]
+pub use trace::{with_execution_trace, ExecutionTrace, TraceEvent};
 
 /// This is public for internal experimentation use only, and should otherwise be considered an
 /// implementation detail of `near-vm-runner`.
@@ -0,0 +1,41 @@
+use crate::vm_kind::VMKind;
+use near_metrics::{try_create_int_counter_vec, try_create_int_gauge, IntCounterVec, IntGauge};
+use once_cell::sync::Lazy;
+
+pub static COMPILED_CONTRACT_CACHE_HITS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_vm_compiled_contract_cache_hits_total",
+        "The number of times a compiled contract was found in the in-memory cache or on disk",
+        &["vm_kind", "layer"],
+    )
+    .unwrap()
+});
+
+pub static COMPILED_CONTRACT_CACHE_MISSES_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_vm_compiled_contract_cache_misses_total",
+        "The number of times a contract had to be compiled because it was not in the cache",
+        &["vm_kind"],
+    )
+    .unwrap()
+});
+
+pub static COMPILED_CONTRACT_CACHE_MEMORY_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_vm_compiled_contract_cache_memory_bytes",
+        "Approximate size of the compiled contracts kept in the in-memory cache",
+    )
+    .unwrap()
+});
+
+pub(crate) fn record_memory_hit(vm_kind: VMKind) {
+    COMPILED_CONTRACT_CACHE_HITS_TOTAL.with_label_values(&[vm_kind.as_str(), "memory"]).inc();
+}
+
+pub(crate) fn record_disk_hit(vm_kind: VMKind) {
+    COMPILED_CONTRACT_CACHE_HITS_TOTAL.with_label_values(&[vm_kind.as_str(), "disk"]).inc();
+}
+
+pub(crate) fn record_miss(vm_kind: VMKind) {
+    COMPILED_CONTRACT_CACHE_MISSES_TOTAL.with_label_values(&[vm_kind.as_str()]).inc();
+}
@@ -2,9 +2,10 @@
 //! wasm module before execution.
 
 use near_vm_errors::PrepareError;
-use near_vm_logic::VMConfig;
+use near_vm_logic::{ModuleSummary, VMConfig, WasmFeaturesVersion};
 use parity_wasm::builder;
-use parity_wasm::elements::{self, External, MemorySection};
+use parity_wasm::elements::{self, BulkInstruction, External, Instruction, MemorySection, Section};
+use std::borrow::Cow;
 
 pub(crate) const WASM_FEATURES: wasmparser::WasmFeatures = wasmparser::WasmFeatures {
     reference_types: false,
@@ -21,6 +22,17 @@ pub(crate) const WASM_FEATURES: wasmparser::WasmFeatures = wasmparser::WasmFeatu
     memory64: false,
 };
 
+/// Returns the WebAssembly features which contracts are allowed to use with the given version.
+///
+/// Note that `wasmparser` always accepts the sign-extension operators, so they are rejected for
+/// older versions while preparing the contract instead.
+pub(crate) fn wasm_features(version: WasmFeaturesVersion) -> wasmparser::WasmFeatures {
+    match version {
+        WasmFeaturesVersion::V0 => WASM_FEATURES,
+        WasmFeaturesVersion::V1 => wasmparser::WasmFeatures { bulk_memory: true, ..WASM_FEATURES },
+    }
+}
+
 /// Decode and validate the provided WebAssembly code with the `wasmparser` crate.
 ///
 /// This function will return the number of functions defined globally in the provided WebAssembly
@@ -28,10 +40,11 @@ pub(crate) const WASM_FEATURES: wasmparser::WasmFeatures = wasmparser::WasmFeatu
 /// `None` is returned in its place.
 fn wasmparser_decode(
     code: &[u8],
+    features: wasmparser::WasmFeatures,
 ) -> Result<(Option<u64>, Option<u64>), wasmparser::BinaryReaderError> {
     use wasmparser::{ImportSectionEntryType, ValidPayload};
     let mut validator = wasmparser::Validator::new();
-    validator.wasm_features(WASM_FEATURES);
+    validator.wasm_features(features);
     let mut function_count = Some(0u64);
     let mut local_count = Some(0u64);
     for payload in wasmparser::Parser::new(0).parse_all(code) {
@@ -74,7 +87,8 @@ fn wasmparser_decode(
 }
 
 fn validate_contract(code: &[u8], config: &VMConfig) -> Result<(), PrepareError> {
-    let (function_count, local_count) = wasmparser_decode(code).map_err(|e| {
+    let features = wasm_features(config.limit_config.wasm_features_version);
+    let (function_count, local_count) = wasmparser_decode(code, features).map_err(|e| {
         tracing::debug!(err=?e, "wasmparser failed decoding a contract");
         PrepareError::Deserialization
     })?;
@@ -126,6 +140,48 @@ pub fn prepare_contract(original_code: &[u8], config: &VMConfig) -> Result<Vec<u
     }
 }
 
+/// Computes the shape of the module given in `code`, which determines the cost of instantiating
+/// the contract.
+///
+/// The summary describes the original code rather than the prepared one, so it doesn't depend on
+/// what has been injected into the module while it was prepared.
+pub fn module_summary(code: &[u8]) -> Result<ModuleSummary, PrepareError> {
+    use wasmparser::{ImportSectionEntryType, Payload};
+    let mut summary = ModuleSummary::default();
+    let mut decode = || -> Result<(), wasmparser::BinaryReaderError> {
+        for payload in wasmparser::Parser::new(0).parse_all(code) {
+            match payload? {
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        if let ImportSectionEntryType::Function(_) = import?.ty {
+                            summary.functions += 1;
+                        }
+                    }
+                }
+                Payload::FunctionSection(reader) => {
+                    summary.functions += u64::from(reader.get_count())
+                }
+                Payload::GlobalSection(reader) => summary.globals += u64::from(reader.get_count()),
+                Payload::TableSection(reader) => {
+                    for table in reader {
+                        summary.table_elements += u64::from(table?.limits.initial);
+                    }
+                }
+                Payload::DataSection(reader) => {
+                    summary.data_segments += u64::from(reader.get_count())
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    };
+    decode().map_err(|e| {
+        tracing::debug!(err=?e, "wasmparser failed decoding a contract");
+        PrepareError::Deserialization
+    })?;
+    Ok(summary)
+}
+
 struct ContractModule<'a> {
     module: elements::Module,
     config: &'a VMConfig,
@@ -133,10 +189,37 @@ struct ContractModule<'a> {
 
 impl<'a> ContractModule<'a> {
     fn init(original_code: &[u8], config: &'a VMConfig) -> Result<Self, PrepareError> {
-        let module = parity_wasm::deserialize_buffer(original_code).map_err(|e| {
+        let code = match config.limit_config.wasm_features_version {
+            WasmFeaturesVersion::V0 => Cow::Borrowed(original_code),
+            WasmFeaturesVersion::V1 => {
+                Cow::Owned(encode_bulk_for_parity(original_code).map_err(|e| {
+                    tracing::debug!(err=?e, "failed transcoding bulk memory operations");
+                    PrepareError::Deserialization
+                })?)
+            }
+        };
+        let mut module: elements::Module = parity_wasm::deserialize_buffer(&code).map_err(|e| {
             tracing::debug!(err=?e, "parity_wasm failed decoding a contract");
             PrepareError::Deserialization
         })?;
+        if config.limit_config.wasm_features_version == WasmFeaturesVersion::V0
+            && module.code_section().map_or(false, |code_section| {
+                code_section.bodies().iter().any(|func_body| {
+                    func_body
+                        .code()
+                        .elements()
+                        .iter()
+                        .any(|instruction| matches!(instruction, Instruction::SignExt(_)))
+                })
+            })
+        {
+            // Older versions of `parity_wasm` used to reject these operators.
+            tracing::debug!("sign-extension operators are not enabled");
+            return Err(PrepareError::Deserialization);
+        }
+        // The module builder would misplace the data count section after the data section, so
+        // it is dropped here and added back by `into_wasm_code` if the code needs it.
+        module.sections_mut().retain(|section| !matches!(section, Section::DataCount(_)));
         Ok(ContractModule { module, config })
     }
 
@@ -181,8 +264,14 @@ impl<'a> ContractModule<'a> {
         if config.regular_op_cost == 0 {
             return Ok(Self { module, config });
         }
-        let gas_rules = crate::instrument::rules::Set::new(1, Default::default())
+        let mut gas_rules = crate::instrument::rules::Set::new(1, Default::default())
             .with_grow_cost(config.grow_mem_cost);
+        if config.limit_config.wasm_features_version != WasmFeaturesVersion::V0 {
+            // A config which enables bulk memory operations must specify their cost.
+            let bytes_per_op =
+                config.bulk_memory_bytes_per_op.ok_or(PrepareError::GasInstrumentation)?;
+            gas_rules = gas_rules.with_bulk_memory_cost(bytes_per_op);
+        }
         let module = crate::instrument::gas::inject_gas_counter(module, &gas_rules, "env")
             .map_err(|_| PrepareError::GasInstrumentation)?;
         Ok(Self { module, config })
@@ -260,10 +349,177 @@ impl<'a> ContractModule<'a> {
     }
 
     fn into_wasm_code(self) -> Result<Vec<u8>, PrepareError> {
-        elements::serialize(self.module).map_err(|_| PrepareError::Serialization)
+        let Self { mut module, config } = self;
+        if config.limit_config.wasm_features_version != WasmFeaturesVersion::V0 {
+            // `memory.init` and `data.drop` are only valid if the data count section is present.
+            let uses_data_count = module.code_section().map_or(false, |code_section| {
+                code_section.bodies().iter().any(|func_body| {
+                    func_body.code().elements().iter().any(|instruction| {
+                        matches!(
+                            instruction,
+                            Instruction::Bulk(
+                                BulkInstruction::MemoryInit(_) | BulkInstruction::MemoryDrop(_)
+                            )
+                        )
+                    })
+                })
+            });
+            if uses_data_count {
+                let count = module.data_section().map_or(0, |data| data.entries().len() as u32);
+                let code_position = module
+                    .sections()
+                    .iter()
+                    .position(|section| matches!(section, Section::Code(_)))
+                    .unwrap_or(module.sections().len());
+                module.sections_mut().insert(code_position, Section::DataCount(count));
+            }
+            // The code section is serialized separately, with the final bulk memory encoding.
+            for section in module.sections_mut() {
+                if let Section::Code(code_section) = section {
+                    let payload = serialize_code_section(std::mem::take(code_section))
+                        .map_err(|_| PrepareError::Serialization)?;
+                    *section = Section::Unparsed { id: CODE_SECTION_ID, payload };
+                }
+            }
+        }
+        elements::serialize(module).map_err(|_| PrepareError::Serialization)
     }
 }
 
+const CODE_SECTION_ID: u8 = 10;
+
+/// `parity_wasm` implements an older draft of the bulk memory proposal, where `memory.copy` and
+/// `table.copy` are followed by a single reserved byte rather than by two memory or table
+/// indices, and `memory.init` and `table.init` have their immediates in the opposite order.
+///
+/// This rewrites the code section of an already validated module from the final encoding to the
+/// one `parity_wasm` expects. Memory and table indices are always zero, as contracts have at most
+/// one memory and table. `table.init` and `elem.drop` are rejected here: they operate on passive
+/// element segments, which `parity_wasm` only decodes in a draft encoding without the element
+/// kind.
+fn encode_bulk_for_parity(code: &[u8]) -> Result<Vec<u8>, String> {
+    use wasmparser::{Chunk, Operator, Parser, Payload};
+
+    let mut parser = Parser::new(0);
+    let mut offset = 0;
+    let mut section = None;
+    let mut bodies = Vec::new();
+    loop {
+        let (consumed, payload) = match parser.parse(&code[offset..], true) {
+            Ok(Chunk::Parsed { consumed, payload }) => (consumed, payload),
+            Ok(Chunk::NeedMoreData(_)) => return Err("unexpected end of module".to_string()),
+            Err(e) => return Err(e.to_string()),
+        };
+        match payload {
+            Payload::CodeSectionStart { range, .. } => section = Some(offset..range.end),
+            Payload::CodeSectionEntry(body) => {
+                let range = body.range();
+                let mut encoded = Vec::with_capacity(range.end - range.start);
+                let mut copied = range.start;
+                let mut operators = body
+                    .get_operators_reader()
+                    .map_err(|e| e.to_string())?
+                    .into_iter_with_offsets()
+                    .peekable();
+                while let Some(operator) = operators.next() {
+                    let (operator, start) = operator.map_err(|e| e.to_string())?;
+                    let (opcode, segment) = match operator {
+                        Operator::MemoryInit { segment, .. } => (0x08, Some(segment)),
+                        Operator::MemoryCopy { .. } => (0x0a, None),
+                        Operator::TableCopy { .. } => (0x0e, None),
+                        Operator::TableInit { .. } | Operator::ElemDrop { .. } => {
+                            return Err(format!("unsupported operator {:?}", operator))
+                        }
+                        _ => continue,
+                    };
+                    // A function body always ends with `end`, so these instructions are followed
+                    // by another operator.
+                    let end = match operators.peek() {
+                        Some(Ok((_, next))) => *next,
+                        _ => return Err("unterminated function body".to_string()),
+                    };
+                    encoded.extend_from_slice(&code[copied..start]);
+                    encoded.extend_from_slice(&[0xfc, opcode, 0x00]);
+                    if let Some(segment) = segment {
+                        write_var_u32(&mut encoded, segment as usize)?;
+                    }
+                    copied = end;
+                }
+                encoded.extend_from_slice(&code[copied..range.end]);
+                bodies.push(encoded);
+            }
+            Payload::End => break,
+            _ => {}
+        }
+        offset += consumed;
+    }
+    let section = match section {
+        Some(section) => section,
+        None => return Ok(code.to_vec()),
+    };
+
+    let mut content = Vec::new();
+    write_var_u32(&mut content, bodies.len())?;
+    for body in bodies {
+        write_var_u32(&mut content, body.len())?;
+        content.extend(body);
+    }
+    let mut result = Vec::with_capacity(code.len());
+    result.extend_from_slice(&code[..section.start]);
+    result.push(CODE_SECTION_ID);
+    write_var_u32(&mut result, content.len())?;
+    result.extend(content);
+    result.extend_from_slice(&code[section.end..]);
+    Ok(result)
+}
+
+/// Serializes the contents of the code section with the final encoding of the bulk memory
+/// proposal, which adds the second index to `memory.copy` and `table.copy` and puts the memory
+/// index of `memory.init` after the segment index.
+///
+/// See [`encode_bulk_for_parity`] for the reverse transformation.
+fn serialize_code_section(code_section: elements::CodeSection) -> Result<Vec<u8>, elements::Error> {
+    use parity_wasm::elements::BulkInstruction::{MemoryCopy, MemoryInit, TableCopy};
+    use parity_wasm::elements::{Serialize, VarUint32};
+
+    let bodies = code_section.bodies();
+    let mut content = Vec::new();
+    VarUint32::from(bodies.len()).serialize(&mut content)?;
+    for func_body in bodies {
+        let mut body = Vec::new();
+        VarUint32::from(func_body.locals().len()).serialize(&mut body)?;
+        for local in func_body.locals() {
+            (*local).serialize(&mut body)?;
+        }
+        for instruction in func_body.code().elements() {
+            match instruction {
+                Instruction::Bulk(MemoryInit(segment)) => {
+                    body.extend_from_slice(&[0xfc, 0x08]);
+                    VarUint32::from(*segment).serialize(&mut body)?;
+                    body.push(0x00);
+                }
+                Instruction::Bulk(MemoryCopy | TableCopy) => {
+                    instruction.clone().serialize(&mut body)?;
+                    body.push(0x00);
+                }
+                _ => instruction.clone().serialize(&mut body)?,
+            }
+        }
+        VarUint32::from(body.len()).serialize(&mut content)?;
+        content.extend(body);
+    }
+    let mut payload = Vec::with_capacity(content.len() + 5);
+    VarUint32::from(content.len()).serialize(&mut payload)?;
+    payload.extend(content);
+    Ok(payload)
+}
+
+fn write_var_u32(buf: &mut Vec<u8>, value: usize) -> Result<(), String> {
+    use parity_wasm::elements::{Serialize, VarUint32};
+    let value = u32::try_from(value).map_err(|e| e.to_string())?;
+    VarUint32::from(value).serialize(buf).map_err(|e| e.to_string())
+}
+
 /// Legacy validation for old protocol versions.
 mod pwasm_12 {
     use near_vm_errors::PrepareError;
@@ -508,4 +764,77 @@ mod tests {
         assert_matches!(r, Err(Error::Instantiate));
         */
     }
+
+    #[test]
+    fn bulk_memory() {
+        // The prepared code must keep both indices of `memory.copy` and `table.copy`.
+        let code = parse_and_prepare_wat(
+            r#"(module
+          (memory 1)
+          (table 1 funcref)
+          (func (export "main")
+            (memory.copy (i32.const 0) (i32.const 10) (i32.const 10))
+            (memory.fill (i32.const 0) (i32.const 1) (i32.const 10))
+            (table.copy (i32.const 0) (i32.const 0) (i32.const 1)))
+        )"#,
+        )
+        .unwrap();
+        let mut validator = wasmparser::Validator::new();
+        validator.wasm_features(wasm_features(WasmFeaturesVersion::V1));
+        validator.validate_all(&code).unwrap();
+
+        // `memory.init` keeps its segment index before the memory index, and the data count
+        // section it requires is kept before the code section.
+        let code = parse_and_prepare_wat(
+            r#"(module
+          (memory 1)
+          (data "abc")
+          (func (export "main")
+            (memory.init 0 (i32.const 0) (i32.const 0) (i32.const 3))
+            (data.drop 0))
+        )"#,
+        )
+        .unwrap();
+        let mut validator = wasmparser::Validator::new();
+        validator.wasm_features(wasm_features(WasmFeaturesVersion::V1));
+        validator.validate_all(&code).unwrap();
+
+        // The instructions operating on passive element segments aren't supported.
+        for instruction in
+            ["(table.init 0 (i32.const 0) (i32.const 0) (i32.const 1))", "(elem.drop 0)"]
+        {
+            let r = parse_and_prepare_wat(&format!(
+                r#"(module
+              (memory 1)
+              (table 1 funcref)
+              (elem func 0)
+              (func (export "main") {}))"#,
+                instruction
+            ));
+            assert_matches!(r, Err(PrepareError::Deserialization), "{}", instruction);
+        }
+    }
+
+    #[test]
+    fn summary() {
+        let wasm = wat::parse_str(
+            r#"(module
+          (import "env" "input" (func (param i64)))
+          (import "env" "memory" (memory 1))
+          (global i32 (i32.const 0))
+          (global (mut i64) (i64.const 0))
+          (table 3 10 funcref)
+          (data (i32.const 0) "abc")
+          (data (i32.const 8) "def")
+          (func (export "main"))
+          (func)
+        )"#,
+        )
+        .unwrap();
+        assert_eq!(
+            module_summary(&wasm),
+            Ok(ModuleSummary { functions: 3, globals: 2, table_elements: 3, data_segments: 2 })
+        );
+        assert_eq!(module_summary(&[0, 1, 2]), Err(PrepareError::Deserialization));
+    }
 }
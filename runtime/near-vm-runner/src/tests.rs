@@ -4,6 +4,7 @@ mod fuzzers;
 mod rs_contract;
 mod runtime_errors;
 pub(crate) mod test_builder;
+mod trace;
 mod ts_contract;
 mod wasm_validation;
 
@@ -5,7 +5,10 @@ use super::{create_context, with_vm_variants, LATEST_PROTOCOL_VERSION};
 use crate::internal::VMKind;
 use crate::runner::VMResult;
 use crate::wasmer2_runner::Wasmer2VM;
-use crate::{prepare, MockCompiledContractCache};
+use crate::{
+    get_contract_cache_key, is_stale_cache_entry, precompile_contract_vm, prepare,
+    MockCompiledContractCache,
+};
 use assert_matches::assert_matches;
 use near_primitives::contract::ContractCode;
 use near_primitives::hash::CryptoHash;
@@ -71,6 +74,27 @@ fn test_does_not_cache_io_error() {
     })
 }
 
+#[test]
+fn test_stale_cache_entries() {
+    let cache = MockCompiledContractCache::default();
+    let config = VMConfig::test();
+    let code = ContractCode::new(near_test_contracts::trivial_contract().to_vec(), None);
+    precompile_contract_vm(VMKind::Wasmer2, &code, &config, Some(&cache)).unwrap().unwrap();
+    let key = get_contract_cache_key(&code, VMKind::Wasmer2, &config);
+    let value = cache.get(&key).unwrap().unwrap();
+    assert!(!is_stale_cache_entry(key.as_ref(), &value, &[&config]));
+
+    let mut other_config = config.clone();
+    other_config.regular_op_cost += 1;
+    assert!(is_stale_cache_entry(key.as_ref(), &value, &[&other_config]));
+    assert!(!is_stale_cache_entry(key.as_ref(), &value, &[&other_config, &config]));
+
+    // Entries stored under a key which doesn't match their content or written by the older
+    // versions of the cache.
+    assert!(is_stale_cache_entry(CryptoHash::default().as_ref(), &value, &[&config]));
+    assert!(is_stale_cache_entry(key.as_ref(), &[1, 0, 0, 0, 0], &[&config]));
+}
+
 fn make_cached_contract_call_vm(
     cache: &dyn CompiledContractCache,
     code: &[u8],
@@ -835,3 +835,78 @@ mod fix_contract_loading_cost_protocol_upgrade {
             ]);
     }
 }
+
+#[cfg(feature = "protocol_feature_structured_loading_cost")]
+mod structured_loading_cost_protocol_upgrade {
+    use super::*;
+    use crate::tests::prepaid_loading_gas;
+
+    /// Two functions, two globals, three table elements and two data segments.
+    static MODULE_SHAPE_CONTRACT: &str = r#"
+(module
+  (global (mut i32) (i32.const 0))
+  (global i64 (i64.const 1))
+  (table 3 funcref)
+  (memory 1)
+  (data (i32.const 0) "a")
+  (data (i32.const 8) "b")
+  (func (export "main"))
+  (func $unused)
+)"#;
+
+    /// Cost of the shape of `MODULE_SHAPE_CONTRACT`.
+    ///
+    /// Note that this hard-codes the `wasm_contract_loading_function`,
+    /// `wasm_contract_loading_global`, `wasm_contract_loading_table_element` and
+    /// `wasm_contract_loading_data_segment` parameters.
+    const MODULE_SHAPE_GAS: u64 = 2 * 3_312_000 + 2 * 1_407_000 + 3 * 569_000 + 2 * 11_250_000;
+
+    // Executing the empty `main` costs nothing beyond loading the contract, so it runs out of
+    // gas only if the shape of the module is charged for.
+    #[test]
+    fn test_module_shape_loading_gas_protocol_upgrade() {
+        let tb = test_builder().wat(MODULE_SHAPE_CONTRACT);
+        let loading_cost = prepaid_loading_gas(tb.get_wasm().len());
+        tb.gas(loading_cost + MODULE_SHAPE_GAS)
+            .charge_module_shape()
+            .opaque_outcome()
+            .protocol_features(&[ProtocolFeature::StructuredLoadingCost])
+            .expects(&[expect![[r#""#]], expect![[r#""#]]]);
+
+        let tb = test_builder().wat(MODULE_SHAPE_CONTRACT);
+        let loading_cost = prepaid_loading_gas(tb.get_wasm().len());
+        tb.gas(loading_cost + MODULE_SHAPE_GAS - 1)
+            .charge_module_shape()
+            .opaque_outcome()
+            .protocol_features(&[ProtocolFeature::StructuredLoadingCost])
+            .expects(&[
+                expect![[r#""#]],
+                expect![[r#"
+                    Err: Exceeded the prepaid gas.
+                "#]],
+            ]);
+    }
+
+    // Imported functions count as functions of the module.
+    #[test]
+    fn test_module_shape_loading_gas_imports() {
+        let tb = test_builder().wat(
+            r#"
+(module
+  (import "env" "panic" (func $panic))
+  (func (export "main"))
+)"#,
+        );
+        let loading_cost = prepaid_loading_gas(tb.get_wasm().len());
+        tb.gas(loading_cost + 2 * 3_312_000 - 1)
+            .charge_module_shape()
+            .opaque_outcome()
+            .protocol_features(&[ProtocolFeature::StructuredLoadingCost])
+            .expects(&[
+                expect![[r#""#]],
+                expect![[r#"
+                    Err: Exceeded the prepaid gas.
+                "#]],
+            ]);
+    }
+}
@@ -36,6 +36,7 @@ pub(crate) fn test_builder() -> TestBuilder {
         skip: HashSet::new(),
         opaque_error: false,
         opaque_outcome: false,
+        charge_module_shape: false,
     }
 }
 
@@ -47,6 +48,7 @@ pub(crate) struct TestBuilder {
     skip: HashSet<VMKind>,
     opaque_error: bool,
     opaque_outcome: bool,
+    charge_module_shape: bool,
 }
 
 impl TestBuilder {
@@ -87,6 +89,16 @@ impl TestBuilder {
         self
     }
 
+    /// Charge for the functions, globals, table elements and data segments of the loaded module.
+    ///
+    /// Tests don't pay for the shape of the module by default, so that their gas expectations
+    /// don't change with `StructuredLoadingCost`, which is covered by dedicated tests.
+    #[allow(dead_code)]
+    pub(crate) fn charge_module_shape(mut self) -> Self {
+        self.charge_module_shape = true;
+        self
+    }
+
     // We only test trapping tests on Wasmer, as of version 0.17, when tests executed in parallel,
     // Wasmer signal handlers may catch signals thrown from the Wasmtime, and produce fake failing tests.
     pub(crate) fn skip_wasmtime(mut self) -> Self {
@@ -155,7 +167,13 @@ impl TestBuilder {
                 let runtime_config = runtime_config_store.get_config(protocol_version);
 
                 let mut fake_external = MockedExternal::new();
-                let config = runtime_config.wasm_config.clone();
+                let mut config = runtime_config.wasm_config.clone();
+                if !self.charge_module_shape {
+                    config.ext_costs.contract_loading_function = 0;
+                    config.ext_costs.contract_loading_global = 0;
+                    config.ext_costs.contract_loading_table_element = 0;
+                    config.ext_costs.contract_loading_data_segment = 0;
+                }
                 let fees = RuntimeFeesConfig::test();
                 let context = self.context.clone();
 
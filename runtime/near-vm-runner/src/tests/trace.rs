@@ -0,0 +1,74 @@
+use near_primitives::contract::ContractCode;
+use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_primitives::test_utils::encode;
+use near_vm_logic::mocks::mock_external::MockedExternal;
+use near_vm_logic::VMConfig;
+
+use crate::tests::{create_context, with_vm_variants, LATEST_PROTOCOL_VERSION};
+use crate::trace::{with_execution_trace, ExecutionTrace, TraceEvent};
+use crate::vm_kind::VMKind;
+
+fn trace_rs_contract(vm_kind: VMKind, method_name: &str, input: Vec<u8>) -> ExecutionTrace {
+    let code = ContractCode::new(near_test_contracts::rs_contract().to_vec(), None);
+    let mut fake_external = MockedExternal::new();
+    let fees = RuntimeFeesConfig::test();
+    let runtime = vm_kind.runtime(VMConfig::test()).expect("runtime has not been compiled");
+    let (_, trace) = with_execution_trace(|| {
+        runtime.run(
+            &code,
+            method_name,
+            &mut fake_external,
+            create_context(input),
+            &fees,
+            &[],
+            LATEST_PROTOCOL_VERSION,
+            None,
+        )
+    });
+    trace
+}
+
+#[test]
+fn test_trace_records_host_calls() {
+    with_vm_variants(|vm_kind: VMKind| {
+        let trace = trace_rs_contract(vm_kind, "write_key_value", encode(&[10u64, 20u64]));
+        let names: Vec<_> = trace
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                TraceEvent::HostCall { name, .. } => Some(*name),
+                TraceEvent::Outcome { .. } => None,
+            })
+            .collect();
+        assert!(names.contains(&"input"), "{}", trace);
+        assert!(names.contains(&"storage_write"), "{}", trace);
+        assert!(!names.contains(&"gas"), "{}", trace);
+        assert!(
+            matches!(trace.events.last(), Some(TraceEvent::Outcome { error: None, .. })),
+            "{}",
+            trace
+        );
+    });
+}
+
+#[test]
+fn test_trace_is_the_same_on_all_vms() {
+    let vm_kinds: Vec<_> = [VMKind::Wasmer0, VMKind::Wasmtime, VMKind::Wasmer2]
+        .into_iter()
+        .filter(|vm_kind| vm_kind.runtime(VMConfig::test()).is_some())
+        .collect();
+    for (method_name, input) in [
+        ("write_key_value", encode(&[10u64, 20u64])),
+        ("read_value", encode(&[10u64])),
+        ("log_something", vec![]),
+        ("abort_with_zero", vec![]),
+    ] {
+        let traces: Vec<_> = vm_kinds
+            .iter()
+            .map(|vm_kind| trace_rs_contract(*vm_kind, method_name, input.clone()).to_string())
+            .collect();
+        for (vm_kind, trace) in vm_kinds.iter().zip(&traces) {
+            assert_eq!(trace, &traces[0], "{} on {:?}", method_name, vm_kind);
+        }
+    }
+}
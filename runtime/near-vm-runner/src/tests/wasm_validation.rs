@@ -1,7 +1,9 @@
 use super::test_builder::test_builder;
 use crate::prepare::prepare_contract;
+use assert_matches::assert_matches;
 use expect_test::expect;
-use near_vm_logic::VMConfig;
+use near_vm_errors::PrepareError;
+use near_vm_logic::{VMConfig, WasmFeaturesVersion};
 
 static SIMD: &str = r#"
 (module
@@ -33,6 +35,7 @@ static REFERENCE_TYPES: &str = r#"
 
 static BULK_MEMORY: &str = r#"
 (module
+  (memory 1)
   (func $entry (result i32) i32.const 0)
   (func (export "memory.copy") (param i32 i32 i32)
     local.get 0
@@ -42,6 +45,15 @@ static BULK_MEMORY: &str = r#"
 )
 "#;
 
+static SIGN_EXTENSION: &str = r#"
+(module
+  (func $entry (result i32) i32.const 0)
+  (func (export "i32.extend8_s") (param i32) (result i32)
+    local.get 0
+    i32.extend8_s)
+)
+"#;
+
 static MULTI_VALUE: &str = r#"
 (module
   (func $entry (result i32) i32.const 0)
@@ -92,12 +104,15 @@ static EXPECTED_UNSUPPORTED: &[(&str, &str)] = &[
     // ("module_linking", MODULE_LINKING),
     ("tail_call", TAIL_CALL),
     ("multi_value", MULTI_VALUE),
-    ("bulk_memory", BULK_MEMORY),
     ("reference_types", REFERENCE_TYPES),
     ("threads", THREADS),
     ("simd", SIMD),
 ];
 
+/// Features which are supported starting with [`WasmFeaturesVersion::V1`].
+static SUPPORTED_SINCE_V1: &[(&str, &str)] =
+    &[("sign_extension", SIGN_EXTENSION), ("bulk_memory", BULK_MEMORY)];
+
 #[test]
 fn ensure_fails_verification() {
     for (feature_name, wat) in EXPECTED_UNSUPPORTED {
@@ -117,3 +132,89 @@ fn ensure_fails_execution() {
         "#]]);
     }
 }
+
+#[test]
+fn ensure_versioned_features_verification() {
+    for (feature_name, wat) in SUPPORTED_SINCE_V1 {
+        let wasm = wat::parse_str(wat).expect("parsing test wat should succeed");
+        let mut config = VMConfig::test();
+        config.limit_config.wasm_features_version = WasmFeaturesVersion::V0;
+        assert_matches!(
+            prepare_contract(&wasm, &config),
+            Err(PrepareError::Deserialization),
+            "wasm containing use of {} feature did not fail to prepare",
+            feature_name
+        );
+        config.limit_config.wasm_features_version = WasmFeaturesVersion::V1;
+        if let Err(err) = prepare_contract(&wasm, &config) {
+            panic!("wasm containing use of {} feature failed to prepare: {:?}", feature_name, err);
+        }
+    }
+}
+
+#[test]
+fn test_sign_extension_and_bulk_memory_execution() {
+    test_builder()
+        .wat(
+            r#"
+(module
+  (memory 1)
+  (func (export "main")
+    (memory.fill (i32.const 0) (i32.const 255) (i32.const 1000))
+    (memory.copy (i32.const 1000) (i32.const 0) (i32.const 1000))
+    (if (i32.ne (i32.extend8_s (i32.load8_u (i32.const 1999))) (i32.const -1))
+      (then unreachable)))
+)
+"#,
+        )
+        // Wasmer 0.x doesn't support these proposals, but it isn't used by protocol versions
+        // which enable them anyway.
+        .skip_wasmer0()
+        .opaque_error()
+        .opaque_outcome()
+        .protocol_features(&[
+            #[cfg(feature = "protocol_feature_wasm_extensions")]
+            near_primitives::version::ProtocolFeature::WasmExtensions,
+        ])
+        .expects(&[
+            expect![[r#"
+                Err: ...
+            "#]],
+            #[cfg(feature = "protocol_feature_wasm_extensions")]
+            expect![[r#""#]],
+        ]);
+}
+
+#[test]
+fn test_passive_data_segment_execution() {
+    test_builder()
+        .wat(
+            r#"
+(module
+  (memory 1)
+  (data "\ff\ff\ff")
+  (func (export "main")
+    (memory.init 0 (i32.const 1000) (i32.const 1) (i32.const 2))
+    (data.drop 0)
+    (if (i32.ne (i32.load16_u (i32.const 1000)) (i32.const 0xffff))
+      (then unreachable))
+    (if (i32.ne (i32.load8_u (i32.const 1002)) (i32.const 0))
+      (then unreachable)))
+)
+"#,
+        )
+        .skip_wasmer0()
+        .opaque_error()
+        .opaque_outcome()
+        .protocol_features(&[
+            #[cfg(feature = "protocol_feature_wasm_extensions")]
+            near_primitives::version::ProtocolFeature::WasmExtensions,
+        ])
+        .expects(&[
+            expect![[r#"
+                Err: ...
+            "#]],
+            #[cfg(feature = "protocol_feature_wasm_extensions")]
+            expect![[r#""#]],
+        ]);
+}
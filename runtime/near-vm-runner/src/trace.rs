@@ -0,0 +1,218 @@
+//! Deterministic traces of contract executions, for differential testing of
+//! the VMs.
+//!
+//! With `execution_trace` feature, and always in the tests of this crate, host
+//! functions called by the contract within [`with_execution_trace`] are
+//! recorded along with their arguments, results and the gas spent after the
+//! call.  The trace ends with the outcome of the execution.  Running the same
+//! contract on different [`VMKind`]s must produce identical traces.
+//!
+//! Only the parts of the execution which are meant to be the same on all VMs
+//! are recorded.  In particular, calls to the `gas` host function are skipped
+//! since some VMs count gas without it, and error messages coming from the VM
+//! itself are reduced to the kind of the error.
+//!
+//! [`VMKind`]: crate::internal::VMKind
+
+use near_primitives::types::{Balance, Gas, StorageUsage};
+use near_vm_errors::{FunctionCallError, VMError, VMLogicError};
+use near_vm_logic::{ReturnData, VMLogic, VMOutcome};
+use std::cell::RefCell;
+use std::fmt;
+
+use crate::VMResult;
+
+thread_local! {
+    static TRACE: RefCell<Option<Vec<TraceEvent>>> = RefCell::new(None);
+}
+
+/// Runs `f` while recording the trace of the contract execution it does, and
+/// returns its result along with the trace.
+///
+/// Executions must happen on the current thread to be recorded.
+pub fn with_execution_trace(f: impl FnOnce() -> VMResult) -> (VMResult, ExecutionTrace) {
+    let previous = TRACE.with(|trace| trace.replace(Some(Vec::new())));
+    let result = f();
+    let mut events = TRACE.with(|trace| trace.replace(previous)).unwrap_or_default();
+    events.push(TraceEvent::outcome(&result));
+    (result, ExecutionTrace { events })
+}
+
+/// Records a call to a host function, if the trace is being recorded.
+pub(crate) fn record_host_call<T: HostFunctionReturn>(
+    name: &'static str,
+    args: &[u64],
+    result: &Result<T, VMLogicError>,
+    logic: &VMLogic,
+) {
+    TRACE.with(|trace| {
+        if let Some(events) = trace.borrow_mut().as_mut() {
+            let (burnt_gas, used_gas) = logic.gas_spent();
+            events.push(TraceEvent::HostCall {
+                name,
+                args: args.to_vec(),
+                result: match result {
+                    Ok(values) => Ok(values.trace_values()),
+                    Err(err) => Err(logic_error_kind(err)),
+                },
+                burnt_gas,
+                used_gas,
+            });
+        }
+    });
+}
+
+/// Values returned by host functions.
+pub(crate) trait HostFunctionReturn {
+    fn trace_values(&self) -> Vec<u64>;
+}
+
+impl HostFunctionReturn for () {
+    fn trace_values(&self) -> Vec<u64> {
+        vec![]
+    }
+}
+
+impl HostFunctionReturn for u64 {
+    fn trace_values(&self) -> Vec<u64> {
+        vec![*self]
+    }
+}
+
+/// Trace of a contract execution.
+///
+/// The `Display` implementation gives the canonical text form of the trace,
+/// with one event per line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExecutionTrace {
+    pub events: Vec<TraceEvent>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceEvent {
+    /// Host function called by the contract.
+    HostCall {
+        name: &'static str,
+        args: Vec<u64>,
+        /// Values returned by the host function, or kind of the error it
+        /// failed with.
+        result: Result<Vec<u64>, String>,
+        /// Gas checkpoint right after the call.
+        burnt_gas: Gas,
+        used_gas: Gas,
+    },
+    /// Final outcome of the execution.
+    Outcome {
+        balance: Balance,
+        storage_usage: StorageUsage,
+        return_data: ReturnData,
+        burnt_gas: Gas,
+        used_gas: Gas,
+        logs: Vec<String>,
+        /// Receivers of the receipts created by the contract.
+        receipts: Vec<String>,
+        /// Kind of the error which aborted the execution.
+        error: Option<String>,
+    },
+}
+
+impl TraceEvent {
+    fn outcome(result: &VMResult) -> TraceEvent {
+        let VMOutcome {
+            balance,
+            storage_usage,
+            return_data,
+            burnt_gas,
+            used_gas,
+            logs,
+            action_receipts,
+            ..
+        } = result.outcome();
+        TraceEvent::Outcome {
+            balance: *balance,
+            storage_usage: *storage_usage,
+            return_data: return_data.clone(),
+            burnt_gas: *burnt_gas,
+            used_gas: *used_gas,
+            logs: logs.clone(),
+            receipts: action_receipts
+                .iter()
+                .map(|(receiver_id, _)| receiver_id.to_string())
+                .collect(),
+            error: result.error().map(error_kind),
+        }
+    }
+}
+
+fn logic_error_kind(err: &VMLogicError) -> String {
+    match err {
+        VMLogicError::HostError(err) => format!("HostError({:?})", err),
+        VMLogicError::ExternalError(_) => "ExternalError".to_string(),
+        VMLogicError::InconsistentStateError(err) => format!("InconsistentStateError({:?})", err),
+    }
+}
+
+fn error_kind(err: &VMError) -> String {
+    match err {
+        VMError::FunctionCallError(err) => match err {
+            FunctionCallError::MethodResolveError(err) => format!("MethodResolveError({:?})", err),
+            FunctionCallError::WasmTrap(err) => format!("WasmTrap({:?})", err),
+            FunctionCallError::HostError(err) => format!("HostError({:?})", err),
+            // Messages of these errors come from the VM and are different for
+            // each of them.
+            FunctionCallError::CompilationError(_) => "CompilationError".to_string(),
+            FunctionCallError::LinkError { .. } => "LinkError".to_string(),
+            FunctionCallError::WasmUnknownError { .. } => "WasmUnknownError".to_string(),
+            FunctionCallError::_EVMError => "EVMError".to_string(),
+            FunctionCallError::Nondeterministic(_) => "Nondeterministic".to_string(),
+        },
+        VMError::ExternalError(_) => "ExternalError".to_string(),
+        VMError::InconsistentStateError(err) => format!("InconsistentStateError({:?})", err),
+        VMError::CacheError(err) => format!("CacheError({:?})", err),
+    }
+}
+
+impl fmt::Display for ExecutionTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceEvent::HostCall { name, args, result, burnt_gas, used_gas } => {
+                write!(f, "call {}{:?} -> ", name, args)?;
+                match result {
+                    Ok(values) => write!(f, "ok {:?}", values)?,
+                    Err(err) => write!(f, "err {}", err)?,
+                }
+                write!(f, " burnt_gas={} used_gas={}", burnt_gas, used_gas)
+            }
+            TraceEvent::Outcome {
+                balance,
+                storage_usage,
+                return_data,
+                burnt_gas,
+                used_gas,
+                logs,
+                receipts,
+                error,
+            } => {
+                write!(
+                    f,
+                    "outcome balance={} storage_usage={} return_data={:?} burnt_gas={} \
+                     used_gas={} logs={:?} receipts={:?}",
+                    balance, storage_usage, return_data, burnt_gas, used_gas, logs, receipts
+                )?;
+                if let Some(error) = error {
+                    write!(f, " error={}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
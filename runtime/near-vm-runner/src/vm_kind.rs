@@ -1,9 +1,9 @@
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use near_primitives::checked_feature;
 use near_vm_logic::ProtocolVersion;
 use std::hash::Hash;
 
-#[derive(Clone, Copy, Debug, Hash, BorshSerialize, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Hash, BorshDeserialize, BorshSerialize, PartialEq, Eq)]
 // Note, that VMKind is part of serialization protocol, so we cannot remove entries
 // from this list if particular VM reached publicly visible networks.
 //
@@ -49,4 +49,13 @@ impl VMKind {
             VMKind::Wasmtime
         }
     }
+
+    #[cfg(target_arch = "x86_64")]
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            VMKind::Wasmer0 => "wasmer0",
+            VMKind::Wasmtime => "wasmtime",
+            VMKind::Wasmer2 => "wasmer2",
+        }
+    }
 }
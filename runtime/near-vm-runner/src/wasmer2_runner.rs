@@ -1,7 +1,8 @@
-use crate::cache::into_vm_result;
+use crate::cache::{into_vm_result, ContractCacheKey};
 use crate::imports::wasmer2::Wasmer2Imports;
-use crate::prepare::WASM_FEATURES;
+use crate::prepare::wasm_features;
 use crate::runner::VMResult;
+use crate::vm_kind::VMKind;
 use crate::{cache, imports};
 use memoffset::offset_of;
 use near_primitives::contract::ContractCode;
@@ -11,7 +12,7 @@ use near_stable_hasher::StableHasher;
 use near_vm_errors::{CompilationError, FunctionCallError, MethodResolveError, VMError, WasmTrap};
 use near_vm_logic::gas_counter::FastGasCounter;
 use near_vm_logic::types::{PromiseResult, ProtocolVersion};
-use near_vm_logic::{External, MemoryLike, VMConfig, VMContext, VMLogic};
+use near_vm_logic::{External, MemoryLike, VMConfig, VMContext, VMLogic, WasmFeaturesVersion};
 use std::hash::{Hash, Hasher};
 use std::mem::size_of;
 use std::sync::Arc;
@@ -23,18 +24,21 @@ use wasmer_vm::{
     Artifact, Instantiatable, LinearMemory, LinearTable, Memory, MemoryStyle, TrapCode, VMMemory,
 };
 
-const WASMER_FEATURES: Features = Features {
-    threads: WASM_FEATURES.threads,
-    reference_types: WASM_FEATURES.reference_types,
-    simd: WASM_FEATURES.simd,
-    bulk_memory: WASM_FEATURES.bulk_memory,
-    multi_value: WASM_FEATURES.multi_value,
-    tail_call: WASM_FEATURES.tail_call,
-    module_linking: WASM_FEATURES.module_linking,
-    multi_memory: WASM_FEATURES.multi_memory,
-    memory64: WASM_FEATURES.memory64,
-    exceptions: WASM_FEATURES.exceptions,
-};
+fn wasmer_features(version: WasmFeaturesVersion) -> Features {
+    let features = wasm_features(version);
+    Features {
+        threads: features.threads,
+        reference_types: features.reference_types,
+        simd: features.simd,
+        bulk_memory: features.bulk_memory,
+        multi_value: features.multi_value,
+        tail_call: features.tail_call,
+        module_linking: features.module_linking,
+        multi_memory: features.multi_memory,
+        memory64: features.memory64,
+        exceptions: features.exceptions,
+    }
+}
 
 #[derive(Clone)]
 pub struct Wasmer2Memory(Arc<LinearMemory>);
@@ -239,10 +243,8 @@ impl Wasmer2VM {
         let compiler = Singlepass::new();
         // We only support universal engine at the moment.
         assert_eq!(WASMER2_CONFIG.engine, WasmerEngine::Universal);
-        Self {
-            config,
-            engine: Universal::new(compiler).target(target).features(WASMER_FEATURES).engine(),
-        }
+        let features = wasmer_features(config.limit_config.wasm_features_version);
+        Self { config, engine: Universal::new(compiler).target(target).features(features).engine() }
     }
 
     pub(crate) fn new(config: VMConfig) -> Self {
@@ -495,14 +497,15 @@ impl crate::runner::VM for Wasmer2VM {
 
         let artifact =
             cache::wasmer2_cache::compile_module_cached_wasmer2(code, &self.config, cache);
-        let artifact = match into_vm_result(artifact) {
+        let (artifact, summary) = match into_vm_result(artifact) {
             Ok(it) => it,
             Err(err) => {
                 return VMResult::abort(logic, err);
             }
         };
 
-        let result = logic.after_loading_executable(current_protocol_version, code.code().len());
+        let result =
+            logic.after_loading_executable(current_protocol_version, code.code().len(), &summary);
         if let Err(e) = result {
             return VMResult::abort(logic, e);
         }
@@ -531,9 +534,10 @@ impl crate::runner::VM for Wasmer2VM {
         code_hash: &near_primitives::hash::CryptoHash,
         cache: &dyn CompiledContractCache,
     ) -> Option<VMError> {
+        let key = ContractCacheKey::new(*code_hash, VMKind::Wasmer2, &self.config);
         let result = crate::cache::wasmer2_cache::compile_and_serialize_wasmer2(
             code,
-            code_hash,
+            &key,
             &self.config,
             cache,
         );
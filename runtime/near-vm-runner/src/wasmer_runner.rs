@@ -1,8 +1,9 @@
-use crate::cache::into_vm_result;
+use crate::cache::{into_vm_result, ContractCacheKey};
 use crate::errors::IntoVMError;
 use crate::memory::WasmerMemory;
 use crate::prepare::WASM_FEATURES;
 use crate::runner::VMResult;
+use crate::vm_kind::VMKind;
 use crate::{cache, imports};
 use near_primitives::config::VMConfig;
 use near_primitives::contract::ContractCode;
@@ -291,7 +292,7 @@ impl crate::runner::VM for Wasmer0VM {
 
         // TODO: consider using get_module() here, once we'll go via deployment path.
         let module = cache::wasmer0_cache::compile_module_cached_wasmer0(code, &self.config, cache);
-        let module = match into_vm_result(module) {
+        let (module, summary) = match into_vm_result(module) {
             Ok(x) => x,
             // Note on backwards-compatibility: This error used to be an error
             // without result, later refactored to NOP outcome. Now this returns
@@ -303,7 +304,8 @@ impl crate::runner::VM for Wasmer0VM {
             Err(err) => return VMResult::abort(logic, err),
         };
 
-        let result = logic.after_loading_executable(current_protocol_version, code.code().len());
+        let result =
+            logic.after_loading_executable(current_protocol_version, code.code().len(), &summary);
         if let Err(e) = result {
             return VMResult::abort(logic, e);
         }
@@ -331,10 +333,11 @@ impl crate::runner::VM for Wasmer0VM {
         code_hash: &near_primitives::hash::CryptoHash,
         cache: &dyn CompiledContractCache,
     ) -> Option<VMError> {
+        let key = ContractCacheKey::new(*code_hash, VMKind::Wasmer0, &self.config);
         let result = crate::cache::wasmer0_cache::compile_and_serialize_wasmer(
             code,
             &self.config,
-            code_hash,
+            &key,
             cache,
         );
         into_vm_result(result).err()
@@ -1,5 +1,5 @@
 use crate::errors::IntoVMError;
-use crate::prepare::WASM_FEATURES;
+use crate::prepare::wasm_features;
 use crate::runner::VMResult;
 use crate::{imports, prepare};
 use near_primitives::config::VMConfig;
@@ -150,17 +150,18 @@ pub fn get_engine(config: &mut wasmtime::Config) -> Engine {
     Engine::new(config.strategy(wasmtime::Strategy::Lightbeam).unwrap()).unwrap()
 }
 
-pub(super) fn default_config() -> wasmtime::Config {
+pub(super) fn default_config(vm_config: &VMConfig) -> wasmtime::Config {
+    let features = wasm_features(vm_config.limit_config.wasm_features_version);
     let mut config = wasmtime::Config::default();
     config.max_wasm_stack(1024 * 1024 * 1024).unwrap(); // wasm stack metering is implemented by pwasm-utils, we don't want wasmtime to trap before that
-    config.wasm_threads(WASM_FEATURES.threads);
-    config.wasm_reference_types(WASM_FEATURES.reference_types);
-    config.wasm_simd(WASM_FEATURES.simd);
-    config.wasm_bulk_memory(WASM_FEATURES.bulk_memory);
-    config.wasm_multi_value(WASM_FEATURES.multi_value);
-    config.wasm_multi_memory(WASM_FEATURES.multi_memory);
+    config.wasm_threads(features.threads);
+    config.wasm_reference_types(features.reference_types);
+    config.wasm_simd(features.simd);
+    config.wasm_bulk_memory(features.bulk_memory);
+    config.wasm_multi_value(features.multi_value);
+    config.wasm_multi_memory(features.multi_memory);
     assert_eq!(
-        WASM_FEATURES.module_linking, false,
+        features.module_linking, false,
         "wasmtime currently does not support the module-linking feature"
     );
     config
@@ -193,7 +194,7 @@ impl crate::runner::VM for WasmtimeVM {
         current_protocol_version: ProtocolVersion,
         _cache: Option<&dyn CompiledContractCache>,
     ) -> VMResult {
-        let mut config = default_config();
+        let mut config = default_config(&self.config);
         let engine = get_engine(&mut config);
         let mut store = Store::new(&engine, ());
         let mut memory = WasmtimeMemory::new(
@@ -226,13 +227,18 @@ impl crate::runner::VM for WasmtimeVM {
             Ok(code) => code,
             Err(err) => return VMResult::abort(logic, VMError::from(err)),
         };
+        let summary = match prepare::module_summary(code.code()) {
+            Ok(summary) => summary,
+            Err(err) => return VMResult::abort(logic, VMError::from(err)),
+        };
         let module = match Module::new(&engine, prepared_code) {
             Ok(module) => module,
             Err(err) => return VMResult::abort(logic, err.into_vm_error()),
         };
         let mut linker = Linker::new(&engine);
 
-        let result = logic.after_loading_executable(current_protocol_version, code.code().len());
+        let result =
+            logic.after_loading_executable(current_protocol_version, code.code().len(), &summary);
         if let Err(e) = result {
             return VMResult::abort(logic, e);
         }
@@ -316,7 +322,7 @@ impl crate::runner::VM for WasmtimeVM {
     }
 
     fn check_compile(&self, code: &Vec<u8>) -> bool {
-        let mut config = default_config();
+        let mut config = default_config(&self.config);
         let engine = get_engine(&mut config);
         Module::new(&engine, code).is_ok()
     }
@@ -595,6 +595,32 @@ pub enum Cost {
     ///
     /// Estimation: See `ContractLoadingBase`.
     ContractLoadingPerByte,
+    /// Estimates `wasm_contract_loading_function` which is charged for each
+    /// function in a contract when it is loaded as an executable.
+    ///
+    /// Estimation: Measure the cost to execute an empty contract method on
+    /// contracts with increasing numbers of functions, subtract the per-byte
+    /// cost estimated by `ContractLoadingPerByte` and use least-squares to
+    /// calculate the cost per function.
+    ContractLoadingFunction,
+    /// Estimates `wasm_contract_loading_global` which is charged for each
+    /// global in a contract when it is loaded as an executable.
+    ///
+    /// Estimation: Like `ContractLoadingFunction`, scaling mutable globals.
+    ContractLoadingGlobal,
+    /// Estimates `wasm_contract_loading_table_element` which is charged for
+    /// each initial table element of a contract when it is loaded as an
+    /// executable.
+    ///
+    /// Estimation: Like `ContractLoadingFunction`, scaling the initial table
+    /// size.
+    ContractLoadingTableElement,
+    /// Estimates `wasm_contract_loading_data_segment` which is charged for
+    /// each data segment in a contract when it is loaded as an executable.
+    ///
+    /// Estimation: Like `ContractLoadingFunction`, scaling one-byte data
+    /// segments.
+    ContractLoadingDataSegment,
     /// Estimates the storage loading part of `wasm_contract_loading_bytes`.
     ///
     /// See comment on `ContractLoadingPerByte` why these are combined.
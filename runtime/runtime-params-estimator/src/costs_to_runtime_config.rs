@@ -40,6 +40,9 @@ pub fn costs_to_runtime_config(cost_table: &CostTable) -> anyhow::Result<Runtime
             ext_costs: ext_costs_config(cost_table)?,
             grow_mem_cost: 1,
             regular_op_cost: u32::try_from(regular_op_cost).unwrap(),
+            // Bulk memory operations are charged in regular operations, which isn't estimated
+            // separately.
+            bulk_memory_bytes_per_op: latest_runtime_config.wasm_config.bulk_memory_bytes_per_op,
             limit_config: vm_limit_config,
         },
         account_creation_config: AccountCreationConfig::default(),
@@ -93,6 +96,10 @@ fn ext_costs_config(cost_table: &CostTable) -> anyhow::Result<ExtCostsConfig> {
         base: get(Cost::HostFunctionCall)?,
         contract_loading_base: 0,
         contract_loading_bytes: 0,
+        contract_loading_function: get(Cost::ContractLoadingFunction)?,
+        contract_loading_global: get(Cost::ContractLoadingGlobal)?,
+        contract_loading_table_element: get(Cost::ContractLoadingTableElement)?,
+        contract_loading_data_segment: get(Cost::ContractLoadingDataSegment)?,
         read_memory_base: get(Cost::ReadMemoryBase)?,
         read_memory_byte: get(Cost::ReadMemoryByte)?,
         write_memory_base: get(Cost::WriteMemoryBase)?,
@@ -1,5 +1,5 @@
 use crate::config::{Config, GasMetric};
-use crate::gas_cost::{GasCost, LeastSquaresTolerance};
+use crate::gas_cost::{GasCost, LeastSquaresTolerance, NonNegativeTolerance};
 use crate::vm_estimator::create_context;
 use near_primitives::contract::ContractCode;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
@@ -34,6 +34,83 @@ pub(crate) fn contract_loading_cost(config: &Config) -> (GasCost, GasCost) {
     GasCost::least_squares_method_gas_cost(&xs, &ys, &tolerance, config.debug)
 }
 
+/// Kinds of module components charged for individually when a contract is
+/// instantiated.
+#[derive(Clone, Copy)]
+pub(crate) enum ModuleComponent {
+    Function,
+    Global,
+    TableElement,
+    DataSegment,
+}
+
+/// Estimates the instantiation cost of one module component of the given kind.
+/// The contract size is increased by adding more components of that kind and
+/// the already charged per-byte loading cost is subtracted from each sample.
+pub(crate) fn contract_loading_component_cost(
+    config: &Config,
+    component: ModuleComponent,
+    per_byte: &GasCost,
+) -> GasCost {
+    let mut xs = vec![];
+    let mut ys = vec![];
+    let repeats = config.iter_per_block as u64;
+    let warmup_repeats = config.warmup_iters_per_block as u64;
+    for count in [1, 10, 100, 500, 1000, 5000] {
+        let contract = make_many_components_contract(component, count);
+        let cost = compute_function_call_cost(
+            config.metric,
+            config.vm_kind,
+            repeats,
+            warmup_repeats,
+            &contract,
+        );
+        let bytes_cost = per_byte.clone() * contract.code().len() as u64;
+        xs.push(count as u64);
+        ys.push((cost / repeats).saturating_sub(&bytes_cost, &NonNegativeTolerance::PER_MILLE));
+    }
+
+    let tolerance = LeastSquaresTolerance::default();
+    let (_base, per_component) =
+        GasCost::least_squares_method_gas_cost(&xs, &ys, &tolerance, config.debug);
+    per_component
+}
+
+fn make_many_components_contract(component: ModuleComponent, count: u32) -> ContractCode {
+    let mut components = String::new();
+    match component {
+        ModuleComponent::Function => {
+            for i in 0..count {
+                write!(&mut components, "(func (result i32) i32.const {i})").unwrap();
+            }
+        }
+        ModuleComponent::Global => {
+            for i in 0..count {
+                write!(&mut components, "(global (mut i32) (i32.const {i}))").unwrap();
+            }
+        }
+        ModuleComponent::TableElement => {
+            write!(&mut components, "(table {count} funcref)").unwrap();
+        }
+        ModuleComponent::DataSegment => {
+            write!(&mut components, "(memory 1)").unwrap();
+            for i in 0..count {
+                write!(&mut components, "(data (i32.const {i}) \"x\")").unwrap();
+            }
+        }
+    }
+
+    let code = format!(
+        "
+        (module
+            (func (export \"hello0\"))
+            {}
+            )",
+        components
+    );
+    ContractCode::new(wat::parse_str(code).unwrap(), None)
+}
+
 fn make_many_methods_contract(method_count: i32) -> ContractCode {
     let mut methods = String::new();
     for i in 0..method_count {
@@ -77,6 +77,7 @@ use std::iter;
 use std::time::Instant;
 
 use estimator_params::sha256_cost;
+use function_call::ModuleComponent;
 use gas_cost::{LeastSquaresTolerance, NonNegativeTolerance};
 use gas_metering::gas_metering_cost;
 use near_crypto::{KeyType, SecretKey};
@@ -187,6 +188,10 @@ static ALL_COSTS: &[(Cost, fn(&mut EstimatorContext) -> GasCost)] = &[
     (Cost::DeployBytes, pure_deploy_bytes),
     (Cost::ContractLoadingBase, contract_loading_base),
     (Cost::ContractLoadingPerByte, contract_loading_per_byte),
+    (Cost::ContractLoadingFunction, contract_loading_function),
+    (Cost::ContractLoadingGlobal, contract_loading_global),
+    (Cost::ContractLoadingTableElement, contract_loading_table_element),
+    (Cost::ContractLoadingDataSegment, contract_loading_data_segment),
     (Cost::FunctionCallPerStorageByte, function_call_per_storage_byte),
     (Cost::GasMeteringBase, gas_metering_base),
     (Cost::GasMeteringOp, gas_metering_op),
@@ -715,6 +720,22 @@ fn contract_loading_base_per_byte(ctx: &mut EstimatorContext) -> (GasCost, GasCo
     ctx.cached.contract_loading_base_per_byte = Some((base.clone(), per_byte.clone()));
     (base, per_byte)
 }
+fn contract_loading_function(ctx: &mut EstimatorContext) -> GasCost {
+    contract_loading_component(ctx, ModuleComponent::Function)
+}
+fn contract_loading_global(ctx: &mut EstimatorContext) -> GasCost {
+    contract_loading_component(ctx, ModuleComponent::Global)
+}
+fn contract_loading_table_element(ctx: &mut EstimatorContext) -> GasCost {
+    contract_loading_component(ctx, ModuleComponent::TableElement)
+}
+fn contract_loading_data_segment(ctx: &mut EstimatorContext) -> GasCost {
+    contract_loading_component(ctx, ModuleComponent::DataSegment)
+}
+fn contract_loading_component(ctx: &mut EstimatorContext, component: ModuleComponent) -> GasCost {
+    let per_byte = contract_loading_per_byte(ctx);
+    crate::function_call::contract_loading_component_cost(ctx.config, component, &per_byte)
+}
 fn function_call_per_storage_byte(ctx: &mut EstimatorContext) -> GasCost {
     let vm_config = VMConfig::test();
     let n_actions = 5;
@@ -4,24 +4,32 @@ use near_crypto::PublicKey;
 use near_primitives::account::{AccessKey, AccessKeyPermission, Account};
 use near_primitives::checked_feature;
 use near_primitives::contract::ContractCode;
-use near_primitives::errors::{ActionError, ActionErrorKind, ContractCallError, RuntimeError};
+use near_primitives::errors::{
+    ActionError, ActionErrorKind, ContractCallError, IntegerOverflowError, RuntimeError,
+};
 use near_primitives::hash::CryptoHash;
-use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
+use near_primitives::receipt::{
+    ActionReceipt, DataReceipt, PromiseYieldTimeout, Receipt, ReceiptEnum,
+};
 use near_primitives::runtime::config::AccountCreationConfig;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_primitives::shard_layout::{account_id_to_shard_id, shard_id_to_receiver_account_id};
 use near_primitives::transaction::{
     Action, AddKeyAction, DeleteAccountAction, DeleteKeyAction, DeployContractAction,
-    FunctionCallAction, StakeAction, TransferAction,
+    DeploySharedContractAction, FunctionCallAction, StakeAction, TransferAction,
+    UseSharedContractAction,
 };
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::validator_stake::ValidatorStake;
-use near_primitives::types::{AccountId, BlockHeight, EpochInfoProvider, TrieCacheMode};
+use near_primitives::types::{AccountId, Balance, BlockHeight, EpochInfoProvider, TrieCacheMode};
 use near_primitives::utils::create_random_seed;
 use near_primitives::version::{
     is_implicit_account_creation_enabled, ProtocolFeature, ProtocolVersion,
     DELETE_KEY_STORAGE_USAGE_PROTOCOL_VERSION,
 };
 use near_store::{
-    get_access_key, get_code, remove_access_key, remove_account, set_access_key, set_code,
+    get_access_key, get_account_code, get_promise_yield_indices, has_shared_code,
+    remove_access_key, remove_account, remove_code, set, set_access_key, set_code, set_shared_code,
     StorageError, TrieUpdate,
 };
 use near_vm_errors::{
@@ -30,7 +38,7 @@ use near_vm_errors::{
 use near_vm_logic::types::PromiseResult;
 use near_vm_logic::VMContext;
 
-use crate::config::{safe_add_gas, RuntimeConfig};
+use crate::config::{safe_add_balance, safe_add_gas, RuntimeConfig};
 use crate::ext::{ExternalError, RuntimeExt};
 use crate::{ActionResult, ApplyState};
 use near_primitives::config::ViewConfig;
@@ -242,6 +250,30 @@ pub(crate) fn action_function_call(
     result.logs.extend(outcome.logs);
     result.profile.merge(&outcome.profile);
     if execution_succeeded {
+        let expires_at = apply_state
+            .block_index
+            .checked_add(config.wasm_config.limit_config.yield_timeout_length_in_blocks)
+            .ok_or_else(|| {
+                StorageError::StorageInconsistentState(
+                    "Yielded promise timeout height exceeded the integer limit".to_string(),
+                )
+            })?;
+        for (_, receipt) in outcome.action_receipts.iter().filter(|(_, r)| r.is_promise_yield) {
+            register_promise_yield(
+                state_update,
+                account_id,
+                receipt.input_data_ids[0],
+                expires_at,
+            )?;
+        }
+        let data_receipts = outcome.data_receipts.into_iter().map(|(data_id, data)| Receipt {
+            predecessor_id: account_id.clone(),
+            receiver_id: account_id.clone(),
+            // Actual receipt ID is set in the Runtime.apply_action_receipt(...) in the
+            // "Generating receipt IDs" section
+            receipt_id: CryptoHash::default(),
+            receipt: ReceiptEnum::Data(DataReceipt { data_id, data: Some(data) }),
+        });
         let new_receipts: Vec<_> = outcome
             .action_receipts
             .into_iter()
@@ -266,11 +298,43 @@ pub(crate) fn action_function_call(
         account.set_storage_usage(outcome.storage_usage);
         result.result = Ok(outcome.return_data);
         result.new_receipts.extend(new_receipts);
+        // Data receipts go last, so that receipt indices of the outcome stay valid.
+        result.new_receipts.extend(data_receipts);
     }
 
     Ok(())
 }
 
+/// Makes the promise yielded by `account_id` and waiting for `data_id` resumable until
+/// `expires_at`, and adds it to the timeout queue of the shard.
+pub(crate) fn register_promise_yield(
+    state_update: &mut TrieUpdate,
+    account_id: &AccountId,
+    data_id: CryptoHash,
+    expires_at: BlockHeight,
+) -> Result<(), StorageError> {
+    set(
+        state_update,
+        TrieKey::PendingPromiseYield { receiver_id: account_id.clone(), data_id },
+        &expires_at,
+    );
+    let mut indices = get_promise_yield_indices(state_update)?;
+    set(
+        state_update,
+        TrieKey::PromiseYieldTimeout { index: indices.next_available_index },
+        &PromiseYieldTimeout { account_id: account_id.clone(), data_id, expires_at },
+    );
+    indices.next_available_index =
+        indices.next_available_index.checked_add(1).ok_or_else(|| {
+            StorageError::StorageInconsistentState(
+                "Next available index for yielded promise timeout exceeded the integer limit"
+                    .to_string(),
+            )
+        })?;
+    set(state_update, TrieKey::PromiseYieldIndices, &indices);
+    Ok(())
+}
+
 pub(crate) fn action_stake(
     account: &mut Account,
     result: &mut ActionResult,
@@ -333,15 +397,22 @@ pub(crate) fn try_refund_allowance(
 ) -> Result<(), StorageError> {
     if let Some(mut access_key) = get_access_key(state_update, account_id, public_key)? {
         let mut updated = false;
-        if let AccessKeyPermission::FunctionCall(function_call_permission) =
-            &mut access_key.permission
-        {
-            if let Some(allowance) = function_call_permission.allowance.as_mut() {
-                let new_allowance = allowance.saturating_add(transfer.deposit);
-                if new_allowance > *allowance {
-                    *allowance = new_allowance;
-                    updated = true;
-                }
+        // The allowance of a session key is never refunded above its allowance per period.
+        let allowance = match &mut access_key.permission {
+            AccessKeyPermission::FunctionCall(function_call_permission) => function_call_permission
+                .allowance
+                .as_mut()
+                .map(|allowance| (allowance, Balance::MAX)),
+            AccessKeyPermission::SessionFunctionCall(session_permission) => {
+                Some((&mut session_permission.allowance, session_permission.allowance_per_period))
+            }
+            AccessKeyPermission::FullAccess => None,
+        };
+        if let Some((allowance, max_allowance)) = allowance {
+            let new_allowance = allowance.saturating_add(transfer.deposit).min(max_allowance);
+            if new_allowance > *allowance {
+                *allowance = new_allowance;
+                updated = true;
             }
         }
         if updated {
@@ -463,7 +534,7 @@ pub(crate) fn action_deploy_contract(
 ) -> Result<(), StorageError> {
     let _span = tracing::debug_span!(target: "runtime", "action_deploy_contract").entered();
     let code = ContractCode::new(deploy_contract.code.clone(), None);
-    let prev_code = get_code(state_update, account_id, Some(account.code_hash()))?;
+    let prev_code = get_account_code(state_update, account_id, Some(account.code_hash()))?;
     let prev_code_length = prev_code.map(|code| code.code().len() as u64).unwrap_or_default();
     account.set_storage_usage(account.storage_usage().saturating_sub(prev_code_length));
     account.set_storage_usage(
@@ -489,6 +560,120 @@ pub(crate) fn action_deploy_contract(
     Ok(())
 }
 
+pub(crate) fn action_deploy_shared_contract(
+    state_update: &mut TrieUpdate,
+    account: &mut Account,
+    result: &mut ActionResult,
+    account_id: &AccountId,
+    deploy_shared_contract: &DeploySharedContractAction,
+    apply_state: &ApplyState,
+    current_protocol_version: ProtocolVersion,
+    epoch_info_provider: &dyn EpochInfoProvider,
+) -> Result<(), RuntimeError> {
+    let _span = tracing::debug_span!(target: "runtime", "action_deploy_shared_contract").entered();
+    let code = ContractCode::new(deploy_shared_contract.code.clone(), None);
+    // The registry is keyed by the code hash, so the same code is stored only once. The code is
+    // distributed to all shards at once, so if it's already in the registry of this shard, the
+    // other shards have it or will receive it too.
+    if has_shared_code(state_update, code.hash())? {
+        return Ok(());
+    }
+    // The code is distributed to the registries of the other shards with receipts addressed to
+    // an account of each shard.
+    let shard_layout = epoch_info_provider.shard_layout(&apply_state.epoch_id)?;
+    let shard_id = account_id_to_shard_id(account_id, &shard_layout);
+    let distribution_receipts: Vec<Receipt> = (0..shard_layout.num_shards())
+        .filter(|&other_shard_id| other_shard_id != shard_id)
+        .filter_map(|other_shard_id| shard_id_to_receiver_account_id(other_shard_id, &shard_layout))
+        .map(|receiver_id| {
+            Receipt::new_shared_contract_distribution(&receiver_id, code.code().to_vec())
+        })
+        .collect();
+    // The registry records are not owned by any account, so rather than staking the balance for
+    // their storage, the deployer pays for the records in all shards once and the amount is burnt.
+    let storage_config = &apply_state.config.transaction_costs.storage_usage_config;
+    let num_bytes = code.code().len() as u64 + storage_config.num_extra_bytes_record;
+    let num_records = distribution_receipts.len() as u64 + 1;
+    let storage_cost = Balance::from(num_bytes)
+        .checked_mul(Balance::from(num_records))
+        .and_then(|total_bytes| total_bytes.checked_mul(apply_state.config.storage_amount_per_byte))
+        .ok_or(IntegerOverflowError {})?;
+    match account.amount().checked_sub(storage_cost) {
+        Some(amount) => account.set_amount(amount),
+        None => {
+            result.result = Err(ActionErrorKind::LackBalanceForState {
+                account_id: account_id.clone(),
+                amount: storage_cost,
+            }
+            .into());
+            return Ok(());
+        }
+    }
+    result.burnt_amount = safe_add_balance(result.burnt_amount, storage_cost)?;
+    result.new_receipts.extend(distribution_receipts);
+    set_shared_code(state_update, &code);
+    // Precompile the contract for the accounts which will use it, same as in
+    // `action_deploy_contract`.
+    precompile_contract(
+        &code,
+        &apply_state.config.wasm_config,
+        current_protocol_version,
+        apply_state.cache.as_deref(),
+    )
+    .ok();
+    Ok(())
+}
+
+/// Stores the code deployed to the shared contract registry of another shard in the registry of
+/// this shard. Its storage has been paid for by the deployer in the shard it was deployed in.
+pub(crate) fn action_distribute_shared_contract(
+    state_update: &mut TrieUpdate,
+    deploy_shared_contract: &DeploySharedContractAction,
+    apply_state: &ApplyState,
+    current_protocol_version: ProtocolVersion,
+) -> Result<(), StorageError> {
+    let _span =
+        tracing::debug_span!(target: "runtime", "action_distribute_shared_contract").entered();
+    let code = ContractCode::new(deploy_shared_contract.code.clone(), None);
+    if has_shared_code(state_update, code.hash())? {
+        return Ok(());
+    }
+    set_shared_code(state_update, &code);
+    precompile_contract(
+        &code,
+        &apply_state.config.wasm_config,
+        current_protocol_version,
+        apply_state.cache.as_deref(),
+    )
+    .ok();
+    Ok(())
+}
+
+pub(crate) fn action_use_shared_contract(
+    state_update: &mut TrieUpdate,
+    account: &mut Account,
+    result: &mut ActionResult,
+    account_id: &AccountId,
+    use_shared_contract: &UseSharedContractAction,
+) -> Result<(), StorageError> {
+    let code_hash = use_shared_contract.code_hash;
+    if !has_shared_code(state_update, &code_hash)? {
+        result.result = Err(ActionErrorKind::SharedContractDoesNotExist { code_hash }.into());
+        return Ok(());
+    }
+    // The account stops paying for the code deployed on it, the storage of the shared code has
+    // been paid for when it was deployed.
+    let prev_code = get_account_code(state_update, account_id, Some(account.code_hash()))?;
+    if let Some(prev_code) = prev_code {
+        account.set_storage_usage(
+            account.storage_usage().saturating_sub(prev_code.code().len() as u64),
+        );
+        remove_code(state_update, account_id);
+    }
+    account.set_code_hash(code_hash);
+    Ok(())
+}
+
 pub(crate) fn action_delete_account(
     state_update: &mut TrieUpdate,
     account: &mut Option<Account>,
@@ -502,7 +687,7 @@ pub(crate) fn action_delete_account(
     if current_protocol_version >= ProtocolFeature::DeleteActionRestriction.protocol_version() {
         let account = account.as_ref().unwrap();
         let mut account_storage_usage = account.storage_usage();
-        let contract_code = get_code(state_update, account_id, Some(account.code_hash()))?;
+        let contract_code = get_account_code(state_update, account_id, Some(account.code_hash()))?;
         if let Some(code) = contract_code {
             // account storage usage should be larger than code size
             let code_len = code.code().len() as u64;
@@ -620,7 +805,12 @@ pub(crate) fn check_actor_permissions(
     account_id: &AccountId,
 ) -> Result<(), ActionError> {
     match action {
-        Action::DeployContract(_) | Action::Stake(_) | Action::AddKey(_) | Action::DeleteKey(_) => {
+        Action::DeployContract(_)
+        | Action::Stake(_)
+        | Action::AddKey(_)
+        | Action::DeleteKey(_)
+        | Action::DeploySharedContract(_)
+        | Action::UseSharedContract(_) => {
             if actor_id != account_id {
                 return Err(ActionErrorKind::ActorNoPermission {
                     account_id: account_id.clone(),
@@ -712,7 +902,9 @@ pub(crate) fn check_account_existence(
         | Action::Stake(_)
         | Action::AddKey(_)
         | Action::DeleteKey(_)
-        | Action::DeleteAccount(_) => {
+        | Action::DeleteAccount(_)
+        | Action::DeploySharedContract(_)
+        | Action::UseSharedContract(_) => {
             if account.is_none() {
                 return Err(ActionErrorKind::AccountDoesNotExist {
                     account_id: account_id.clone(),
@@ -726,9 +918,17 @@ pub(crate) fn check_account_existence(
 
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
+    use near_crypto::KeyType;
+    use near_primitives::account::SessionFunctionCallPermission;
     use near_primitives::hash::hash;
+    use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
+    use near_primitives::test_utils::MockEpochInfoProvider;
     use near_primitives::trie_key::TrieKey;
+    use near_primitives::version::PROTOCOL_VERSION;
+    use near_store::get_shared_code;
     use near_store::test_utils::create_tries;
+    use std::sync::Arc;
 
     use super::*;
     use crate::near_primitives::shard_layout::ShardUId;
@@ -910,4 +1110,211 @@ mod tests {
             })
         );
     }
+
+    fn create_apply_state() -> ApplyState {
+        ApplyState {
+            block_index: 1,
+            prev_block_hash: Default::default(),
+            block_hash: Default::default(),
+            epoch_id: Default::default(),
+            epoch_height: 0,
+            gas_price: 0,
+            block_timestamp: 100,
+            gas_limit: None,
+            random_seed: Default::default(),
+            current_protocol_version: PROTOCOL_VERSION,
+            config: Arc::new(RuntimeConfig::test()),
+            cache: None,
+            is_new_chunk: true,
+            migration_data: Arc::new(MigrationData::default()),
+            migration_flags: MigrationFlags::default(),
+        }
+    }
+
+    #[test]
+    fn test_deploy_shared_contract() {
+        let tries = create_tries();
+        let mut state_update =
+            tries.new_trie_update(ShardUId::single_shard(), CryptoHash::default());
+        let apply_state = create_apply_state();
+        let account_id = "alice".parse::<AccountId>().unwrap();
+        let code = vec![1; 1_000];
+        let storage_cost = (code.len() as u64
+            + apply_state.config.transaction_costs.storage_usage_config.num_extra_bytes_record)
+            as Balance
+            * apply_state.config.storage_amount_per_byte;
+        let mut account = Account::new(storage_cost, 0, CryptoHash::default(), 100);
+        let deploy = |state_update: &mut TrieUpdate, account: &mut Account| {
+            let mut action_result = ActionResult::default();
+            action_deploy_shared_contract(
+                state_update,
+                account,
+                &mut action_result,
+                &account_id,
+                &DeploySharedContractAction { code: code.clone() },
+                &apply_state,
+                PROTOCOL_VERSION,
+                &MockEpochInfoProvider::default(),
+            )
+            .unwrap();
+            action_result
+        };
+
+        // The deployer pays for the storage once, the amount is burnt.
+        let action_result = deploy(&mut state_update, &mut account);
+        assert!(action_result.result.is_ok());
+        assert_eq!(action_result.burnt_amount, storage_cost);
+        assert_eq!(account.amount(), 0);
+        assert_eq!(account.storage_usage(), 100);
+        let code_hash = hash(&code);
+        assert_eq!(get_shared_code(&state_update, &code_hash).unwrap().unwrap().code(), &code[..]);
+
+        // The same code is only stored once, so deploying it again is free.
+        let action_result = deploy(&mut state_update, &mut account);
+        assert!(action_result.result.is_ok());
+        assert_eq!(action_result.burnt_amount, 0);
+        assert_eq!(account.amount(), 0);
+    }
+
+    #[test]
+    fn test_deploy_shared_contract_lack_balance() {
+        let tries = create_tries();
+        let mut state_update =
+            tries.new_trie_update(ShardUId::single_shard(), CryptoHash::default());
+        let apply_state = create_apply_state();
+        let account_id = "alice".parse::<AccountId>().unwrap();
+        let code = vec![1; 1_000];
+        let mut account = Account::new(100, 0, CryptoHash::default(), 100);
+
+        let mut action_result = ActionResult::default();
+        action_deploy_shared_contract(
+            &mut state_update,
+            &mut account,
+            &mut action_result,
+            &account_id,
+            &DeploySharedContractAction { code: code.clone() },
+            &apply_state,
+            PROTOCOL_VERSION,
+            &MockEpochInfoProvider::default(),
+        )
+        .unwrap();
+        assert_matches!(
+            action_result.result,
+            Err(ActionError { kind: ActionErrorKind::LackBalanceForState { .. }, .. })
+        );
+        assert_eq!(action_result.burnt_amount, 0);
+        assert_eq!(account.amount(), 100);
+        assert!(!has_shared_code(&state_update, &hash(&code)).unwrap());
+    }
+
+    #[test]
+    fn test_use_shared_contract() {
+        let tries = create_tries();
+        let mut state_update =
+            tries.new_trie_update(ShardUId::single_shard(), CryptoHash::default());
+        let account_id = "alice".parse::<AccountId>().unwrap();
+        let local_code = ContractCode::new(vec![1; 100], None);
+        set_code(&mut state_update, account_id.clone(), &local_code);
+        let shared_code = ContractCode::new(vec![2; 1_000], None);
+        set_shared_code(&mut state_update, &shared_code);
+        let mut account = Account::new(100, 0, *local_code.hash(), 200);
+
+        let mut action_result = ActionResult::default();
+        action_use_shared_contract(
+            &mut state_update,
+            &mut account,
+            &mut action_result,
+            &account_id,
+            &UseSharedContractAction { code_hash: *shared_code.hash() },
+        )
+        .unwrap();
+        assert!(action_result.result.is_ok());
+        assert_eq!(account.code_hash(), *shared_code.hash());
+        assert_eq!(account.storage_usage(), 100);
+        assert!(get_account_code(&state_update, &account_id, None).unwrap().is_none());
+        let code = near_store::get_code(&state_update, &account_id, Some(account.code_hash()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(code.code(), shared_code.code());
+    }
+
+    #[test]
+    fn test_use_missing_shared_contract() {
+        let tries = create_tries();
+        let mut state_update =
+            tries.new_trie_update(ShardUId::single_shard(), CryptoHash::default());
+        let account_id = "alice".parse::<AccountId>().unwrap();
+        let mut account = Account::new(100, 0, CryptoHash::default(), 100);
+        let code_hash = hash(&[1, 2, 3]);
+
+        let mut action_result = ActionResult::default();
+        action_use_shared_contract(
+            &mut state_update,
+            &mut account,
+            &mut action_result,
+            &account_id,
+            &UseSharedContractAction { code_hash },
+        )
+        .unwrap();
+        assert_eq!(
+            action_result.result,
+            Err(ActionError {
+                index: None,
+                kind: ActionErrorKind::SharedContractDoesNotExist { code_hash },
+            })
+        );
+        assert_eq!(account.code_hash(), CryptoHash::default());
+    }
+
+    #[test]
+    fn test_refund_session_key_allowance() {
+        let tries = create_tries();
+        let mut state_update =
+            tries.new_trie_update(ShardUId::single_shard(), CryptoHash::default());
+        let account_id: AccountId = "alice".parse().unwrap();
+        let public_key = PublicKey::empty(KeyType::ED25519);
+        let access_key = AccessKey {
+            nonce: 0,
+            permission: AccessKeyPermission::SessionFunctionCall(SessionFunctionCallPermission {
+                allowance: 70,
+                allowance_per_period: 100,
+                refill_period: 1,
+                allowance_period: 0,
+                expires_at: 1000,
+                receiver_id: "bob".to_string(),
+                method_names: vec![],
+            }),
+        };
+        set_access_key(&mut state_update, account_id.clone(), public_key.clone(), &access_key);
+
+        let allowance = |state_update: &TrieUpdate| match get_access_key(
+            state_update,
+            &account_id,
+            &public_key,
+        )
+        .unwrap()
+        .unwrap()
+        .permission
+        {
+            AccessKeyPermission::SessionFunctionCall(permission) => permission.allowance,
+            permission => panic!("Unexpected permission {:?}", permission),
+        };
+        try_refund_allowance(
+            &mut state_update,
+            &account_id,
+            &public_key,
+            &TransferAction { deposit: 20 },
+        )
+        .unwrap();
+        assert_eq!(allowance(&state_update), 90);
+        // The refund doesn't raise the allowance above the allowance per period.
+        try_refund_allowance(
+            &mut state_update,
+            &account_id,
+            &public_key,
+            &TransferAction { deposit: 20 },
+        )
+        .unwrap();
+        assert_eq!(allowance(&state_update), 100);
+    }
 }
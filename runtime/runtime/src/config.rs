@@ -11,7 +11,8 @@ pub use near_primitives::num_rational::Rational;
 pub use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::runtime::fees::{transfer_exec_fee, transfer_send_fee, RuntimeFeesConfig};
 use near_primitives::transaction::{
-    Action, AddKeyAction, DeployContractAction, FunctionCallAction, Transaction,
+    Action, AddKeyAction, DeployContractAction, DeploySharedContractAction, FunctionCallAction,
+    Transaction,
 };
 use near_primitives::types::{AccountId, Balance, Gas};
 use near_primitives::version::{is_implicit_account_creation_enabled, ProtocolVersion};
@@ -101,12 +102,7 @@ pub fn total_send_fees(
             Stake(_) => cfg.stake_cost.send_fee(sender_is_receiver),
             AddKey(AddKeyAction { access_key, .. }) => match &access_key.permission {
                 AccessKeyPermission::FunctionCall(call_perm) => {
-                    let num_bytes = call_perm
-                        .method_names
-                        .iter()
-                        // Account for null-terminating characters.
-                        .map(|name| name.as_bytes().len() as u64 + 1)
-                        .sum::<u64>();
+                    let num_bytes = method_names_num_bytes(&call_perm.method_names);
                     cfg.add_key_cost.function_call_cost.send_fee(sender_is_receiver)
                         + num_bytes
                             * cfg
@@ -117,15 +113,42 @@ pub fn total_send_fees(
                 AccessKeyPermission::FullAccess => {
                     cfg.add_key_cost.full_access_cost.send_fee(sender_is_receiver)
                 }
+                // Session keys are charged as function call keys.
+                AccessKeyPermission::SessionFunctionCall(session_perm) => {
+                    let num_bytes = method_names_num_bytes(&session_perm.method_names);
+                    cfg.add_key_cost.function_call_cost.send_fee(sender_is_receiver)
+                        + num_bytes
+                            * cfg
+                                .add_key_cost
+                                .function_call_cost_per_byte
+                                .send_fee(sender_is_receiver)
+                }
             },
             DeleteKey(_) => cfg.delete_key_cost.send_fee(sender_is_receiver),
             DeleteAccount(_) => cfg.delete_account_cost.send_fee(sender_is_receiver),
+            // Shared contracts reuse the contract deployment fees.
+            DeploySharedContract(DeploySharedContractAction { code }) => {
+                let num_bytes = code.len() as u64;
+                cfg.deploy_contract_cost.send_fee(sender_is_receiver)
+                    + cfg.deploy_contract_cost_per_byte.send_fee(sender_is_receiver) * num_bytes
+            }
+            UseSharedContract(_) => cfg.deploy_contract_cost.send_fee(sender_is_receiver),
         };
         result = safe_add_gas(result, delta)?;
     }
     Ok(result)
 }
 
+/// Number of bytes of the method names of a function call access key, which
+/// the key is charged for.
+fn method_names_num_bytes(method_names: &[String]) -> u64 {
+    method_names
+        .iter()
+        // Account for null-terminating characters.
+        .map(|name| name.as_bytes().len() as u64 + 1)
+        .sum::<u64>()
+}
+
 pub fn exec_fee(
     config: &RuntimeFeesConfig,
     action: &Action,
@@ -157,19 +180,25 @@ pub fn exec_fee(
         Stake(_) => cfg.stake_cost.exec_fee(),
         AddKey(AddKeyAction { access_key, .. }) => match &access_key.permission {
             AccessKeyPermission::FunctionCall(call_perm) => {
-                let num_bytes = call_perm
-                    .method_names
-                    .iter()
-                    // Account for null-terminating characters.
-                    .map(|name| name.as_bytes().len() as u64 + 1)
-                    .sum::<u64>();
+                let num_bytes = method_names_num_bytes(&call_perm.method_names);
                 cfg.add_key_cost.function_call_cost.exec_fee()
                     + num_bytes * cfg.add_key_cost.function_call_cost_per_byte.exec_fee()
             }
             AccessKeyPermission::FullAccess => cfg.add_key_cost.full_access_cost.exec_fee(),
+            AccessKeyPermission::SessionFunctionCall(session_perm) => {
+                let num_bytes = method_names_num_bytes(&session_perm.method_names);
+                cfg.add_key_cost.function_call_cost.exec_fee()
+                    + num_bytes * cfg.add_key_cost.function_call_cost_per_byte.exec_fee()
+            }
         },
         DeleteKey(_) => cfg.delete_key_cost.exec_fee(),
         DeleteAccount(_) => cfg.delete_account_cost.exec_fee(),
+        DeploySharedContract(DeploySharedContractAction { code }) => {
+            let num_bytes = code.len() as u64;
+            cfg.deploy_contract_cost.exec_fee()
+                + cfg.deploy_contract_cost_per_byte.exec_fee() * num_bytes
+        }
+        UseSharedContract(_) => cfg.deploy_contract_cost.exec_fee(),
     }
 }
 
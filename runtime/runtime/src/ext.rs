@@ -174,6 +174,16 @@ impl<'a> External for RuntimeExt<'a> {
         data_id
     }
 
+    fn yield_resume(&mut self, data_id: CryptoHash) -> ExtResult<bool> {
+        let key = TrieKey::PendingPromiseYield { receiver_id: self.account_id.clone(), data_id };
+        let pending =
+            self.trie_update.get_ref(&key).map(|x| x.is_some()).map_err(wrap_storage_error)?;
+        if pending {
+            self.trie_update.remove(key);
+        }
+        Ok(pending)
+    }
+
     fn get_trie_nodes_count(&self) -> TrieNodesCount {
         self.trie_update.trie.get_trie_nodes_count()
     }
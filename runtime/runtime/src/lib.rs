@@ -26,7 +26,8 @@ use near_primitives::{
     errors::{ActionError, ActionErrorKind, RuntimeError, TxExecutionError},
     hash::CryptoHash,
     receipt::{
-        ActionReceipt, DataReceipt, DelayedReceiptIndices, Receipt, ReceiptEnum, ReceivedData,
+        ActionReceipt, DataReceipt, DelayedReceiptIndices, PromiseYieldTimeout, Receipt,
+        ReceiptEnum, ReceivedData,
     },
     state_record::StateRecord,
     transaction::{
@@ -35,17 +36,18 @@ use near_primitives::{
     },
     trie_key::TrieKey,
     types::{
-        validator_stake::ValidatorStake, AccountId, Balance, EpochInfoProvider, Gas,
+        validator_stake::ValidatorStake, AccountId, Balance, BlockHeight, EpochInfoProvider, Gas,
         RawStateChangesWithTrieKey, ShardId, StateChangeCause, StateRoot,
     },
     utils::{
-        create_action_hash, create_receipt_id_from_receipt, create_receipt_id_from_transaction,
+        create_action_hash, create_receipt_id_from_data_id, create_receipt_id_from_receipt,
+        create_receipt_id_from_transaction,
     },
 };
 use near_store::{
-    get, get_account, get_postponed_receipt, get_received_data, remove_postponed_receipt, set,
-    set_account, set_postponed_receipt, set_received_data, PartialStorage, ShardTries,
-    StorageError, Trie, TrieChanges, TrieUpdate,
+    get, get_account, get_postponed_receipt, get_promise_yield_indices, get_received_data,
+    remove_postponed_receipt, set, set_account, set_postponed_receipt, set_received_data,
+    PartialStorage, ShardTries, StorageError, Trie, TrieChanges, TrieUpdate,
 };
 use near_store::{set_access_key, set_code};
 use near_vm_logic::types::PromiseResult;
@@ -74,6 +76,9 @@ pub mod state_viewer;
 mod verifier;
 
 const EXPECT_ACCOUNT_EXISTS: &str = "account exists, checked above";
+/// The maximum number of expired yielded promises processed in a chunk. The rest are left in the
+/// timeout queue for the following chunks.
+const MAX_PROMISE_YIELD_TIMEOUTS_PER_CHUNK: u64 = 100;
 
 /// Contains information to update validators accounts at the first block of a new epoch.
 #[derive(Debug)]
@@ -134,6 +139,9 @@ pub struct ActionResult {
     pub new_receipts: Vec<Receipt>,
     pub validator_proposals: Vec<ValidatorStake>,
     pub profile: ProfileData,
+    /// Tokens burnt by the actions on top of the gas, e.g. to pay for the storage of shared
+    /// contracts.
+    pub burnt_amount: Balance,
 }
 
 impl ActionResult {
@@ -151,6 +159,7 @@ impl ActionResult {
             next_result.gas_burnt_for_function_call,
         )?;
         self.gas_used = safe_add_gas(self.gas_used, next_result.gas_used)?;
+        self.burnt_amount = safe_add_balance(self.burnt_amount, next_result.burnt_amount)?;
         self.profile.merge(&next_result.profile);
         self.result = next_result.result;
         self.logs.append(&mut next_result.logs);
@@ -180,6 +189,7 @@ impl Default for ActionResult {
             new_receipts: vec![],
             validator_proposals: vec![],
             profile: Default::default(),
+            burnt_amount: 0,
         }
     }
 }
@@ -231,6 +241,8 @@ impl Runtime {
             signed_transaction,
             true,
             Some(apply_state.block_index),
+            apply_state.block_index,
+            apply_state.epoch_height,
             apply_state.current_protocol_version,
         ) {
             Ok(verification_result) => {
@@ -312,6 +324,19 @@ impl Runtime {
         let account_id = &receipt.receiver_id;
         let is_the_only_action = actions.len() == 1;
         let is_refund = AccountId::is_system(&receipt.predecessor_id);
+        // Shared contract code deployed in another shard is distributed to this shard by the system.
+        // The receiver only determines the shard, so its account is neither checked nor changed.
+        if is_refund {
+            if let Action::DeploySharedContract(deploy_shared_contract) = action {
+                action_distribute_shared_contract(
+                    state_update,
+                    deploy_shared_contract,
+                    apply_state,
+                    apply_state.current_protocol_version,
+                )?;
+                return Ok(result);
+            }
+        }
         // Account validation
         if let Err(e) = check_account_existence(
             action,
@@ -442,6 +467,27 @@ impl Runtime {
                     apply_state.current_protocol_version,
                 )?;
             }
+            Action::DeploySharedContract(deploy_shared_contract) => {
+                action_deploy_shared_contract(
+                    state_update,
+                    account.as_mut().expect(EXPECT_ACCOUNT_EXISTS),
+                    &mut result,
+                    account_id,
+                    deploy_shared_contract,
+                    apply_state,
+                    apply_state.current_protocol_version,
+                    epoch_info_provider,
+                )?;
+            }
+            Action::UseSharedContract(use_shared_contract) => {
+                action_use_shared_contract(
+                    state_update,
+                    account.as_mut().expect(EXPECT_ACCOUNT_EXISTS),
+                    &mut result,
+                    account_id,
+                    use_shared_contract,
+                )?;
+            }
         };
         Ok(result)
     }
@@ -597,6 +643,8 @@ impl Runtime {
         // Committing or rolling back state.
         match &result.result {
             Ok(_) => {
+                stats.other_burnt_amount =
+                    safe_add_balance(stats.other_burnt_amount, result.burnt_amount)?;
                 state_update.commit(StateChangeCause::ReceiptProcessing {
                     receipt_hash: receipt.get_hash(),
                 });
@@ -1335,6 +1383,18 @@ impl Runtime {
             }
         }
 
+        if checked_feature!(
+            "protocol_feature_yield_resume",
+            YieldResume,
+            apply_state.current_protocol_version
+        ) {
+            Self::process_promise_yield_timeouts(
+                &mut state_update,
+                apply_state,
+                &mut outgoing_receipts,
+            )?;
+        }
+
         if delayed_receipts_indices != initial_delayed_receipt_indices {
             set(&mut state_update, TrieKey::DelayedReceiptIndices, &delayed_receipts_indices);
         }
@@ -1407,6 +1467,84 @@ impl Runtime {
         Ok(())
     }
 
+    /// Resumes with an error the promises yielded by the accounts of the shard which weren't
+    /// resumed before their timeout.
+    ///
+    /// The timeout queue is processed in order up to the first entry which hasn't expired yet,
+    /// but at most `MAX_PROMISE_YIELD_TIMEOUTS_PER_CHUNK` entries. Entries of the promises which
+    /// were resumed in the meantime are dropped. The storage taken by a timed out promise is
+    /// released from the account which yielded it.
+    fn process_promise_yield_timeouts(
+        state_update: &mut TrieUpdate,
+        apply_state: &ApplyState,
+        outgoing_receipts: &mut Vec<Receipt>,
+    ) -> Result<(), RuntimeError> {
+        let mut indices = get_promise_yield_indices(state_update)?;
+        let initial_indices = indices.clone();
+        let last_index = indices
+            .next_available_index
+            .min(indices.first_index.saturating_add(MAX_PROMISE_YIELD_TIMEOUTS_PER_CHUNK));
+        while indices.first_index < last_index {
+            let key = TrieKey::PromiseYieldTimeout { index: indices.first_index };
+            let timeout: PromiseYieldTimeout = get(state_update, &key)?.ok_or_else(|| {
+                StorageError::StorageInconsistentState(format!(
+                    "Yielded promise timeout #{} should be in the state",
+                    indices.first_index
+                ))
+            })?;
+            if timeout.expires_at > apply_state.block_index {
+                break;
+            }
+            state_update.remove(key);
+            // Math checked above: first_index is less than next_available_index
+            indices.first_index += 1;
+
+            let pending_key = TrieKey::PendingPromiseYield {
+                receiver_id: timeout.account_id.clone(),
+                data_id: timeout.data_id,
+            };
+            if get::<BlockHeight>(state_update, &pending_key)?.is_none() {
+                continue;
+            }
+            state_update.remove(pending_key);
+            if let Some(mut account) = get_account(state_update, &timeout.account_id)? {
+                let storage_usage = PromiseYieldTimeout::storage_usage(
+                    &timeout.account_id,
+                    apply_state
+                        .config
+                        .transaction_costs
+                        .storage_usage_config
+                        .num_extra_bytes_record,
+                );
+                account.set_storage_usage(
+                    account.storage_usage().checked_sub(storage_usage).ok_or_else(|| {
+                        StorageError::StorageInconsistentState(format!(
+                            "Storage usage of {} doesn't cover its yielded promise",
+                            timeout.account_id
+                        ))
+                    })?,
+                );
+                set_account(state_update, timeout.account_id.clone(), &account);
+            }
+            outgoing_receipts.push(Receipt {
+                predecessor_id: timeout.account_id.clone(),
+                receiver_id: timeout.account_id,
+                receipt_id: create_receipt_id_from_data_id(
+                    apply_state.current_protocol_version,
+                    &timeout.data_id,
+                    &apply_state.prev_block_hash,
+                    &apply_state.block_hash,
+                ),
+                receipt: ReceiptEnum::Data(DataReceipt { data_id: timeout.data_id, data: None }),
+            });
+        }
+        if indices != initial_indices {
+            set(state_update, TrieKey::PromiseYieldIndices, &indices);
+            state_update.commit(StateChangeCause::PromiseYieldTimeouts);
+        }
+        Ok(())
+    }
+
     fn apply_state_patch(&self, state_update: &mut TrieUpdate, state_patch: SandboxStatePatch) {
         for record in state_patch.into_records() {
             match record {
@@ -1469,7 +1607,7 @@ mod tests {
     use near_primitives::transaction::{
         AddKeyAction, DeleteKeyAction, FunctionCallAction, TransferAction,
     };
-    use near_primitives::types::MerkleHash;
+    use near_primitives::types::{MerkleHash, StorageUsage};
     use near_primitives::version::PROTOCOL_VERSION;
     use near_store::set_access_key;
     use near_store::test_utils::create_tries;
@@ -2396,4 +2534,423 @@ mod tests {
             .expect("Compiled contract should be cached")
             .expect("Compilation result should be non-empty");
     }
+
+    /// Makes alice yield promises waiting for `data_ids`, paying for their storage like a contract
+    /// call would, and returns the new state root.
+    #[cfg(feature = "protocol_feature_yield_resume")]
+    fn add_promise_yields(
+        tries: &ShardTries,
+        root: CryptoHash,
+        apply_state: &ApplyState,
+        data_ids: &[(CryptoHash, BlockHeight)],
+    ) -> CryptoHash {
+        let mut state_update = tries.new_trie_update(ShardUId::single_shard(), root);
+        let mut account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+        let storage_usage = PromiseYieldTimeout::storage_usage(
+            &alice_account(),
+            apply_state.config.transaction_costs.storage_usage_config.num_extra_bytes_record,
+        );
+        for &(data_id, expires_at) in data_ids {
+            register_promise_yield(&mut state_update, &alice_account(), data_id, expires_at)
+                .unwrap();
+            account.set_storage_usage(account.storage_usage() + storage_usage);
+        }
+        set_account(&mut state_update, alice_account(), &account);
+        state_update.commit(StateChangeCause::InitialState);
+        let trie_changes = state_update.finalize().unwrap().0;
+        let (store_update, root) = tries.apply_all(&trie_changes, ShardUId::single_shard());
+        store_update.commit().unwrap();
+        root
+    }
+
+    /// Applies a chunk without receipts at every block of `block_indices`, and returns the data
+    /// ids of the timed out promises in each of them.
+    #[cfg(feature = "protocol_feature_yield_resume")]
+    fn apply_promise_yield_timeouts(
+        runtime: &Runtime,
+        tries: &ShardTries,
+        root: &mut CryptoHash,
+        apply_state: &mut ApplyState,
+        epoch_info_provider: &dyn EpochInfoProvider,
+        block_indices: impl IntoIterator<Item = BlockHeight>,
+    ) -> Vec<Vec<CryptoHash>> {
+        let mut timed_out = vec![];
+        for block_index in block_indices {
+            apply_state.block_index = block_index;
+            let apply_result = runtime
+                .apply(
+                    tries.get_trie_for_shard(ShardUId::single_shard()),
+                    *root,
+                    &None,
+                    apply_state,
+                    &[],
+                    &[],
+                    epoch_info_provider,
+                    None,
+                )
+                .unwrap();
+            let (store_update, new_root) =
+                tries.apply_all(&apply_result.trie_changes, ShardUId::single_shard());
+            store_update.commit().unwrap();
+            *root = new_root;
+
+            timed_out.push(
+                apply_result
+                    .outgoing_receipts
+                    .iter()
+                    .map(|receipt| match &receipt.receipt {
+                        ReceiptEnum::Data(DataReceipt { data_id, data: None }) => *data_id,
+                        receipt => panic!("Expected a failed data receipt, got {:?}", receipt),
+                    })
+                    .collect(),
+            );
+        }
+        timed_out
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_yield_resume")]
+    fn test_promise_yield_timeouts() {
+        let (runtime, tries, root, mut apply_state, _, epoch_info_provider) =
+            setup_runtime(to_yocto(1_000_000), 0, 10u64.pow(15));
+
+        let timed_out = hash(b"timed out");
+        let resumed = hash(b"resumed");
+        let pending = hash(b"pending");
+        let mut root = add_promise_yields(
+            &tries,
+            root,
+            &apply_state,
+            &[(timed_out, 1), (resumed, 1), (pending, 2)],
+        );
+        // Resuming the promise releases its storage.
+        let mut state_update = tries.new_trie_update(ShardUId::single_shard(), root);
+        state_update.remove(TrieKey::PendingPromiseYield {
+            receiver_id: alice_account(),
+            data_id: resumed,
+        });
+        let mut account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+        account.set_storage_usage(
+            account.storage_usage()
+                - PromiseYieldTimeout::storage_usage(
+                    &alice_account(),
+                    apply_state
+                        .config
+                        .transaction_costs
+                        .storage_usage_config
+                        .num_extra_bytes_record,
+                ),
+        );
+        set_account(&mut state_update, alice_account(), &account);
+        state_update.commit(StateChangeCause::InitialState);
+        let trie_changes = state_update.finalize().unwrap().0;
+        let (store_update, new_root) = tries.apply_all(&trie_changes, ShardUId::single_shard());
+        store_update.commit().unwrap();
+        root = new_root;
+
+        let data_ids = apply_promise_yield_timeouts(
+            &runtime,
+            &tries,
+            &mut root,
+            &mut apply_state,
+            &epoch_info_provider,
+            1..=3,
+        );
+        assert_eq!(data_ids, vec![vec![timed_out], vec![pending], vec![]]);
+
+        // The storage of all the yielded promises is released.
+        let state_update = tries.new_trie_update(ShardUId::single_shard(), root);
+        let account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+        assert_eq!(account.storage_usage(), 182);
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_yield_resume")]
+    fn test_promise_yield_timeouts_limit_per_chunk() {
+        let (runtime, tries, root, mut apply_state, _, epoch_info_provider) =
+            setup_runtime(to_yocto(1_000_000), 0, 10u64.pow(15));
+
+        let data_ids: Vec<_> = (0..MAX_PROMISE_YIELD_TIMEOUTS_PER_CHUNK + 1)
+            .map(|i| (hash(&i.to_le_bytes()), 1))
+            .collect();
+        let mut root = add_promise_yields(&tries, root, &apply_state, &data_ids);
+
+        let timed_out = apply_promise_yield_timeouts(
+            &runtime,
+            &tries,
+            &mut root,
+            &mut apply_state,
+            &epoch_info_provider,
+            1..=3,
+        );
+        // The timeouts which don't fit in the first chunk are carried over to the next one.
+        let (first, rest) = data_ids.split_at(MAX_PROMISE_YIELD_TIMEOUTS_PER_CHUNK as usize);
+        let expected: Vec<Vec<_>> = vec![
+            first.iter().map(|(data_id, _)| *data_id).collect(),
+            rest.iter().map(|(data_id, _)| *data_id).collect(),
+            vec![],
+        ];
+        assert_eq!(timed_out, expected);
+    }
+
+    /// Contract which yields a promise resumed with a fixed payload, and records the result its
+    /// callback gets under the "result" key.
+    #[cfg(feature = "protocol_feature_yield_resume")]
+    fn promise_yield_contract() -> Vec<u8> {
+        near_test_contracts::wat_contract(
+            r#"(module
+  (import "env" "read_register" (func $read_register (param i64 i64)))
+  (import "env" "storage_write" (func $storage_write (param i64 i64 i64 i64 i64) (result i64)))
+  (import "env" "storage_read" (func $storage_read (param i64 i64 i64) (result i64)))
+  (import "env" "storage_remove" (func $storage_remove (param i64 i64 i64) (result i64)))
+  (import "env" "promise_result" (func $promise_result (param i64 i64) (result i64)))
+  (import "env" "promise_yield_create"
+    (func $promise_yield_create (param i64 i64 i64 i64 i64 i64 i64) (result i64)))
+  (import "env" "promise_yield_resume"
+    (func $promise_yield_resume (param i64 i64 i64 i64) (result i64)))
+  (memory 1)
+  (data (i32.const 0) "id")
+  (data (i32.const 8) "result")
+  (data (i32.const 16) "callback")
+  (data (i32.const 32) "payload")
+  (data (i32.const 40) "timeout")
+  ;; Yields a promise calling back "callback" and stores its data id under "id".
+  (func (export "yield")
+    (drop (call $promise_yield_create
+      (i64.const 8) (i64.const 16) (i64.const 0) (i64.const 0)
+      (i64.const 0) (i64.const 1) (i64.const 0)))
+    (call $read_register (i64.const 0) (i64.const 64))
+    (drop (call $storage_write (i64.const 2) (i64.const 0) (i64.const 32) (i64.const 64) (i64.const 1))))
+  ;; Resumes the promise with "payload".
+  (func (export "resume")
+    (drop (call $storage_read (i64.const 2) (i64.const 0) (i64.const 0)))
+    (call $read_register (i64.const 0) (i64.const 64))
+    (drop (call $promise_yield_resume (i64.const 32) (i64.const 64) (i64.const 7) (i64.const 32))))
+  ;; Stores the payload of the promise under "result", or "timeout" if it failed.
+  (func (export "callback")
+    (drop (call $storage_remove (i64.const 2) (i64.const 0) (i64.const 0)))
+    (if (i64.eq (call $promise_result (i64.const 0) (i64.const 0)) (i64.const 1))
+      (then
+        (call $read_register (i64.const 0) (i64.const 64))
+        (drop (call $storage_write (i64.const 6) (i64.const 8) (i64.const 7) (i64.const 64) (i64.const 1))))
+      (else
+        (drop (call $storage_write (i64.const 6) (i64.const 8) (i64.const 7) (i64.const 40) (i64.const 1))))))
+)"#,
+        )
+    }
+
+    /// Yields a promise from a contract, resumes it if `resume` is set, and checks the result its
+    /// callback gets as well as the storage usage of the account along the way.
+    #[cfg(feature = "protocol_feature_yield_resume")]
+    fn check_promise_yield_callback(resume: bool, expected_result: &[u8]) {
+        let (runtime, tries, mut root, mut apply_state, signer, epoch_info_provider) =
+            setup_runtime(to_yocto(1_000_000), 0, 10u64.pow(15));
+        let mut config = RuntimeConfig::test();
+        config.wasm_config.limit_config.yield_timeout_length_in_blocks = 3;
+        let num_extra_bytes_record =
+            config.transaction_costs.storage_usage_config.num_extra_bytes_record;
+        apply_state.config = Arc::new(config);
+
+        let mut apply = |block_index: BlockHeight, receipts: &[Receipt]| {
+            apply_state.block_index = block_index;
+            apply_state.block_hash = hash(&block_index.to_le_bytes());
+            let apply_result = runtime
+                .apply(
+                    tries.get_trie_for_shard(ShardUId::single_shard()),
+                    root,
+                    &None,
+                    &apply_state,
+                    receipts,
+                    &[],
+                    &epoch_info_provider,
+                    None,
+                )
+                .unwrap();
+            let (store_update, new_root) =
+                tries.apply_all(&apply_result.trie_changes, ShardUId::single_shard());
+            store_update.commit().unwrap();
+            root = new_root;
+            let state_update = tries.new_trie_update(ShardUId::single_shard(), root);
+            let account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+            let result = state_update
+                .get(&TrieKey::ContractData {
+                    account_id: alice_account(),
+                    key: b"result".to_vec(),
+                })
+                .unwrap();
+            (apply_result.outgoing_receipts, account.storage_usage(), result)
+        };
+        let function_call = |method_name: &str| {
+            create_receipts_with_actions(
+                alice_account(),
+                signer.clone(),
+                vec![Action::FunctionCall(FunctionCallAction {
+                    method_name: method_name.to_string(),
+                    args: vec![],
+                    gas: 10u64.pow(14),
+                    deposit: 0,
+                })],
+            )
+        };
+
+        let deploy = create_receipts_with_actions(
+            alice_account(),
+            signer.clone(),
+            vec![Action::DeployContract(DeployContractAction { code: promise_yield_contract() })],
+        );
+        let (_, initial_storage_usage, _) = apply(1, &deploy);
+
+        let (mut receipts, storage_usage, _) = apply(2, &function_call("yield"));
+        assert_eq!(
+            storage_usage,
+            initial_storage_usage
+                + b"id".len() as StorageUsage
+                + CryptoHash::default().as_ref().len() as StorageUsage
+                + num_extra_bytes_record
+                + PromiseYieldTimeout::storage_usage(&alice_account(), num_extra_bytes_record)
+        );
+
+        if resume {
+            receipts.extend(function_call("resume"));
+        }
+        let mut result = None;
+        let mut storage_usage = 0;
+        for block_index in 3..10 {
+            let outcome = apply(block_index, &receipts);
+            receipts = outcome.0;
+            storage_usage = outcome.1;
+            if outcome.2.is_some() {
+                result = outcome.2;
+                break;
+            }
+        }
+        assert_eq!(result.as_deref(), Some(expected_result));
+        assert_eq!(
+            storage_usage,
+            initial_storage_usage
+                + b"result".len() as StorageUsage
+                + expected_result.len() as StorageUsage
+                + num_extra_bytes_record
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_yield_resume")]
+    fn test_promise_yield_resume_callback() {
+        check_promise_yield_callback(true, b"payload");
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_yield_resume")]
+    fn test_promise_yield_timeout_callback() {
+        check_promise_yield_callback(false, b"timeout");
+    }
+
+    /// Deploys shared contract code from an account in one shard and uses it from an account in
+    /// another shard.
+    #[test]
+    #[cfg(feature = "protocol_feature_shared_contracts")]
+    fn test_shared_contract_cross_shard() {
+        use near_primitives::shard_layout::{account_id_to_shard_uid, ShardLayout};
+        use near_primitives::transaction::{DeploySharedContractAction, UseSharedContractAction};
+
+        let (runtime, tries, alice_root, apply_state, alice_signer, _) =
+            setup_runtime(to_yocto(1_000_000), 0, 10u64.pow(15));
+        let shard_layout = ShardLayout::v1(vec![], vec![bob_account()], None, 0);
+        let alice_shard_uid = account_id_to_shard_uid(&alice_account(), &shard_layout);
+        let bob_shard_uid = account_id_to_shard_uid(&bob_account(), &shard_layout);
+        assert_eq!(alice_shard_uid, ShardUId::single_shard());
+        assert_ne!(alice_shard_uid, bob_shard_uid);
+        let epoch_info_provider =
+            MockEpochInfoProvider { shard_layout: shard_layout.clone(), ..Default::default() };
+
+        let bob_signer = Arc::new(InMemorySigner::from_seed(
+            bob_account(),
+            KeyType::ED25519,
+            bob_account().as_ref(),
+        ));
+        let mut initial_state = tries.new_trie_update(bob_shard_uid, MerkleHash::default());
+        let mut initial_account = account_new(to_yocto(1_000_000), hash(&[]));
+        initial_account.set_storage_usage(182);
+        set_account(&mut initial_state, bob_account(), &initial_account);
+        set_access_key(
+            &mut initial_state,
+            bob_account(),
+            bob_signer.public_key(),
+            &AccessKey::full_access(),
+        );
+        initial_state.commit(StateChangeCause::InitialState);
+        let trie_changes = initial_state.finalize().unwrap().0;
+        let (store_update, bob_root) = tries.apply_all(&trie_changes, bob_shard_uid);
+        store_update.commit().unwrap();
+
+        let code = near_test_contracts::rs_contract().to_vec();
+        let code_hash = hash(&code);
+        let receipts = create_receipts_with_actions(
+            alice_account(),
+            alice_signer,
+            vec![Action::DeploySharedContract(DeploySharedContractAction { code: code.clone() })],
+        );
+        let apply_result = runtime
+            .apply(
+                tries.get_trie_for_shard(alice_shard_uid),
+                alice_root,
+                &None,
+                &apply_state,
+                &receipts,
+                &[],
+                &epoch_info_provider,
+                None,
+            )
+            .unwrap();
+        assert!(matches!(
+            apply_result.outcomes[0].outcome.status,
+            ExecutionStatus::SuccessValue(_)
+        ));
+        let (store_update, _) = tries.apply_all(&apply_result.trie_changes, alice_shard_uid);
+        store_update.commit().unwrap();
+        let incoming_receipts: Vec<Receipt> = apply_result
+            .outgoing_receipts
+            .into_iter()
+            .filter(|receipt| {
+                account_id_to_shard_uid(&receipt.receiver_id, &shard_layout) == bob_shard_uid
+            })
+            .chain(create_receipts_with_actions(
+                bob_account(),
+                bob_signer,
+                vec![Action::UseSharedContract(UseSharedContractAction { code_hash })],
+            ))
+            .collect();
+        // The code is distributed with a single receipt, followed by bob's receipt.
+        assert_eq!(incoming_receipts.len(), 2);
+
+        let apply_result = runtime
+            .apply(
+                tries.get_trie_for_shard(bob_shard_uid),
+                bob_root,
+                &None,
+                &apply_state,
+                &incoming_receipts,
+                &[],
+                &epoch_info_provider,
+                None,
+            )
+            .unwrap();
+        for outcome in &apply_result.outcomes {
+            assert!(
+                matches!(outcome.outcome.status, ExecutionStatus::SuccessValue(_)),
+                "{:?}",
+                outcome
+            );
+        }
+        let (store_update, bob_root) = tries.apply_all(&apply_result.trie_changes, bob_shard_uid);
+        store_update.commit().unwrap();
+
+        let state_update = tries.new_trie_update(bob_shard_uid, bob_root);
+        let account = get_account(&state_update, &bob_account()).unwrap().unwrap();
+        assert_eq!(account.code_hash(), code_hash);
+        let bob_code =
+            near_store::get_code(&state_update, &bob_account(), Some(code_hash)).unwrap().unwrap();
+        assert_eq!(bob_code.code(), &code[..]);
+    }
 }
@@ -38,6 +38,8 @@ pub enum ViewStateError {
     AccountDoesNotExist { requested_account_id: near_primitives::types::AccountId },
     #[error("The state of {requested_account_id} is too large")]
     AccountStateTooLarge { requested_account_id: near_primitives::types::AccountId },
+    #[error("The limit of values to return must be positive")]
+    InvalidLimit,
     #[error("Internal error: #{error_message}")]
     InternalError { error_message: String },
 }
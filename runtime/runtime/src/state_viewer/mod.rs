@@ -1,6 +1,7 @@
 use crate::near_primitives::version::PROTOCOL_VERSION;
 use crate::{actions::execute_function_call, ext::RuntimeExt};
 use near_crypto::{KeyType, PublicKey};
+use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
 use near_primitives::{
     account::{AccessKey, Account},
@@ -14,17 +15,30 @@ use near_primitives::{
     },
     serialize::to_base64,
     transaction::FunctionCallAction,
-    trie_key::trie_key_parsers,
-    types::{AccountId, EpochInfoProvider, Gas},
-    views::{StateItem, ViewApplyState, ViewStateResult},
+    trie_key::{trie_key_parsers, TrieKey},
+    types::{AccountId, Balance, EpochInfoProvider, Gas},
+    views::{
+        StateItem, StorageKeyUsageView, StoragePrefixUsageView, StorageUsageView, ViewApplyState,
+        ViewStateResult,
+    },
 };
-use near_store::{get_access_key, get_account, get_code, TrieUpdate};
+use near_store::{get_access_key, get_account, get_account_code, get_code, TrieUpdate};
 use near_vm_logic::{ReturnData, ViewConfig};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::rc::Rc;
 use std::{str, sync::Arc, time::Instant};
 use tracing::debug;
 
 pub mod errors;
 
+/// Length of the key prefixes contract data is grouped by in storage usage
+/// views.  Collections of the contract SDKs usually start their keys with a
+/// single byte prefix.
+pub const STORAGE_USAGE_PREFIX_LEN: usize = 1;
+/// Number of the largest contract data records listed in storage usage views.
+pub const STORAGE_USAGE_NUM_LARGEST_KEYS: usize = 10;
+
 pub struct TrieViewer {
     /// Upper bound of the byte size of contract state that is still viewable. None is no limit
     state_size_limit: Option<u64>,
@@ -114,19 +128,35 @@ impl TrieViewer {
         access_keys
     }
 
+    /// Returns contract data of the account under the given prefix.
+    ///
+    /// If neither `start_key` nor `limit` is given, all the values are
+    /// returned, unless the state of the account is larger than the state size
+    /// limit.  Otherwise values are returned starting from `start_key`, until
+    /// there are `limit` of them or their size reaches the state size limit,
+    /// and `next_key` of the result tells where the next page starts.  A zero
+    /// `limit` is rejected, since such a page would never make progress.
     pub fn view_state(
         &self,
         state_update: &TrieUpdate,
         account_id: &AccountId,
         prefix: &[u8],
+        start_key: Option<&[u8]>,
+        limit: Option<u64>,
+        include_proof: bool,
     ) -> Result<ViewStateResult, errors::ViewStateError> {
+        if limit == Some(0) {
+            return Err(errors::ViewStateError::InvalidLimit);
+        }
+        let paginated = start_key.is_some() || limit.is_some();
         match get_account(state_update, account_id)? {
             Some(account) => {
-                let code_len = get_code(state_update, account_id, Some(account.code_hash()))?
-                    .map(|c| c.code().len() as u64)
-                    .unwrap_or_default();
+                let code_len =
+                    get_account_code(state_update, account_id, Some(account.code_hash()))?
+                        .map(|c| c.code().len() as u64)
+                        .unwrap_or_default();
                 if let Some(limit) = self.state_size_limit {
-                    if account.storage_usage().saturating_sub(code_len) > limit {
+                    if !paginated && account.storage_usage().saturating_sub(code_len) > limit {
                         return Err(errors::ViewStateError::AccountStateTooLarge {
                             requested_account_id: account_id.clone(),
                         });
@@ -141,23 +171,161 @@ impl TrieViewer {
         };
 
         let mut values = vec![];
+        let mut next_key = None;
         let query = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, prefix);
         let acc_sep_len = query.len() - prefix.len();
-        let mut iter = state_update.trie.iter(&state_update.get_root())?;
-        iter.seek(&query)?;
+        let start = match start_key {
+            Some(start_key) => std::cmp::max(
+                query.clone(),
+                trie_key_parsers::get_raw_prefix_for_contract_data(account_id, start_key),
+            ),
+            None => query.clone(),
+        };
+        let trie = if include_proof {
+            Rc::new(state_update.trie.recording_reads())
+        } else {
+            state_update.trie.clone()
+        };
+        let mut size_left = if paginated { self.state_size_limit } else { None };
+        let mut iter = trie.iter(&state_update.get_root())?;
+        iter.seek(&start)?;
         for item in iter {
             let (key, value) = item?;
             if !key.starts_with(query.as_ref()) {
                 break;
             }
+            // A page always has at least one value, so that reading makes
+            // progress even if a single value is above the size limit.
+            let fits = consume_size(&mut size_left, (key.len() + value.len()) as u64);
+            if limit.map_or(false, |limit| values.len() as u64 >= limit)
+                || (!fits && !values.is_empty())
+            {
+                next_key = Some(to_base64(&key[acc_sep_len..]));
+                break;
+            }
+            if !fits {
+                size_left = Some(0);
+            }
             values.push(StateItem {
                 key: to_base64(&key[acc_sep_len..]),
                 value: to_base64(&value),
                 proof: vec![],
             });
         }
-        // TODO(2076): Add proofs for the storage items.
-        Ok(ViewStateResult { values, proof: vec![] })
+        let proof = trie
+            .recorded_storage()
+            .map(|storage| storage.nodes.0.iter().map(to_base64).collect())
+            .unwrap_or_default();
+        Ok(ViewStateResult { values, proof, next_key })
+    }
+
+    /// Walks the state owned by the account and computes how much storage
+    /// each kind of its records takes, following the storage accounting of
+    /// the runtime.
+    ///
+    /// Contract data is grouped by the first `prefix_len` bytes of the keys,
+    /// and the `num_largest_keys` largest records are listed.  Once the
+    /// records walked take more than the state size limit of the viewer, the
+    /// walk stops and the view is marked as truncated.
+    pub fn view_storage_usage(
+        &self,
+        state_update: &TrieUpdate,
+        account_id: &AccountId,
+        config: &RuntimeConfig,
+        prefix_len: usize,
+        num_largest_keys: usize,
+    ) -> Result<StorageUsageView, errors::ViewStateError> {
+        let account = get_account(state_update, account_id)?.ok_or_else(|| {
+            errors::ViewStateError::AccountDoesNotExist { requested_account_id: account_id.clone() }
+        })?;
+        let storage_config = &config.transaction_costs.storage_usage_config;
+        let code_bytes = state_update
+            .get_ref(&TrieKey::ContractCode { account_id: account_id.clone() })?
+            .map(|ptr| ptr.len() as u64)
+            .unwrap_or_default();
+
+        let mut size_left = self.state_size_limit;
+        let mut truncated = false;
+
+        let access_keys_prefix = trie_key_parsers::get_raw_prefix_for_access_keys(account_id);
+        let mut access_keys =
+            StoragePrefixUsageView { prefix: vec![].into(), num_records: 0, bytes: 0 };
+        let mut iter = state_update.trie.iter(&state_update.get_root())?;
+        iter.seek(&access_keys_prefix)?;
+        for item in iter {
+            let (key, value) = item?;
+            if !key.starts_with(&access_keys_prefix) {
+                break;
+            }
+            let bytes = (key.len() - access_keys_prefix.len() + value.len()) as u64
+                + storage_config.num_extra_bytes_record;
+            if !consume_size(&mut size_left, bytes) {
+                truncated = true;
+                break;
+            }
+            access_keys.num_records += 1;
+            access_keys.bytes += bytes;
+        }
+
+        let data_prefix = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, &[]);
+        let mut contract_data =
+            StoragePrefixUsageView { prefix: vec![].into(), num_records: 0, bytes: 0 };
+        let mut prefixes = BTreeMap::<Vec<u8>, (u64, u64)>::new();
+        let mut largest_keys = BinaryHeap::new();
+        if !truncated {
+            let mut iter = state_update.trie.iter(&state_update.get_root())?;
+            iter.seek(&data_prefix)?;
+            for item in iter {
+                let (key, value) = item?;
+                if !key.starts_with(&data_prefix) {
+                    break;
+                }
+                let key = &key[data_prefix.len()..];
+                let bytes =
+                    (key.len() + value.len()) as u64 + storage_config.num_extra_bytes_record;
+                if !consume_size(&mut size_left, bytes) {
+                    truncated = true;
+                    break;
+                }
+                contract_data.num_records += 1;
+                contract_data.bytes += bytes;
+                let group = prefixes.entry(key[..key.len().min(prefix_len)].to_vec()).or_default();
+                group.0 += 1;
+                group.1 += bytes;
+                largest_keys.push(Reverse((bytes, key.to_vec())));
+                if largest_keys.len() > num_largest_keys {
+                    largest_keys.pop();
+                }
+            }
+        }
+
+        let mut contract_data_prefixes: Vec<_> = prefixes
+            .into_iter()
+            .map(|(prefix, (num_records, bytes))| StoragePrefixUsageView {
+                prefix: prefix.into(),
+                num_records,
+                bytes,
+            })
+            .collect();
+        contract_data_prefixes.sort_by_key(|prefix| Reverse(prefix.bytes));
+        let largest_keys = largest_keys
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((bytes, key))| StorageKeyUsageView { key: key.into(), bytes })
+            .collect();
+
+        Ok(StorageUsageView {
+            storage_usage: account.storage_usage(),
+            locked_for_storage: Balance::from(account.storage_usage())
+                * config.storage_amount_per_byte,
+            account_bytes: storage_config.num_bytes_account,
+            code_bytes,
+            access_keys,
+            contract_data,
+            contract_data_prefixes,
+            largest_keys,
+            truncated,
+        })
     }
 
     pub fn call_function(
@@ -260,3 +428,16 @@ impl TrieViewer {
         }
     }
 }
+
+/// Takes `bytes` from the size left, if it is limited, and returns whether
+/// they fit into it.
+fn consume_size(size_left: &mut Option<u64>, bytes: u64) -> bool {
+    match size_left {
+        Some(left) if *left < bytes => false,
+        Some(left) => {
+            *left -= bytes;
+            true
+        }
+        None => true,
+    }
+}
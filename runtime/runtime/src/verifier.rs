@@ -1,7 +1,7 @@
 use near_crypto::key_conversion::is_valid_staking_key;
 use near_primitives::runtime::get_insufficient_storage_stake;
 use near_primitives::{
-    account::AccessKeyPermission,
+    account::{AccessKey, AccessKeyPermission},
     config::VMLimitConfig,
     errors::{
         ActionsValidationError, InvalidAccessKeyError, InvalidTxError, ReceiptValidationError,
@@ -9,8 +9,8 @@ use near_primitives::{
     },
     receipt::{ActionReceipt, DataReceipt, Receipt, ReceiptEnum},
     transaction::{
-        Action, AddKeyAction, DeployContractAction, FunctionCallAction, SignedTransaction,
-        StakeAction,
+        Action, AddKeyAction, DeployContractAction, DeploySharedContractAction, FunctionCallAction,
+        SignedTransaction, StakeAction,
     },
     types::{AccountId, Balance},
     version::ProtocolVersion,
@@ -23,7 +23,7 @@ use crate::config::{total_prepaid_gas, tx_cost, TransactionCost};
 use crate::VerificationResult;
 use near_primitives::checked_feature;
 use near_primitives::runtime::config::RuntimeConfig;
-use near_primitives::types::BlockHeight;
+use near_primitives::types::{BlockHeight, EpochHeight};
 
 /// Validates the transaction without using the state. It allows any node to validate a
 /// transaction before forwarding it to the node that tracks the `signer_id` account.
@@ -57,6 +57,8 @@ pub fn validate_transaction(
 
     validate_actions(&config.wasm_config.limit_config, &transaction.actions)
         .map_err(InvalidTxError::ActionsValidation)?;
+    validate_actions_protocol_features(&transaction.actions, current_protocol_version)
+        .map_err(InvalidTxError::ActionsValidation)?;
 
     let sender_is_receiver = &transaction.receiver_id == signer_id;
 
@@ -72,13 +74,19 @@ pub fn validate_transaction(
 
 /// Verifies the signed transaction on top of given state, charges transaction fees
 /// and balances, and updates the state for the used account and access keys.
+///
+/// `block_height` is the height of the block the transaction is included in, if it's known.
+/// `min_block_height` is the lowest height of a block the transaction can be included in, it's
+/// used to reject transactions signed with expired access keys even when the block is not known.
 pub fn verify_and_charge_transaction(
     config: &RuntimeConfig,
     state_update: &mut TrieUpdate,
     gas_price: Balance,
     signed_transaction: &SignedTransaction,
     verify_signature: bool,
-    #[allow(unused)] block_height: Option<BlockHeight>,
+    block_height: Option<BlockHeight>,
+    min_block_height: BlockHeight,
+    epoch_height: EpochHeight,
     current_protocol_version: ProtocolVersion,
 ) -> Result<VerificationResult, RuntimeError> {
     let TransactionCost { gas_burnt, gas_remaining, receipt_gas_price, total_cost, burnt_amount } =
@@ -142,19 +150,37 @@ pub fn verify_and_charge_transaction(
         }
     })?);
 
-    if let AccessKeyPermission::FunctionCall(ref mut function_call_permission) =
-        access_key.permission
-    {
-        if let Some(ref mut allowance) = function_call_permission.allowance {
-            *allowance = allowance.checked_sub(total_cost).ok_or_else(|| {
-                InvalidTxError::InvalidAccessKeyError(InvalidAccessKeyError::NotEnoughAllowance {
-                    account_id: signer_id.clone(),
-                    public_key: transaction.public_key.clone(),
-                    allowance: *allowance,
-                    cost: total_cost,
-                })
-            })?;
+    let allowance = match access_key.permission {
+        AccessKeyPermission::FunctionCall(ref mut function_call_permission) => {
+            function_call_permission.allowance.as_mut()
+        }
+        AccessKeyPermission::SessionFunctionCall(ref mut session_permission) => {
+            // If the key is expired at the lowest height, it is expired at any height the
+            // transaction can be included at.
+            if session_permission.is_expired(block_height.unwrap_or(min_block_height)) {
+                return Err(InvalidTxError::InvalidAccessKeyError(
+                    InvalidAccessKeyError::AccessKeyExpired {
+                        account_id: signer_id.clone(),
+                        public_key: transaction.public_key.clone(),
+                        expires_at: session_permission.expires_at,
+                    },
+                )
+                .into());
+            }
+            session_permission.refill_allowance(epoch_height);
+            Some(&mut session_permission.allowance)
         }
+        AccessKeyPermission::FullAccess => None,
+    };
+    if let Some(allowance) = allowance {
+        *allowance = allowance.checked_sub(total_cost).ok_or_else(|| {
+            InvalidTxError::InvalidAccessKeyError(InvalidAccessKeyError::NotEnoughAllowance {
+                account_id: signer_id.clone(),
+                public_key: transaction.public_key.clone(),
+                allowance: *allowance,
+                cost: total_cost,
+            })
+        })?;
     }
 
     match get_insufficient_storage_stake(&signer, config) {
@@ -171,7 +197,16 @@ pub fn verify_and_charge_transaction(
         }
     };
 
-    if let AccessKeyPermission::FunctionCall(ref function_call_permission) = access_key.permission {
+    let function_call_permission = match access_key.permission {
+        AccessKeyPermission::FunctionCall(ref function_call_permission) => {
+            Some((&function_call_permission.receiver_id, &function_call_permission.method_names))
+        }
+        AccessKeyPermission::SessionFunctionCall(ref session_permission) => {
+            Some((&session_permission.receiver_id, &session_permission.method_names))
+        }
+        AccessKeyPermission::FullAccess => None,
+    };
+    if let Some((ak_receiver_id, ak_method_names)) = function_call_permission {
         if transaction.actions.len() != 1 {
             return Err(InvalidTxError::InvalidAccessKeyError(
                 InvalidAccessKeyError::RequiresFullAccess,
@@ -185,18 +220,17 @@ pub fn verify_and_charge_transaction(
                 )
                 .into());
             }
-            if transaction.receiver_id.as_ref() != function_call_permission.receiver_id {
+            if transaction.receiver_id.as_ref() != ak_receiver_id.as_str() {
                 return Err(InvalidTxError::InvalidAccessKeyError(
                     InvalidAccessKeyError::ReceiverMismatch {
                         tx_receiver: transaction.receiver_id.clone(),
-                        ak_receiver: function_call_permission.receiver_id.clone(),
+                        ak_receiver: ak_receiver_id.clone(),
                     },
                 )
                 .into());
             }
-            if !function_call_permission.method_names.is_empty()
-                && function_call_permission
-                    .method_names
+            if !ak_method_names.is_empty()
+                && ak_method_names
                     .iter()
                     .all(|method_name| &function_call.method_name != method_name)
             {
@@ -315,6 +349,56 @@ pub(crate) fn validate_actions(
     Ok(())
 }
 
+/// Checks that protocol features required by the given actions are enabled.
+///
+/// Receipts can only get such actions from transactions, so it is enough to
+/// check them when validating a transaction.
+fn validate_actions_protocol_features(
+    actions: &[Action],
+    current_protocol_version: ProtocolVersion,
+) -> Result<(), ActionsValidationError> {
+    let requires_shared_contracts = actions.iter().any(|action| {
+        matches!(action, Action::DeploySharedContract(_) | Action::UseSharedContract(_))
+    });
+    if requires_shared_contracts
+        && !checked_feature!(
+            "protocol_feature_shared_contracts",
+            SharedContracts,
+            current_protocol_version
+        )
+    {
+        return Err(ActionsValidationError::UnsupportedProtocolFeature {
+            protocol_feature: "SharedContracts".to_string(),
+            version: current_protocol_version,
+        });
+    }
+    let requires_session_access_keys = actions.iter().any(|action| {
+        matches!(
+            action,
+            Action::AddKey(AddKeyAction {
+                access_key: AccessKey {
+                    permission: AccessKeyPermission::SessionFunctionCall(_),
+                    ..
+                },
+                ..
+            })
+        )
+    });
+    if requires_session_access_keys
+        && !checked_feature!(
+            "protocol_feature_session_access_keys",
+            SessionAccessKeys,
+            current_protocol_version
+        )
+    {
+        return Err(ActionsValidationError::UnsupportedProtocolFeature {
+            protocol_feature: "SessionAccessKeys".to_string(),
+            version: current_protocol_version,
+        });
+    }
+    Ok(())
+}
+
 /// Validates a single given action. Checks limits if applicable.
 pub fn validate_action(
     limit_config: &VMLimitConfig,
@@ -329,6 +413,8 @@ pub fn validate_action(
         Action::AddKey(a) => validate_add_key_action(limit_config, a),
         Action::DeleteKey(_) => Ok(()),
         Action::DeleteAccount(_) => Ok(()),
+        Action::DeploySharedContract(a) => validate_deploy_shared_contract_action(limit_config, a),
+        Action::UseSharedContract(_) => Ok(()),
     }
 }
 
@@ -347,6 +433,22 @@ fn validate_deploy_contract_action(
     Ok(())
 }
 
+/// Validates `DeploySharedContractAction`. Checks that the given contract size doesn't exceed the
+/// limit.
+fn validate_deploy_shared_contract_action(
+    limit_config: &VMLimitConfig,
+    action: &DeploySharedContractAction,
+) -> Result<(), ActionsValidationError> {
+    if action.code.len() as u64 > limit_config.max_contract_size {
+        return Err(ActionsValidationError::ContractSizeExceeded {
+            size: action.code.len() as u64,
+            limit: limit_config.max_contract_size,
+        });
+    }
+
+    Ok(())
+}
+
 /// Validates `FunctionCallAction`. Checks that the method name length doesn't exceed the limit and
 /// the length of the arguments doesn't exceed the limit.
 fn validate_function_call_action(
@@ -385,22 +487,29 @@ fn validate_stake_action(action: &StakeAction) -> Result<(), ActionsValidationEr
     Ok(())
 }
 
-/// Validates `AddKeyAction`. If the access key permission is `FunctionCall`, checks that the
-/// total number of bytes of the method names doesn't exceed the limit and
-/// every method name length doesn't exceed the limit.
+/// Validates `AddKeyAction`. If the access key permission is `FunctionCall` or
+/// `SessionFunctionCall`, checks that the total number of bytes of the method names doesn't exceed
+/// the limit and every method name length doesn't exceed the limit.
 fn validate_add_key_action(
     limit_config: &VMLimitConfig,
     action: &AddKeyAction,
 ) -> Result<(), ActionsValidationError> {
-    if let AccessKeyPermission::FunctionCall(fc) = &action.access_key.permission {
+    let function_call_permission = match &action.access_key.permission {
+        AccessKeyPermission::FunctionCall(fc) => Some((&fc.receiver_id, &fc.method_names)),
+        AccessKeyPermission::SessionFunctionCall(session) => {
+            Some((&session.receiver_id, &session.method_names))
+        }
+        AccessKeyPermission::FullAccess => None,
+    };
+    if let Some((receiver_id, method_names)) = function_call_permission {
         // Check whether `receiver_id` is a valid account_id. Historically, we
         // allowed arbitrary strings there!
         match limit_config.account_id_validity_rules_version {
             near_vm_logic::AccountIdValidityRulesVersion::V0 => (),
             near_vm_logic::AccountIdValidityRulesVersion::V1 => {
-                if let Err(_) = fc.receiver_id.parse::<AccountId>() {
+                if let Err(_) = receiver_id.parse::<AccountId>() {
                     return Err(ActionsValidationError::InvalidAccountId {
-                        account_id: truncate_string(&fc.receiver_id, AccountId::MAX_LEN * 2),
+                        account_id: truncate_string(receiver_id, AccountId::MAX_LEN * 2),
                     });
                 }
             }
@@ -408,7 +517,7 @@ fn validate_add_key_action(
 
         // Checking method name length limits
         let mut total_number_of_bytes = 0;
-        for method_name in &fc.method_names {
+        for method_name in method_names {
             let length = method_name.len() as u64;
             if length > limit_config.max_length_method_name {
                 return Err(ActionsValidationError::AddKeyMethodNameLengthExceeded {
@@ -460,11 +569,14 @@ mod tests {
     use std::sync::Arc;
 
     use near_crypto::{InMemorySigner, KeyType, PublicKey, Signer};
-    use near_primitives::account::{AccessKey, Account, FunctionCallPermission};
+    use near_primitives::account::{
+        AccessKey, Account, FunctionCallPermission, SessionFunctionCallPermission,
+    };
     use near_primitives::hash::{hash, CryptoHash};
     use near_primitives::test_utils::account_new;
     use near_primitives::transaction::{
         CreateAccountAction, DeleteAccountAction, DeleteKeyAction, StakeAction, TransferAction,
+        UseSharedContractAction,
     };
     use near_primitives::types::{AccountId, Balance, MerkleHash, StateChangeCause};
     use near_primitives::version::PROTOCOL_VERSION;
@@ -543,6 +655,8 @@ mod tests {
                 signed_transaction,
                 true,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -576,6 +690,8 @@ mod tests {
             &transaction,
             true,
             None,
+            0,
+            0,
             PROTOCOL_VERSION,
         )
         .expect("valid transaction");
@@ -648,6 +764,8 @@ mod tests {
                 ),
                 false,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -715,6 +833,8 @@ mod tests {
                 ),
                 true,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -748,6 +868,8 @@ mod tests {
                 ),
                 true,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -797,6 +919,8 @@ mod tests {
             ),
             true,
             None,
+            0,
+            0,
             PROTOCOL_VERSION,
         )
         .expect_err("expected an error");
@@ -849,6 +973,8 @@ mod tests {
             ),
             true,
             None,
+            0,
+            0,
             PROTOCOL_VERSION,
         )
         .expect_err("expected an error");
@@ -865,6 +991,175 @@ mod tests {
         }
     }
 
+    fn session_access_key(allowance: Balance, expires_at: BlockHeight) -> AccessKey {
+        AccessKey {
+            nonce: 0,
+            permission: AccessKeyPermission::SessionFunctionCall(SessionFunctionCallPermission {
+                allowance,
+                allowance_per_period: NEAR_BASE,
+                refill_period: 10,
+                allowance_period: 0,
+                expires_at,
+                receiver_id: bob_account().into(),
+                method_names: vec![],
+            }),
+        }
+    }
+
+    fn session_transaction(signer: &dyn Signer) -> SignedTransaction {
+        SignedTransaction::from_actions(
+            1,
+            alice_account(),
+            bob_account(),
+            signer,
+            vec![Action::FunctionCall(FunctionCallAction {
+                method_name: "hello".to_string(),
+                args: b"abc".to_vec(),
+                gas: 300,
+                deposit: 0,
+            })],
+            CryptoHash::default(),
+        )
+    }
+
+    #[test]
+    fn test_validate_transaction_session_key_expired() {
+        let config = RuntimeConfig::test();
+        let (signer, mut state_update, gas_price) =
+            setup_common(TESTING_INIT_BALANCE, 0, Some(session_access_key(NEAR_BASE, 10)));
+
+        verify_and_charge_transaction(
+            &config,
+            &mut state_update,
+            gas_price,
+            &session_transaction(&*signer),
+            true,
+            Some(10),
+            10,
+            0,
+            PROTOCOL_VERSION,
+        )
+        .expect("valid transaction");
+        state_update.rollback();
+
+        assert_eq!(
+            verify_and_charge_transaction(
+                &config,
+                &mut state_update,
+                gas_price,
+                &session_transaction(&*signer),
+                true,
+                Some(11),
+                11,
+                0,
+                PROTOCOL_VERSION,
+            )
+            .expect_err("expected an error"),
+            RuntimeError::InvalidTxError(InvalidTxError::InvalidAccessKeyError(
+                InvalidAccessKeyError::AccessKeyExpired {
+                    account_id: alice_account(),
+                    public_key: signer.public_key(),
+                    expires_at: 10,
+                }
+            )),
+        );
+    }
+
+    /// Transactions entering the pool don't know the block they will be included in, but are
+    /// rejected if the key is expired at the lowest height they can be included at.
+    #[test]
+    fn test_validate_transaction_session_key_expired_in_pool() {
+        let config = RuntimeConfig::test();
+        let (signer, mut state_update, gas_price) =
+            setup_common(TESTING_INIT_BALANCE, 0, Some(session_access_key(NEAR_BASE, 10)));
+
+        verify_and_charge_transaction(
+            &config,
+            &mut state_update,
+            gas_price,
+            &session_transaction(&*signer),
+            true,
+            None,
+            10,
+            0,
+            PROTOCOL_VERSION,
+        )
+        .expect("valid transaction");
+        state_update.rollback();
+
+        assert_eq!(
+            verify_and_charge_transaction(
+                &config,
+                &mut state_update,
+                gas_price,
+                &session_transaction(&*signer),
+                true,
+                None,
+                11,
+                0,
+                PROTOCOL_VERSION,
+            )
+            .expect_err("expected an error"),
+            RuntimeError::InvalidTxError(InvalidTxError::InvalidAccessKeyError(
+                InvalidAccessKeyError::AccessKeyExpired {
+                    account_id: alice_account(),
+                    public_key: signer.public_key(),
+                    expires_at: 10,
+                }
+            )),
+        );
+    }
+
+    #[test]
+    fn test_validate_transaction_session_key_refill() {
+        let config = RuntimeConfig::test();
+        let (signer, mut state_update, gas_price) =
+            setup_common(TESTING_INIT_BALANCE, 0, Some(session_access_key(0, 100)));
+
+        // The allowance isn't refilled within the first refill period.
+        let err = verify_and_charge_transaction(
+            &config,
+            &mut state_update,
+            gas_price,
+            &session_transaction(&*signer),
+            true,
+            Some(20),
+            20,
+            9,
+            PROTOCOL_VERSION,
+        )
+        .expect_err("expected an error");
+        assert!(matches!(
+            err,
+            RuntimeError::InvalidTxError(InvalidTxError::InvalidAccessKeyError(
+                InvalidAccessKeyError::NotEnoughAllowance { allowance: 0, .. }
+            ))
+        ));
+
+        // The allowance is refilled even when the block height isn't known,
+        // as it is the case for transactions entering the pool.
+        verify_and_charge_transaction(
+            &config,
+            &mut state_update,
+            gas_price,
+            &session_transaction(&*signer),
+            true,
+            None,
+            0,
+            25,
+            PROTOCOL_VERSION,
+        )
+        .expect("valid transaction");
+        let access_key =
+            get_access_key(&state_update, &alice_account(), &signer.public_key()).unwrap().unwrap();
+        if let AccessKeyPermission::SessionFunctionCall(permission) = access_key.permission {
+            assert_eq!(permission.allowance_period, 2);
+            assert!(permission.allowance > 0 && permission.allowance < NEAR_BASE);
+        } else {
+            panic!("Incorrect permission");
+        }
+    }
+
     /// Setup: account has 1B yoctoN and is 180 bytes. Storage requirement is 1M per byte.
     /// Test that such account can not send 950M yoctoN out as that will leave it under storage requirements.
     #[test]
@@ -891,6 +1186,8 @@ mod tests {
                 ),
                 true,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -942,6 +1239,8 @@ mod tests {
                 ),
                 true,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -965,6 +1264,8 @@ mod tests {
                 ),
                 true,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -988,6 +1289,8 @@ mod tests {
                 ),
                 true,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -1033,6 +1336,8 @@ mod tests {
                 ),
                 true,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -1081,6 +1386,8 @@ mod tests {
                 ),
                 true,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -1126,6 +1433,8 @@ mod tests {
                 ),
                 true,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -1162,6 +1471,8 @@ mod tests {
                 &transaction,
                 false,
                 None,
+                0,
+                0,
                 PROTOCOL_VERSION,
             )
             .expect_err("expected an error"),
@@ -1179,11 +1490,45 @@ mod tests {
             &transaction,
             false,
             None,
+            0,
+            0,
             PROTOCOL_VERSION,
         )
         .expect("valid transaction");
     }
 
+    #[test]
+    fn test_validate_actions_shared_contracts_protocol_feature() {
+        let actions =
+            vec![Action::UseSharedContract(UseSharedContractAction { code_hash: hash(&[1]) })];
+        assert_eq!(
+            validate_actions_protocol_features(&actions, 0).expect_err("expected an error"),
+            ActionsValidationError::UnsupportedProtocolFeature {
+                protocol_feature: "SharedContracts".to_string(),
+                version: 0,
+            },
+        );
+        #[cfg(feature = "protocol_feature_shared_contracts")]
+        validate_actions_protocol_features(&actions, PROTOCOL_VERSION).expect("valid actions");
+    }
+
+    #[test]
+    fn test_validate_actions_session_access_keys_protocol_feature() {
+        let actions = vec![Action::AddKey(AddKeyAction {
+            public_key: PublicKey::empty(KeyType::ED25519),
+            access_key: session_access_key(NEAR_BASE, 100),
+        })];
+        assert_eq!(
+            validate_actions_protocol_features(&actions, 0).expect_err("expected an error"),
+            ActionsValidationError::UnsupportedProtocolFeature {
+                protocol_feature: "SessionAccessKeys".to_string(),
+                version: 0,
+            },
+        );
+        #[cfg(feature = "protocol_feature_session_access_keys")]
+        validate_actions_protocol_features(&actions, PROTOCOL_VERSION).expect("valid actions");
+    }
+
     // Receipts
 
     #[test]
@@ -661,6 +661,11 @@ impl Account {
                         function_call_keys.push(key.signer.clone())
                     }
                 }
+                AccessKeyPermission::SessionFunctionCall(session_permission) => {
+                    if session_permission.receiver_id == receiver_id {
+                        function_call_keys.push(key.signer.clone())
+                    }
+                }
             }
         }
         function_call_keys
@@ -1,3 +1,4 @@
+use crate::column_dump::{self, ColumnDumpFilter};
 use crate::commands::*;
 use crate::epoch_info;
 use crate::rocksdb_stats::get_rocksdb_stats;
@@ -46,6 +47,13 @@ pub enum StateViewerSubCommand {
     /// Dump contract data in storage of given account to binary file.
     #[clap(alias = "dump_account_storage")]
     DumpAccountStorage(DumpAccountStorageCmd),
+    /// Print how much storage each kind of records of given account takes.
+    #[clap(alias = "storage_usage")]
+    StorageUsage(StorageUsageCmd),
+    /// Report what garbage collection would remove, including data kept by
+    /// the column retention policies from `gc.column_policies` in config.json.
+    #[clap(alias = "gc_dry_run")]
+    GcDryRun,
     /// Print `EpochInfo` of an epoch given by `--epoch_id` or by `--epoch_height`.
     #[clap(alias = "epoch_info")]
     EpochInfo(EpochInfoCmd),
@@ -67,6 +75,15 @@ pub enum StateViewerSubCommand {
     /// even if it's not included in any block on disk
     #[clap(alias = "apply_receipt")]
     ApplyReceipt(ApplyReceiptCmd),
+    /// Export records of a single column, optionally filtered by height or
+    /// key range, to a JSON-lines dump.  See README.md for the format.
+    #[clap(alias = "export_column")]
+    ExportColumn(ExportColumnCmd),
+    /// Write records from a dump created by `export-column` into the
+    /// database, overwriting existing values or, in reference-counted
+    /// columns, adding to their reference counts.  Requires `--readwrite`.
+    #[clap(alias = "import_column")]
+    ImportColumn(ImportColumnCmd),
 }
 
 impl StateViewerSubCommand {
@@ -90,6 +107,8 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::CheckBlock => check_block_chunk_existence(store, near_config),
             StateViewerSubCommand::DumpCode(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::DumpAccountStorage(cmd) => cmd.run(home_dir, near_config, store),
+            StateViewerSubCommand::StorageUsage(cmd) => cmd.run(home_dir, near_config, store),
+            StateViewerSubCommand::GcDryRun => gc_dry_run(home_dir, near_config, store),
             StateViewerSubCommand::EpochInfo(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::RocksDBStats(cmd) => cmd.run(&store_opener.get_path()),
             StateViewerSubCommand::Receipts(cmd) => cmd.run(near_config, store),
@@ -98,6 +117,8 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::ApplyChunk(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::ApplyTx(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::ApplyReceipt(cmd) => cmd.run(home_dir, near_config, store),
+            StateViewerSubCommand::ExportColumn(cmd) => cmd.run(store),
+            StateViewerSubCommand::ImportColumn(cmd) => cmd.run(store),
         }
     }
 }
@@ -327,6 +348,37 @@ impl DumpAccountStorageCmd {
         );
     }
 }
+
+#[derive(Parser)]
+pub struct StorageUsageCmd {
+    #[clap(long)]
+    account_id: AccountId,
+    /// Length of the key prefixes contract data is grouped by.
+    #[clap(long, default_value = "1")]
+    prefix_len: usize,
+    /// Number of the largest contract data records to list.
+    #[clap(long, default_value = "10")]
+    num_largest_keys: usize,
+    /// Stop walking the state of the account after this many bytes.
+    /// If not set, the whole state is walked.
+    #[clap(long)]
+    size_limit: Option<u64>,
+}
+
+impl StorageUsageCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        view_storage_usage(
+            self.account_id,
+            self.prefix_len,
+            self.num_largest_keys,
+            self.size_limit,
+            home_dir,
+            near_config,
+            store,
+        );
+    }
+}
+
 #[derive(Args)]
 pub struct EpochInfoCmd {
     #[clap(subcommand)]
@@ -439,3 +491,62 @@ impl ApplyReceiptCmd {
         apply_receipt(home_dir, near_config, store, hash).unwrap();
     }
 }
+
+#[derive(Parser)]
+pub struct ExportColumnCmd {
+    /// Name of the column, e.g. `BlockHeader`.
+    #[clap(long)]
+    column: String,
+    /// Lowest height to export, inclusive.  Only for columns keyed by height
+    /// or block hash.
+    #[clap(long)]
+    start_height: Option<BlockHeight>,
+    /// Highest height to export, inclusive.  Only for columns keyed by height
+    /// or block hash.
+    #[clap(long)]
+    end_height: Option<BlockHeight>,
+    /// Lowest base64 encoded key to export, inclusive.
+    #[clap(long)]
+    from_key: Option<String>,
+    /// Base64 encoded key to stop the export at, exclusive.
+    #[clap(long)]
+    to_key: Option<String>,
+    #[clap(long, parse(from_os_str))]
+    output: PathBuf,
+}
+
+impl ExportColumnCmd {
+    pub fn run(self, store: Store) {
+        let col = column_dump::parse_column(&self.column).unwrap();
+        let filter = ColumnDumpFilter {
+            start_height: self.start_height,
+            end_height: self.end_height,
+            from_key: self.from_key.map(|key| column_dump::parse_key(&key).unwrap()),
+            to_key: self.to_key.map(|key| column_dump::parse_key(&key).unwrap()),
+        };
+        let file = std::io::BufWriter::new(std::fs::File::create(&self.output).unwrap());
+        let num_records = column_dump::export_column(&store, col, &filter, file)
+            .expect("Failed to export column");
+        println!(
+            "Exported {} records of {} to {}",
+            num_records,
+            self.column,
+            self.output.display()
+        );
+    }
+}
+
+#[derive(Parser)]
+pub struct ImportColumnCmd {
+    #[clap(long, parse(from_os_str))]
+    input: PathBuf,
+}
+
+impl ImportColumnCmd {
+    pub fn run(self, store: Store) {
+        let file = std::io::BufReader::new(std::fs::File::open(&self.input).unwrap());
+        let (col, num_records) =
+            column_dump::import_column(&store, file).expect("Failed to import column");
+        println!("Imported {} records of {} from {}", num_records, col, self.input.display());
+    }
+}
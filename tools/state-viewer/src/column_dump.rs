@@ -0,0 +1,441 @@
+//! Export of a single column of the database to a file and import of such
+//! a file back into a database.
+//!
+//! The dump is a JSON-lines file.  Its first line is a [`DumpHeader`] and each
+//! following line is a [`DumpRecord`].  See README.md for the description of
+//! the format.
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::io::{BufRead, Write};
+
+use borsh::BorshDeserialize;
+use near_primitives::block::{Block, BlockHeader};
+use near_primitives::epoch_manager::block_info::BlockInfo;
+use near_primitives::hash::CryptoHash;
+use near_primitives::merkle::PartialMerkleTree;
+use near_primitives::receipt::Receipt;
+use near_primitives::serialize::{from_base64, to_base64};
+use near_primitives::sharding::{ChunkHash, PartialEncodedChunk, ReceiptProof, ShardChunk};
+use near_primitives::transaction::{ExecutionOutcomeWithIdAndProof, SignedTransaction};
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{BlockExtra, BlockHeight, EpochId};
+use near_primitives::version::DbVersion;
+use near_store::db::{refcount, VERSION_KEY};
+use near_store::{DBCol, Store};
+use strum::IntoEnumIterator;
+
+/// Value of the `format` field of the header of a dump.
+const DUMP_FORMAT: &str = "near-column-dump";
+/// Version of the dump format.  Bump it on incompatible changes.
+const DUMP_FORMAT_VERSION: u32 = 1;
+/// Number of records written to the database in a single transaction by
+/// [`import_column`].
+const IMPORT_BATCH_SIZE: usize = 10_000;
+
+/// First line of a dump.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DumpHeader {
+    format: String,
+    version: u32,
+    column: String,
+    /// Version of the database the raw values come from.
+    db_version: DbVersion,
+}
+
+/// A single key-value pair of the column.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DumpRecord {
+    /// Base64 encoded key.
+    key: String,
+    /// Base64 encoded raw value, including the reference count for
+    /// reference-counted columns.
+    value: String,
+    /// Height of the block the key refers to, for columns keyed by height or
+    /// block hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    height: Option<BlockHeight>,
+    /// Human-readable form of the value for known columns.  Ignored by import.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    decoded: Option<String>,
+}
+
+/// Selects which records of the column are exported.
+#[derive(Default)]
+pub(crate) struct ColumnDumpFilter {
+    /// Lowest height to export, inclusive.
+    pub start_height: Option<BlockHeight>,
+    /// Highest height to export, inclusive.
+    pub end_height: Option<BlockHeight>,
+    /// Lowest key to export, inclusive.
+    pub from_key: Option<Vec<u8>>,
+    /// Key to stop the export at, exclusive.
+    pub to_key: Option<Vec<u8>>,
+}
+
+impl ColumnDumpFilter {
+    fn has_height_range(&self) -> bool {
+        self.start_height.is_some() || self.end_height.is_some()
+    }
+
+    fn contains_key(&self, key: &[u8]) -> bool {
+        self.from_key.as_ref().map_or(true, |from_key| key >= from_key.as_slice())
+            && self.to_key.as_ref().map_or(true, |to_key| key < to_key.as_slice())
+    }
+
+    fn contains_height(&self, height: Option<BlockHeight>) -> bool {
+        if !self.has_height_range() {
+            return true;
+        }
+        match height {
+            Some(height) => {
+                self.start_height.map_or(true, |start| start <= height)
+                    && self.end_height.map_or(true, |end| height <= end)
+            }
+            None => false,
+        }
+    }
+}
+
+/// How the height of a record is determined from its key.
+enum KeyKind {
+    /// Key starts with a little-endian height.
+    Height,
+    /// Key starts with a block hash.
+    BlockHash,
+    Other,
+}
+
+fn key_kind(col: DBCol) -> KeyKind {
+    match col {
+        DBCol::BlockHeight
+        | DBCol::HeaderHashesByHeight
+        | DBCol::ChunkHashesByHeight
+        | DBCol::BlockPerHeight
+        | DBCol::ProcessedBlockHeights => KeyKind::Height,
+        DBCol::Block
+        | DBCol::BlockHeader
+        | DBCol::BlockInfo
+        | DBCol::BlockExtra
+        | DBCol::ChunkExtra
+        | DBCol::IncomingReceipts
+        | DBCol::OutgoingReceipts
+        | DBCol::OutcomeIds
+        | DBCol::StateChanges
+        | DBCol::StateChangesForSplitStates
+        | DBCol::TrieChanges
+        | DBCol::NextBlockHashes
+        | DBCol::BlockMerkleTree
+        | DBCol::BlockRefCount => KeyKind::BlockHash,
+        _ => KeyKind::Other,
+    }
+}
+
+fn record_height(store: &Store, col: DBCol, key: &[u8]) -> anyhow::Result<Option<BlockHeight>> {
+    match key_kind(col) {
+        KeyKind::Height => {
+            Ok(key.get(..8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap())))
+        }
+        KeyKind::BlockHash => {
+            let block_hash = match key.get(..32).map(CryptoHash::try_from) {
+                Some(Ok(block_hash)) => block_hash,
+                _ => return Ok(None),
+            };
+            let header = store.get_ser::<BlockHeader>(DBCol::BlockHeader, block_hash.as_ref())?;
+            Ok(header.map(|header| header.height()))
+        }
+        KeyKind::Other => Ok(None),
+    }
+}
+
+/// Decodes the value of a column whose type is known.
+fn decode_value(col: DBCol, value: &[u8]) -> Option<String> {
+    fn debug<T: BorshDeserialize + Debug>(value: &[u8]) -> Option<String> {
+        T::try_from_slice(value).ok().map(|value| format!("{:?}", value))
+    }
+
+    let value = if col.is_rc() { refcount::decode_value_with_rc(value).0? } else { value };
+    match col {
+        DBCol::Block => debug::<Block>(value),
+        DBCol::BlockHeader => debug::<BlockHeader>(value),
+        DBCol::BlockHeight => debug::<CryptoHash>(value),
+        DBCol::BlockInfo => debug::<BlockInfo>(value),
+        DBCol::BlockExtra => debug::<BlockExtra>(value),
+        DBCol::BlockMerkleTree => debug::<PartialMerkleTree>(value),
+        DBCol::BlockPerHeight => debug::<HashMap<EpochId, HashSet<CryptoHash>>>(value),
+        DBCol::BlockRefCount => debug::<u64>(value),
+        DBCol::ChunkExtra => debug::<ChunkExtra>(value),
+        DBCol::ChunkHashesByHeight => debug::<HashSet<ChunkHash>>(value),
+        DBCol::Chunks => debug::<ShardChunk>(value),
+        DBCol::HeaderHashesByHeight => debug::<HashSet<CryptoHash>>(value),
+        DBCol::IncomingReceipts => debug::<Vec<ReceiptProof>>(value),
+        DBCol::NextBlockHashes => debug::<CryptoHash>(value),
+        DBCol::OutcomeIds => debug::<Vec<CryptoHash>>(value),
+        DBCol::OutgoingReceipts => debug::<Vec<Receipt>>(value),
+        DBCol::PartialChunks => debug::<PartialEncodedChunk>(value),
+        DBCol::Receipts => debug::<Receipt>(value),
+        DBCol::TransactionResult => debug::<Vec<ExecutionOutcomeWithIdAndProof>>(value),
+        DBCol::Transactions => debug::<SignedTransaction>(value),
+        _ => None,
+    }
+}
+
+/// Reads the version of the database, which determines the format of raw
+/// values.
+fn db_version(store: &Store) -> anyhow::Result<DbVersion> {
+    store
+        .get(DBCol::DbVersion, VERSION_KEY)?
+        .and_then(|value| serde_json::from_slice(&value).ok())
+        .ok_or_else(|| anyhow::anyhow!("failed to read database version"))
+}
+
+/// Parses a column given by its name, e.g. `BlockHeader`.
+pub(crate) fn parse_column(name: &str) -> anyhow::Result<DBCol> {
+    DBCol::iter()
+        .find(|col| col.variant_name() == name)
+        .ok_or_else(|| anyhow::anyhow!("unknown column: {}", name))
+}
+
+/// Parses a key given in the same encoding as keys in a dump.
+pub(crate) fn parse_key(key: &str) -> anyhow::Result<Vec<u8>> {
+    from_base64(key).map_err(|err| anyhow::anyhow!("invalid key {}: {}", key, err))
+}
+
+/// Writes the records of `col` selected by `filter` to `writer`.  Returns the
+/// number of exported records.
+pub(crate) fn export_column(
+    store: &Store,
+    col: DBCol,
+    filter: &ColumnDumpFilter,
+    mut writer: impl Write,
+) -> anyhow::Result<u64> {
+    if filter.has_height_range() {
+        anyhow::ensure!(
+            !matches!(key_kind(col), KeyKind::Other),
+            "{} can't be filtered by height",
+            col.variant_name()
+        );
+    }
+    let header = DumpHeader {
+        format: DUMP_FORMAT.to_string(),
+        version: DUMP_FORMAT_VERSION,
+        column: col.variant_name().to_string(),
+        db_version: db_version(store)?,
+    };
+    serde_json::to_writer(&mut writer, &header)?;
+    writeln!(writer)?;
+
+    let mut num_records = 0;
+    for item in store.iter_raw_bytes(col) {
+        let (key, value) = item?;
+        if !filter.contains_key(&key) {
+            continue;
+        }
+        let height = record_height(store, col, &key)?;
+        if !filter.contains_height(height) {
+            continue;
+        }
+        let record = DumpRecord {
+            key: to_base64(&key),
+            value: to_base64(&value),
+            height,
+            decoded: decode_value(col, &value),
+        };
+        serde_json::to_writer(&mut writer, &record)?;
+        writeln!(writer)?;
+        num_records += 1;
+    }
+    writer.flush()?;
+    Ok(num_records)
+}
+
+/// Writes the records of a dump read from `reader` into the database,
+/// overwriting existing values of the same keys.  Returns the column and the
+/// number of imported records.
+///
+/// Records of reference-counted columns are added to the database with their
+/// reference count rather than overwritten, so that existing references are
+/// kept.  Records with non-positive reference count are skipped.
+pub(crate) fn import_column(store: &Store, reader: impl BufRead) -> anyhow::Result<(DBCol, u64)> {
+    let mut lines = reader.lines();
+    let header: DumpHeader = match lines.next() {
+        Some(line) => serde_json::from_str(&line?)?,
+        None => anyhow::bail!("dump is empty"),
+    };
+    anyhow::ensure!(header.format == DUMP_FORMAT, "not a column dump: {}", header.format);
+    anyhow::ensure!(
+        header.version == DUMP_FORMAT_VERSION,
+        "unsupported dump format version {}, expected {}",
+        header.version,
+        DUMP_FORMAT_VERSION
+    );
+    let db_version = db_version(store)?;
+    anyhow::ensure!(
+        header.db_version == db_version,
+        "dump was created from DB version {}, expected {}",
+        header.db_version,
+        db_version
+    );
+    let col = parse_column(&header.column)?;
+
+    let mut num_records = 0;
+    let mut store_update = store.store_update();
+    for (index, line) in lines.enumerate() {
+        let line = line?;
+        let record: DumpRecord = serde_json::from_str(&line)
+            .map_err(|err| anyhow::anyhow!("invalid record on line {}: {}", index + 2, err))?;
+        let key = parse_key(&record.key)?;
+        let value = from_base64(&record.value)
+            .map_err(|err| anyhow::anyhow!("invalid value on line {}: {}", index + 2, err))?;
+        if col.is_rc() {
+            let (data, rc) = refcount::decode_value_with_rc(&value);
+            let data = match data {
+                Some(data) => data,
+                None => continue,
+            };
+            let rc = u32::try_from(rc)
+                .ok()
+                .and_then(std::num::NonZeroU32::new)
+                .ok_or_else(|| anyhow::anyhow!("invalid refcount {} on line {}", rc, index + 2))?;
+            store_update.increment_refcount_by(col, &key, data, rc);
+        } else {
+            store_update.set_raw_bytes(col, &key, &value);
+        }
+        num_records += 1;
+        if num_records % IMPORT_BATCH_SIZE as u64 == 0 {
+            store_update.commit()?;
+            store_update = store.store_update();
+        }
+    }
+    store_update.commit()?;
+    Ok((col, num_records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::hash::hash;
+    use near_primitives::utils::index_to_bytes;
+    use near_primitives::version::DB_VERSION;
+    use near_store::migrations::set_store_version;
+    use near_store::test_utils::create_test_store;
+
+    fn create_store(db_version: DbVersion) -> Store {
+        let store = create_test_store();
+        set_store_version(&store, db_version);
+        store
+    }
+
+    fn store_with_block_heights() -> Store {
+        let store = create_store(DB_VERSION);
+        let mut store_update = store.store_update();
+        for height in 1..=5 {
+            store_update
+                .set_ser(DBCol::BlockHeight, &index_to_bytes(height), &hash(&[height as u8]))
+                .unwrap();
+        }
+        store_update.commit().unwrap();
+        store
+    }
+
+    fn block_heights(store: &Store) -> Vec<Vec<u8>> {
+        store.iter_raw_bytes(DBCol::BlockHeight).map(|item| item.unwrap().0.to_vec()).collect()
+    }
+
+    #[test]
+    fn test_export_import_height_range() {
+        let store = store_with_block_heights();
+        let filter =
+            ColumnDumpFilter { start_height: Some(2), end_height: Some(3), ..Default::default() };
+        let mut dump = Vec::new();
+        assert_eq!(export_column(&store, DBCol::BlockHeight, &filter, &mut dump).unwrap(), 2);
+
+        let record: DumpRecord =
+            serde_json::from_str(std::str::from_utf8(&dump).unwrap().lines().nth(1).unwrap())
+                .unwrap();
+        assert_eq!(record.height, Some(2));
+        assert_eq!(record.decoded, Some(format!("{:?}", hash(&[2]))));
+
+        let other_store = create_store(DB_VERSION);
+        let (col, num_records) = import_column(&other_store, dump.as_slice()).unwrap();
+        assert_eq!((col, num_records), (DBCol::BlockHeight, 2));
+        assert_eq!(
+            block_heights(&other_store),
+            vec![index_to_bytes(2).to_vec(), index_to_bytes(3).to_vec()]
+        );
+        assert_eq!(
+            other_store.get(DBCol::BlockHeight, &index_to_bytes(3)).unwrap(),
+            store.get(DBCol::BlockHeight, &index_to_bytes(3)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_export_key_range() {
+        let store = store_with_block_heights();
+        let filter = ColumnDumpFilter {
+            from_key: Some(index_to_bytes(4).to_vec()),
+            to_key: Some(index_to_bytes(5).to_vec()),
+            ..Default::default()
+        };
+        assert_eq!(export_column(&store, DBCol::BlockHeight, &filter, Vec::new()).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_height_range_unsupported_column() {
+        let filter = ColumnDumpFilter { start_height: Some(1), ..Default::default() };
+        let store = create_store(DB_VERSION);
+        assert!(export_column(&store, DBCol::Peers, &filter, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_import_rejects_other_format_version() {
+        let dump = format!(
+            "{{\"format\":\"{}\",\"version\":{},\"column\":\"BlockHeight\",\"db_version\":{}}}\n",
+            DUMP_FORMAT,
+            DUMP_FORMAT_VERSION + 1,
+            DB_VERSION
+        );
+        assert!(import_column(&create_store(DB_VERSION), dump.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_import_rejects_other_db_version() {
+        let mut dump = Vec::new();
+        export_column(
+            &store_with_block_heights(),
+            DBCol::BlockHeight,
+            &ColumnDumpFilter::default(),
+            &mut dump,
+        )
+        .unwrap();
+        assert!(import_column(&create_store(DB_VERSION - 1), dump.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_import_refcounted_column() {
+        let store = create_store(DB_VERSION);
+        let mut store_update = store.store_update();
+        store_update.increment_refcount(DBCol::Receipts, b"foo", b"bar");
+        store_update.increment_refcount(DBCol::Receipts, b"foo", b"bar");
+        store_update.commit().unwrap();
+        let mut dump = Vec::new();
+        export_column(&store, DBCol::Receipts, &ColumnDumpFilter::default(), &mut dump).unwrap();
+
+        // The reference counts from the dump are added to the existing ones.
+        let other_store = create_store(DB_VERSION);
+        let mut store_update = other_store.store_update();
+        store_update.increment_refcount(DBCol::Receipts, b"foo", b"bar");
+        store_update.commit().unwrap();
+        import_column(&other_store, dump.as_slice()).unwrap();
+        let records: Vec<_> = other_store
+            .iter_raw_bytes(DBCol::Receipts)
+            .map(|item| {
+                let (key, value) = item.unwrap();
+                (key.to_vec(), value.to_vec())
+            })
+            .collect();
+        assert_eq!(
+            records,
+            vec![(b"foo".to_vec(), [b"bar".as_slice(), &3i64.to_le_bytes()].concat())]
+        );
+    }
+}
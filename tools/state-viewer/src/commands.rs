@@ -247,6 +247,39 @@ pub(crate) fn dump_account_storage(
     std::process::exit(1);
 }
 
+pub(crate) fn view_storage_usage(
+    account_id: AccountId,
+    prefix_len: usize,
+    num_largest_keys: usize,
+    size_limit: Option<u64>,
+    home_dir: &Path,
+    mut near_config: NearConfig,
+    store: Store,
+) {
+    near_config.config.trie_viewer_state_size_limit = size_limit;
+    let (runtime, state_roots, header) = load_trie(store, home_dir, &near_config);
+    let epoch_id = header.epoch_id();
+    let shard_id = runtime.account_id_to_shard_id(&account_id, epoch_id).unwrap();
+    let shard_uid = runtime.shard_id_to_uid(shard_id, epoch_id).unwrap();
+    let protocol_version = runtime.get_epoch_protocol_version(epoch_id).unwrap();
+    match runtime.view_storage_usage(
+        &shard_uid,
+        state_roots[shard_id as usize],
+        &account_id,
+        prefix_len,
+        num_largest_keys,
+        protocol_version,
+    ) {
+        Ok(storage_usage) => {
+            println!("{}", serde_json::to_string_pretty(&storage_usage).unwrap())
+        }
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
 pub(crate) fn print_chain(
     start_height: BlockHeight,
     end_height: BlockHeight,
@@ -614,6 +647,23 @@ pub(crate) fn check_block_chunk_existence(store: Store, near_config: NearConfig)
     println!("Block check succeed");
 }
 
+pub(crate) fn gc_dry_run(home_dir: &Path, near_config: NearConfig, store: Store) {
+    if near_config.client_config.archive {
+        println!("Archival nodes don't garbage collect block data");
+        return;
+    }
+    let chain_store = ChainStore::new(
+        store.clone(),
+        near_config.genesis.config.genesis_height,
+        !near_config.client_config.archive,
+    );
+    let runtime = NightshadeRuntime::from_config(home_dir, store, &near_config);
+    let report =
+        near_chain::gc_policy::gc_dry_run(&chain_store, &runtime, &near_config.client_config.gc)
+            .unwrap();
+    print!("{}", report);
+}
+
 pub(crate) fn print_epoch_info(
     epoch_selection: epoch_info::EpochSelection,
     validator_account_id: Option<AccountId>,
@@ -3,6 +3,7 @@
 mod apply_chain_range;
 mod apply_chunk;
 pub mod cli;
+mod column_dump;
 mod commands;
 mod epoch_info;
 mod rocksdb_stats;
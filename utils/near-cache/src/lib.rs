@@ -1,4 +1,5 @@
 mod cell;
+mod sized;
 mod sync;
 
-pub use crate::{cell::CellLruCache, sync::SyncLruCache};
+pub use crate::{cell::CellLruCache, sized::SyncSizedLruCache, sync::SyncLruCache};
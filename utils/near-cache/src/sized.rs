@@ -0,0 +1,105 @@
+use lru::LruCache;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+/// A thread safe `LRU` cache which is bounded by the total size of its values rather than by their
+/// number. The size of a value is provided by the caller when it is inserted.
+pub struct SyncSizedLruCache<K, V> {
+    inner: Mutex<Inner<K, V>>,
+}
+
+struct Inner<K, V> {
+    cache: LruCache<K, (V, usize)>,
+    size: usize,
+    capacity: usize,
+}
+
+impl<K, V> SyncSizedLruCache<K, V>
+where
+    K: Hash + Eq,
+    V: Clone,
+{
+    /// Creates a new `LRU` cache that holds values of at most `capacity` total size.
+    pub fn new(capacity: usize) -> Self {
+        Self { inner: Mutex::new(Inner { cache: LruCache::unbounded(), size: 0, capacity }) }
+    }
+
+    /// Returns the number of key-value pairs that are currently in the cache.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().cache.len()
+    }
+
+    /// Returns the total size of the values that are currently in the cache.
+    pub fn size(&self) -> usize {
+        self.inner.lock().unwrap().size
+    }
+
+    /// Returns the value of the key in the cache or None if it is not present in the cache.
+    /// Moves the key to the head of the LRU list if it exists.
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.inner.lock().unwrap().cache.get(key).map(|(value, _)| value.clone())
+    }
+
+    /// Puts a key-value pair of the given size into the cache, evicting the least recently used
+    /// entries until the total size fits into the capacity. Values larger than the whole capacity
+    /// are not cached.
+    pub fn put(&self, key: K, value: V, size: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some((_, old_size)) = inner.cache.pop(&key) {
+            inner.size -= old_size;
+        }
+        if size > inner.capacity {
+            return;
+        }
+        while inner.size + size > inner.capacity {
+            match inner.cache.pop_lru() {
+                Some((_, (_, evicted_size))) => inner.size -= evicted_size,
+                None => break,
+            }
+        }
+        inner.size += size;
+        inner.cache.put(key, (value, size));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let cache = SyncSizedLruCache::<u64, Vec<u64>>::new(100);
+
+        assert_eq!(cache.get(&0u64), None);
+        cache.put(123u64, vec![123u64, 123], 10);
+        assert_eq!(cache.get(&123u64), Some(vec![123u64, 123]));
+        assert_eq!(cache.get(&0u64), None);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.size(), 10);
+    }
+
+    #[test]
+    fn test_eviction_by_size() {
+        let cache = SyncSizedLruCache::<u64, u64>::new(100);
+
+        cache.put(1, 1, 40);
+        cache.put(2, 2, 40);
+        // Makes 2 the least recently used entry.
+        assert_eq!(cache.get(&1), Some(1));
+        cache.put(3, 3, 40);
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&1), Some(1));
+        assert_eq!(cache.get(&3), Some(3));
+        assert_eq!(cache.size(), 80);
+
+        // Replacing a value accounts for the size of the old one.
+        cache.put(3, 3, 60);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.size(), 100);
+
+        // Values larger than the capacity are not cached.
+        cache.put(4, 4, 101);
+        assert_eq!(cache.get(&4), None);
+        assert_eq!(cache.size(), 100);
+    }
+}
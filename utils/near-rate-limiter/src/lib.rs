@@ -1,6 +1,8 @@
 #![doc = include_str!("../README.md")]
 pub(crate) mod framed_read;
 mod message_wrapper;
+mod token_bucket;
 pub use message_wrapper::{ActixMessageResponse, ActixMessageWrapper};
 
 pub use framed_read::{ThrottleController, ThrottleFramedRead, ThrottleToken};
+pub use token_bucket::TokenBucket;
@@ -0,0 +1,92 @@
+use std::time::Instant;
+
+/// A token bucket rate limiter.
+///
+/// The bucket holds at most `burst` tokens and is refilled at a constant rate of
+/// `rate` tokens per second.  Every accepted event consumes one token; once the
+/// bucket is empty, events are rejected until it gets refilled.  In the long run
+/// at most `rate` events per second are accepted, while short bursts of up to
+/// `burst` events are allowed.
+///
+/// The bucket doesn't read the clock on its own, the current time has to be
+/// passed to each call.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    /// Number of tokens added per second.
+    rate: f64,
+    /// Maximum number of tokens in the bucket.
+    burst: f64,
+    /// Number of tokens currently in the bucket.
+    tokens: f64,
+    /// Last time the bucket was refilled.
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    pub fn new(rate: f64, burst: u32, now: Instant) -> Self {
+        let burst = burst as f64;
+        Self { rate, burst, tokens: burst, last_refill: now }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.burst);
+        self.last_refill = self.last_refill.max(now);
+    }
+
+    /// Tries to consume a single token.  Returns whether the event should be accepted.
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenBucket;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_burst_then_rate() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2., 3, now);
+        assert!(bucket.try_acquire(now));
+        assert!(bucket.try_acquire(now));
+        assert!(bucket.try_acquire(now));
+        assert!(!bucket.try_acquire(now));
+
+        let now = now + Duration::from_millis(500);
+        assert!(bucket.try_acquire(now));
+        assert!(!bucket.try_acquire(now));
+
+        // The bucket never holds more than `burst` tokens.
+        let now = now + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(bucket.try_acquire(now));
+        }
+        assert!(!bucket.try_acquire(now));
+    }
+
+    #[test]
+    fn test_zero_rate() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(0., 1, now);
+        assert!(bucket.try_acquire(now));
+        assert!(!bucket.try_acquire(now + Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn test_clock_going_backwards() {
+        let now = Instant::now() + Duration::from_secs(10);
+        let mut bucket = TokenBucket::new(1., 1, now);
+        assert!(bucket.try_acquire(now));
+        assert!(!bucket.try_acquire(now - Duration::from_secs(5)));
+        assert!(bucket.try_acquire(now + Duration::from_secs(1)));
+    }
+}